@@ -0,0 +1,437 @@
+//! `rbxsync ci`: the checks a pull request needs, without Studio
+//!
+//! Every project under a directory (each folder with an rbxsync.json) goes
+//! through the same steps: `fmt-project --check`, `verify`, the Luau syntax
+//! check, Wally lock verification, `build` and `sourcemap`. A failing step
+//! doesn't stop the ones after it. Results are written as JUnit XML, one
+//! test suite per project and one test case per step, and summarized.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::output::{Exit, Output, EXIT_FAILURE};
+
+/// Directories never searched for projects
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "build", "Packages", "ServerPackages", "DevPackages"];
+
+/// Where the JUnit report goes by default, relative to the searched directory
+const DEFAULT_JUNIT_PATH: &str = "build/rbxsync-ci.xml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StepResult {
+    name: &'static str,
+    status: StepStatus,
+    /// Why the step failed or was skipped
+    message: Option<String>,
+    seconds: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectResult {
+    name: String,
+    path: PathBuf,
+    steps: Vec<StepResult>,
+}
+
+/// `rbxsync ci --format json`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CiReport<'a> {
+    success: bool,
+    junit: &'a Path,
+    projects: &'a [ProjectResult],
+}
+
+enum Outcome {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+impl From<Result<()>> for Outcome {
+    fn from(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Outcome::Passed,
+            Err(e) => Outcome::Failed(format!("{:#}", e)),
+        }
+    }
+}
+
+fn step(name: &'static str, started: Instant, outcome: Outcome) -> StepResult {
+    let (status, message) = match outcome {
+        Outcome::Passed => (StepStatus::Passed, None),
+        Outcome::Failed(message) => (StepStatus::Failed, Some(message)),
+        Outcome::Skipped(message) => (StepStatus::Skipped, Some(message)),
+    };
+    StepResult {
+        name,
+        status,
+        message,
+        seconds: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// Run every step for every project under `path`
+pub async fn cmd_ci(path: Option<PathBuf>, junit: Option<PathBuf>, output: Output) -> Result<()> {
+    let root = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+    let projects = find_projects(&root);
+    if projects.is_empty() {
+        bail!("No RbxSync projects found in {}", root.display());
+    }
+
+    let mut results = Vec::new();
+    for dir in projects {
+        let name = match dir.strip_prefix(&root) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rbxsync_core::path_to_string(rel),
+            _ => root
+                .canonicalize()
+                .ok()
+                .and_then(|root| root.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| ".".to_string()),
+        };
+        if output.is_text() {
+            println!("Checking {}...", name);
+        }
+        let steps = check_project(&dir).await;
+        results.push(ProjectResult { name, path: dir, steps });
+    }
+
+    let junit_path = junit.unwrap_or_else(|| root.join(DEFAULT_JUNIT_PATH));
+    if let Some(parent) = junit_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).context("Failed to create JUnit report directory")?;
+    }
+    std::fs::write(&junit_path, junit_xml(&results))
+        .with_context(|| format!("Failed to write {}", junit_path.display()))?;
+
+    let count = |status: StepStatus| results.iter().flat_map(|p| &p.steps).filter(|s| s.status == status).count();
+    let failed = count(StepStatus::Failed);
+    output.json(&CiReport {
+        success: failed == 0,
+        junit: &junit_path,
+        projects: &results,
+    })?;
+    if output.is_text() {
+        print_summary(&results);
+        println!(
+            "\n{} passed, {} failed, {} skipped in {} project(s)",
+            count(StepStatus::Passed),
+            failed,
+            count(StepStatus::Skipped),
+            results.len()
+        );
+        println!("JUnit report: {}", junit_path.display());
+    }
+
+    if failed > 0 {
+        bail!(Exit(EXIT_FAILURE));
+    }
+    Ok(())
+}
+
+/// The steps for one project
+async fn check_project(dir: &Path) -> Vec<StepResult> {
+    let quiet = Output {
+        quiet: true,
+        ..Output::default()
+    };
+    let mut steps = Vec::new();
+
+    let started = Instant::now();
+    let outcome = match crate::format_project_files(dir, true) {
+        Ok((_, unformatted)) if unformatted.is_empty() => Outcome::Passed,
+        Ok((_, unformatted)) => {
+            let files: Vec<String> = unformatted
+                .iter()
+                .map(|file| rbxsync_core::path_to_string(file.strip_prefix(dir).unwrap_or(file)))
+                .collect();
+            Outcome::Failed(format!(
+                "{} file(s) need formatting; run `rbxsync fmt-project`:\n{}",
+                files.len(),
+                files.join("\n")
+            ))
+        }
+        Err(e) => Outcome::Failed(format!("{:#}", e)),
+    };
+    steps.push(step("format", started, outcome));
+
+    // One pass over the tree reports both the tree and the Luau problems
+    let started = Instant::now();
+    let src_dir = dir.join("src");
+    let issues = src_dir.is_dir().then(|| rbxsync_core::verify_tree(&src_dir, None));
+    for (name, kind) in [("verify", rbxsync_core::IssueKind::Tree), ("luau-syntax", rbxsync_core::IssueKind::Luau)] {
+        let outcome = match &issues {
+            None => Outcome::Failed(format!("Source directory not found: {}", src_dir.display())),
+            Some(issues) => {
+                let problems: Vec<String> = issues
+                    .iter()
+                    .filter(|issue| issue.kind == kind)
+                    .map(|issue| format!("src/{}", issue))
+                    .collect();
+                if problems.is_empty() {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed(problems.join("\n"))
+                }
+            }
+        };
+        steps.push(step(name, started, outcome));
+    }
+
+    let started = Instant::now();
+    steps.push(step("wally", started, check_wally(dir)));
+
+    let started = Instant::now();
+    let result = crate::cmd_build(Some(dir.to_path_buf()), None, "rbxl".to_string(), false, None, quiet).await;
+    steps.push(step("build", started, result.into()));
+
+    let started = Instant::now();
+    let result = crate::cmd_sourcemap(Some(dir.to_path_buf()), None, false, quiet);
+    steps.push(step("sourcemap", started, result.into()));
+
+    steps
+}
+
+/// Whether wally.lock has every dependency of wally.toml at an allowed version
+fn check_wally(dir: &Path) -> Outcome {
+    let Some(manifest_path) = rbxsync_core::find_wally_manifest(dir) else {
+        return Outcome::Skipped("No wally.toml".to_string());
+    };
+    let Some(lock_path) = rbxsync_core::find_wally_lock(dir) else {
+        return Outcome::Failed("wally.toml has no wally.lock; run `wally install` and commit wally.lock".to_string());
+    };
+    let manifest = match rbxsync_core::WallyManifest::from_file(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(e) => return Outcome::Failed(format!("{}: {}", manifest_path.display(), e)),
+    };
+    let lock = match rbxsync_core::WallyLock::from_file(&lock_path) {
+        Ok(lock) => lock,
+        Err(e) => return Outcome::Failed(format!("{}: {}", lock_path.display(), e)),
+    };
+
+    let problems = lock.check_manifest(&manifest);
+    if problems.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("wally.lock is out of date; run `wally install`:\n{}", problems.join("\n")))
+    }
+}
+
+/// Projects under `root`: every directory with an rbxsync.json, or `root`
+/// itself when there are none but it has a src directory
+fn find_projects(root: &Path) -> Vec<PathBuf> {
+    fn visit(dir: &Path, projects: &mut Vec<PathBuf>) {
+        let is_project = dir.join("rbxsync.json").is_file();
+        if is_project {
+            projects.push(dir.to_path_buf());
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Symlinks aren't followed, so a link back up can't loop
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if !is_dir || name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) || (is_project && name == "src") {
+                continue;
+            }
+            visit(&entry.path(), projects);
+        }
+    }
+
+    let mut projects = Vec::new();
+    visit(root, &mut projects);
+    if projects.is_empty() && root.join("src").is_dir() {
+        projects.push(root.to_path_buf());
+    }
+    projects.sort();
+    projects
+}
+
+fn print_summary(results: &[ProjectResult]) {
+    for project in results {
+        println!("\n{}", project.name);
+        for step in &project.steps {
+            match step.status {
+                StepStatus::Passed => println!("  \x1b[32m✓ {}\x1b[0m ({:.2}s)", step.name, step.seconds),
+                StepStatus::Skipped => {
+                    println!("  \x1b[2m- {}: {}\x1b[0m", step.name, step.message.as_deref().unwrap_or_default())
+                }
+                StepStatus::Failed => {
+                    println!("  \x1b[31m✗ {}\x1b[0m ({:.2}s)", step.name, step.seconds);
+                    for line in step.message.as_deref().unwrap_or_default().lines() {
+                        println!("      {}", line);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// JUnit XML with a test suite per project and a test case per step
+fn junit_xml(results: &[ProjectResult]) -> String {
+    let count = |steps: &[StepResult], status: StepStatus| steps.iter().filter(|s| s.status == status).count();
+    let seconds = |steps: &[StepResult]| steps.iter().map(|s| s.seconds).sum::<f64>();
+    let all: Vec<StepResult> = results.iter().flat_map(|p| p.steps.iter().cloned()).collect();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"rbxsync ci\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        all.len(),
+        count(&all, StepStatus::Failed),
+        count(&all, StepStatus::Skipped),
+        seconds(&all)
+    ));
+    for project in results {
+        let name = xml_escape(&project.name);
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            name,
+            project.steps.len(),
+            count(&project.steps, StepStatus::Failed),
+            count(&project.steps, StepStatus::Skipped),
+            seconds(&project.steps)
+        ));
+        for step in &project.steps {
+            let testcase = format!("    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"", name, step.name, step.seconds);
+            let message = step.message.as_deref().unwrap_or_default();
+            let summary = xml_escape(message.lines().next().unwrap_or_default());
+            match step.status {
+                StepStatus::Passed => xml.push_str(&format!("{}/>\n", testcase)),
+                StepStatus::Failed => xml.push_str(&format!(
+                    "{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    testcase,
+                    summary,
+                    xml_escape(message)
+                )),
+                StepStatus::Skipped => xml.push_str(&format!(
+                    "{}>\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                    testcase, summary
+                )),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for an XML attribute or element, dropping control
+/// characters XML can't hold
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &'static str, status: StepStatus, message: Option<&str>) -> StepResult {
+        StepResult {
+            name,
+            status,
+            message: message.map(String::from),
+            seconds: 0.5,
+        }
+    }
+
+    fn project(name: &str, steps: Vec<StepResult>) -> ProjectResult {
+        ProjectResult {
+            name: name.to_string(),
+            path: PathBuf::from(name),
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_junit_failed_and_skipped_steps() {
+        let xml = junit_xml(&[project(
+            "game",
+            vec![
+                step("fmt-project", StepStatus::Passed, None),
+                step("verify", StepStatus::Failed, Some("2 problems\nsrc/Workspace/A.rbxjson: bad ref")),
+                step("wally", StepStatus::Skipped, Some("No wally.toml")),
+            ],
+        )]);
+        assert!(xml.contains("<testsuites name=\"rbxsync ci\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"1.500\">"));
+        assert!(xml.contains("<testsuite name=\"game\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"1.500\">"));
+        assert!(xml.contains("<testcase classname=\"game\" name=\"fmt-project\" time=\"0.500\"/>"));
+        assert!(xml.contains(
+            "<testcase classname=\"game\" name=\"verify\" time=\"0.500\">\n      \
+             <failure message=\"2 problems\">2 problems\nsrc/Workspace/A.rbxjson: bad ref</failure>\n    </testcase>"
+        ));
+        assert!(xml.contains(
+            "<testcase classname=\"game\" name=\"wally\" time=\"0.500\">\n      \
+             <skipped message=\"No wally.toml\"/>\n    </testcase>"
+        ));
+    }
+
+    #[test]
+    fn test_junit_escaping() {
+        let xml = junit_xml(&[project(
+            "a<b> & \"c\"",
+            vec![step("verify", StepStatus::Failed, Some("x < y && z\u{1b}[31m \"q\"\ttab"))],
+        )]);
+        assert!(xml.contains("<testsuite name=\"a&lt;b&gt; &amp; &quot;c&quot;\""));
+        assert!(xml.contains("<failure message=\"x &lt; y &amp;&amp; z[31m &quot;q&quot;\ttab\">"));
+        assert!(!xml.contains('\u{1b}'));
+        assert_eq!(xml_escape("it's\r\n\u{0}"), "it&apos;s\r\n");
+    }
+
+    #[test]
+    fn test_find_projects() {
+        let root = tempfile::TempDir::new().unwrap();
+        let dir = root.path();
+        let projects = [
+            "games/lobby",
+            "games/lobby/tools/editor",
+            "games/arena",
+            "games/arena/Packages/dep",
+            "node_modules/pkg",
+            ".cache/old",
+        ];
+        for project in projects {
+            std::fs::create_dir_all(dir.join(project)).unwrap();
+            std::fs::write(dir.join(project).join("rbxsync.json"), "{}").unwrap();
+        }
+        // A project's src is never searched
+        std::fs::create_dir_all(dir.join("games/lobby/src/Inner")).unwrap();
+        std::fs::write(dir.join("games/lobby/src/Inner/rbxsync.json"), "{}").unwrap();
+
+        assert_eq!(
+            find_projects(dir),
+            vec![dir.join("games/arena"), dir.join("games/lobby"), dir.join("games/lobby/tools/editor")]
+        );
+
+        // Without any rbxsync.json, a directory with src is the project
+        let bare = tempfile::TempDir::new().unwrap();
+        assert!(find_projects(bare.path()).is_empty());
+        std::fs::create_dir_all(bare.path().join("src")).unwrap();
+        assert_eq!(find_projects(bare.path()), vec![bare.path().to_path_buf()]);
+    }
+}
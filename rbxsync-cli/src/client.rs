@@ -0,0 +1,381 @@
+//! HTTP client for the sync server
+//!
+//! Every command that talks to a running server goes through
+//! [`RbxSyncClient`], so they all target the same server and report a
+//! missing server or a failed request the same way. The server is taken
+//! from `--server`, then `RBXSYNC_URL`, then `server` in the project's
+//! rbxsync.json, and defaults to `http://localhost:44755`. A bad URL only
+//! fails commands that use the server.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Server used when nothing else is configured
+pub const DEFAULT_SERVER_URL: &str = "http://localhost:44755";
+
+/// Environment variable naming the server
+pub const SERVER_URL_ENV: &str = "RBXSYNC_URL";
+
+/// Pick the server URL: `--server`, then `RBXSYNC_URL`, then `server` in
+/// `project_dir`'s rbxsync.json, then the default
+pub fn resolve_server_url(flag: Option<&str>, project_dir: &Path) -> Result<String> {
+    let env = std::env::var(SERVER_URL_ENV).ok().filter(|url| !url.trim().is_empty());
+    let config = std::fs::read_to_string(project_dir.join("rbxsync.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|config| config.get("server").and_then(|s| s.as_str()).map(String::from));
+
+    match (flag, env, config) {
+        (Some(url), _, _) => normalize_server_url(url).context("Invalid --server"),
+        (None, Some(url), _) => normalize_server_url(&url).with_context(|| format!("Invalid {}", SERVER_URL_ENV)),
+        (None, None, Some(url)) => normalize_server_url(&url).context("Invalid `server` in rbxsync.json"),
+        (None, None, None) => Ok(DEFAULT_SERVER_URL.to_string()),
+    }
+}
+
+/// Accept a full URL, `host:port`, or just a port (`44800` means
+/// `http://localhost:44800`)
+pub fn normalize_server_url(url: &str) -> Result<String> {
+    let url = url.trim().trim_end_matches('/');
+    let url = if !url.is_empty() && url.chars().all(|c| c.is_ascii_digit()) {
+        format!("http://localhost:{}", url)
+    } else if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{}", url)
+    };
+    let parsed = reqwest::Url::parse(&url).with_context(|| format!("'{}' is not a server URL", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        bail!("'{}' is not an http(s) server URL", url);
+    }
+    Ok(url)
+}
+
+/// `/health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub status: String,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// `/extract/status`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractStatus {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub chunks_received: u64,
+    #[serde(default)]
+    pub total_chunks: Option<u64>,
+    #[serde(default)]
+    pub complete: bool,
+    #[serde(default)]
+    pub finalized: bool,
+    /// `no_active_session` when nothing is being extracted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// A plugin command's or batch's response (`/sync/command`, `/sync/batch`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// `/sync/read-tree`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadTree {
+    #[serde(default)]
+    pub instances: Vec<serde_json::Value>,
+}
+
+/// An instance only one side of a diff has
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffEntry {
+    pub path: String,
+    #[serde(default)]
+    pub class_name: String,
+}
+
+/// `/diff`: the files (new side) against Studio (old side)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct StudioDiff {
+    /// In files, not in Studio
+    #[serde(default)]
+    pub added: Vec<DiffEntry>,
+    /// In Studio, not in files
+    #[serde(default)]
+    pub removed: Vec<DiffEntry>,
+    /// Per-instance differences, as produced by `rbxsync_core::diff_instances`
+    #[serde(default)]
+    pub modified: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub unchanged: usize,
+    #[serde(default)]
+    pub ambiguous: Vec<String>,
+    #[serde(default)]
+    pub file_count: usize,
+    #[serde(default)]
+    pub studio_count: usize,
+}
+
+impl StudioDiff {
+    /// Whether the files and Studio match
+    pub fn in_sync(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// `/sync/branch-switch`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BranchSwitches {
+    #[serde(default)]
+    pub switches: Vec<serde_json::Value>,
+}
+
+/// Client for one sync server
+#[derive(Debug, Clone)]
+pub struct RbxSyncClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RbxSyncClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Port to start a server on when none is running, if the URL points at
+    /// this machine
+    pub fn local_port(&self) -> Option<u16> {
+        let url = reqwest::Url::parse(&self.base_url).ok()?;
+        match url.host_str()? {
+            "localhost" | "127.0.0.1" | "[::1]" | "::1" => url.port_or_known_default(),
+            _ => None,
+        }
+    }
+
+    /// Whether the server answers `/health`
+    pub async fn is_running(&self) -> bool {
+        self.client
+            .get(self.url("/health"))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success())
+    }
+
+    /// Fail with a hint on how to start the server unless it is running
+    pub async fn ensure_running(&self) -> Result<()> {
+        if self.is_running().await {
+            return Ok(());
+        }
+        bail!("RbxSync server is not running at {}. Start it with: rbxsync serve", self.base_url)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.send(self.client.get(self.url(path))).await?;
+        read_json(path, response).await
+    }
+
+    pub async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        let response = self.send(self.client.post(self.url(path)).json(body)).await?;
+        read_json(path, response).await
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        request
+            .send()
+            .await
+            .with_context(|| format!("Could not reach the RbxSync server at {}", self.base_url))
+    }
+
+    pub async fn health(&self) -> Result<Health> {
+        self.get("/health").await
+    }
+
+    /// Prometheus metrics text
+    pub async fn metrics(&self) -> Result<String> {
+        let response = self
+            .send(self.client.get(self.url("/metrics")).timeout(Duration::from_secs(5)))
+            .await?;
+        Ok(response.error_for_status()?.text().await?)
+    }
+
+    /// Run a plugin command, returning its data or failing with its error
+    pub async fn command(&self, command: &str, payload: serde_json::Value) -> Result<serde_json::Value> {
+        let response: PluginResponse = self
+            .post("/sync/command", &serde_json::json!({ "command": command, "payload": payload }))
+            .await?;
+        if !response.success {
+            bail!("{}", response.error.as_deref().unwrap_or("Unknown error"));
+        }
+        Ok(response.data)
+    }
+
+    pub async fn start_extraction(
+        &self,
+        services: Option<&[String]>,
+        include_terrain: bool,
+        include_assets: bool,
+        force: bool,
+    ) -> Result<serde_json::Value> {
+        self.post(
+            "/extract/start",
+            &serde_json::json!({
+                "services": services,
+                "include_terrain": include_terrain,
+                "include_assets": include_assets,
+                "force": force,
+            }),
+        )
+        .await
+    }
+
+    pub async fn extraction_status(&self) -> Result<ExtractStatus> {
+        self.get("/extract/status").await
+    }
+
+    pub async fn read_tree(&self, project_dir: &str) -> Result<ReadTree> {
+        self.post("/sync/read-tree", &serde_json::json!({ "project_dir": project_dir })).await
+    }
+
+    /// Compare the project's files with Studio, failing if the comparison did
+    pub async fn diff(&self, project_dir: &str) -> Result<StudioDiff> {
+        let response: serde_json::Value = self.post("/diff", &serde_json::json!({ "project_dir": project_dir })).await?;
+        if response.get("success").and_then(|v| v.as_bool()) == Some(false) {
+            bail!("{}", response["error"].as_str().unwrap_or("Studio comparison failed"));
+        }
+        serde_json::from_value(response).context("Unexpected response to /diff")
+    }
+
+    /// Send operations to Studio as one batch
+    pub async fn sync_batch(&self, operations: &[serde_json::Value], project_dir: &str, force: bool) -> Result<PluginResponse> {
+        let body = serde_json::json!({
+            "operations": operations,
+            "projectDir": project_dir,
+            "force": force
+        });
+        let response = self.send(self.client.post(self.url("/sync/batch")).json(&body)).await?;
+        if response.status() == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+            let result: serde_json::Value = response.json().await.unwrap_or_default();
+            bail!(
+                "{}\nRaise the limit in rbxsync.json or re-run with --force.",
+                result["error"].as_str().unwrap_or("Sync is over a size limit")
+            );
+        }
+        read_json("/sync/batch", response).await
+    }
+
+    pub async fn branch_switches(&self) -> Result<BranchSwitches> {
+        self.get("/sync/branch-switch").await
+    }
+
+    /// Apply or discard a pending branch switch, returning its `data`
+    pub async fn resolve_branch_switch(&self, project_dir: &str, apply: bool) -> Result<serde_json::Value> {
+        let response: PluginResponse = self
+            .post(
+                "/sync/branch-switch",
+                &serde_json::json!({
+                    "project_dir": project_dir,
+                    "action": if apply { "apply" } else { "discard" }
+                }),
+            )
+            .await?;
+        Ok(response.data)
+    }
+}
+
+/// Decode a response, turning error statuses into errors carrying the
+/// server's message
+async fn read_json<T: DeserializeOwned>(path: &str, response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read the response to {}", path))?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<serde_json::Value>(&body).ok().and_then(|v| {
+            v.get("error")
+                .or_else(|| v.get("message"))
+                .and_then(|e| e.as_str())
+                .map(String::from)
+        });
+        match message {
+            Some(message) => bail!("{}", message),
+            None => bail!("{} returned {}: {}", path, status, body.trim()),
+        }
+    }
+    serde_json::from_str(&body).with_context(|| format!("Unexpected response to {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rbxsync_server::{create_router, AppState};
+
+    /// A real server on a free port, with nothing connected as the plugin
+    async fn server() -> RbxSyncClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, create_router(AppState::new())).await.unwrap();
+        });
+        RbxSyncClient::new(url)
+    }
+
+    #[tokio::test]
+    async fn test_diff_fails_when_the_plugin_does() {
+        let client = server().await;
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(project.path().join("src/Workspace")).unwrap();
+
+        // A plugin that doesn't know `studio:hashes`
+        let plugin = RbxSyncClient::new(client.base_url());
+        let answer = tokio::spawn(async move {
+            loop {
+                let response = plugin.client.get(plugin.url("/rbxsync/request")).send().await.unwrap();
+                if response.status() == reqwest::StatusCode::OK {
+                    let request: serde_json::Value = response.json().await.unwrap();
+                    assert_eq!(request["command"], "studio:hashes");
+                    let error = serde_json::json!({
+                        "id": request["id"],
+                        "success": false,
+                        "error": "Unknown command: studio:hashes",
+                    });
+                    plugin.client.post(plugin.url("/rbxsync/response")).json(&error).send().await.unwrap();
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+
+        let err = client.diff(&project.path().to_string_lossy()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Unknown command: studio:hashes");
+        answer.await.unwrap();
+    }
+}
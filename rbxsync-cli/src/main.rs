@@ -2,6 +2,10 @@
 //!
 //! Command-line interface for Roblox game extraction and synchronization.
 
+mod ci;
+mod client;
+mod output;
+
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufWriter;
@@ -17,14 +21,32 @@ use rbx_dom_weak::{InstanceBuilder, WeakDom};
 use rbxsync_core::{
     build_plugin, find_existing_rbxsync_plugin, find_rojo_project, get_studio_plugins_folder,
     install_plugin, parse_rojo_project, rojo_to_tree_mapping, PluginBuildConfig, ProjectConfig,
+    SourceFormat,
 };
+use rbxsync_server::daemon::{ControlCommand, DaemonInfo, DaemonPaths, DaemonStatus};
+use rbxsync_server::plugin_requests::CommandTimeouts;
 use rbxsync_server::{run_server, ServerConfig};
 
+use crate::client::RbxSyncClient;
+use crate::output::{Exit, Output, OutputFormat, EXIT_ERROR, EXIT_FAILURE};
+
 #[derive(Parser)]
 #[command(name = "rbxsync")]
 #[command(about = "Roblox game extraction and synchronization tool")]
 #[command(version)]
 struct Cli {
+    /// Sync server to talk to, as a URL, host:port or port (default: $RBXSYNC_URL, then `server` in rbxsync.json, then http://localhost:44755)
+    #[arg(long, global = true, value_name = "URL")]
+    server: Option<String>,
+
+    /// Output format: text, or json for one JSON document on stdout
+    #[arg(long = "format", id = "output_format", value_name = "FORMAT", global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Print nothing but errors; the exit code tells the result
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -79,6 +101,10 @@ enum Commands {
         /// Output directory (default: project src directory)
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Ignore the size limits in rbxsync.json
+        #[arg(long)]
+        force: bool,
     },
 
     /// Start the sync server (connects to Studio plugin)
@@ -90,6 +116,24 @@ enum Commands {
         /// Run server in background (detached)
         #[arg(short, long)]
         background: bool,
+
+        /// Persist connected places, workspaces and sync state across restarts
+        #[arg(long)]
+        persist_state: bool,
+
+        /// Directory for persisted state (default: ~/.rbxsync/state, implies --persist-state)
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+
+        /// Plugin response timeout for a command or command group, e.g. `run:code=120` or `default=60` (repeatable)
+        #[arg(long = "timeout", value_name = "COMMAND=SECS")]
+        timeouts: Vec<String>,
+    },
+
+    /// Run and manage the sync server as a background daemon
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
     },
 
     /// Stop the running sync server
@@ -102,8 +146,26 @@ enum Commands {
     /// Show sync status
     Status,
 
-    /// Show diff between local files and Studio
-    Diff,
+    /// Show server metrics (queues, plugin latency, live sync, extraction)
+    Stats {
+        /// Server port on this machine (default: the --server URL)
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Print the raw Prometheus metrics
+        #[arg(long)]
+        raw: bool,
+    },
+
+    /// Show diff between local files and Studio, or between two project
+    /// trees, git revisions or place files without Studio
+    Diff {
+        /// Old side: a project directory, git revision or .rbxl/.rbxm/.rbxlx/.rbxmx file
+        a: Option<String>,
+
+        /// New side, in the same forms (default: current directory)
+        b: Option<String>,
+    },
 
     /// Sync local changes to connected Studio instance
     Sync {
@@ -114,6 +176,10 @@ enum Commands {
         /// Keep orphaned instances in Studio (by default, they are deleted)
         #[arg(long)]
         no_delete: bool,
+
+        /// Ignore the size limits in rbxsync.json
+        #[arg(long)]
+        force: bool,
     },
 
     /// Build the Studio plugin as .rbxm file
@@ -174,8 +240,8 @@ enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Output format: rbxl, rbxm, rbxlx (XML place), or rbxmx (XML model)
-        #[arg(short, long, default_value = "rbxl")]
+        /// File type: rbxl, rbxm, rbxlx (XML place), or rbxmx (XML model)
+        #[arg(short = 'f', long = "type", value_name = "TYPE", default_value = "rbxl")]
         format: String,
 
         /// Watch for file changes and rebuild automatically
@@ -198,6 +264,30 @@ enum Commands {
         check: bool,
     },
 
+    /// Check that the project tree is valid and builds, without writing output
+    Verify {
+        /// Project directory (default: current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Check the files staged for commit and the files that depend on them
+        #[arg(long)]
+        staged: bool,
+    },
+
+    /// Run the checks for a pull request without Studio: fmt-project --check,
+    /// verify, Luau syntax, the Wally lock, build and sourcemap, for every
+    /// project under a directory, with a JUnit XML report
+    Ci {
+        /// Directory to search for projects (default: current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// JUnit XML report to write (default: build/rbxsync-ci.xml in the directory)
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
+    },
+
     /// Open RbxSync documentation in browser
     Doc,
 
@@ -265,6 +355,120 @@ enum Commands {
         #[command(subcommand)]
         action: HarnessAction,
     },
+
+    /// Git integration
+    Git {
+        #[command(subcommand)]
+        action: GitAction,
+    },
+
+    /// Git hooks
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
+    /// Three-way merge of a .rbxjson file, run by git as a merge driver
+    MergeDriver {
+        /// Common ancestor (%O)
+        base: PathBuf,
+
+        /// Current branch's version (%A), overwritten with the result
+        ours: PathBuf,
+
+        /// Other branch's version (%B)
+        theirs: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Install a pre-commit hook that runs `rbxsync verify --staged`, and the .rbxjson merge driver
+    Install {
+        /// Project directory (default: current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Replace an existing pre-commit hook
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GitAction {
+    /// Register the .rbxjson merge driver in git config and .gitattributes
+    /// (`hooks install` also adds the pre-commit hook)
+    #[command(alias = "install-hooks")]
+    InstallMergeDriver {
+        /// Project directory (default: current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+    },
+
+    /// Review the changes a checkout or pull made, which live sync holds back
+    BranchSwitch {
+        /// Project directory (default: current directory)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Send the changes to Studio as one batch and resume live sync
+        #[arg(long, conflicts_with = "discard")]
+        apply: bool,
+
+        /// Resume live sync without changing Studio
+        #[arg(long)]
+        discard: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Start the daemon in the background
+    Start {
+        /// Port to listen on
+        #[arg(short, long, default_value = "44755")]
+        port: u16,
+
+        /// Persist connected places, workspaces and sync state across restarts
+        #[arg(long)]
+        persist_state: bool,
+
+        /// Directory for persisted state (default: ~/.rbxsync/state, implies --persist-state)
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+
+        /// Plugin response timeout for a command or command group, e.g. `run:code=120` or `default=60` (repeatable)
+        #[arg(long = "timeout", value_name = "COMMAND=SECS")]
+        timeouts: Vec<String>,
+    },
+    /// Run the daemon in the foreground (used by `daemon start`)
+    #[command(hide = true)]
+    Run {
+        #[arg(short, long, default_value = "44755")]
+        port: u16,
+
+        #[arg(long)]
+        state_dir: Option<PathBuf>,
+
+        #[arg(long = "timeout")]
+        timeouts: Vec<String>,
+    },
+    /// Stop the daemon
+    Stop {
+        #[arg(short, long, default_value = "44755")]
+        port: u16,
+    },
+    /// Show attached places, workspaces and file watchers
+    Status {
+        #[arg(short, long, default_value = "44755")]
+        port: u16,
+    },
+    /// Reload project configs and restart file watchers
+    Reload {
+        #[arg(short, long, default_value = "44755")]
+        port: u16,
+    },
 }
 
 #[derive(Subcommand)]
@@ -481,52 +685,146 @@ fn check_duplicate_installations() {
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("rbxsync=info".parse().unwrap()),
+async fn main() {
+    let cli = Cli::parse();
+    let output = Output {
+        format: cli.output_format,
+        quiet: cli.quiet,
+    };
+
+    let code = match run(cli, output).await {
+        Ok(()) => 0,
+        Err(e) => match e.downcast_ref::<Exit>() {
+            Some(Exit(code)) => *code,
+            None => {
+                if output.is_json() {
+                    println!("{}", serde_json::json!({ "error": format!("{:#}", e) }));
+                } else {
+                    eprintln!("Error: {:?}", e);
+                }
+                EXIT_ERROR
+            }
+        },
+    };
+    std::process::exit(code);
+}
+
+/// Whether a command supports `--format json` and `--quiet`
+///
+/// The ones left out have no single result to report (see "Scripting" in
+/// docs/cli/commands.md).
+fn has_machine_output(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Status
+            | Commands::Stats { .. }
+            | Commands::Diff { .. }
+            | Commands::Sync { .. }
+            | Commands::Extract { .. }
+            | Commands::Debug { .. }
+            | Commands::Plugin { .. }
+            | Commands::Version
+            | Commands::Harness { .. }
+            | Commands::Sourcemap { .. }
+            | Commands::Build { .. }
+            | Commands::Verify { .. }
+            | Commands::Ci { .. }
+            | Commands::FmtProject { .. }
+            | Commands::Git { .. }
+            | Commands::Hooks { .. }
+            | Commands::Daemon { action: DaemonAction::Status { .. } }
+    )
+}
+
+async fn run(cli: Cli, output: Output) -> Result<()> {
+    // Initialize logging (daemons log to a rotating file instead of stderr)
+    let filter = tracing_subscriber::EnvFilter::from_default_env()
+        .add_directive("rbxsync=info".parse().unwrap());
+    if let Commands::Daemon { action: DaemonAction::Run { port, .. } } = &cli.command {
+        let paths = DaemonPaths::for_port(*port).context("Could not determine home directory")?;
+        let log = rbxsync_server::daemon::RotatingLog::open(
+            &paths.log_file,
+            rbxsync_server::daemon::LOG_MAX_BYTES,
+            rbxsync_server::daemon::LOG_KEEP_FILES,
         )
-        .init();
+        .context("Failed to open daemon log file")?;
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(log))
+            .init();
+    } else if output.is_text() {
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    } else {
+        // Keep stdout for the command's own output
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .init();
+    }
+
+    if !output.is_text() && !has_machine_output(&cli.command) {
+        bail!("--format json and --quiet are not supported by this command");
+    }
 
     // Check for duplicate installations that might cause confusion
     check_duplicate_installations();
 
-    let cli = Cli::parse();
+    // The project whose rbxsync.json may name the server
+    let project_dir = match &cli.command {
+        Commands::Sync { path: Some(path), .. }
+        | Commands::Git { action: GitAction::BranchSwitch { path: Some(path), .. } } => path.clone(),
+        _ => std::env::current_dir().unwrap_or_default(),
+    };
+    // Only commands that talk to a server fail on a bad URL
+    let client = client::resolve_server_url(cli.server.as_deref(), &project_dir).map(RbxSyncClient::new);
 
     match cli.command {
         Commands::Init { name, path, no_sourcemap } => {
             cmd_init(name, path, no_sourcemap).await?;
         }
         Commands::Studio { place, serve } => {
-            cmd_studio(place, serve).await?;
+            cmd_studio(&client?, place, serve).await?;
         }
         Commands::Debug { action } => {
-            cmd_debug(action).await?;
+            cmd_debug(&client?, action, output).await?;
         }
         Commands::Extract {
             service,
             terrain,
             assets,
-            output,
+            output: output_dir,
+            force,
         } => {
-            cmd_extract(service, terrain, assets, output).await?;
+            cmd_extract(&client?, service, terrain, assets, output_dir, force, output).await?;
+        }
+        Commands::Serve { port, background, persist_state, state_dir, timeouts } => {
+            cmd_serve(port, background, resolve_state_dir(persist_state, state_dir), timeouts).await?;
         }
-        Commands::Serve { port, background } => {
-            cmd_serve(port, background).await?;
+        Commands::Daemon { action } => {
+            cmd_daemon(action, output).await?;
         }
         Commands::Stop { port } => {
             cmd_stop(&port).await?;
         }
         Commands::Status => {
-            cmd_status().await?;
+            cmd_status(&client?, output).await?;
         }
-        Commands::Diff => {
-            cmd_diff().await?;
+        Commands::Stats { port, raw } => {
+            let client = match port {
+                Some(port) => RbxSyncClient::new(format!("http://localhost:{}", port)),
+                None => client?,
+            };
+            cmd_stats(&client, raw, output).await?;
         }
-        Commands::Sync { path, no_delete } => {
-            cmd_sync(path, !no_delete).await?;
+        Commands::Diff { a: None, .. } => {
+            cmd_diff(&client?, output).await?;
+        }
+        Commands::Diff { a: Some(a), b } => {
+            cmd_diff_offline(&a, b.as_deref().unwrap_or("."), output)?;
+        }
+        Commands::Sync { path, no_delete, force } => {
+            cmd_sync(&client?, path, !no_delete, force, output).await?;
         }
         Commands::BuildPlugin {
             source,
@@ -539,26 +837,32 @@ async fn main() -> Result<()> {
             cmd_build_plugin(source, output, name, install, !no_obfuscate, obfuscate_config)?;
         }
         Commands::Plugin { action } => {
-            cmd_plugin(action).await?;
+            cmd_plugin(action, output).await?;
         }
         Commands::Sourcemap {
             path,
-            output,
+            output: output_file,
             include_non_scripts,
         } => {
-            cmd_sourcemap(path, output, include_non_scripts)?;
+            cmd_sourcemap(path, output_file, include_non_scripts, output)?;
         }
         Commands::Build {
             path,
-            output,
+            output: output_file,
             format,
             watch,
             plugin,
         } => {
-            cmd_build(path, output, format, watch, plugin).await?;
+            cmd_build(path, output_file, format, watch, plugin, output).await?;
         }
         Commands::FmtProject { path, check } => {
-            cmd_fmt_project(path, check)?;
+            cmd_fmt_project(path, check, output)?;
+        }
+        Commands::Verify { path, staged } => {
+            cmd_verify(path, staged, output)?;
+        }
+        Commands::Ci { path, junit } => {
+            ci::cmd_ci(path, junit, output).await?;
         }
         Commands::Doc => {
             cmd_doc()?;
@@ -567,7 +871,7 @@ async fn main() -> Result<()> {
             cmd_update(from_source, vscode, yes).await?;
         }
         Commands::Version => {
-            cmd_version().await?;
+            cmd_version(output).await?;
         }
         Commands::Flux { local, set_api_key } => {
             // Flux agent is not yet implemented in the CLI
@@ -586,7 +890,16 @@ async fn main() -> Result<()> {
             cmd_migrate(from, path, force)?;
         }
         Commands::Harness { action } => {
-            cmd_harness(action).await?;
+            cmd_harness(&client?, action, output).await?;
+        }
+        Commands::Git { action } => {
+            cmd_git(client, action, output).await?;
+        }
+        Commands::Hooks { action } => {
+            cmd_hooks(action, output)?;
+        }
+        Commands::MergeDriver { base, ours, theirs } => {
+            cmd_merge_driver(&base, &ours, &theirs)?;
         }
     }
 
@@ -640,7 +953,7 @@ async fn cmd_init(name: Option<String>, path: Option<PathBuf>, no_sourcemap: boo
 
     // Create or update .gitignore (append entries instead of overwriting)
     let gitignore_path = project_dir.join(".gitignore");
-    let rbxsync_entries = [".rbxsync/", "*.rbxl", "*.rbxlx", ".DS_Store", "Thumbs.db"];
+    let rbxsync_entries = [".rbxsync/", ".rbxsync-backup/", ".rbxsync-staging/", "*.rbxl", "*.rbxlx", ".DS_Store", "Thumbs.db"];
 
     let existing_content = if gitignore_path.exists() {
         std::fs::read_to_string(&gitignore_path).unwrap_or_default()
@@ -670,7 +983,7 @@ async fn cmd_init(name: Option<String>, path: Option<PathBuf>, no_sourcemap: boo
         std::fs::write(&gitignore_path, new_content).context("Failed to write .gitignore")?;
     } else if !gitignore_path.exists() {
         // Create new .gitignore if it doesn't exist
-        let gitignore_content = "# RbxSync\n.rbxsync/\n*.rbxl\n*.rbxlx\n\n# OS files\n.DS_Store\nThumbs.db\n";
+        let gitignore_content = "# RbxSync\n.rbxsync/\n.rbxsync-backup/\n.rbxsync-staging/\n*.rbxl\n*.rbxlx\n\n# OS files\n.DS_Store\nThumbs.db\n";
         std::fs::write(&gitignore_path, gitignore_content).context("Failed to write .gitignore")?;
     }
 
@@ -700,7 +1013,7 @@ async fn cmd_init(name: Option<String>, path: Option<PathBuf>, no_sourcemap: boo
 }
 
 /// Launch Roblox Studio
-async fn cmd_studio(place: Option<PathBuf>, serve: bool) -> Result<()> {
+async fn cmd_studio(client: &RbxSyncClient, place: Option<PathBuf>, serve: bool) -> Result<()> {
     // Find Roblox Studio installation
     let studio_path = find_studio_path()?;
 
@@ -708,19 +1021,11 @@ async fn cmd_studio(place: Option<PathBuf>, serve: bool) -> Result<()> {
 
     // Optionally start the sync server
     if serve {
-        let client = reqwest::Client::new();
-        if client.get("http://localhost:44755/health").send().await.is_err() {
+        if !client.is_running().await {
             println!("Starting sync server in background...");
-            let config = ServerConfig::default();
-            tokio::spawn(async move {
-                if let Err(e) = run_server(config).await {
-                    tracing::error!("Server error: {}", e);
-                }
-            });
-            // Give server time to start
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+            start_local_server(client).await?;
         } else {
-            println!("Sync server already running.");
+            println!("Sync server already running at {}.", client.base_url());
         }
     }
 
@@ -776,94 +1081,51 @@ async fn cmd_studio(place: Option<PathBuf>, serve: bool) -> Result<()> {
 }
 
 /// Control playtest in Studio
-async fn cmd_debug(action: DebugAction) -> Result<()> {
-    let client = reqwest::Client::new();
-
-    // Check server is running
-    if client.get("http://localhost:44755/health").send().await.is_err() {
-        println!("RbxSync server is not running. Start it with: rbxsync serve");
-        return Ok(());
-    }
+async fn cmd_debug(client: &RbxSyncClient, action: DebugAction, output: Output) -> Result<()> {
+    client.ensure_running().await?;
 
     match action {
         DebugAction::Start { mode } => {
-            println!("Starting playtest (mode: {})...", mode);
-
-            let response = client
-                .post("http://localhost:44755/sync/command")
-                .json(&serde_json::json!({
-                    "command": "debug:start",
-                    "payload": {
-                        "mode": mode
-                    }
-                }))
-                .send()
+            if output.is_text() {
+                println!("Starting playtest (mode: {})...", mode);
+            }
+            client
+                .command("debug:start", serde_json::json!({ "mode": mode }))
                 .await
-                .context("Failed to send debug start command")?;
-
-            let result: serde_json::Value = response.json().await?;
-            if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
+                .context("Failed to start playtest")?;
+            output.json(&serde_json::json!({ "running": true, "mode": mode }))?;
+            if output.is_text() {
                 println!("Playtest started.");
-            } else {
-                let error = result
-                    .get("error")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown error");
-                println!("Failed to start playtest: {}", error);
             }
         }
         DebugAction::Stop => {
-            println!("Stopping playtest...");
-
-            let response = client
-                .post("http://localhost:44755/sync/command")
-                .json(&serde_json::json!({
-                    "command": "debug:stop",
-                    "payload": {}
-                }))
-                .send()
+            if output.is_text() {
+                println!("Stopping playtest...");
+            }
+            client
+                .command("debug:stop", serde_json::json!({}))
                 .await
-                .context("Failed to send debug stop command")?;
-
-            let result: serde_json::Value = response.json().await?;
-            if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
+                .context("Failed to stop playtest")?;
+            output.json(&serde_json::json!({ "running": false, "mode": null }))?;
+            if output.is_text() {
                 println!("Playtest stopped.");
-            } else {
-                let error = result
-                    .get("error")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown error");
-                println!("Failed to stop playtest: {}", error);
             }
         }
         DebugAction::Status => {
-            let response = client
-                .post("http://localhost:44755/sync/command")
-                .json(&serde_json::json!({
-                    "command": "debug:status",
-                    "payload": {}
-                }))
-                .send()
+            let data = client
+                .command("debug:status", serde_json::json!({}))
                 .await
-                .context("Failed to get debug status")?;
-
-            let result: serde_json::Value = response.json().await?;
-            if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
-                let data = result.get("data").cloned().unwrap_or_default();
-                let running = data.get("running").and_then(|v| v.as_bool()).unwrap_or(false);
-                let mode = data.get("mode").and_then(|v| v.as_str()).unwrap_or("unknown");
+                .context("Failed to get playtest status")?;
+            let running = data.get("running").and_then(|v| v.as_bool()).unwrap_or(false);
+            let mode = data.get("mode").and_then(|v| v.as_str());
 
+            output.json(&serde_json::json!({ "running": running, "mode": mode }))?;
+            if output.is_text() {
                 if running {
-                    println!("Playtest is running (mode: {})", mode);
+                    println!("Playtest is running (mode: {})", mode.unwrap_or("unknown"));
                 } else {
                     println!("No playtest running");
                 }
-            } else {
-                let error = result
-                    .get("error")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown error");
-                println!("Failed to get status: {}", error);
             }
         }
     }
@@ -931,74 +1193,54 @@ fn find_studio_path() -> Result<PathBuf> {
 
 /// Extract game from Studio
 async fn cmd_extract(
+    client: &RbxSyncClient,
     services: Option<Vec<String>>,
     terrain: bool,
     assets: bool,
     _output: Option<PathBuf>,
+    force: bool,
+    output: Output,
 ) -> Result<()> {
     tracing::info!("Starting extraction...");
 
     // Check if server is running
-    let client = reqwest::Client::new();
-    let health_check = client.get("http://localhost:44755/health").send().await;
-
-    if health_check.is_err() {
-        println!("RbxSync server is not running.");
-        println!("Starting server in background...");
-
-        // Start server in background
-        tokio::spawn(async {
-            if let Err(e) = run_server(ServerConfig::default()).await {
-                tracing::error!("Server error: {}", e);
-            }
-        });
-
-        // Wait for server to start
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    if !client.is_running().await {
+        if output.is_text() {
+            println!("RbxSync server is not running.");
+            println!("Starting server in background...");
+        }
+        start_local_server(client).await?;
     }
 
     // Send extraction request
-    let response = client
-        .post("http://localhost:44755/extract/start")
-        .json(&serde_json::json!({
-            "services": services,
-            "include_terrain": terrain,
-            "include_assets": assets,
-        }))
-        .send()
+    let result = client
+        .start_extraction(services.as_deref(), terrain, assets, force)
         .await
         .context("Failed to start extraction")?;
+    if output.is_text() {
+        println!("Extraction started: {}", serde_json::to_string_pretty(&result)?);
 
-    let result: serde_json::Value = response.json().await?;
-    println!("Extraction started: {}", serde_json::to_string_pretty(&result)?);
-
-    println!("\nWaiting for Studio plugin to send data...");
-    println!("Make sure the RbxSync plugin is enabled in Roblox Studio.");
+        println!("\nWaiting for Studio plugin to send data...");
+        println!("Make sure the RbxSync plugin is enabled in Roblox Studio.");
+    }
 
     // Poll for completion
     loop {
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
-        let status = client
-            .get("http://localhost:44755/extract/status")
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
-
-        if let Some(complete) = status.get("complete").and_then(|v| v.as_bool()) {
-            if complete {
-                let chunks = status.get("chunksReceived").and_then(|v| v.as_u64()).unwrap_or(0);
-                println!("\nExtraction complete! Received {} chunks.", chunks);
-                break;
+        let status = client.extraction_status().await?;
+        if status.complete {
+            output.json(&status)?;
+            if output.is_text() {
+                println!("\nExtraction complete! Received {} chunks.", status.chunks_received);
             }
+            break;
         }
 
-        if let Some(received) = status.get("chunksReceived").and_then(|v| v.as_u64()) {
-            if let Some(total) = status.get("totalChunks").and_then(|v| v.as_u64()) {
-                print!("\rReceived {}/{} chunks...", received, total);
-            } else {
-                print!("\rReceived {} chunks...", received);
+        if status.session_id.is_some() && output.is_text() {
+            match status.total_chunks {
+                Some(total) => print!("\rReceived {}/{} chunks...", status.chunks_received, total),
+                None => print!("\rReceived {} chunks...", status.chunks_received),
             }
         }
     }
@@ -1006,6 +1248,26 @@ async fn cmd_extract(
     Ok(())
 }
 
+/// Start a server in this process on the client's port, for commands that
+/// bring one up when none is running
+async fn start_local_server(client: &RbxSyncClient) -> Result<()> {
+    let Some(port) = client.local_port() else {
+        bail!("RbxSync server is not running at {}", client.base_url());
+    };
+    let config = ServerConfig {
+        port,
+        ..ServerConfig::default()
+    };
+    tokio::spawn(async move {
+        if let Err(e) = run_server(config).await {
+            tracing::error!("Server error: {}", e);
+        }
+    });
+    // Give server time to start
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    Ok(())
+}
+
 /// Detect project structure for zero-config mode
 fn detect_project_structure() -> Option<String> {
     let cwd = std::env::current_dir().ok()?;
@@ -1036,7 +1298,13 @@ fn detect_project_structure() -> Option<String> {
 }
 
 /// Start the sync server
-async fn cmd_serve(port: u16, background: bool) -> Result<()> {
+async fn cmd_serve(
+    port: u16,
+    background: bool,
+    state_dir: Option<PathBuf>,
+    timeouts: Vec<String>,
+) -> Result<()> {
+    let command_timeouts = parse_command_timeouts(&timeouts)?;
     let config_path = std::env::current_dir()?.join("rbxsync.json");
     let zero_config_mode = !config_path.exists();
 
@@ -1099,34 +1367,16 @@ async fn cmd_serve(port: u16, background: bool) -> Result<()> {
     }
 
     if background {
-        // Spawn server as a detached background process
-        let exe = std::env::current_exe()?;
-        let mut cmd = std::process::Command::new(&exe);
-        cmd.args(["serve", "--port", &port.to_string()]);
+        let pid = spawn_daemon(port, state_dir.as_deref(), &timeouts)?;
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::process::CommandExt;
-            cmd.process_group(0); // Create new process group
+        println!("RbxSync server started in background (PID: {})", pid);
+        println!("  Port: {}", port);
+        if let Some(paths) = DaemonPaths::for_port(port) {
+            println!("  Log: {}", paths.log_file.display());
         }
-
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            const DETACHED_PROCESS: u32 = 0x00000008;
-            const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
-            cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+        if let Some(ref dir) = state_dir {
+            println!("  State: {}", dir.display());
         }
-
-        let child = cmd
-            .stdin(std::process::Stdio::null())
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-            .context("Failed to spawn background server")?;
-
-        println!("RbxSync server started in background (PID: {})", child.id());
-        println!("  Port: {}", port);
         println!("  Stop with: rbxsync stop");
         return Ok(());
     }
@@ -1135,13 +1385,245 @@ async fn cmd_serve(port: u16, background: bool) -> Result<()> {
     println!("RbxSync server running on port {}", port);
     println!("Stop with: Ctrl+C or `rbxsync stop` from another terminal");
     println!("Run in background with: rbxsync serve --background");
+    if let Some(ref dir) = state_dir {
+        println!("Persisting server state to {}", dir.display());
+    }
     run_server(ServerConfig {
         port,
+        state_dir,
+        command_timeouts,
         ..Default::default()
     })
     .await
 }
 
+/// Parse `--timeout COMMAND=SECS` overrides on top of the default timeouts
+fn parse_command_timeouts(specs: &[String]) -> Result<CommandTimeouts> {
+    let mut timeouts = CommandTimeouts::default();
+    for spec in specs {
+        timeouts.apply_override(spec).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    Ok(timeouts)
+}
+
+/// Resolve `--persist-state`/`--state-dir` to a state directory
+fn resolve_state_dir(persist_state: bool, state_dir: Option<PathBuf>) -> Option<PathBuf> {
+    state_dir.or_else(|| {
+        if persist_state {
+            rbxsync_server::state_store::StateStore::default_dir()
+        } else {
+            None
+        }
+    })
+}
+
+/// Spawn `rbxsync daemon run` as a detached background process
+fn spawn_daemon(port: u16, state_dir: Option<&std::path::Path>, timeouts: &[String]) -> Result<u32> {
+    let exe = std::env::current_exe()?;
+    let mut cmd = std::process::Command::new(&exe);
+    cmd.args(["daemon", "run", "--port", &port.to_string()]);
+    if let Some(dir) = state_dir {
+        cmd.arg("--state-dir").arg(dir);
+    }
+    for spec in timeouts {
+        cmd.arg("--timeout").arg(spec);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0); // Create new process group
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    let child = cmd
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .context("Failed to spawn background server")?;
+
+    Ok(child.id())
+}
+
+/// Manage the sync server daemon
+async fn cmd_daemon(action: DaemonAction, output: Output) -> Result<()> {
+    match action {
+        DaemonAction::Start { port, persist_state, state_dir, timeouts } => {
+            // Validate here so bad specs fail fast instead of in the daemon log
+            parse_command_timeouts(&timeouts)?;
+            let paths = DaemonPaths::for_port(port).context("Could not determine home directory")?;
+            if let Some(info) = DaemonInfo::read(&paths.pid_file).filter(|info| info.is_alive()) {
+                println!("Daemon already running on port {} (PID {}).", port, info.pid);
+                println!("Check it with: rbxsync daemon status --port {}", port);
+                return Ok(());
+            }
+            if !is_port_available(port) {
+                bail!("Port {} is already in use. Try: rbxsync stop --port {}", port, port);
+            }
+
+            let state_dir = resolve_state_dir(persist_state, state_dir);
+            let pid = spawn_daemon(port, state_dir.as_deref(), &timeouts)?;
+
+            // Wait for the daemon to come up so startup errors aren't silent
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(1))
+                .build()?;
+            let health_url = format!("http://localhost:{}/health", port);
+            let mut healthy = false;
+            for _ in 0..50 {
+                if client.get(&health_url).send().await.is_ok() {
+                    healthy = true;
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            if !healthy {
+                bail!("Daemon did not start. See log: {}", paths.log_file.display());
+            }
+
+            println!("RbxSync daemon started (PID: {})", pid);
+            println!("  Port: {}", port);
+            println!("  Log: {}", paths.log_file.display());
+            #[cfg(unix)]
+            println!("  Socket: {}", paths.socket.display());
+            if let Some(ref dir) = state_dir {
+                println!("  State: {}", dir.display());
+            }
+            println!("  Stop with: rbxsync daemon stop");
+        }
+        DaemonAction::Run { port, state_dir, timeouts } => {
+            let paths = DaemonPaths::for_port(port).context("Could not determine home directory")?;
+            run_server(ServerConfig {
+                port,
+                state_dir,
+                daemon: Some(paths),
+                command_timeouts: parse_command_timeouts(&timeouts)?,
+                ..Default::default()
+            })
+            .await?;
+        }
+        DaemonAction::Stop { port } => {
+            if daemon_request(port, ControlCommand::Stop).await.is_err() {
+                println!("No daemon running on port {}.", port);
+                return Ok(());
+            }
+            if wait_for_port_release(port, 5000).await {
+                println!("Daemon stopped.");
+            } else {
+                println!("Daemon did not stop in time, forcing shutdown...");
+                stop_server_on_port(port).await?;
+            }
+        }
+        DaemonAction::Status { port } => {
+            let Ok(response) = daemon_request(port, ControlCommand::Status).await else {
+                output.json(&serde_json::json!({ "running": false, "port": port, "status": null }))?;
+                if output.is_text() {
+                    println!("No daemon running on port {}.", port);
+                    println!("Start it with: rbxsync daemon start");
+                }
+                bail!(Exit(EXIT_FAILURE));
+            };
+            let status: DaemonStatus =
+                serde_json::from_value(response).context("Unexpected status response")?;
+            output.json(&serde_json::json!({ "running": true, "port": port, "status": status }))?;
+            if output.is_text() {
+                print_daemon_status(port, &status);
+            }
+        }
+        DaemonAction::Reload { port } => {
+            let Ok(response) = daemon_request(port, ControlCommand::Reload).await else {
+                println!("No daemon running on port {}.", port);
+                return Ok(());
+            };
+            let reloaded = response["reloaded"].as_array().cloned().unwrap_or_default();
+            println!("Reloaded {} project(s).", reloaded.len());
+            for dir in reloaded {
+                println!("  {}", dir.as_str().unwrap_or_default());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Send a control command to the daemon, via its socket when available or HTTP otherwise
+async fn daemon_request(port: u16, command: ControlCommand) -> Result<serde_json::Value> {
+    #[cfg(unix)]
+    {
+        if let Some(paths) = DaemonPaths::for_port(port).filter(|p| p.socket.exists()) {
+            match rbxsync_server::daemon::send_control_command(&paths.socket, command.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => tracing::debug!("Control socket unavailable, falling back to HTTP: {}", e),
+            }
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+    let base = format!("http://localhost:{}", port);
+    let request = match command {
+        ControlCommand::Status => client.get(format!("{}/daemon/status", base)),
+        ControlCommand::Reload => client.post(format!("{}/daemon/reload", base)),
+        ControlCommand::Stop => client.post(format!("{}/shutdown", base)),
+    };
+    Ok(request.send().await?.error_for_status()?.json().await?)
+}
+
+/// Print `rbxsync daemon status` output
+fn print_daemon_status(port: u16, status: &DaemonStatus) {
+    let uptime = status.uptime_secs;
+    let kind = if status.daemon { "daemon" } else { "server (not a daemon)" };
+    println!(
+        "RbxSync {} v{} on port {} (PID {}), up {}h {}m {}s",
+        kind,
+        status.version,
+        port,
+        status.pid,
+        uptime / 3600,
+        (uptime % 3600) / 60,
+        uptime % 60
+    );
+    if let Some(ref log) = status.log_file {
+        println!("  Log: {}", log.display());
+    }
+    if let Some(ref socket) = status.socket {
+        println!("  Socket: {}", socket.display());
+    }
+    if let Some(ref state_file) = status.state_file {
+        println!("  State: {}", state_file.display());
+    }
+
+    println!();
+    if status.projects.is_empty() {
+        println!("No projects attached.");
+    }
+    for project in &status.projects {
+        println!("{}", project.project_dir);
+        println!(
+            "  VS Code: {}  Watcher: {}",
+            if project.workspace { "connected" } else { "-" },
+            if project.watching { "running" } else { "stopped" }
+        );
+        for place in &project.places {
+            println!("  Studio: {} ({})", place.place_name, place.place_id);
+        }
+    }
+    if !status.unattached_places.is_empty() {
+        println!("Places without a project:");
+        for place in &status.unattached_places {
+            println!("  Studio: {} ({})", place.place_name, place.place_id);
+        }
+    }
+}
+
 /// Stop the running sync server
 async fn cmd_stop(port: &str) -> Result<()> {
     // Handle "all" to stop all rbxsync servers
@@ -1304,79 +1786,251 @@ async fn stop_server_on_port(port: u16) -> Result<()> {
 }
 
 /// Show status
-async fn cmd_status() -> Result<()> {
-    let client = reqwest::Client::new();
-
-    match client.get("http://localhost:44755/health").send().await {
-        Ok(response) => {
-            let health: serde_json::Value = response.json().await?;
-            println!("Server status: {}", serde_json::to_string_pretty(&health)?);
-
-            // Check extraction status
-            let status = client
-                .get("http://localhost:44755/extract/status")
-                .send()
-                .await?
-                .json::<serde_json::Value>()
-                .await?;
+async fn cmd_status(client: &RbxSyncClient, output: Output) -> Result<()> {
+    let health = client.health().await.ok();
+    let extraction = match health {
+        Some(_) => Some(client.extraction_status().await?),
+        None => None,
+    };
 
-            println!("Extraction status: {}", serde_json::to_string_pretty(&status)?);
-        }
-        Err(_) => {
-            println!("Server is not running.");
-            println!("Start it with: rbxsync serve");
+    output.json(&StatusReport {
+        server: client.base_url().to_string(),
+        running: health.is_some(),
+        version: health.as_ref().and_then(|h| h.version.clone()),
+        extraction: extraction.clone(),
+    })?;
+    if output.is_text() {
+        match (&health, &extraction) {
+            (Some(health), Some(extraction)) => {
+                println!("Server: {}", client.base_url());
+                println!("Server status: {}", serde_json::to_string_pretty(health)?);
+                println!("Extraction status: {}", serde_json::to_string_pretty(extraction)?);
+            }
+            _ => {
+                println!("Server is not running at {}.", client.base_url());
+                println!("Start it with: rbxsync serve");
+            }
         }
     }
 
+    if health.is_none() {
+        bail!(Exit(EXIT_FAILURE));
+    }
     Ok(())
 }
 
-/// Show diff between local files and Studio
-async fn cmd_diff() -> Result<()> {
-    let project_dir = std::env::current_dir().unwrap();
-    let project_dir_str = project_dir.to_string_lossy().to_string();
+/// `rbxsync status --format json`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusReport {
+    server: String,
+    running: bool,
+    version: Option<String>,
+    extraction: Option<client::ExtractStatus>,
+}
 
-    let client = reqwest::Client::new();
+/// Show server metrics
+async fn cmd_stats(client: &RbxSyncClient, raw: bool, output: Output) -> Result<()> {
+    if raw && output.is_json() {
+        bail!("--raw prints Prometheus text and can't be combined with --format json");
+    }
+    client.ensure_running().await?;
+    let text = client.metrics().await?;
 
-    // Check server is running
-    if client.get("http://localhost:44755/health").send().await.is_err() {
-        println!("RbxSync server is not running. Start it with: rbxsync serve");
+    if raw {
+        if output.is_text() {
+            print!("{}", text);
+        }
         return Ok(());
     }
 
-    println!("Comparing files with Studio...");
+    // Parse `name{labels} value` samples
+    let samples: Vec<(String, String, f64)> = text
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.is_empty())
+        .filter_map(|line| {
+            let (key, value) = line.rsplit_once(' ')?;
+            let value: f64 = value.parse().ok()?;
+            let (name, labels) = match key.split_once('{') {
+                Some((name, labels)) => (name, labels.trim_end_matches('}')),
+                None => (key, ""),
+            };
+            Some((name.to_string(), labels.to_string(), value))
+        })
+        .collect();
+    let total = |name: &str| -> f64 {
+        samples.iter().filter(|(n, _, _)| n == name).fold(0.0, |sum, (_, _, v)| sum + v)
+    };
+    let labeled = |name: &str| -> Vec<(String, f64)> {
+        samples
+            .iter()
+            .filter(|(n, _, _)| n == name)
+            .map(|(_, labels, v)| {
+                let label = labels
+                    .split_once('=')
+                    .map(|(_, value)| value.trim_matches('"').to_string())
+                    .unwrap_or_default();
+                (label, *v)
+            })
+            .collect()
+    };
+    let average = |name: &str| -> f64 {
+        let count = total(&format!("{}_count", name));
+        if count > 0.0 { total(&format!("{}_sum", name)) / count } else { 0.0 }
+    };
 
-    // Call diff endpoint
-    let response = client
-        .post("http://localhost:44755/diff")
-        .json(&serde_json::json!({
-            "project_dir": project_dir_str
-        }))
-        .send()
-        .await
-        .context("Failed to get diff")?;
+    if output.is_json() {
+        let outcomes = labeled("rbxsync_plugin_responses_total");
+        let outcome = |name: &str| outcomes.iter().find(|(o, _)| o == name).map(|(_, v)| *v as u64).unwrap_or(0);
+        let count = |name: &str| total(name) as u64;
+        let counts = |name: &str| {
+            labeled(name)
+                .into_iter()
+                .map(|(label, v)| (label, v as u64))
+                .collect::<std::collections::BTreeMap<_, _>>()
+        };
+        return output.json(&serde_json::json!({
+            "server": client.base_url(),
+            "uptimeSeconds": count("rbxsync_uptime_seconds"),
+            "connectedPlaces": count("rbxsync_connected_places"),
+            "connectedWorkspaces": count("rbxsync_connected_workspaces"),
+            "playtestActive": total("rbxsync_playtest_active") > 0.0,
+            "queues": counts("rbxsync_queue_depth"),
+            "pendingResponses": count("rbxsync_pending_responses"),
+            "pluginRequests": {
+                "total": count("rbxsync_plugin_requests_total"),
+                "byCommand": counts("rbxsync_plugin_requests_total"),
+                "succeeded": outcome("success"),
+                "failed": outcome("failure"),
+                "averageSeconds": average("rbxsync_plugin_response_seconds"),
+                "timeouts": count("rbxsync_plugin_response_timeouts_total"),
+                "lateResponses": count("rbxsync_plugin_responses_dropped_total"),
+            },
+            "polls": {
+                "total": count("rbxsync_polls_total"),
+                "averageWaitSeconds": average("rbxsync_poll_wait_seconds"),
+            },
+            "liveSync": {
+                "batches": count("rbxsync_live_sync_batches_total"),
+                "averageOperations": average("rbxsync_live_sync_batch_operations"),
+            },
+            "extraction": {
+                "runs": count("rbxsync_extraction_seconds_count"),
+                "instances": count("rbxsync_extraction_instances_total"),
+                "bytes": count("rbxsync_extraction_bytes_total"),
+                "averageSeconds": average("rbxsync_extraction_seconds"),
+            },
+            "console": {
+                "messages": count("rbxsync_console_messages_total"),
+                "dropped": count("rbxsync_console_dropped_total"),
+                "eventsLagged": count("rbxsync_events_lagged_total"),
+            },
+        }));
+    }
+    if output.quiet {
+        return Ok(());
+    }
 
-    let diff: serde_json::Value = response.json().await?;
+    let uptime = total("rbxsync_uptime_seconds") as u64;
+    println!("RbxSync server stats ({})", client.base_url());
+    println!("  Uptime: {}h {}m {}s", uptime / 3600, (uptime % 3600) / 60, uptime % 60);
+    println!(
+        "  Studio places: {}  VS Code workspaces: {}  Playtest: {}",
+        total("rbxsync_connected_places"),
+        total("rbxsync_connected_workspaces"),
+        if total("rbxsync_playtest_active") > 0.0 { "active" } else { "idle" }
+    );
 
-    if diff.get("success").and_then(|v| v.as_bool()) != Some(true) {
-        let error = diff.get("error").and_then(|v| v.as_str()).unwrap_or("Unknown error");
-        println!("Error: {}", error);
-        return Ok(());
+    println!();
+    println!("Queues");
+    for (queue, depth) in labeled("rbxsync_queue_depth") {
+        println!("  {}: {}", queue, depth);
+    }
+    println!("  Waiting for response: {}", total("rbxsync_pending_responses"));
+
+    println!();
+    println!("Plugin requests");
+    let mut commands = labeled("rbxsync_plugin_requests_total");
+    commands.sort_by(|a, b| b.1.total_cmp(&a.1));
+    println!("  Dispatched: {}", total("rbxsync_plugin_requests_total"));
+    for (command, count) in commands.iter().take(10) {
+        println!("    {:<28} {}", command, count);
+    }
+    let outcomes = labeled("rbxsync_plugin_responses_total");
+    let outcome = |name: &str| outcomes.iter().find(|(o, _)| o == name).map(|(_, v)| *v).unwrap_or(0.0);
+    println!(
+        "  Responses: {} ok, {} failed (avg {:.3}s)",
+        outcome("success"),
+        outcome("failure"),
+        average("rbxsync_plugin_response_seconds")
+    );
+    println!(
+        "  Timeouts: {}  Late responses: {}",
+        total("rbxsync_plugin_response_timeouts_total"),
+        total("rbxsync_plugin_responses_dropped_total")
+    );
+    println!(
+        "  Polls: {} (avg wait {:.2}s)",
+        total("rbxsync_polls_total"),
+        average("rbxsync_poll_wait_seconds")
+    );
+
+    println!();
+    println!("Live sync");
+    println!(
+        "  Batches: {} (avg {:.1} operations)",
+        total("rbxsync_live_sync_batches_total"),
+        average("rbxsync_live_sync_batch_operations")
+    );
+
+    println!();
+    println!("Extraction");
+    println!(
+        "  Runs: {}  Instances: {}  Received: {:.1} MB  Avg duration: {:.1}s",
+        total("rbxsync_extraction_seconds_count"),
+        total("rbxsync_extraction_instances_total"),
+        total("rbxsync_extraction_bytes_total") / (1024.0 * 1024.0),
+        average("rbxsync_extraction_seconds")
+    );
+
+    println!();
+    println!("Console");
+    println!(
+        "  Messages: {}  Dropped (slow subscribers): {}  Events lagged: {}",
+        total("rbxsync_console_messages_total"),
+        total("rbxsync_console_dropped_total"),
+        total("rbxsync_events_lagged_total")
+    );
+
+    Ok(())
+}
+
+/// Show diff between local files and Studio
+async fn cmd_diff(client: &RbxSyncClient, output: Output) -> Result<()> {
+    let project_dir = std::env::current_dir().unwrap();
+    let project_dir_str = project_dir.to_string_lossy().to_string();
+
+    client.ensure_running().await?;
+
+    if output.is_text() {
+        println!("Comparing files with Studio...");
     }
 
-    let added = diff.get("added").and_then(|v| v.as_array()).cloned().unwrap_or_default();
-    let removed = diff.get("removed").and_then(|v| v.as_array()).cloned().unwrap_or_default();
-    let common = diff.get("common").and_then(|v| v.as_u64()).unwrap_or(0);
-    let file_count = diff.get("file_count").and_then(|v| v.as_u64()).unwrap_or(0);
-    let studio_count = diff.get("studio_count").and_then(|v| v.as_u64()).unwrap_or(0);
+    let diff = client.diff(&project_dir_str).await.context("Failed to get diff")?;
+    output.json(&StudioDiffReport {
+        in_sync: diff.in_sync(),
+        diff: &diff,
+    })?;
+    if !output.is_text() {
+        return diff_exit(diff.in_sync());
+    }
+    let (added, removed, modified, ambiguous) = (&diff.added, &diff.removed, &diff.modified, &diff.ambiguous);
 
     // Print added (in files, not in Studio)
     if !added.is_empty() {
         println!("\n\x1b[32mFiles → Studio (would be created): {}\x1b[0m", added.len());
         for entry in added.iter().take(20) {
-            let path = entry.get("path").and_then(|v| v.as_str()).unwrap_or("");
-            let class = entry.get("className").and_then(|v| v.as_str()).unwrap_or("");
-            println!("  + {} ({})", path, class);
+            println!("  + {} ({})", entry.path, entry.class_name);
         }
         if added.len() > 20 {
             println!("  ... and {} more", added.len() - 20);
@@ -1387,61 +2041,273 @@ async fn cmd_diff() -> Result<()> {
     if !removed.is_empty() {
         println!("\n\x1b[31mStudio only (would be deleted with --delete): {}\x1b[0m", removed.len());
         for entry in removed.iter().take(20) {
-            let path = entry.get("path").and_then(|v| v.as_str()).unwrap_or("");
-            let class = entry.get("className").and_then(|v| v.as_str()).unwrap_or("");
-            println!("  - {} ({})", path, class);
+            println!("  - {} ({})", entry.path, entry.class_name);
         }
         if removed.len() > 20 {
             println!("  ... and {} more", removed.len() - 20);
         }
     }
 
-    // Summary
+    // Print modified (in both, but different)
+    if !modified.is_empty() {
+        println!("\n\x1b[33mModified (files differ from Studio): {}\x1b[0m", modified.len());
+        for entry in modified.iter().take(20) {
+            print_instance_diff(entry);
+        }
+        if modified.len() > 20 {
+            println!("  ... and {} more", modified.len() - 20);
+        }
+    }
+
+    if !ambiguous.is_empty() {
+        println!("\n\x1b[2mNot compared (several instances share the path): {}\x1b[0m", ambiguous.len());
+        for path in ambiguous.iter().take(20) {
+            println!("  ? {}", path);
+        }
+    }
+
+    // Summary
+    println!("\n\x1b[1mSummary:\x1b[0m");
+    println!("  Files: {} instances", diff.file_count);
+    println!("  Studio: {} instances", diff.studio_count);
+    println!("  Unchanged: {} (in sync)", diff.unchanged);
+    println!("  Modified: {}", modified.len());
+    println!("  Added: {} (files → studio)", added.len());
+    println!("  Removed: {} (studio only)", removed.len());
+
+    if diff.in_sync() {
+        println!("\n\x1b[32m✓ Files and Studio are in sync!\x1b[0m");
+    }
+
+    diff_exit(diff.in_sync())
+}
+
+/// `rbxsync diff --format json`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StudioDiffReport<'a> {
+    in_sync: bool,
+    #[serde(flatten)]
+    diff: &'a client::StudioDiff,
+}
+
+/// `rbxsync diff <a> [b] --format json`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeDiffReport<'a> {
+    old: DiffSide<'a>,
+    new: DiffSide<'a>,
+    in_sync: bool,
+    #[serde(flatten)]
+    diff: &'a rbxsync_core::TreeDiff,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffSide<'a> {
+    source: &'a str,
+    instance_count: usize,
+}
+
+/// Diffs exit with 1 when the sides differ, like `diff(1)`
+fn diff_exit(in_sync: bool) -> Result<()> {
+    if !in_sync {
+        bail!(Exit(EXIT_FAILURE));
+    }
+    Ok(())
+}
+
+/// A git revision exported to a temporary directory, removed on drop
+struct ExportedRevision(PathBuf);
+
+impl Drop for ExportedRevision {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Load one side of an offline diff: a place or model file, a project
+/// directory, or a git revision of the current project. Returns the
+/// instances and whether they came from a place or model file.
+fn load_diff_side(spec: &str, exports: &mut Vec<ExportedRevision>) -> Result<(Vec<serde_json::Value>, bool)> {
+    let path = PathBuf::from(spec);
+    if rbxsync_core::is_place_file(&path) {
+        let instances = rbxsync_core::read_place_instances(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        return Ok((instances, true));
+    }
+
+    let project_dir = if path.is_dir() {
+        path
+    } else {
+        let export = ExportedRevision(
+            std::env::temp_dir().join(format!("rbxsync-diff-{}-{}", std::process::id(), exports.len())),
+        );
+        let current_dir = std::env::current_dir()?;
+        rbxsync_server::git::export_revision(&current_dir, spec, &export.0)
+            .map_err(|e| anyhow::anyhow!("'{}' is not a directory, place file or git revision: {}", spec, e))?;
+        let dir = export.0.clone();
+        exports.push(export);
+        dir
+    };
+    if !project_dir.join("src").is_dir() {
+        bail!("No src directory in {}", spec);
+    }
+    Ok((rbxsync_server::read_tree_instances(&project_dir.to_string_lossy()), false))
+}
+
+/// Diff two project trees, git revisions or place files without a server
+fn cmd_diff_offline(a: &str, b: &str, output: Output) -> Result<()> {
+    let mut exports = Vec::new();
+    let (old, old_is_place) = load_diff_side(a, &mut exports)?;
+    let (new, new_is_place) = load_diff_side(b, &mut exports)?;
+
+    // Place files carry every property while project files only store
+    // non-default ones, so compare just what both sides have
+    let scope = if old_is_place || new_is_place {
+        rbxsync_core::PropertyScope::Shared
+    } else {
+        rbxsync_core::PropertyScope::All
+    };
+    let current_dir = std::env::current_dir()?;
+    let format = SourceFormat::from_project_config(
+        rbxsync_server::load_project_config(&current_dir.to_string_lossy()).as_ref(),
+    );
+    let diff = rbxsync_core::diff_trees(&old, &new, &format, scope);
+
+    output.json(&TreeDiffReport {
+        old: DiffSide { source: a, instance_count: old.len() },
+        new: DiffSide { source: b, instance_count: new.len() },
+        in_sync: diff.is_empty(),
+        diff: &diff,
+    })?;
+    if !output.is_text() {
+        return diff_exit(diff.is_empty());
+    }
+
+    println!("Comparing {} → {}", a, b);
+
+    if !diff.added.is_empty() {
+        println!("\n\x1b[32mAdded: {}\x1b[0m", diff.added.len());
+        for entry in &diff.added {
+            println!("  + {} ({})", entry.path, entry.class_name);
+        }
+    }
+
+    if !diff.removed.is_empty() {
+        println!("\n\x1b[31mRemoved: {}\x1b[0m", diff.removed.len());
+        for entry in &diff.removed {
+            println!("  - {} ({})", entry.path, entry.class_name);
+        }
+    }
+
+    if !diff.moved.is_empty() {
+        println!("\n\x1b[36mMoved: {}\x1b[0m", diff.moved.len());
+        for entry in &diff.moved {
+            println!("  > {} → {} ({})", entry.from, entry.to, entry.class_name);
+            if let Some(changes) = &entry.changes {
+                if let Ok(changes) = serde_json::to_value(changes) {
+                    print_instance_diff(&changes);
+                }
+            }
+        }
+    }
+
+    if !diff.modified.is_empty() {
+        println!("\n\x1b[33mModified: {}\x1b[0m", diff.modified.len());
+        for entry in &diff.modified {
+            if let Ok(entry) = serde_json::to_value(entry) {
+                print_instance_diff(&entry);
+            }
+        }
+    }
+
+    if !diff.ambiguous.is_empty() {
+        println!("\n\x1b[2mNot compared (several instances share the path): {}\x1b[0m", diff.ambiguous.len());
+        for path in &diff.ambiguous {
+            println!("  ? {}", path);
+        }
+    }
+
     println!("\n\x1b[1mSummary:\x1b[0m");
-    println!("  Files: {} instances", file_count);
-    println!("  Studio: {} instances", studio_count);
-    println!("  Common: {} (in sync)", common);
-    println!("  Added: {} (files → studio)", added.len());
-    println!("  Removed: {} (studio only)", removed.len());
-
-    if added.is_empty() && removed.is_empty() {
-        println!("\n\x1b[32m✓ Files and Studio are in sync!\x1b[0m");
+    println!("  {}: {} instances", a, old.len());
+    println!("  {}: {} instances", b, new.len());
+    println!("  Unchanged: {}", diff.unchanged);
+    println!("  Modified: {}", diff.modified.len());
+    println!("  Moved: {}", diff.moved.len());
+    println!("  Added: {}", diff.added.len());
+    println!("  Removed: {}", diff.removed.len());
+
+    if diff.is_empty() {
+        println!("\n\x1b[32m✓ No differences\x1b[0m");
     }
 
-    Ok(())
+    diff_exit(diff.is_empty())
+}
+
+/// Print one modified instance from a `/diff` response: class, property,
+/// attribute and tag changes, then the script's source diff
+fn print_instance_diff(entry: &serde_json::Value) {
+    let path = entry.get("path").and_then(|v| v.as_str()).unwrap_or("");
+    let class = entry.get("className").and_then(|v| v.as_str()).unwrap_or("");
+    println!("  ~ {} ({})", path, class);
+
+    if let Some(old_class) = entry.get("oldClassName").and_then(|v| v.as_str()) {
+        println!("      class: {} → {}", old_class, class);
+    }
+    let show = |value: Option<&serde_json::Value>| match value {
+        // Property values are {type, value}; attributes are plain values
+        Some(v) => v.get("value").unwrap_or(v).to_string(),
+        None => "(none)".to_string(),
+    };
+    for (key, label) in [("properties", ""), ("attributes", "@")] {
+        for change in entry.get(key).and_then(|v| v.as_array()).into_iter().flatten() {
+            println!(
+                "      {}{}: {} → {}",
+                label,
+                change.get("name").and_then(|v| v.as_str()).unwrap_or(""),
+                show(change.get("old")),
+                show(change.get("new"))
+            );
+        }
+    }
+    for (key, sign) in [("tagsAdded", '+'), ("tagsRemoved", '-')] {
+        for tag in entry.get(key).and_then(|v| v.as_array()).into_iter().flatten() {
+            println!("      tag {}{}", sign, tag.as_str().unwrap_or(""));
+        }
+    }
+    if let Some(source_diff) = entry.get("sourceDiff").and_then(|v| v.as_str()) {
+        for line in source_diff.lines() {
+            let color = match line.chars().next() {
+                _ if line.starts_with("+++") || line.starts_with("---") => "\x1b[1m",
+                Some('+') => "\x1b[32m",
+                Some('-') => "\x1b[31m",
+                Some('@') => "\x1b[36m",
+                _ => "",
+            };
+            println!("      {}{}\x1b[0m", color, line);
+        }
+    }
 }
 
 /// Sync local changes to Studio
-async fn cmd_sync(path: Option<PathBuf>, delete: bool) -> Result<()> {
+async fn cmd_sync(client: &RbxSyncClient, path: Option<PathBuf>, delete: bool, force: bool, output: Output) -> Result<()> {
     let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
     let project_dir_str = project_dir.to_string_lossy().to_string();
 
     tracing::info!("Syncing from {:?}...", project_dir);
 
-    let client = reqwest::Client::new();
-
-    // Check server is running
-    if client.get("http://localhost:44755/health").send().await.is_err() {
-        println!("RbxSync server is not running. Start it with: rbxsync serve");
-        return Ok(());
-    }
+    client.ensure_running().await?;
 
     // Read the local tree
-    println!("Reading local files...");
-    let tree_response = client
-        .post("http://localhost:44755/sync/read-tree")
-        .json(&serde_json::json!({
-            "project_dir": project_dir_str
-        }))
-        .send()
-        .await
-        .context("Failed to read local tree")?;
-
-    let tree: serde_json::Value = tree_response.json().await?;
-    let instances = tree.get("instances").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    if output.is_text() {
+        println!("Reading local files...");
+    }
+    let tree = client.read_tree(&project_dir_str).await.context("Failed to read local tree")?;
 
     // Build sync operations for updates
-    let mut operations: Vec<serde_json::Value> = instances
+    let mut operations: Vec<serde_json::Value> = tree
+        .instances
         .into_iter()
         .map(|inst| {
             serde_json::json!({
@@ -1454,78 +2320,91 @@ async fn cmd_sync(path: Option<PathBuf>, delete: bool) -> Result<()> {
 
     // If --delete flag is set, get diff and add delete operations
     if delete {
-        println!("Checking for orphaned instances in Studio...");
-        let diff_response = client
-            .post("http://localhost:44755/diff")
-            .json(&serde_json::json!({
-                "project_dir": project_dir_str
-            }))
-            .send()
-            .await
-            .context("Failed to get diff")?;
-
-        let diff: serde_json::Value = diff_response.json().await?;
-        let removed = diff.get("removed").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        if output.is_text() {
+            println!("Checking for orphaned instances in Studio...");
+        }
+        let diff = client.diff(&project_dir_str).await.context("Failed to get diff")?;
 
-        if !removed.is_empty() {
-            println!("Found {} orphaned instances to delete", removed.len());
-            for entry in removed {
-                let path = entry.get("path").and_then(|v| v.as_str()).unwrap_or("");
-                let class_name = entry.get("class_name").and_then(|v| v.as_str()).unwrap_or("Instance");
-                println!("  \x1b[31m- {}\x1b[0m ({})", path, class_name);
+        if !diff.removed.is_empty() {
+            if output.is_text() {
+                println!("Found {} orphaned instances to delete", diff.removed.len());
+            }
+            for entry in diff.removed {
+                if output.is_text() {
+                    println!("  \x1b[31m- {}\x1b[0m ({})", entry.path, entry.class_name);
+                }
                 operations.push(serde_json::json!({
                     "type": "delete",
-                    "path": path
+                    "path": entry.path
                 }));
             }
         }
     }
 
+    let mut report = SyncReport {
+        success: true,
+        updated: 0,
+        deleted: 0,
+        errors: Vec::new(),
+        terrain: None,
+    };
+
     if operations.is_empty() {
-        println!("No changes to sync.");
+        output.json(&report)?;
+        if output.is_text() {
+            println!("No changes to sync.");
+        }
         return Ok(());
     }
 
     let update_count = operations.iter().filter(|op| op.get("type").and_then(|v| v.as_str()) == Some("update")).count();
     let delete_count = operations.iter().filter(|op| op.get("type").and_then(|v| v.as_str()) == Some("delete")).count();
 
-    if delete_count > 0 {
-        println!("Syncing {} updates and {} deletes to Studio...", update_count, delete_count);
-    } else {
-        println!("Syncing {} instances to Studio...", update_count);
+    if output.is_text() {
+        if delete_count > 0 {
+            println!("Syncing {} updates and {} deletes to Studio...", update_count, delete_count);
+        } else {
+            println!("Syncing {} instances to Studio...", update_count);
+        }
     }
 
     // Send batch sync
-    let sync_response = client
-        .post("http://localhost:44755/sync/batch")
-        .json(&serde_json::json!({
-            "operations": operations
-        }))
-        .send()
+    let result = client
+        .sync_batch(&operations, &project_dir_str, force)
         .await
         .context("Failed to sync")?;
 
-    let result: serde_json::Value = sync_response.json().await?;
+    // Use our own counts since server response may not include all operations
+    report.success = result.success;
+    report.updated = update_count;
+    report.deleted = delete_count;
+    if !result.success {
+        report.errors.extend(result.error.clone());
+        let errors = result.data.get("errors").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        report
+            .errors
+            .extend(errors.iter().map(|e| e.as_str().map(String::from).unwrap_or_else(|| e.to_string())));
+    }
 
-    if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
-        // Use our own counts since server response may not include all operations
-        if delete_count > 0 {
+    if output.is_text() {
+        if !result.success {
+            println!("Sync completed with errors:");
+            for err in &report.errors {
+                println!("  - {}", err);
+            }
+        } else if delete_count > 0 {
             println!("\x1b[32m✓ Synced {} instances, deleted {} orphans.\x1b[0m", update_count, delete_count);
         } else {
             println!("\x1b[32m✓ Synced {} instances to Studio.\x1b[0m", update_count);
         }
-    } else {
-        let errors = result.get("errors").and_then(|v| v.as_array()).cloned().unwrap_or_default();
-        println!("Sync completed with errors:");
-        for err in errors {
-            println!("  - {}", err);
-        }
     }
 
     // Check for terrain data and sync if present
     let terrain_file = project_dir.join("src").join("Workspace").join("Terrain").join("terrain.rbxjson");
     if terrain_file.exists() {
-        println!("Syncing terrain...");
+        if output.is_text() {
+            println!("Syncing terrain...");
+        }
 
         // Read terrain data
         let terrain_json = std::fs::read_to_string(&terrain_file)
@@ -1534,39 +2413,58 @@ async fn cmd_sync(path: Option<PathBuf>, delete: bool) -> Result<()> {
             .context("Failed to parse terrain file")?;
 
         // Send terrain sync command
-        let terrain_response = client
-            .post("http://localhost:44755/sync/command")
-            .json(&serde_json::json!({
-                "command": "terrain:sync",
-                "payload": {
-                    "terrain": terrain_data,
-                    "clear": true
-                }
-            }))
-            .send()
-            .await
-            .context("Failed to sync terrain")?;
-
-        let terrain_result: serde_json::Value = terrain_response.json().await?;
-
-        if terrain_result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
-            let chunks = terrain_result.get("data")
-                .and_then(|d| d.get("chunksApplied"))
-                .and_then(|c| c.as_u64())
-                .unwrap_or(0);
-            println!("\x1b[32m✓ Synced {} terrain chunks.\x1b[0m", chunks);
-        } else {
-            let error = terrain_result.get("error")
-                .or_else(|| terrain_result.get("data").and_then(|d| d.get("error")))
-                .and_then(|e| e.as_str())
-                .unwrap_or("Unknown error");
-            println!("\x1b[33m⚠ Terrain sync failed: {}\x1b[0m", error);
+        let terrain_result = client
+            .command("terrain:sync", serde_json::json!({ "terrain": terrain_data, "clear": true }))
+            .await;
+
+        let terrain = match terrain_result {
+            Ok(data) => TerrainSyncReport {
+                success: true,
+                chunks: data.get("chunksApplied").and_then(|c| c.as_u64()).unwrap_or(0),
+                error: None,
+            },
+            Err(e) => TerrainSyncReport {
+                success: false,
+                chunks: 0,
+                error: Some(format!("{:#}", e)),
+            },
+        };
+        if output.is_text() {
+            match &terrain.error {
+                Some(error) => println!("\x1b[33m⚠ Terrain sync failed: {}\x1b[0m", error),
+                None => println!("\x1b[32m✓ Synced {} terrain chunks.\x1b[0m", terrain.chunks),
+            }
         }
+        report.success &= terrain.success;
+        report.terrain = Some(terrain);
     }
 
+    output.json(&report)?;
+    if !report.success {
+        bail!(Exit(EXIT_FAILURE));
+    }
     Ok(())
 }
 
+/// `rbxsync sync --format json`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncReport {
+    success: bool,
+    updated: usize,
+    deleted: usize,
+    errors: Vec<String>,
+    terrain: Option<TerrainSyncReport>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerrainSyncReport {
+    success: bool,
+    chunks: u64,
+    error: Option<String>,
+}
+
 /// Build the Studio plugin as .rbxm
 fn cmd_build_plugin(
     source: Option<PathBuf>,
@@ -1617,7 +2515,7 @@ fn cmd_build_plugin(
 }
 
 /// Manage the Studio plugin
-async fn cmd_plugin(action: PluginAction) -> Result<()> {
+async fn cmd_plugin(action: PluginAction, output: Output) -> Result<()> {
     let plugins_folder = get_studio_plugins_folder()
         .context("Could not determine Studio plugins folder")?;
 
@@ -1628,6 +2526,10 @@ async fn cmd_plugin(action: PluginAction) -> Result<()> {
             // Check for existing marketplace plugin
             if !force {
                 if let Some(existing) = find_existing_rbxsync_plugin() {
+                    output.json(&serde_json::json!({ "installed": null, "existing": existing }))?;
+                    if !output.is_text() {
+                        bail!(Exit(EXIT_FAILURE));
+                    }
                     println!("\x1b[33m⚠ Existing RbxSync plugin detected:\x1b[0m {}", existing.display());
                     println!();
                     println!("Marketplace plugin detected. Please uninstall from Roblox first,");
@@ -1639,7 +2541,7 @@ async fn cmd_plugin(action: PluginAction) -> Result<()> {
                     println!("  3. Uninstall RbxSync");
                     println!();
                     println!("Then run: rbxsync plugin install");
-                    return Ok(());
+                    bail!(Exit(EXIT_FAILURE));
                 }
             }
 
@@ -1649,13 +2551,15 @@ async fn cmd_plugin(action: PluginAction) -> Result<()> {
                 p
             } else if download {
                 // Force download from GitHub
-                download_plugin_from_github().await?
+                download_plugin_from_github(output).await?
             } else if PathBuf::from("build/RbxSync.rbxm").exists() {
                 // Use local build
                 PathBuf::from("build/RbxSync.rbxm")
             } else if PathBuf::from("plugin/src").exists() {
                 // Build from source
-                println!("Building plugin from source...");
+                if output.is_text() {
+                    println!("Building plugin from source...");
+                }
                 let output_path = PathBuf::from("build/RbxSync.rbxm");
                 let config = PluginBuildConfig {
                     source_dir: PathBuf::from("plugin/src"),
@@ -1668,62 +2572,79 @@ async fn cmd_plugin(action: PluginAction) -> Result<()> {
                 output_path
             } else {
                 // Download from GitHub
-                println!("Downloading plugin from GitHub releases...");
-                download_plugin_from_github().await?
+                if output.is_text() {
+                    println!("Downloading plugin from GitHub releases...");
+                }
+                download_plugin_from_github(output).await?
             };
 
-            println!("Installing plugin to Studio...");
+            if output.is_text() {
+                println!("Installing plugin to Studio...");
+            }
             let installed_path =
                 install_plugin(&plugin_path, &plugin_name).context("Failed to install plugin")?;
-            println!("Plugin installed to: {}", installed_path.display());
-            println!("\nRestart Roblox Studio to load the plugin.");
+            output.json(&serde_json::json!({ "installed": installed_path, "existing": null }))?;
+            if output.is_text() {
+                println!("Plugin installed to: {}", installed_path.display());
+                println!("\nRestart Roblox Studio to load the plugin.");
+            }
         }
         PluginAction::Uninstall { name } => {
             let plugin_name = name.unwrap_or_else(|| "RbxSync".to_string());
             let plugin_path = plugins_folder.join(format!("{}.rbxm", plugin_name));
 
-            if !plugin_path.exists() {
-                println!("Plugin '{}' is not installed.", plugin_name);
-                return Ok(());
+            let installed = plugin_path.exists();
+            if installed {
+                std::fs::remove_file(&plugin_path).context("Failed to remove plugin file")?;
+            }
+            output.json(&serde_json::json!({ "name": plugin_name, "path": plugin_path, "removed": installed }))?;
+            if output.is_text() {
+                if installed {
+                    println!("Plugin '{}' uninstalled from: {}", plugin_name, plugin_path.display());
+                    println!("\nRestart Roblox Studio to apply changes.");
+                } else {
+                    println!("Plugin '{}' is not installed.", plugin_name);
+                }
             }
-
-            std::fs::remove_file(&plugin_path).context("Failed to remove plugin file")?;
-            println!("Plugin '{}' uninstalled from: {}", plugin_name, plugin_path.display());
-            println!("\nRestart Roblox Studio to apply changes.");
         }
         PluginAction::List => {
+            let mut plugins = Vec::new();
+            if plugins_folder.exists() {
+                for entry in std::fs::read_dir(&plugins_folder).context("Failed to read plugins folder")?.flatten() {
+                    let path = entry.path();
+                    if !path.extension().is_some_and(|ext| ext == "rbxm" || ext == "rbxmx") {
+                        continue;
+                    }
+                    plugins.push(serde_json::json!({
+                        "name": entry.file_name().to_string_lossy(),
+                        "path": path,
+                        "size": entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    }));
+                }
+            }
+            output.json(&serde_json::json!({
+                "folder": plugins_folder,
+                "exists": plugins_folder.exists(),
+                "plugins": plugins,
+            }))?;
+            if !output.is_text() {
+                return Ok(());
+            }
+
             println!("Studio plugins folder: {}", plugins_folder.display());
             println!();
 
             if !plugins_folder.exists() {
                 println!("  (folder does not exist)");
-                return Ok(());
-            }
-
-            let entries: Vec<_> = std::fs::read_dir(&plugins_folder)
-                .context("Failed to read plugins folder")?
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.path()
-                        .extension()
-                        .map(|ext| ext == "rbxm" || ext == "rbxmx")
-                        .unwrap_or(false)
-                })
-                .collect();
-
-            if entries.is_empty() {
+            } else if plugins.is_empty() {
                 println!("  No plugins installed.");
             } else {
                 println!("Installed plugins:");
-                for entry in entries {
-                    let name = entry.file_name();
-                    let metadata = entry.metadata().ok();
-                    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-
+                for plugin in &plugins {
                     println!(
                         "  {} ({:.1} KB)",
-                        name.to_string_lossy(),
-                        size as f64 / 1024.0
+                        plugin["name"].as_str().unwrap_or_default(),
+                        plugin["size"].as_u64().unwrap_or(0) as f64 / 1024.0
                     );
                 }
             }
@@ -1736,18 +2657,21 @@ async fn cmd_plugin(action: PluginAction) -> Result<()> {
 /// Generate sourcemap for Luau LSP
 fn cmd_sourcemap(
     path: Option<PathBuf>,
-    output: Option<PathBuf>,
+    output_file: Option<PathBuf>,
     include_non_scripts: bool,
+    output: Output,
 ) -> Result<()> {
     let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
-    let output_path = output.unwrap_or_else(|| project_dir.join("sourcemap.json"));
+    let output_path = output_file.unwrap_or_else(|| project_dir.join("sourcemap.json"));
     let src_dir = project_dir.join("src");
 
     if !src_dir.exists() {
         anyhow::bail!("Source directory not found: {}", src_dir.display());
     }
 
-    println!("Generating sourcemap from {:?}...", src_dir);
+    if output.is_text() {
+        println!("Generating sourcemap from {:?}...", src_dir);
+    }
 
     // Build the sourcemap tree
     let root = build_sourcemap_node("game", "DataModel", &src_dir, include_non_scripts)?;
@@ -1756,6 +2680,10 @@ fn cmd_sourcemap(
     let json = serde_json::to_string_pretty(&root)?;
     std::fs::write(&output_path, json).context("Failed to write sourcemap")?;
 
+    output.json(&serde_json::json!({ "output": output_path }))?;
+    if !output.is_text() {
+        return Ok(());
+    }
     println!("Sourcemap written to: {}", output_path.display());
     println!("\nTo use with Luau LSP, add to .luaurc:");
     println!("{{");
@@ -1871,6 +2799,19 @@ fn build_sourcemap_node(
                         "className": class_name,
                         "filePaths": [entry_path.to_string_lossy()]
                     }));
+                } else if rbxsync_core::data_file_info(&entry_name).is_some() {
+                    // Data file: JSON/TOML/YAML modules are scripts, the rest only with non-scripts
+                    let Some(mut node) = std::fs::read_to_string(&entry_path)
+                        .ok()
+                        .and_then(|content| rbxsync_core::parse_data_file(&entry_name, &content).ok())
+                        .map(|instance| data_file_sourcemap_node(&instance))
+                    else {
+                        continue;
+                    };
+                    if include_non_scripts || node["className"] == "ModuleScript" {
+                        node["filePaths"] = serde_json::json!([entry_path.to_string_lossy()]);
+                        children.push(node);
+                    }
                 }
             }
         }
@@ -1884,6 +2825,25 @@ fn build_sourcemap_node(
     }))
 }
 
+/// Sourcemap node for a parsed data file, including .model.json children
+fn data_file_sourcemap_node(instance: &serde_json::Value) -> serde_json::Value {
+    let children: Vec<_> = instance
+        .get("children")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .map(data_file_sourcemap_node)
+        .collect();
+    let mut node = serde_json::json!({
+        "name": instance["name"],
+        "className": instance["className"],
+    });
+    if !children.is_empty() {
+        node["children"] = serde_json::Value::Array(children);
+    }
+    node
+}
+
 /// Parse script name and class from filename
 fn parse_script_name(filename: &str) -> (String, &'static str) {
     let name = filename
@@ -1902,11 +2862,15 @@ fn parse_script_name(filename: &str) -> (String, &'static str) {
 /// Build a .rbxl or .rbxm file from project files
 async fn cmd_build(
     path: Option<PathBuf>,
-    output: Option<PathBuf>,
+    output_file: Option<PathBuf>,
     format: String,
     watch: bool,
     plugin: Option<String>,
+    output: Output,
 ) -> Result<()> {
+    if watch && !output.is_text() {
+        bail!("--watch can't be combined with --format json or --quiet");
+    }
     let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
     let src_dir = project_dir.join("src");
 
@@ -1931,7 +2895,7 @@ async fn cmd_build(
             .context("Could not determine Studio plugins folder")?;
         std::fs::create_dir_all(&plugins_folder).ok();
         plugins_folder.join(plugin_name)
-    } else if let Some(out) = output {
+    } else if let Some(out) = output_file {
         out
     } else {
         std::fs::create_dir_all(project_dir.join("build")).ok();
@@ -1939,11 +2903,12 @@ async fn cmd_build(
     };
 
     // Initial build
-    do_build(&src_dir, &output_path, extension, is_xml)?;
+    do_build(&src_dir, &output_path, extension, is_xml, output)?;
 
     // If not watch mode, we're done
     if !watch {
-        return Ok(());
+        let size = std::fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0);
+        return output.json(&serde_json::json!({ "output": output_path, "size": size }));
     }
 
     // Watch mode
@@ -1975,7 +2940,7 @@ async fn cmd_build(
                 // Debounce: only rebuild if enough time has passed
                 if last_build.elapsed() >= debounce {
                     println!("\nChange detected, rebuilding...");
-                    match do_build(&src_dir, &output_path, extension, is_xml) {
+                    match do_build(&src_dir, &output_path, extension, is_xml, output) {
                         Ok(()) => last_build = std::time::Instant::now(),
                         Err(e) => println!("Build error: {}", e),
                     }
@@ -1995,10 +2960,12 @@ async fn cmd_build(
 }
 
 /// Perform the actual build operation
-fn do_build(src_dir: &PathBuf, output_path: &PathBuf, extension: &str, is_xml: bool) -> Result<()> {
+fn do_build(src_dir: &PathBuf, output_path: &PathBuf, extension: &str, is_xml: bool, output: Output) -> Result<()> {
     let is_place = extension == "rbxl" || extension == "rbxlx";
 
-    println!("Building {} from {:?}...", extension, src_dir);
+    if output.is_text() {
+        println!("Building {} from {:?}...", extension, src_dir);
+    }
 
     // Build the DOM
     let dom = build_dom_from_src(src_dir, is_place)?;
@@ -2027,6 +2994,9 @@ fn do_build(src_dir: &PathBuf, output_path: &PathBuf, extension: &str, is_xml: b
             .context("Failed to write binary output file")?;
     }
 
+    if !output.is_text() {
+        return Ok(());
+    }
     println!("Built successfully: {}", output_path.display());
 
     // Show file size
@@ -2103,7 +3073,7 @@ fn build_dom_from_src(src_dir: &std::path::Path, is_place: bool) -> Result<WeakD
                     root_ref,
                     InstanceBuilder::new(class_name)
                         .with_name(&script_name)
-                        .with_property("Source", Variant::String(source)),
+                        .with_property("Source", Variant::String(SourceFormat::to_studio(&source))),
                 );
             }
         }
@@ -2135,7 +3105,7 @@ fn build_dom_children(
                 if let Some(instance) = dom.get_by_ref_mut(parent_ref) {
                     instance
                         .properties
-                        .insert("Source".to_string(), Variant::String(source));
+                        .insert("Source".to_string(), Variant::String(SourceFormat::to_studio(&source)));
                 }
             }
             break;
@@ -2233,15 +3203,46 @@ fn build_dom_children(
                     parent_ref,
                     InstanceBuilder::new(class_name)
                         .with_name(&script_name)
-                        .with_property("Source", Variant::String(source)),
+                        .with_property("Source", Variant::String(SourceFormat::to_studio(&source))),
                 );
             }
+        } else if rbxsync_core::data_file_info(&entry_name).is_some() {
+            // Data file (.json, .toml, .yaml, .txt, .csv, .model.json)
+            let content = std::fs::read_to_string(&entry_path)
+                .with_context(|| format!("Failed to read {}", entry_path.display()))?;
+            let instance = rbxsync_core::parse_data_file(&entry_name, &content)
+                .with_context(|| format!("Failed to parse {}", entry_path.display()))?;
+            insert_json_instance(dom, parent_ref, &instance);
         }
     }
 
     Ok(())
 }
 
+/// Insert an instance in .rbxjson form, with any inline children, into the DOM
+fn insert_json_instance(
+    dom: &mut WeakDom,
+    parent_ref: rbx_dom_weak::types::Ref,
+    instance: &serde_json::Value,
+) {
+    let class_name = instance.get("className").and_then(|c| c.as_str()).unwrap_or("Folder");
+    let name = instance.get("name").and_then(|n| n.as_str()).unwrap_or(class_name);
+
+    let mut builder = InstanceBuilder::new(class_name).with_name(name);
+    if let Some(props) = instance.get("properties").and_then(|p| p.as_object()) {
+        for (prop_name, prop_value) in props {
+            if let Some(value) = json_to_variant(prop_value) {
+                builder = builder.with_property(prop_name, value);
+            }
+        }
+    }
+
+    let child_ref = dom.insert(parent_ref, builder);
+    for child in instance.get("children").and_then(|c| c.as_array()).into_iter().flatten() {
+        insert_json_instance(dom, child_ref, child);
+    }
+}
+
 /// Get the appropriate class name for a service directory
 fn service_class_name(name: &str) -> &'static str {
     match name {
@@ -2449,8 +3450,45 @@ fn json_to_variant(value: &serde_json::Value) -> Option<Variant> {
 }
 
 /// Format project JSON files with consistent style
-fn cmd_fmt_project(path: Option<PathBuf>, check: bool) -> Result<()> {
+fn cmd_fmt_project(path: Option<PathBuf>, check: bool, output: Output) -> Result<()> {
     let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+    let (formatted, unformatted) = format_project_files(&project_dir, check)?;
+
+    output.json(&serde_json::json!({
+        "check": check,
+        "unformatted": unformatted,
+        "formatted": formatted,
+    }))?;
+    if output.is_text() {
+        if check {
+            if unformatted.is_empty() {
+                println!("All files are properly formatted.");
+            } else {
+                println!("The following files need formatting:");
+                for path in &unformatted {
+                    println!("  {}", path.display());
+                }
+            }
+        } else if formatted.is_empty() {
+            println!("All files are already properly formatted.");
+        } else {
+            for path in &formatted {
+                println!("Formatted: {}", path.display());
+            }
+            println!("\nFormatted {} file(s).", formatted.len());
+        }
+    }
+
+    if !unformatted.is_empty() {
+        bail!(Exit(EXIT_FAILURE));
+    }
+    Ok(())
+}
+
+/// Format the project's `.rbxjson` files and rbxsync.json, or with `check`
+/// only find the ones that need it. Returns the formatted and the
+/// unformatted files.
+fn format_project_files(project_dir: &std::path::Path, check: bool) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
     let src_dir = project_dir.join("src");
 
     if !src_dir.exists() {
@@ -2458,14 +3496,14 @@ fn cmd_fmt_project(path: Option<PathBuf>, check: bool) -> Result<()> {
     }
 
     let mut unformatted = Vec::new();
-    let mut formatted_count = 0;
+    let mut formatted = Vec::new();
 
     // Recursively find all .rbxjson files
     fn visit_dir(
         dir: &std::path::Path,
         check: bool,
         unformatted: &mut Vec<PathBuf>,
-        formatted_count: &mut usize,
+        formatted: &mut Vec<PathBuf>,
     ) -> Result<()> {
         if !dir.is_dir() {
             return Ok(());
@@ -2477,7 +3515,7 @@ fn cmd_fmt_project(path: Option<PathBuf>, check: bool) -> Result<()> {
             let path = entry.path();
 
             if path.is_dir() {
-                visit_dir(&path, check, unformatted, formatted_count)?;
+                visit_dir(&path, check, unformatted, formatted)?;
             } else if path.extension().map_or(false, |ext| ext == "rbxjson") {
                 let content = std::fs::read_to_string(&path)
                     .with_context(|| format!("Failed to read {}", path.display()))?;
@@ -2486,16 +3524,15 @@ fn cmd_fmt_project(path: Option<PathBuf>, check: bool) -> Result<()> {
                 let value: serde_json::Value = serde_json::from_str(&content)
                     .with_context(|| format!("Failed to parse {}", path.display()))?;
 
-                let formatted = serde_json::to_string_pretty(&value)? + "\n";
+                let pretty = serde_json::to_string_pretty(&value)? + "\n";
 
-                if content != formatted {
+                if content != pretty {
                     if check {
                         unformatted.push(path);
                     } else {
-                        std::fs::write(&path, &formatted)
+                        std::fs::write(&path, &pretty)
                             .with_context(|| format!("Failed to write {}", path.display()))?;
-                        println!("Formatted: {}", path.display());
-                        *formatted_count += 1;
+                        formatted.push(path);
                     }
                 }
             }
@@ -2504,44 +3541,131 @@ fn cmd_fmt_project(path: Option<PathBuf>, check: bool) -> Result<()> {
         Ok(())
     }
 
-    visit_dir(&src_dir, check, &mut unformatted, &mut formatted_count)?;
+    visit_dir(&src_dir, check, &mut unformatted, &mut formatted)?;
 
     // Also format rbxsync.json if it exists
     let config_path = project_dir.join("rbxsync.json");
     if config_path.exists() {
         if let Ok(content) = std::fs::read_to_string(&config_path) {
             if let Ok(value) = serde_json::from_str::<serde_json::Value>(&content) {
-                let formatted = serde_json::to_string_pretty(&value)? + "\n";
-                if content != formatted {
+                let pretty = serde_json::to_string_pretty(&value)? + "\n";
+                if content != pretty {
                     if check {
                         unformatted.push(config_path);
                     } else {
-                        std::fs::write(&config_path, &formatted)?;
-                        println!("Formatted: {}", config_path.display());
-                        formatted_count += 1;
+                        std::fs::write(&config_path, &pretty)?;
+                        formatted.push(config_path);
                     }
                 }
             }
         }
     }
 
-    if check {
-        if unformatted.is_empty() {
-            println!("All files are properly formatted.");
-        } else {
-            println!("The following files need formatting:");
-            for path in &unformatted {
-                println!("  {}", path.display());
+    Ok((formatted, unformatted))
+}
+
+/// Check the project tree, or only the staged files and their dependents
+fn cmd_verify(path: Option<PathBuf>, staged: bool, output: Output) -> Result<()> {
+    let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+
+    // Staged mode checks the index rather than the working tree, exported to
+    // a temporary directory
+    let (tree_dir, changed) = if staged {
+        let files: std::collections::BTreeSet<String> = rbxsync_server::git::staged_files(&project_dir)
+            .context("Failed to list staged files")?
+            .iter()
+            .filter_map(|f| f.strip_prefix("src/"))
+            .map(String::from)
+            .collect();
+        if files.is_empty() {
+            output.json(&serde_json::json!({ "valid": true, "staged": true, "files": 0, "issues": [], "buildError": null }))?;
+            if output.is_text() {
+                println!("No staged project files to verify");
             }
-            std::process::exit(1);
+            return Ok(());
         }
-    } else if formatted_count == 0 {
-        println!("All files are already properly formatted.");
+
+        // Instances the staged files held before, whose referrers must be checked too
+        let mut previous_ids = HashSet::new();
+        for file in files.iter().filter(|f| f.ends_with(".rbxjson")) {
+            let previous = rbxsync_server::git::file_at(&project_dir, "HEAD", &format!("src/{}", file))
+                .context("Failed to read the last commit")?;
+            if let Some(value) = previous.and_then(|content| serde_json::from_str(&content).ok()) {
+                previous_ids.extend(rbxsync_core::reference_ids(&value));
+            }
+        }
+
+        let export_dir = std::env::temp_dir().join(format!("rbxsync-verify-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&export_dir);
+        rbxsync_server::git::export_index(&project_dir, &export_dir).context("Failed to read the staged tree")?;
+        (export_dir, Some(rbxsync_core::ChangedFiles { files, previous_ids }))
     } else {
-        println!("\nFormatted {} file(s).", formatted_count);
+        (project_dir.clone(), None)
+    };
+
+    let src_dir = tree_dir.join("src");
+    let result = if src_dir.exists() {
+        let issues = rbxsync_core::verify_tree(&src_dir, changed.as_ref());
+        // Only a tree that passes the checks is built; the build stops at its first error
+        let build = if issues.is_empty() {
+            build_dom_from_src(&src_dir, true).err()
+        } else {
+            None
+        };
+        Ok((issues, build))
+    } else if staged {
+        // Every staged file is a deletion of the whole tree
+        Ok((Vec::new(), None))
+    } else {
+        Err(anyhow::anyhow!("Source directory not found: {}", src_dir.display()))
+    };
+    if staged {
+        let _ = std::fs::remove_dir_all(&tree_dir);
+    }
+    let (issues, build) = result?;
+
+    output.json(&serde_json::json!({
+        "valid": issues.is_empty() && build.is_none(),
+        "staged": staged,
+        "files": changed.as_ref().map(|changed| changed.files.len()),
+        "issues": issues
+            .iter()
+            .map(|issue| serde_json::json!({
+                "file": format!("src/{}", issue.file),
+                "line": issue.line,
+                "message": issue.message,
+            }))
+            .collect::<Vec<_>>(),
+        "buildError": build.as_ref().map(|e| format!("{:#}", e)),
+    }))?;
+    if !output.is_text() {
+        if issues.is_empty() && build.is_none() {
+            return Ok(());
+        }
+        bail!(Exit(EXIT_FAILURE));
     }
 
-    Ok(())
+    if issues.is_empty() && build.is_none() {
+        match &changed {
+            Some(changed) => println!("✓ {} staged file(s) verified", changed.files.len()),
+            None => println!("✓ Project tree is valid"),
+        }
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("✗ src/{}", issue);
+    }
+    if let Some(e) = build {
+        println!("✗ Build failed: {:#}", e);
+    }
+    let count = issues.len().max(1);
+    if staged {
+        println!("\n{} problem(s) in the staged tree. Fix them, or commit anyway with `git commit --no-verify`.", count);
+    } else {
+        println!("\n{} problem(s) found.", count);
+    }
+    bail!(Exit(EXIT_FAILURE));
 }
 
 /// Open documentation in browser
@@ -2615,13 +3739,15 @@ async fn download_file(client: &reqwest::Client, url: &str, path: &PathBuf) -> R
 }
 
 /// Download the latest plugin from GitHub releases
-async fn download_plugin_from_github() -> Result<PathBuf> {
+async fn download_plugin_from_github(output: Output) -> Result<PathBuf> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()?;
 
     // Fetch latest release info
-    print!("Fetching latest release... ");
+    if output.is_text() {
+        print!("Fetching latest release... ");
+    }
     let response = client
         .get("https://api.github.com/repos/Smokestack-Games/rbxsync/releases/latest")
         .header("User-Agent", "rbxsync-cli")
@@ -2639,7 +3765,9 @@ async fn download_plugin_from_github() -> Result<PathBuf> {
     let version = release.get("tag_name")
         .and_then(|t| t.as_str())
         .unwrap_or("unknown");
-    println!("{}", version);
+    if output.is_text() {
+        println!("{}", version);
+    }
 
     // Find plugin download URL
     let assets = release.get("assets")
@@ -2659,9 +3787,13 @@ async fn download_plugin_from_github() -> Result<PathBuf> {
 
     let plugin_path = download_dir.join("RbxSync.rbxm");
 
-    print!("Downloading plugin... ");
+    if output.is_text() {
+        print!("Downloading plugin... ");
+    }
     download_file(&client, plugin_url, &plugin_path).await?;
-    println!("done!");
+    if output.is_text() {
+        println!("done!");
+    }
 
     Ok(plugin_path)
 }
@@ -3070,58 +4202,69 @@ fn is_newer_version(latest: &str, current: &str) -> bool {
 }
 
 /// Show version information
-async fn cmd_version() -> Result<()> {
+async fn cmd_version(output: Output) -> Result<()> {
     let version = env!("CARGO_PKG_VERSION");
 
-    println!("RbxSync v{}", version);
-    println!();
+    if output.is_text() {
+        println!("RbxSync v{}", version);
+        println!();
+    }
 
     // Try to get git info
-    if let Ok(output) = std::process::Command::new("git")
+    let commit = std::process::Command::new("git")
         .args(["rev-parse", "--short", "HEAD"])
         .output()
-    {
-        if output.status.success() {
-            let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            println!("Git commit: {}", commit);
-        }
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if let Some(commit) = commit.as_ref().filter(|_| output.is_text()) {
+        println!("Git commit: {}", commit);
     }
 
     // Check for updates from GitHub releases
-    println!();
-    print!("Checking for updates... ");
+    if output.is_text() {
+        println!();
+        print!("Checking for updates... ");
+    }
 
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(5))
         .build()?;
 
-    match client
+    let latest = match client
         .get("https://api.github.com/repos/Smokestack-Games/rbxsync/releases/latest")
         .header("User-Agent", "rbxsync-cli")
         .send()
         .await
     {
-        Ok(response) => {
-            if let Ok(release) = response.json::<serde_json::Value>().await {
-                if let Some(tag) = release.get("tag_name").and_then(|t| t.as_str()) {
-                    let latest = tag.trim_start_matches('v');
-                    if is_newer_version(latest, version) {
-                        println!("\x1b[33mUpdate available: v{}\x1b[0m", latest);
-                        println!("  Run: rbxsync update");
-                        println!("  Or download: https://github.com/Smokestack-Games/rbxsync/releases/latest");
-                    } else {
-                        println!("\x1b[32mUp to date!\x1b[0m");
-                    }
-                } else {
-                    println!("Could not parse version");
-                }
-            } else {
-                println!("Could not parse response");
-            }
-        }
-        Err(_) => {
-            println!("Could not check (offline?)");
+        Ok(response) => match response.json::<serde_json::Value>().await {
+            Ok(release) => match release.get("tag_name").and_then(|t| t.as_str()) {
+                Some(tag) => Ok(tag.trim_start_matches('v').to_string()),
+                None => Err("Could not parse version"),
+            },
+            Err(_) => Err("Could not parse response"),
+        },
+        Err(_) => Err("Could not check (offline?)"),
+    };
+
+    output.json(&serde_json::json!({
+        "version": version,
+        "commit": commit,
+        "latest": latest.as_ref().ok(),
+        "updateAvailable": latest.as_ref().ok().map(|latest| is_newer_version(latest, version)),
+    }))?;
+    if !output.is_text() {
+        return Ok(());
+    }
+
+    match latest {
+        Ok(latest) if is_newer_version(&latest, version) => {
+            println!("\x1b[33mUpdate available: v{}\x1b[0m", latest);
+            println!("  Run: rbxsync update");
+            println!("  Or download: https://github.com/Smokestack-Games/rbxsync/releases/latest");
         }
+        Ok(_) => println!("\x1b[32mUp to date!\x1b[0m"),
+        Err(message) => println!("{}", message),
     }
 
     println!();
@@ -3435,16 +4578,168 @@ fn cmd_migrate(from: String, path: Option<PathBuf>, force: bool) -> Result<()> {
     Ok(())
 }
 
-/// Manage AI development harness
-async fn cmd_harness(action: HarnessAction) -> Result<()> {
-    let client = reqwest::Client::new();
+/// Git integration commands
+async fn cmd_git(client: Result<RbxSyncClient>, action: GitAction, output: Output) -> Result<()> {
+    match action {
+        GitAction::InstallMergeDriver { path } => {
+            let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let changed = rbxsync_server::git::install_merge_driver(&project_dir)
+                .context("Failed to install merge driver")?;
+            output.json(&serde_json::json!({ "mergeDriver": true, "gitattributesChanged": changed }))?;
+            if output.is_text() {
+                println!("Registered the rbxsync merge driver in git config");
+                if changed {
+                    println!("Added '{}' to .gitattributes (commit it to share with your team)", rbxsync_server::git::MERGE_ATTRIBUTE);
+                }
+            }
+        }
+        GitAction::BranchSwitch { path, apply, discard } => {
+            let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            cmd_branch_switch(&client?, &project_dir.to_string_lossy(), apply, discard, output).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Git hook commands
+fn cmd_hooks(action: HooksAction, output: Output) -> Result<()> {
+    match action {
+        HooksAction::Install { path, force } => {
+            let project_dir = path.unwrap_or_else(|| std::env::current_dir().unwrap());
+            let hook = match rbxsync_server::git::install_pre_commit_hook(&project_dir, force) {
+                Err(e @ rbxsync_server::git::GitError::HookExists(_)) => {
+                    bail!("{}. Add `rbxsync verify --staged` to it yourself, or replace it with --force", e)
+                }
+                result => result.context("Failed to install pre-commit hook")?,
+            };
+            let changed = rbxsync_server::git::install_merge_driver(&project_dir)
+                .context("Failed to install merge driver")?;
+            output.json(&serde_json::json!({ "hook": hook, "mergeDriver": true, "gitattributesChanged": changed }))?;
+            if output.is_text() {
+                println!("Installed pre-commit hook: {}", hook.display());
+                println!("Commits now run `rbxsync verify --staged` first");
+                println!("Registered the rbxsync merge driver in git config");
+                if changed {
+                    println!("Added '{}' to .gitattributes (commit it to share with your team)", rbxsync_server::git::MERGE_ATTRIBUTE);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show a pending branch switch, or apply or discard it
+async fn cmd_branch_switch(client: &RbxSyncClient, project_dir: &str, apply: bool, discard: bool, output: Output) -> Result<()> {
+    client.ensure_running().await?;
+    let listing = client.branch_switches().await?;
+    let Some(switch) = listing
+        .switches
+        .iter()
+        .find(|s| s.get("projectDir").and_then(|v| v.as_str()) == Some(project_dir))
+    else {
+        output.json(&serde_json::json!({ "pending": null, "resolved": null, "requestId": null }))?;
+        if output.is_text() {
+            println!("No branch switch pending for {}", project_dir);
+        }
+        return Ok(());
+    };
+
+    if apply || discard {
+        let data = client
+            .resolve_branch_switch(project_dir, apply)
+            .await
+            .context("Failed to resolve branch switch")?;
+        output.json(&serde_json::json!({
+            "pending": switch,
+            "resolved": if apply { "applied" } else { "discarded" },
+            "requestId": data.get("requestId"),
+        }))?;
+        if !output.is_text() {
+            return Ok(());
+        }
+        if discard {
+            println!("Discarded; live sync resumed and Studio was not changed");
+        } else if let Some(id) = data.get("requestId").and_then(|v| v.as_str()) {
+            println!("Sent to Studio as one batch ({}); live sync resumed", id);
+            println!("Check the result with: curl {}", client.url(&format!("/sync/batches/{}", id)));
+        } else {
+            println!("Nothing left to send; live sync resumed");
+        }
+        return Ok(());
+    }
 
-    // Check server is running
-    if client.get("http://localhost:44755/health").send().await.is_err() {
-        println!("RbxSync server is not running. Start it with: rbxsync serve");
+    output.json(&serde_json::json!({ "pending": switch, "resolved": null, "requestId": null }))?;
+    if !output.is_text() {
         return Ok(());
     }
 
+    let label = |head: &serde_json::Value| {
+        head.get("branch")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .or_else(|| head.get("commit").and_then(|v| v.as_str()).map(|c| c.chars().take(7).collect()))
+            .unwrap_or_default()
+    };
+    println!("Branch switch {} → {}", label(&switch["from"]), label(&switch["to"]));
+    if let Some(action) = switch.get("action").and_then(|v| v.as_str()) {
+        println!("  ({})", action);
+    }
+    let diff = &switch["diff"];
+    let entries = |key: &str| diff.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    for entry in entries("added") {
+        println!("  \x1b[32m+ {}\x1b[0m", entry["path"].as_str().unwrap_or_default());
+    }
+    for entry in entries("removed") {
+        println!("  \x1b[31m- {}\x1b[0m", entry["path"].as_str().unwrap_or_default());
+    }
+    for entry in entries("moved") {
+        println!(
+            "  \x1b[36m→ {} → {}\x1b[0m",
+            entry["from"].as_str().unwrap_or_default(),
+            entry["to"].as_str().unwrap_or_default()
+        );
+    }
+    for entry in entries("modified") {
+        println!("  \x1b[33m~ {}\x1b[0m", entry["path"].as_str().unwrap_or_default());
+    }
+    println!("\nLive sync is paused for this project. Run with --apply to send these changes to Studio, or --discard to skip them.");
+    Ok(())
+}
+
+/// Merge a .rbxjson file property by property, writing the result over
+/// `ours`. Exits with 1 when conflicts remain, as git expects.
+fn cmd_merge_driver(base: &std::path::Path, ours: &std::path::Path, theirs: &std::path::Path) -> Result<()> {
+    let read = |path: &std::path::Path| std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()));
+    let (base_text, ours_text, theirs_text) = (read(base)?, read(ours)?, read(theirs)?);
+
+    match rbxsync_core::merge_instance_files(&base_text, &ours_text, &theirs_text) {
+        Ok(outcome) => {
+            std::fs::write(ours, &outcome.content).with_context(|| format!("Failed to write {}", ours.display()))?;
+            if !outcome.is_clean() {
+                eprintln!("rbxsync: conflicting changes to {}", outcome.conflicts.join(", "));
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            // Not instance JSON on every side: merge it line by line instead
+            eprintln!("rbxsync: falling back to a line merge ({})", e);
+            let status = std::process::Command::new("git")
+                .args(["merge-file", "-L", "ours", "-L", "base", "-L", "theirs"])
+                .args([ours, base, theirs])
+                .status()
+                .context("Failed to run git merge-file")?;
+            if !status.success() {
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Manage AI development harness
+async fn cmd_harness(client: &RbxSyncClient, action: HarnessAction, output: Output) -> Result<()> {
+    client.ensure_running().await?;
+
     match action {
         HarnessAction::Init {
             name,
@@ -3457,7 +4752,9 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                 .to_string_lossy()
                 .to_string();
 
-            println!("Initializing harness for project: {}", project_dir);
+            if output.is_text() {
+                println!("Initializing harness for project: {}", project_dir);
+            }
 
             let mut body = serde_json::json!({
                 "projectDir": project_dir,
@@ -3471,14 +4768,14 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                 body["description"] = serde_json::Value::String(d);
             }
 
-            let response = client
-                .post("http://localhost:44755/harness/init")
-                .json(&body)
-                .send()
+            let result: serde_json::Value = client
+                .post("/harness/init", &body)
                 .await
                 .context("Failed to initialize harness")?;
-
-            let result: serde_json::Value = response.json().await?;
+            output.json(&result)?;
+            if !output.is_text() {
+                return harness_exit(&result, "success");
+            }
             if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
                 let harness_dir = result
                     .get("harnessDir")
@@ -3498,6 +4795,7 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unknown error");
                 println!("Failed to initialize harness: {}", error);
+                bail!(Exit(EXIT_FAILURE));
             }
         }
 
@@ -3507,21 +4805,19 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                 .to_string_lossy()
                 .to_string();
 
-            let response = client
-                .post("http://localhost:44755/harness/status")
-                .json(&serde_json::json!({
-                    "projectDir": project_dir,
-                }))
-                .send()
+            let result: serde_json::Value = client
+                .post("/harness/status", &serde_json::json!({ "projectDir": project_dir }))
                 .await
                 .context("Failed to get harness status")?;
-
-            let result: serde_json::Value = response.json().await?;
+            output.json(&result)?;
+            if !output.is_text() {
+                return harness_exit(&result, "initialized");
+            }
 
             if !result.get("initialized").and_then(|v| v.as_bool()).unwrap_or(false) {
                 println!("Harness not initialized for this project.");
                 println!("Run: rbxsync harness init --name 'Your Game'");
-                return Ok(());
+                bail!(Exit(EXIT_FAILURE));
             }
 
             // Print game info
@@ -3584,20 +4880,28 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                 .to_string_lossy()
                 .to_string();
 
-            let response = client
-                .post("http://localhost:44755/harness/status")
-                .json(&serde_json::json!({
-                    "projectDir": project_dir,
-                }))
-                .send()
+            let mut result: serde_json::Value = client
+                .post("/harness/status", &serde_json::json!({ "projectDir": project_dir }))
                 .await
                 .context("Failed to get features")?;
-
-            let result: serde_json::Value = response.json().await?;
+            if !output.is_text() {
+                // Just the initialized flag and the features, with --status applied
+                if let (Some(filter), Some(features)) = (&status, result.get_mut("features").and_then(|v| v.as_array_mut())) {
+                    features.retain(|feature| {
+                        let status = feature.get("status").and_then(|v| v.as_str()).unwrap_or("unknown");
+                        feature_status_matches(status, filter)
+                    });
+                }
+                output.json(&serde_json::json!({
+                    "initialized": result.get("initialized").cloned().unwrap_or(serde_json::Value::Bool(false)),
+                    "features": result.get("features").cloned().unwrap_or_else(|| serde_json::json!([])),
+                }))?;
+                return harness_exit(&result, "initialized");
+            }
 
             if !result.get("initialized").and_then(|v| v.as_bool()).unwrap_or(false) {
                 println!("Harness not initialized for this project.");
-                return Ok(());
+                bail!(Exit(EXIT_FAILURE));
             }
 
             let features = result.get("features").and_then(|v| v.as_array());
@@ -3624,10 +4928,7 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
 
                     // Skip if doesn't match filter
                     if let Some(ref filter) = status_filter {
-                        // Handle snake_case vs camelCase
-                        let normalized_filter = filter.replace("_", "").replace("-", "");
-                        let normalized_status = feature_status.replace("_", "").replace("-", "");
-                        if !normalized_status.contains(&normalized_filter) {
+                        if !feature_status_matches(&feature_status, filter) {
                             continue;
                         }
                     }
@@ -3699,14 +5000,14 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                 body["addNote"] = serde_json::Value::String(n);
             }
 
-            let response = client
-                .post("http://localhost:44755/harness/feature/update")
-                .json(&body)
-                .send()
+            let result: serde_json::Value = client
+                .post("/harness/feature/update", &body)
                 .await
                 .context("Failed to update feature")?;
-
-            let result: serde_json::Value = response.json().await?;
+            output.json(&result)?;
+            if !output.is_text() {
+                return harness_exit(&result, "success");
+            }
             if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
                 let feature_id = result
                     .get("featureId")
@@ -3725,6 +5026,7 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                     .and_then(|v| v.as_str())
                     .unwrap_or("Unknown error");
                 println!("Failed to update feature: {}", error);
+                bail!(Exit(EXIT_FAILURE));
             }
         }
 
@@ -3743,14 +5045,14 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                     body["initialGoals"] = serde_json::Value::String(g);
                 }
 
-                let response = client
-                    .post("http://localhost:44755/harness/session/start")
-                    .json(&body)
-                    .send()
+                let result: serde_json::Value = client
+                    .post("/harness/session/start", &body)
                     .await
                     .context("Failed to start session")?;
-
-                let result: serde_json::Value = response.json().await?;
+                output.json(&result)?;
+                if !output.is_text() {
+                    return harness_exit(&result, "success");
+                }
                 if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
                     let session_id = result
                         .get("sessionId")
@@ -3768,6 +5070,7 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                         .and_then(|v| v.as_str())
                         .unwrap_or("Unknown error");
                     println!("Failed to start session: {}", error);
+                    bail!(Exit(EXIT_FAILURE));
                 }
             }
 
@@ -3797,14 +5100,14 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                     );
                 }
 
-                let response = client
-                    .post("http://localhost:44755/harness/session/end")
-                    .json(&body)
-                    .send()
+                let result: serde_json::Value = client
+                    .post("/harness/session/end", &body)
                     .await
                     .context("Failed to end session")?;
-
-                let result: serde_json::Value = response.json().await?;
+                output.json(&result)?;
+                if !output.is_text() {
+                    return harness_exit(&result, "success");
+                }
                 if result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
                     println!("Session ended successfully!");
                 } else {
@@ -3813,6 +5116,7 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
                         .and_then(|v| v.as_str())
                         .unwrap_or("Unknown error");
                     println!("Failed to end session: {}", error);
+                    bail!(Exit(EXIT_FAILURE));
                 }
             }
         },
@@ -3820,3 +5124,18 @@ async fn cmd_harness(action: HarnessAction) -> Result<()> {
 
     Ok(())
 }
+
+/// Whether a feature's status matches `rbxsync harness features --status`,
+/// ignoring case, `_` and `-`
+fn feature_status_matches(status: &str, filter: &str) -> bool {
+    let normalize = |s: &str| s.to_lowercase().replace(['_', '-'], "");
+    normalize(status).contains(&normalize(filter))
+}
+
+/// Exit with 1 unless a harness response's `flag` is true
+fn harness_exit(result: &serde_json::Value, flag: &str) -> Result<()> {
+    if !result.get(flag).and_then(|v| v.as_bool()).unwrap_or(false) {
+        bail!(Exit(EXIT_FAILURE));
+    }
+    Ok(())
+}
@@ -0,0 +1,66 @@
+//! Output modes and exit codes
+//!
+//! By default commands print text for people. With `--format json` a
+//! command prints exactly one JSON document to stdout instead (an error
+//! becomes `{"error": "..."}`), and with `--quiet` it prints nothing but
+//! errors. Logs go to stderr in both modes.
+//!
+//! Exit codes follow `diff(1)`: 0 when everything is fine, 1 when the
+//! command ran but found something to act on (files out of sync, a project
+//! that fails `verify`, a sync with errors), and 2 when it failed.
+
+use std::fmt;
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// The command ran but found something to act on
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The command failed
+pub const EXIT_ERROR: i32 = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How a command reports its result
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub quiet: bool,
+}
+
+impl Output {
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Whether to print text, including progress
+    pub fn is_text(&self) -> bool {
+        self.format == OutputFormat::Text && !self.quiet
+    }
+
+    /// Print the command's JSON document, in JSON mode
+    pub fn json(&self, document: &impl Serialize) -> Result<()> {
+        if self.is_json() {
+            println!("{}", serde_json::to_string_pretty(document)?);
+        }
+        Ok(())
+    }
+}
+
+/// Ends a command with an exit code once its output is printed
+#[derive(Debug)]
+pub struct Exit(pub i32);
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit code {}", self.0)
+    }
+}
+
+impl std::error::Error for Exit {}
@@ -0,0 +1,630 @@
+//! Rojo-style data files
+//!
+//! Besides `.luau` scripts and `.rbxjson` instances, a synced tree may hold
+//! plain data files that map to a single instance:
+//!
+//! | File | Instance |
+//! |------|----------|
+//! | `Name.json`, `Name.toml`, `Name.yaml` | ModuleScript returning the data as a table |
+//! | `Name.txt` | StringValue with the file as `Value` |
+//! | `Name.csv` | LocalizationTable with one entry per row |
+//! | `Name.model.json` | Rojo model (any class, with children) |
+//!
+//! Parsed files come back in the same JSON shape as `.rbxjson` files so every
+//! consumer (live sync, read-tree, build) can treat them alike.
+
+use serde_json::{json, Map, Value};
+
+/// Kind of data file, from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFileKind {
+    Json,
+    Toml,
+    Yaml,
+    Text,
+    Csv,
+    Model,
+}
+
+impl DataFileKind {
+    /// Class of the instance the file becomes (`None` for models, which name their own)
+    pub fn class_name(self) -> Option<&'static str> {
+        match self {
+            DataFileKind::Json | DataFileKind::Toml | DataFileKind::Yaml => Some("ModuleScript"),
+            DataFileKind::Text => Some("StringValue"),
+            DataFileKind::Csv => Some("LocalizationTable"),
+            DataFileKind::Model => None,
+        }
+    }
+}
+
+/// Errors from parsing a data file
+#[derive(Debug, thiserror::Error)]
+pub enum DataFileError {
+    #[error("Not a data file: {0}")]
+    Unsupported(String),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("Invalid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Invalid CSV: {0}")]
+    Csv(String),
+
+    #[error("Invalid model: {0}")]
+    Model(String),
+}
+
+/// Instance name and kind for a data file name
+///
+/// Rojo's `*.meta.json` and `*.project.json` files are not data files.
+pub fn data_file_info(filename: &str) -> Option<(&str, DataFileKind)> {
+    if filename.ends_with(".meta.json") || filename.ends_with(".project.json") {
+        return None;
+    }
+    let (name, kind) = if let Some(name) = filename.strip_suffix(".model.json") {
+        (name, DataFileKind::Model)
+    } else if let Some(name) = filename.strip_suffix(".json") {
+        (name, DataFileKind::Json)
+    } else if let Some(name) = filename.strip_suffix(".toml") {
+        (name, DataFileKind::Toml)
+    } else if let Some(name) = filename
+        .strip_suffix(".yaml")
+        .or_else(|| filename.strip_suffix(".yml"))
+    {
+        (name, DataFileKind::Yaml)
+    } else if let Some(name) = filename.strip_suffix(".txt") {
+        (name, DataFileKind::Text)
+    } else if let Some(name) = filename.strip_suffix(".csv") {
+        (name, DataFileKind::Csv)
+    } else {
+        return None;
+    };
+    (!name.is_empty()).then_some((name, kind))
+}
+
+/// Parse a data file into instance JSON (`className`, `name`, `properties`)
+///
+/// Models keep their descendants in a `children` array (empty when the model
+/// has none); see [`flatten_instance`].
+pub fn parse_data_file(filename: &str, content: &str) -> Result<Value, DataFileError> {
+    let (name, kind) =
+        data_file_info(filename).ok_or_else(|| DataFileError::Unsupported(filename.to_string()))?;
+
+    let module = |data: Value| {
+        let source = format!("return {}\n", to_luau(&data));
+        json!({
+            "className": "ModuleScript",
+            "name": name,
+            "source": source,
+            "properties": { "Source": { "type": "string", "value": source } },
+        })
+    };
+
+    Ok(match kind {
+        DataFileKind::Json => module(serde_json::from_str(content)?),
+        DataFileKind::Toml => module(toml::from_str(content)?),
+        DataFileKind::Yaml => module(serde_yaml::from_str(content)?),
+        DataFileKind::Text => json!({
+            "className": "StringValue",
+            "name": name,
+            "properties": { "Value": { "type": "string", "value": content } },
+        }),
+        DataFileKind::Csv => {
+            let entries = parse_localization_csv(content)?;
+            json!({
+                "className": "LocalizationTable",
+                "name": name,
+                "properties": {
+                    "Contents": { "type": "string", "value": Value::Array(entries).to_string() }
+                },
+            })
+        }
+        DataFileKind::Model => model_to_instance(name, &serde_json::from_str(content)?)?,
+    })
+}
+
+/// Flatten an instance with inline `children` into `(path, instance)` pairs,
+/// parents first
+pub fn flatten_instance(instance: &Value, path: &str) -> Vec<(String, Value)> {
+    let mut out = Vec::new();
+    let mut stack = vec![(path.to_string(), instance.clone())];
+    while let Some((path, mut instance)) = stack.pop() {
+        let children = instance
+            .as_object_mut()
+            .and_then(|obj| obj.remove("children"))
+            .and_then(|c| match c {
+                Value::Array(children) => Some(children),
+                _ => None,
+            })
+            .unwrap_or_default();
+        for child in children.into_iter().rev() {
+            let name = child.get("name").and_then(|n| n.as_str()).unwrap_or("Instance");
+            stack.push((format!("{}/{}", path, name), child));
+        }
+        out.push((path, instance));
+    }
+    out
+}
+
+// ============================================================================
+// Luau table literals
+// ============================================================================
+
+const LUAU_KEYWORDS: &[&str] = &[
+    "and", "break", "continue", "do", "else", "elseif", "end", "false", "for", "function", "if",
+    "in", "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Render a JSON value as a Luau expression
+pub fn to_luau(value: &Value) -> String {
+    let mut out = String::new();
+    write_luau(value, 0, &mut out);
+    out
+}
+
+fn write_luau(value: &Value, depth: usize, out: &mut String) {
+    let indent = "\t".repeat(depth + 1);
+    match value {
+        Value::Null => out.push_str("nil"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => out.push_str(&luau_string(s)),
+        Value::Array(items) if items.is_empty() => out.push_str("{}"),
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push_str("{\n");
+            for item in items {
+                out.push_str(&indent);
+                write_luau(item, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&"\t".repeat(depth));
+            out.push('}');
+        }
+        Value::Object(map) => {
+            out.push_str("{\n");
+            for (key, item) in map {
+                out.push_str(&indent);
+                if is_luau_identifier(key) {
+                    out.push_str(key);
+                } else {
+                    out.push('[');
+                    out.push_str(&luau_string(key));
+                    out.push(']');
+                }
+                out.push_str(" = ");
+                write_luau(item, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&"\t".repeat(depth));
+            out.push('}');
+        }
+    }
+}
+
+fn is_luau_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !LUAU_KEYWORDS.contains(&key)
+}
+
+fn luau_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // `\ddd` would run into a following digit and means a byte, not a character
+            c if c.is_control() => out.push_str(&format!("\\u{{{:X}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ============================================================================
+// Localization CSV
+// ============================================================================
+
+/// Split CSV text into rows (RFC 4180 quoting, `\n` or `\r\n` line endings)
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, DataFileError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(DataFileError::Csv("unterminated quoted field".to_string()));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Parse a localization CSV into LocalizationTable entries
+///
+/// The header row names the columns: `Key`, `Source`, `Context` and `Example`
+/// are entry fields, every other column is a locale id. Each entry needs a
+/// key or a source.
+pub fn parse_localization_csv(text: &str) -> Result<Vec<Value>, DataFileError> {
+    let mut rows = parse_csv(text)?.into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| DataFileError::Csv("missing header row".to_string()))?;
+
+    let mut entries = Vec::new();
+    for (line, row) in rows.enumerate() {
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let mut entry = Map::new();
+        let mut values = Map::new();
+        for (column, cell) in header.iter().zip(row) {
+            if cell.is_empty() {
+                continue;
+            }
+            match column.to_ascii_lowercase().as_str() {
+                field @ ("key" | "source" | "context" | "example") => {
+                    entry.insert(field.to_string(), Value::String(cell));
+                }
+                _ => {
+                    values.insert(column.clone(), Value::String(cell));
+                }
+            }
+        }
+        if !entry.contains_key("key") && !entry.contains_key("source") {
+            return Err(DataFileError::Csv(format!("row {} has no Key or Source", line + 2)));
+        }
+        entry.insert("values".to_string(), Value::Object(values));
+        entries.push(Value::Object(entry));
+    }
+    Ok(entries)
+}
+
+/// Render LocalizationTable entries (its `Contents` JSON) as CSV
+pub fn localization_csv(entries: &[Value]) -> String {
+    let mut locales: Vec<&str> = entries
+        .iter()
+        .filter_map(|e| e.get("values").and_then(|v| v.as_object()))
+        .flat_map(|values| values.keys().map(|k| k.as_str()))
+        .collect();
+    locales.sort_unstable();
+    locales.dedup();
+
+    let quote = |cell: &str| {
+        if cell.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", cell.replace('"', "\"\""))
+        } else {
+            cell.to_string()
+        }
+    };
+
+    let mut out = String::from("Key,Source,Context,Example");
+    for locale in &locales {
+        out.push(',');
+        out.push_str(&quote(locale));
+    }
+    out.push('\n');
+
+    for entry in entries {
+        let field = |name: &str| entry.get(name).and_then(|v| v.as_str()).unwrap_or("");
+        let mut cells: Vec<String> = ["key", "source", "context", "example"]
+            .iter()
+            .map(|name| quote(field(name)))
+            .collect();
+        for locale in &locales {
+            let value = entry
+                .get("values")
+                .and_then(|v| v.get(*locale))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            cells.push(quote(value));
+        }
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+// ============================================================================
+// Rojo models
+// ============================================================================
+
+/// Convert a Rojo `.model.json` node to instance JSON
+///
+/// The root is named after the file; children use their `Name`.
+fn model_to_instance(name: &str, model: &Value) -> Result<Value, DataFileError> {
+    let obj = model
+        .as_object()
+        .ok_or_else(|| DataFileError::Model("expected an object".to_string()))?;
+    let class_name = obj
+        .get("ClassName")
+        .and_then(|c| c.as_str())
+        .ok_or_else(|| DataFileError::Model(format!("{} has no ClassName", name)))?;
+
+    let convert_all = |key: &str| -> Map<String, Value> {
+        obj.get(key)
+            .and_then(|p| p.as_object())
+            .map(|props| {
+                props
+                    .iter()
+                    .filter_map(|(prop, value)| {
+                        rojo_value(prop, value).map(|v| (prop.clone(), v))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut instance = json!({
+        "className": class_name,
+        "name": name,
+        "properties": convert_all("Properties"),
+    });
+
+    let attributes = convert_all("Attributes");
+    if !attributes.is_empty() {
+        instance["attributes"] = Value::Object(attributes);
+    }
+    if let Some(tags) = obj.get("Tags").and_then(|t| t.as_array()) {
+        instance["tags"] = Value::Array(tags.clone());
+    }
+
+    // Always present: a model owns its subtree, so an empty list removes children
+    let children = obj
+        .get("Children")
+        .and_then(|c| c.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|child| {
+            let child_name = child
+                .get("Name")
+                .and_then(|n| n.as_str())
+                .ok_or_else(|| DataFileError::Model(format!("child of {} has no Name", name)))?;
+            model_to_instance(child_name, child)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    instance["children"] = Value::Array(children);
+    Ok(instance)
+}
+
+/// Convert a Rojo property value to our `{type, value}` form
+///
+/// Implicit values are typed by their JSON shape (numbers become `float`,
+/// three-number arrays become `Color3` for `*Color*` properties and `Vector3`
+/// otherwise). Explicit values such as `{"Int32": 5}` keep their type.
+/// Unsupported types return `None` and are skipped.
+fn rojo_value(prop: &str, value: &Value) -> Option<Value> {
+    let floats = |v: &Value, n: usize| -> Option<Vec<f64>> {
+        let items = v.as_array()?;
+        (items.len() == n)
+            .then(|| items.iter().map(|i| i.as_f64()).collect::<Option<Vec<_>>>())
+            .flatten()
+    };
+    let xyz = |v: &[f64]| json!({ "x": v[0], "y": v[1], "z": v[2] });
+    let rgb = |v: &[f64]| json!({ "r": v[0], "g": v[1], "b": v[2] });
+    let udim = |v: &Value| floats(v, 2).map(|u| json!({ "scale": u[0], "offset": u[1] as i64 }));
+
+    let (ty, value) = match value {
+        Value::Bool(b) => ("bool", json!(b)),
+        Value::Number(n) => ("float", json!(n)),
+        Value::String(s) => ("string", json!(s)),
+        Value::Array(_) => match value.as_array().map(Vec::len) {
+            Some(2) => floats(value, 2).map(|v| ("Vector2", json!({ "x": v[0], "y": v[1] })))?,
+            Some(3) if prop.contains("Color") => floats(value, 3).map(|v| ("Color3", rgb(&v)))?,
+            Some(3) => floats(value, 3).map(|v| ("Vector3", xyz(&v)))?,
+            _ => return None,
+        },
+        Value::Object(obj) => {
+            let (ty, inner) = obj.iter().next().filter(|_| obj.len() == 1)?;
+            match ty.as_str() {
+                "Bool" => ("bool", json!(inner.as_bool()?)),
+                "String" | "Content" => ("string", json!(inner.as_str()?)),
+                "Float32" => ("float", json!(inner.as_f64()?)),
+                "Float64" => ("double", json!(inner.as_f64()?)),
+                "Int32" => ("int", json!(inner.as_i64()?)),
+                "Int64" => ("int64", json!(inner.as_i64()?)),
+                "Vector2" => floats(inner, 2).map(|v| ("Vector2", json!({ "x": v[0], "y": v[1] })))?,
+                "Vector3" => floats(inner, 3).map(|v| ("Vector3", xyz(&v)))?,
+                "Color3" => floats(inner, 3).map(|v| ("Color3", rgb(&v)))?,
+                "Color3uint8" => floats(inner, 3).map(|v| {
+                    ("Color3uint8", json!({ "r": v[0] as u8, "g": v[1] as u8, "b": v[2] as u8 }))
+                })?,
+                "BrickColor" => ("BrickColor", json!(inner.as_u64()?)),
+                "UDim" => ("UDim", udim(inner)?),
+                "UDim2" => {
+                    let axes = inner.as_array().filter(|a| a.len() == 2)?;
+                    ("UDim2", json!({ "x": udim(&axes[0])?, "y": udim(&axes[1])? }))
+                }
+                "CFrame" => {
+                    let position = floats(inner.get("position")?, 3)?;
+                    let rows = inner.get("orientation")?.as_array().filter(|r| r.len() == 3)?;
+                    let rotation = rows
+                        .iter()
+                        .map(|row| floats(row, 3))
+                        .collect::<Option<Vec<_>>>()?
+                        .concat();
+                    ("CFrame", json!({ "position": position, "rotation": rotation }))
+                }
+                _ => return None,
+            }
+        }
+        Value::Null => return None,
+    };
+    Some(json!({ "type": ty, "value": value }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_file_info() {
+        assert_eq!(data_file_info("Config.json"), Some(("Config", DataFileKind::Json)));
+        assert_eq!(data_file_info("Tree.model.json"), Some(("Tree", DataFileKind::Model)));
+        assert_eq!(data_file_info("Balance.yml"), Some(("Balance", DataFileKind::Yaml)));
+        assert_eq!(data_file_info("Strings.csv"), Some(("Strings", DataFileKind::Csv)));
+        assert_eq!(data_file_info("init.meta.json"), None);
+        assert_eq!(data_file_info("default.project.json"), None);
+        assert_eq!(data_file_info("Main.luau"), None);
+        assert_eq!(data_file_info(".json"), None);
+    }
+
+    #[test]
+    fn test_json_module() {
+        let inst = parse_data_file(
+            "Items.json",
+            r#"{"sword": {"damage": 10, "name": "Iron \"Sword\""}, "end": [1, 2.5, true, null]}"#,
+        )
+        .unwrap();
+        assert_eq!(inst["className"], "ModuleScript");
+        assert_eq!(inst["name"], "Items");
+        let source = inst["properties"]["Source"]["value"].as_str().unwrap();
+        assert!(source.starts_with("return {\n"));
+        assert!(source.contains("[\"end\"] = {\n\t\t1,\n\t\t2.5,\n\t\ttrue,\n\t\tnil,\n\t},"));
+        assert!(source.contains("\tsword = {\n\t\tdamage = 10,\n\t\tname = \"Iron \\\"Sword\\\"\",\n\t},"));
+        assert_eq!(inst["source"], inst["properties"]["Source"]["value"]);
+    }
+
+    #[test]
+    fn test_control_characters_escaped() {
+        assert_eq!(luau_string("\u{1}2"), "\"\\u{1}2\"");
+        assert_eq!(luau_string("a\u{85}\u{7f}"), "\"a\\u{85}\\u{7F}\"");
+        assert_eq!(luau_string("tab\there"), "\"tab\\there\"");
+    }
+
+    #[test]
+    fn test_toml_and_yaml_modules() {
+        let toml = parse_data_file("Balance.toml", "[enemy]\nhealth = 100\n").unwrap();
+        let yaml = parse_data_file("Balance.yaml", "enemy:\n  health: 100\n").unwrap();
+        assert_eq!(toml["properties"]["Source"], yaml["properties"]["Source"]);
+        assert_eq!(
+            toml["properties"]["Source"]["value"],
+            "return {\n\tenemy = {\n\t\thealth = 100,\n\t},\n}\n"
+        );
+        assert!(parse_data_file("Bad.toml", "[[").is_err());
+    }
+
+    #[test]
+    fn test_text_value() {
+        let inst = parse_data_file("Motd.txt", "Welcome!\n").unwrap();
+        assert_eq!(inst["className"], "StringValue");
+        assert_eq!(inst["properties"]["Value"]["value"], "Welcome!\n");
+    }
+
+    #[test]
+    fn test_localization_csv_round_trip() {
+        let csv = "Key,Source,Context,Example,es,fr\r\n\
+                   greeting,Hello,,,Hola,Bonjour\r\n\
+                   ,\"Say \"\"hi\"\", friend\",,,\"Di \"\"hola\"\", amigo\",\n\
+                   \n";
+        let entries = parse_localization_csv(csv).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["key"], "greeting");
+        assert_eq!(entries[0]["values"]["fr"], "Bonjour");
+        assert_eq!(entries[1]["source"], "Say \"hi\", friend");
+        assert!(entries[1].get("key").is_none());
+        assert!(entries[1]["values"].get("fr").is_none());
+
+        let written = localization_csv(&entries);
+        assert_eq!(parse_localization_csv(&written).unwrap(), entries);
+
+        assert!(parse_localization_csv("Key,es\n,Hola\n").is_err());
+        assert!(parse_localization_csv("Key,es\n\"open,Hola\n").is_err());
+
+        let inst = parse_data_file("Strings.csv", csv).unwrap();
+        assert_eq!(inst["className"], "LocalizationTable");
+        let contents: Value =
+            serde_json::from_str(inst["properties"]["Contents"]["value"].as_str().unwrap()).unwrap();
+        assert_eq!(contents, Value::Array(entries));
+    }
+
+    #[test]
+    fn test_model_json() {
+        let inst = parse_data_file(
+            "Spawn.model.json",
+            r#"{
+                "ClassName": "Model",
+                "Attributes": {"Team": "Red"},
+                "Tags": ["Spawn"],
+                "Children": [{
+                    "Name": "Pad",
+                    "ClassName": "Part",
+                    "Properties": {
+                        "Anchored": true,
+                        "Size": [4, 1, 4],
+                        "Color": [1, 0, 0],
+                        "Transparency": 0.5,
+                        "CFrame": {"CFrame": {"position": [0, 5, 0], "orientation": [[1, 0, 0], [0, 1, 0], [0, 0, 1]]}},
+                        "CollisionGroup": {"String": "Pads"},
+                        "Unknown": {"SharedString": "abc"}
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(inst["className"], "Model");
+        assert_eq!(inst["name"], "Spawn");
+        assert_eq!(inst["attributes"]["Team"], json!({"type": "string", "value": "Red"}));
+        assert_eq!(inst["tags"], json!(["Spawn"]));
+
+        let flat = flatten_instance(&inst, "Workspace/Spawn");
+        assert_eq!(flat.len(), 2);
+        assert_eq!(flat[0].0, "Workspace/Spawn");
+        assert!(flat[0].1.get("children").is_none());
+        let (path, pad) = &flat[1];
+        assert_eq!(path, "Workspace/Spawn/Pad");
+        let props = &pad["properties"];
+        assert_eq!(props["Anchored"], json!({"type": "bool", "value": true}));
+        assert_eq!(props["Size"]["type"], "Vector3");
+        assert_eq!(props["Color"]["value"], json!({"r": 1.0, "g": 0.0, "b": 0.0}));
+        assert_eq!(props["Transparency"]["type"], "float");
+        assert_eq!(props["CFrame"]["value"]["position"], json!([0.0, 5.0, 0.0]));
+        assert_eq!(props["CollisionGroup"]["value"], "Pads");
+        assert!(props.get("Unknown").is_none());
+
+        assert!(parse_data_file("Bad.model.json", r#"{"Name": "x"}"#).is_err());
+    }
+}
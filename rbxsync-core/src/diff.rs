@@ -0,0 +1,627 @@
+//! Comparing instances between files and Studio, or between two snapshots
+//!
+//! Sending every property of every instance to compare a whole place would be
+//! slow, so both sides hash a canonical form of each instance's properties,
+//! attributes, tags and script source. Only instances whose hashes differ are
+//! fetched in full and compared value by value.
+//!
+//! [`diff_trees`] compares two complete sets of instances offline (project
+//! trees, git revisions or place files) and also detects moved and renamed
+//! instances.
+//!
+//! The plugin implements the same canonical form and hash in
+//! `plugin/src/Diff.luau`; a change to either must be made to both. Numbers
+//! are rounded to 4 decimal places, which hides float noise from the JSON
+//! round trip through the files.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::TextDiff;
+
+use crate::SourceFormat;
+
+/// Hashes of the parts of an instance a diff compares (`None` when empty)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceHashes {
+    pub properties: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl InstanceHashes {
+    /// Hashes of serialized instance data, with the script source in
+    /// `properties.Source` or `source`
+    pub fn of(inst: &Value) -> Self {
+        let attributes = inst.get("attributes").filter(|a| !is_empty(a));
+        let tags = sorted_tags(inst);
+        Self {
+            properties: content_hash(&canonical(&Value::Object(properties(inst)))),
+            attributes: attributes.map(|a| content_hash(&canonical(a))),
+            tags: (!tags.is_empty()).then(|| content_hash(&canonical(&Value::from(tags)))),
+            source: instance_source(inst).map(|s| content_hash(&SourceFormat::to_studio(s))),
+        }
+    }
+}
+
+/// Canonical text of a JSON value: object keys sorted, strings
+/// length-prefixed, numbers rounded, empty arrays and objects alike
+pub fn canonical(value: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&canonical_number(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => write_string(s, out),
+        _ if is_empty(value) => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for key in keys {
+                write_string(key, out);
+                out.push('=');
+                write_canonical(&map[key], out);
+                out.push(';');
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('s');
+    out.push_str(&s.len().to_string());
+    out.push(':');
+    out.push_str(s);
+}
+
+/// A number rounded to 4 decimal places without trailing zeros (`1.5`, `-2`)
+pub fn canonical_number(n: f64) -> String {
+    if n.is_nan() {
+        return "nan".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let s = format!("{:.4}", n);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// 32-bit FNV-1a over little-endian 4-byte words (the last one zero-padded),
+/// then the length; 8 hex digits
+pub fn content_hash(text: &str) -> String {
+    const OFFSET: u32 = 2_166_136_261;
+    const PRIME: u32 = 16_777_619;
+
+    let mut hash = OFFSET;
+    for chunk in text.as_bytes().chunks(4) {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        hash = (hash ^ u32::from_le_bytes(word)).wrapping_mul(PRIME);
+    }
+    hash = (hash ^ text.len() as u32).wrapping_mul(PRIME);
+    format!("{:08x}", hash)
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Properties other than `Source`
+fn properties(inst: &Value) -> serde_json::Map<String, Value> {
+    let mut props = inst
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .cloned()
+        .unwrap_or_default();
+    props.remove("Source");
+    props
+}
+
+fn sorted_tags(inst: &Value) -> Vec<String> {
+    let mut tags: Vec<String> = inst
+        .get("tags")
+        .and_then(|t| t.as_array())
+        .map(|t| t.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    tags.sort();
+    tags
+}
+
+/// Script source from instance data (`properties.Source.value` or `source`)
+pub fn instance_source(inst: &Value) -> Option<&str> {
+    inst.get("properties")
+        .and_then(|p| p.get("Source"))
+        .and_then(|s| s.get("value"))
+        .and_then(|v| v.as_str())
+        .or_else(|| inst.get("source").and_then(|v| v.as_str()))
+}
+
+/// A property or attribute whose value differs (`None` where it is missing)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueChange {
+    pub name: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// How an instance differs between two sides: the old side (Studio, or the
+/// first snapshot) and the new side (the files, or the second snapshot)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDiff {
+    pub path: String,
+
+    /// Class on the new side
+    pub class_name: String,
+
+    /// Class on the old side, when it differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_class_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<ValueChange>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<ValueChange>,
+
+    /// Tags only on the new side
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags_added: Vec<String>,
+
+    /// Tags only on the old side
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags_removed: Vec<String>,
+
+    /// Unified diff from the old source to the new one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_diff: Option<String>,
+}
+
+/// Which properties [`diff_instance`] compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyScope {
+    /// Properties the new side has. The old side may report more (Studio
+    /// serializes every property), which syncing the new side leaves alone.
+    New,
+
+    /// Properties both sides have, for when either side is a place file that
+    /// carries every property
+    Shared,
+
+    /// Properties either side has, for two snapshots of a project
+    All,
+}
+
+/// Compare two versions of an instance
+///
+/// Returns `None` when nothing differs.
+pub fn diff_instance(
+    path: &str,
+    old: &Value,
+    new: &Value,
+    format: &SourceFormat,
+    scope: PropertyScope,
+) -> Option<InstanceDiff> {
+    let class_of = |inst: &Value| inst.get("className").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let class_name = class_of(new);
+    let old_class = class_of(old);
+
+    let old_props = properties(old);
+    let new_props = properties(new);
+    let properties = if scope == PropertyScope::All {
+        diff_maps(Some(&Value::Object(old_props)), Some(&Value::Object(new_props)))
+    } else {
+        new_props
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let old_value = old_props.get(&name);
+                if scope == PropertyScope::Shared && old_value.is_none() {
+                    return None;
+                }
+                let same = old_value.is_some_and(|o| canonical(o) == canonical(&value));
+                (!same).then(|| ValueChange {
+                    name,
+                    old: old_value.cloned(),
+                    new: Some(value),
+                })
+            })
+            .collect()
+    };
+
+    let attributes = diff_maps(old.get("attributes"), new.get("attributes"));
+
+    let old_tags = sorted_tags(old);
+    let new_tags = sorted_tags(new);
+    let tags_added = new_tags.iter().filter(|t| !old_tags.contains(t)).cloned().collect();
+    let tags_removed = old_tags.iter().filter(|t| !new_tags.contains(t)).cloned().collect();
+
+    let source_diff = match (instance_source(old), instance_source(new)) {
+        (old_source, Some(new_source)) if !format.same_source(old_source.unwrap_or_default(), new_source) => {
+            Some(unified_diff(
+                path,
+                &SourceFormat::to_studio(old_source.unwrap_or_default()),
+                &SourceFormat::to_studio(new_source),
+            ))
+        }
+        _ => None,
+    };
+
+    let diff = InstanceDiff {
+        path: path.to_string(),
+        old_class_name: (old_class != class_name).then_some(old_class),
+        class_name,
+        properties,
+        attributes,
+        tags_added,
+        tags_removed,
+        source_diff,
+    };
+    let changed = diff.old_class_name.is_some()
+        || !diff.properties.is_empty()
+        || !diff.attributes.is_empty()
+        || !diff.tags_added.is_empty()
+        || !diff.tags_removed.is_empty()
+        || diff.source_diff.is_some();
+    changed.then_some(diff)
+}
+
+/// Entries of two JSON objects that differ, in either direction
+fn diff_maps(old: Option<&Value>, new: Option<&Value>) -> Vec<ValueChange> {
+    let empty = serde_json::Map::new();
+    let old = old.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let new = new.and_then(|v| v.as_object()).unwrap_or(&empty);
+
+    let mut names: Vec<&String> = new.keys().chain(old.keys().filter(|k| !new.contains_key(*k))).collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (o, n) = (old.get(name), new.get(name));
+            let same = matches!((o, n), (Some(o), Some(n)) if canonical(o) == canonical(n));
+            (!same).then(|| ValueChange {
+                name: name.clone(),
+                old: o.cloned(),
+                new: n.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Unified diff (3 lines of context) from `old` to `new`, with headers
+/// `a/<path>` and `b/<path>`
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// An instance only one side has
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeEntry {
+    pub path: String,
+    pub class_name: String,
+}
+
+/// An instance at a different path on each side, matched by its
+/// `referenceId` or, failing that, by identical content
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedInstance {
+    pub from: String,
+    pub to: String,
+    pub class_name: String,
+
+    /// What else changed, when matched by `referenceId`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<InstanceDiff>,
+}
+
+/// Result of [`diff_trees`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeDiff {
+    /// Only on the new side
+    pub added: Vec<TreeEntry>,
+    /// Only on the old side
+    pub removed: Vec<TreeEntry>,
+    /// Moved or renamed (descendants that moved along with a parent are not listed)
+    pub moved: Vec<MovedInstance>,
+    /// At the same path on both sides, but different
+    pub modified: Vec<InstanceDiff>,
+    /// At the same path on both sides and identical
+    pub unchanged: usize,
+    /// Paths shared by several instances on one side, which can't be matched
+    pub ambiguous: Vec<String>,
+}
+
+impl TreeDiff {
+    /// Whether the two sides are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Compare two sets of instances, each with its `path`
+///
+/// Instances at the same path are compared value by value. The rest are
+/// matched by `referenceId`, then by class and content where exactly one
+/// instance on each side has them, and reported as moves. Anything left is
+/// added or removed.
+pub fn diff_trees(old: &[Value], new: &[Value], format: &SourceFormat, scope: PropertyScope) -> TreeDiff {
+    let mut ambiguous = HashSet::new();
+    let old = index_by_path(old, &mut ambiguous);
+    let new = index_by_path(new, &mut ambiguous);
+    let mut diff = TreeDiff::default();
+
+    let mut old_only: Vec<(&str, &Value)> = Vec::new();
+    for (&path, &inst) in &old {
+        if !new.contains_key(path) {
+            old_only.push((path, inst));
+        }
+    }
+    let mut new_only: Vec<(&str, &Value)> = Vec::new();
+    for (&path, &new_inst) in &new {
+        match old.get(path) {
+            None => new_only.push((path, new_inst)),
+            Some(_) if ambiguous.contains(path) => {}
+            Some(&old_inst) => match diff_instance(path, old_inst, new_inst, format, scope) {
+                Some(changes) => diff.modified.push(changes),
+                None => diff.unchanged += 1,
+            },
+        }
+    }
+
+    // Moves by referenceId
+    let reference_id = |inst: &Value| inst.get("referenceId").and_then(|v| v.as_str()).map(str::to_string);
+    let mut old_by_id: HashMap<String, (&str, &Value)> = HashMap::new();
+    for &(path, inst) in &old_only {
+        if let Some(id) = reference_id(inst) {
+            old_by_id.insert(id, (path, inst));
+        }
+    }
+    let mut matched_old: HashSet<&str> = HashSet::new();
+    let mut matched_new: HashSet<&str> = HashSet::new();
+    for &(to, new_inst) in &new_only {
+        let Some((from, old_inst)) = reference_id(new_inst).and_then(|id| old_by_id.remove(&id)) else {
+            continue;
+        };
+        diff.moved.push(MovedInstance {
+            from: from.to_string(),
+            to: to.to_string(),
+            class_name: class_name(new_inst),
+            changes: diff_instance(to, old_inst, new_inst, format, scope),
+        });
+        matched_old.insert(from);
+        matched_new.insert(to);
+    }
+
+    // Moves by content, where it identifies one instance on each side
+    let content_key = |inst: &Value| (class_name(inst), InstanceHashes::of(inst));
+    let mut old_by_content: HashMap<_, Vec<&str>> = HashMap::new();
+    for &(path, inst) in old_only.iter().filter(|(p, _)| !matched_old.contains(p)) {
+        old_by_content.entry(content_key(inst)).or_default().push(path);
+    }
+    let mut new_by_content: HashMap<_, Vec<&str>> = HashMap::new();
+    for &(path, inst) in new_only.iter().filter(|(p, _)| !matched_new.contains(p)) {
+        new_by_content.entry(content_key(inst)).or_default().push(path);
+    }
+    for (key, to) in &new_by_content {
+        if let (Some([from]), [to]) = (old_by_content.get(key).map(Vec::as_slice), to.as_slice()) {
+            diff.moved.push(MovedInstance {
+                from: from.to_string(),
+                to: to.to_string(),
+                class_name: key.0.clone(),
+                changes: None,
+            });
+            matched_old.insert(from);
+            matched_new.insert(to);
+        }
+    }
+
+    // Descendants of a moved instance move with it
+    let moves: HashSet<(String, String)> = diff.moved.iter().map(|m| (m.from.clone(), m.to.clone())).collect();
+    diff.moved.retain(|m| {
+        let implied = match (m.from.rsplit_once('/'), m.to.rsplit_once('/')) {
+            (Some((from_parent, from_name)), Some((to_parent, to_name))) => {
+                from_name == to_name && moves.contains(&(from_parent.to_string(), to_parent.to_string()))
+            }
+            _ => false,
+        };
+        !implied || m.changes.is_some()
+    });
+
+    for (path, inst) in old_only.into_iter().filter(|(p, _)| !matched_old.contains(p)) {
+        diff.removed.push(TreeEntry { path: path.to_string(), class_name: class_name(inst) });
+    }
+    for (path, inst) in new_only.into_iter().filter(|(p, _)| !matched_new.contains(p)) {
+        diff.added.push(TreeEntry { path: path.to_string(), class_name: class_name(inst) });
+    }
+
+    diff.added.sort_by(|a, b| a.path.cmp(&b.path));
+    diff.removed.sort_by(|a, b| a.path.cmp(&b.path));
+    diff.moved.sort_by(|a, b| a.to.cmp(&b.to));
+    diff.modified.sort_by(|a, b| a.path.cmp(&b.path));
+    diff.ambiguous = ambiguous.into_iter().map(str::to_string).collect();
+    diff.ambiguous.sort();
+    diff
+}
+
+fn index_by_path<'a>(instances: &'a [Value], ambiguous: &mut HashSet<&'a str>) -> HashMap<&'a str, &'a Value> {
+    let mut index = HashMap::new();
+    for inst in instances {
+        let Some(path) = inst.get("path").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if index.insert(path, inst).is_some() {
+            ambiguous.insert(path);
+        }
+    }
+    index
+}
+
+fn class_name(inst: &Value) -> String {
+    inst.get("className").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_form() {
+        assert_eq!(canonical_number(1.5), "1.5");
+        assert_eq!(canonical_number(2.0), "2");
+        assert_eq!(canonical_number(-0.00001), "0");
+        assert_eq!(canonical_number(0.1 + 0.2), "0.3");
+
+        let a = json!({ "b": [1, 2.00001], "a": "x", "e": [] });
+        let b = json!({ "a": "x", "e": {}, "b": [1.0, 2.0] });
+        assert_eq!(canonical(&a), canonical(&b));
+        assert_eq!(canonical(&a), "{s1:a=s1:x;s1:b=[1,2];s1:e={};}");
+    }
+
+    #[test]
+    fn test_content_hash_vectors() {
+        // Mirrored in plugin/src/Diff.luau; both must produce these
+        assert_eq!(content_hash(""), "050c5d1f");
+        assert_eq!(content_hash("abc"), "779c06fd");
+        assert_eq!(content_hash("print('hi')\n"), "d11a245c");
+        assert_ne!(content_hash("abcd"), content_hash("abcd\0"));
+    }
+
+    #[test]
+    fn test_hashes_ignore_source_format() {
+        let file = json!({
+            "className": "Script",
+            "properties": { "Disabled": { "type": "bool", "value": false },
+                            "Source": { "type": "string", "value": "print(1)\r\n" } },
+            "tags": ["b", "a"]
+        });
+        let studio = json!({
+            "className": "Script",
+            "properties": { "Disabled": { "type": "bool", "value": false } },
+            "source": "\u{feff}print(1)\n",
+            "tags": ["a", "b"],
+            "attributes": {}
+        });
+        assert_eq!(InstanceHashes::of(&file), InstanceHashes::of(&studio));
+    }
+
+    #[test]
+    fn test_diff_instance() {
+        let file = json!({
+            "className": "Part",
+            "properties": { "Anchored": { "type": "bool", "value": true },
+                            "Size": { "type": "Vector3", "value": { "x": 4, "y": 1, "z": 2 } } },
+            "attributes": { "Health": { "type": "number", "value": 100 } },
+            "tags": ["Enemy"]
+        });
+        let studio = json!({
+            "className": "Part",
+            "properties": { "Anchored": { "type": "bool", "value": true },
+                            "Size": { "type": "Vector3", "value": { "x": 4.00001, "y": 1, "z": 3 } },
+                            "Transparency": { "type": "float", "value": 0 } },
+            "attributes": { "Speed": { "type": "number", "value": 5 } },
+            "tags": ["Boss"]
+        });
+
+        let diff = diff_instance("Workspace/Part", &studio, &file, &SourceFormat::default(), PropertyScope::New).unwrap();
+        let names = |changes: &[ValueChange]| changes.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&diff.properties), vec!["Size"]);
+        assert_eq!(names(&diff.attributes), vec!["Health", "Speed"]);
+        assert_eq!(diff.tags_added, vec!["Enemy"]);
+        assert_eq!(diff.tags_removed, vec!["Boss"]);
+        assert!(diff_instance("Workspace/Part", &file, &file, &SourceFormat::default(), PropertyScope::New).is_none());
+
+        // Properties only one side has are compared in neither direction in
+        // shared scope, and in both in full scope
+        let diff = diff_instance("Workspace/Part", &file, &studio, &SourceFormat::default(), PropertyScope::Shared).unwrap();
+        assert_eq!(names(&diff.properties), vec!["Size"]);
+        let diff = diff_instance("Workspace/Part", &studio, &file, &SourceFormat::default(), PropertyScope::All).unwrap();
+        assert_eq!(names(&diff.properties), vec!["Size", "Transparency"]);
+    }
+
+    #[test]
+    fn test_source_diff() {
+        let file = json!({ "className": "ModuleScript", "properties": {}, "source": "local a = 1\nreturn a\n" });
+        let studio = json!({ "className": "ModuleScript", "properties": {}, "source": "local a = 2\nreturn a\n" });
+        let diff = diff_instance("ReplicatedStorage/Mod", &studio, &file, &SourceFormat::default(), PropertyScope::New).unwrap();
+        assert_eq!(
+            diff.source_diff.unwrap(),
+            "--- a/ReplicatedStorage/Mod\n+++ b/ReplicatedStorage/Mod\n@@ -1,2 +1,2 @@\n-local a = 2\n+local a = 1\n return a\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_trees_detects_moves() {
+        let part = |path: &str, id: &str, anchored: bool| {
+            json!({ "path": path, "className": "Part", "referenceId": id,
+                    "properties": { "Anchored": { "type": "bool", "value": anchored } } })
+        };
+        let folder = |path: &str| json!({ "path": path, "className": "Folder", "properties": {} });
+        let old = vec![
+            part("Workspace/Same", "1", true),
+            part("Workspace/Old", "2", true),
+            folder("Workspace/Map"),
+            part("Workspace/Map/Wall", "3", true),
+            part("Workspace/Gone", "4", false),
+        ];
+        let new = vec![
+            part("Workspace/Same", "1", false),
+            part("Workspace/Renamed", "2", true),
+            folder("Workspace/Level"),
+            part("Workspace/Level/Wall", "3", true),
+            part("Workspace/New", "5", true),
+        ];
+
+        let diff = diff_trees(&old, &new, &SourceFormat::default(), PropertyScope::New);
+        assert_eq!(diff.modified.len(), 1);
+        assert_eq!(diff.modified[0].path, "Workspace/Same");
+        let moves: Vec<(&str, &str)> = diff.moved.iter().map(|m| (m.from.as_str(), m.to.as_str())).collect();
+        // Wall moved along with its folder, which was matched by content
+        assert_eq!(moves, vec![("Workspace/Map", "Workspace/Level"), ("Workspace/Old", "Workspace/Renamed")]);
+        assert_eq!(diff.added, vec![TreeEntry { path: "Workspace/New".into(), class_name: "Part".into() }]);
+        assert_eq!(diff.removed, vec![TreeEntry { path: "Workspace/Gone".into(), class_name: "Part".into() }]);
+        assert!(!diff.is_empty());
+        assert!(diff_trees(&old, &old, &SourceFormat::default(), PropertyScope::New).is_empty());
+    }
+}
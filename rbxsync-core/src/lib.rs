@@ -6,23 +6,46 @@
 //! - Project configuration
 //! - Plugin building (.rbxm generation)
 //! - Rojo project file parsing and migration
+//! - Rojo-style data files (.json, .toml, .yaml, .txt, .csv, .model.json)
+//! - Line ending and BOM normalization of script sources
+//! - Instance hashing and diffs between files, Studio and snapshots
+//! - Reading place and model files as instance data
+//! - Three-way merging of .rbxjson files for git
+//! - Luau syntax checks and project tree verification
 //! - Luau obfuscation for build-time transforms
 
+pub mod data_files;
+pub mod diff;
+pub mod luau_syntax;
+pub mod merge;
 pub mod obfuscator;
 pub mod path_utils;
+pub mod place_file;
 pub mod plugin_builder;
 pub mod rojo;
+pub mod source_format;
 pub mod types;
+pub mod verify;
 
 // Re-export commonly used types
+pub use data_files::{
+    data_file_info, flatten_instance, localization_csv, parse_data_file, parse_localization_csv,
+    DataFileError, DataFileKind,
+};
+pub use diff::{diff_instance, diff_trees, InstanceDiff, InstanceHashes, MovedInstance, PropertyScope, TreeDiff, TreeEntry, ValueChange};
+pub use luau_syntax::{check_luau_syntax, LuauSyntaxError};
+pub use merge::{merge_instance_files, MergeOutcome};
 pub use obfuscator::{Obfuscator, ObfuscatorConfig, ObfuscationResult};
+pub use place_file::{is_place_file, read_place_instances};
 pub use plugin_builder::{build_plugin, build_plugin_with_stats, find_existing_rbxsync_plugin, get_studio_plugins_folder, install_plugin, PluginBuildConfig, PluginBuildStats};
 pub use rojo::{
     find_rojo_project, parse_rojo_project, rojo_to_tree_mapping, RojoError, RojoProject, RojoTree,
 };
+pub use source_format::{BomPolicy, LineEndings, SourceFormat, TrailingNewline};
+pub use verify::{reference_ids, verify_tree, ChangedFiles, IssueKind, VerifyIssue};
 pub use types::{
-    AttributeValue, CFrame, Color3, EnumValue, Instance, InstanceMeta, ProjectConfig,
-    PropertyValue, Vector2, Vector3,
+    AttributeValue, CFrame, Color3, EnumValue, Instance, InstanceMeta, LimitsConfig, ProjectConfig,
+    PropertyValue, Vector2, Vector3, SERVICES,
     // Wally package support
     PackageConfig, PackageDirectories, WallyError, WallyLock, WallyLockedPackage,
     WallyManifest, WallyPackageInfo, find_wally_manifest, find_wally_lock, is_package_path,
@@ -30,4 +53,7 @@ pub use types::{
     Feature, FeaturePriority, FeatureStatus, FeaturesFile, GameDefinition,
     HarnessState, SessionLog, SessionLogEntry,
 };
-pub use path_utils::{normalize_path, path_to_string, path_with_suffix, pathbuf_with_suffix, sanitize_filename};
+pub use path_utils::{
+    file_instance_path, is_init_script, normalize_path, path_to_string, path_with_suffix,
+    pathbuf_with_suffix, sanitize_filename, script_file_info,
+};
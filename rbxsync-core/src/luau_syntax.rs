@@ -0,0 +1,481 @@
+//! Fast syntax check for Luau sources
+//!
+//! Not a full parser: the source is tokenized and its blocks and brackets
+//! are matched, which catches what usually breaks a file in practice, such
+//! as a missing or extra `end`, unbalanced brackets, unfinished strings and
+//! comments, and characters that aren't Luau. Valid files always pass.
+//! Errors are worded like Luau's own.
+
+/// First syntax error in a Luau source
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct LuauSyntaxError {
+    /// 1-based line of the error
+    pub line: usize,
+    pub message: String,
+}
+
+/// A bracket or block waiting for its closing token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    Paren,
+    Bracket,
+    Brace,
+    /// `{` inside an interpolated string
+    Interpolation,
+    Function,
+    Do,
+    /// `if` statement waiting for `then`
+    IfCondition,
+    /// `if` statement body, closed by `end`
+    If,
+    /// `if` expression waiting for `then`
+    IfExpressionCondition,
+    /// `if` expression waiting for `else`
+    IfExpression,
+    Repeat,
+}
+
+impl Open {
+    fn opener(self) -> &'static str {
+        match self {
+            Open::Paren => "(",
+            Open::Bracket => "[",
+            Open::Brace | Open::Interpolation => "{",
+            Open::Function => "function",
+            Open::Do => "do",
+            Open::IfCondition | Open::If | Open::IfExpressionCondition | Open::IfExpression => "if",
+            Open::Repeat => "repeat",
+        }
+    }
+
+    fn closer(self) -> &'static str {
+        match self {
+            Open::Paren => ")",
+            Open::Bracket => "]",
+            Open::Brace | Open::Interpolation => "}",
+            Open::Function | Open::Do | Open::If => "end",
+            Open::IfCondition | Open::IfExpressionCondition => "then",
+            Open::IfExpression => "else",
+            Open::Repeat => "until",
+        }
+    }
+}
+
+struct Checker<'a> {
+    source: &'a [u8],
+    pos: usize,
+    line: usize,
+    stack: Vec<(Open, usize)>,
+    /// Whether the next token starts an expression, which makes `if` an
+    /// `if` expression rather than a statement
+    expression_next: bool,
+}
+
+/// Check a Luau source for syntax errors, returning the first one found
+pub fn check_luau_syntax(source: &str) -> Result<(), LuauSyntaxError> {
+    Checker {
+        source: source.as_bytes(),
+        pos: 0,
+        line: 1,
+        stack: Vec::new(),
+        expression_next: false,
+    }
+    .run()
+}
+
+impl Checker<'_> {
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.source.get(self.pos + offset).copied()
+    }
+
+    fn error<T>(&self, line: usize, message: impl Into<String>) -> Result<T, LuauSyntaxError> {
+        Err(LuauSyntaxError { line, message: message.into() })
+    }
+
+    fn run(mut self) -> Result<(), LuauSyntaxError> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'\n' => {
+                    self.line += 1;
+                    self.pos += 1;
+                }
+                b' ' | b'\t' | b'\r' | 0x0b | 0x0c => self.pos += 1,
+                b'-' if self.peek(1) == Some(b'-') => {
+                    self.pos += 2;
+                    if let Some(level) = self.long_bracket() {
+                        self.skip_long(level, "Unfinished long comment")?;
+                    } else {
+                        while self.peek(0).is_some_and(|c| c != b'\n') {
+                            self.pos += 1;
+                        }
+                    }
+                }
+                b'"' | b'\'' => {
+                    self.string(c)?;
+                    self.expression_next = false;
+                }
+                b'`' => {
+                    self.pos += 1;
+                    self.interpolated_string()?;
+                }
+                b'[' if self.long_bracket().is_some() => {
+                    let level = self.long_bracket().unwrap_or_default();
+                    self.skip_long(level, "Malformed string; did you forget to finish it?")?;
+                    self.expression_next = false;
+                }
+                b'(' | b'[' | b'{' => {
+                    let open = match c {
+                        b'(' => Open::Paren,
+                        b'[' => Open::Bracket,
+                        _ => Open::Brace,
+                    };
+                    self.stack.push((open, self.line));
+                    self.pos += 1;
+                    self.expression_next = true;
+                }
+                b')' | b']' | b'}' => {
+                    self.pos += 1;
+                    if c == b'}' && matches!(self.stack.last(), Some((Open::Interpolation, _))) {
+                        self.stack.pop();
+                        self.interpolated_string()?;
+                        continue;
+                    }
+                    let token = (c as char).to_string();
+                    self.close(&token, |open| matches!((open, c), (Open::Paren, b')') | (Open::Bracket, b']') | (Open::Brace, b'}')))?;
+                    self.expression_next = false;
+                }
+                b'0'..=b'9' => self.number(),
+                b'.' if self.peek(1).is_some_and(|c| c.is_ascii_digit()) => self.number(),
+                b'.' => {
+                    let dots = self.source[self.pos..].iter().take(3).take_while(|&&c| c == b'.').count();
+                    self.pos += dots;
+                    // `...` is a value; `.` and `..` are followed by one
+                    self.expression_next = dots != 3;
+                }
+                c if c == b'_' || c.is_ascii_alphabetic() => {
+                    let start = self.pos;
+                    while self.peek(0).is_some_and(|c| c == b'_' || c.is_ascii_alphanumeric()) {
+                        self.pos += 1;
+                    }
+                    let word = std::str::from_utf8(&self.source[start..self.pos]).unwrap_or_default();
+                    self.word(word)?;
+                }
+                b';' => {
+                    self.pos += 1;
+                    self.expression_next = false;
+                }
+                // `?` and `>` usually end a type (`string?`, `{ T }?`, `Map<K, V>`),
+                // and an `if` after a statement is a new statement
+                b'?' | b'>' if self.peek(1) != Some(b'=') => {
+                    self.pos += 1;
+                    self.expression_next = false;
+                }
+                b'+' | b'-' | b'*' | b'/' | b'%' | b'^' | b'#' | b'=' | b'<' | b'>' | b'~' | b',' | b':' | b'&' | b'|' | b'@' => {
+                    self.pos += 1;
+                    self.expression_next = true;
+                }
+                _ => {
+                    let found = std::str::from_utf8(&self.source[self.pos..])
+                        .ok()
+                        .and_then(|rest| rest.chars().next())
+                        .unwrap_or(c as char);
+                    return self.error(self.line, format!("Unexpected symbol '{}'", found));
+                }
+            }
+        }
+
+        match self.stack.last() {
+            Some(&(Open::Interpolation, line)) => self.error(line, "Malformed interpolated string; did you forget to add a '`'?"),
+            Some(&(open, line)) => self.error(
+                self.line,
+                format!("Expected '{}' (to close '{}' at line {}), got <eof>", open.closer(), open.opener(), line),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Handle an identifier or keyword
+    fn word(&mut self, word: &str) -> Result<(), LuauSyntaxError> {
+        let line = self.line;
+        self.expression_next = match word {
+            "function" => {
+                self.stack.push((Open::Function, line));
+                false
+            }
+            "do" => {
+                self.stack.push((Open::Do, line));
+                false
+            }
+            "repeat" => {
+                self.stack.push((Open::Repeat, line));
+                false
+            }
+            "if" => {
+                let open = if self.expression_next { Open::IfExpressionCondition } else { Open::IfCondition };
+                self.stack.push((open, line));
+                true
+            }
+            "then" => match self.stack.last_mut() {
+                Some((open @ Open::IfCondition, _)) => {
+                    *open = Open::If;
+                    false
+                }
+                Some((open @ Open::IfExpressionCondition, _)) => {
+                    *open = Open::IfExpression;
+                    true
+                }
+                _ => return self.unexpected(word),
+            },
+            "elseif" => match self.stack.last_mut() {
+                Some((open @ Open::If, _)) => {
+                    *open = Open::IfCondition;
+                    true
+                }
+                Some((open @ Open::IfExpression, _)) => {
+                    *open = Open::IfExpressionCondition;
+                    true
+                }
+                _ => return self.unexpected(word),
+            },
+            "else" => match self.stack.last() {
+                Some((Open::If, _)) => false,
+                Some((Open::IfExpression, _)) => {
+                    self.stack.pop();
+                    true
+                }
+                _ => return self.unexpected(word),
+            },
+            "end" => {
+                self.close(word, |open| matches!(open, Open::Function | Open::Do | Open::If))?;
+                false
+            }
+            "until" => {
+                self.close(word, |open| open == Open::Repeat)?;
+                true
+            }
+            "return" | "and" | "or" | "not" | "in" | "while" => true,
+            _ => false,
+        };
+        Ok(())
+    }
+
+    /// Error for a token that doesn't fit the innermost open block
+    fn unexpected<T>(&self, token: &str) -> Result<T, LuauSyntaxError> {
+        match self.stack.last() {
+            Some(&(open, line)) => self.error(
+                self.line,
+                format!("Expected '{}' (to close '{}' at line {}), got '{}'", open.closer(), open.opener(), line, token),
+            ),
+            None => self.error(self.line, format!("Unexpected '{}'", token)),
+        }
+    }
+
+    /// Pop the innermost open block if `token` closes it
+    fn close(&mut self, token: &str, closes: impl Fn(Open) -> bool) -> Result<(), LuauSyntaxError> {
+        match self.stack.last() {
+            Some(&(open, _)) if closes(open) => {
+                self.stack.pop();
+                Ok(())
+            }
+            Some(_) => self.unexpected(token),
+            None => self.error(self.line, format!("Expected <eof>, got '{}'", token)),
+        }
+    }
+
+    /// Level of a long bracket (`[[` or `[==[`) starting here
+    fn long_bracket(&self) -> Option<usize> {
+        if self.peek(0) != Some(b'[') {
+            return None;
+        }
+        let level = self.source[self.pos + 1..].iter().take_while(|&&c| c == b'=').count();
+        (self.peek(level + 1) == Some(b'[')).then_some(level)
+    }
+
+    /// Skip a long string or comment, from its opening bracket to the
+    /// matching closing one
+    fn skip_long(&mut self, level: usize, unfinished: &str) -> Result<(), LuauSyntaxError> {
+        let start = self.line;
+        self.pos += level + 2;
+        let mut closing = vec![b']'];
+        closing.extend(std::iter::repeat_n(b'=', level));
+        closing.push(b']');
+        while self.pos < self.source.len() {
+            if self.source[self.pos..].starts_with(&closing) {
+                self.pos += closing.len();
+                return Ok(());
+            }
+            if self.source[self.pos] == b'\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+        self.error(start, unfinished)
+    }
+
+    /// Skip an escape sequence, with `pos` on the backslash
+    fn escape(&mut self) {
+        self.pos += 1;
+        match self.peek(0) {
+            Some(b'\n') => {
+                self.line += 1;
+                self.pos += 1;
+            }
+            // `\z` skips the whitespace after it, line breaks included
+            Some(b'z') => {
+                self.pos += 1;
+                while let Some(c) = self.peek(0).filter(|c| c.is_ascii_whitespace()) {
+                    if c == b'\n' {
+                        self.line += 1;
+                    }
+                    self.pos += 1;
+                }
+            }
+            Some(_) => self.pos += 1,
+            None => {}
+        }
+    }
+
+    /// Skip a quoted string
+    fn string(&mut self, quote: u8) -> Result<(), LuauSyntaxError> {
+        let start = self.line;
+        self.pos += 1;
+        loop {
+            match self.peek(0) {
+                Some(b'\\') => self.escape(),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(b'\n') | None => return self.error(start, "Malformed string; did you forget to finish it?"),
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skip an interpolated string up to its end or its next `{`
+    fn interpolated_string(&mut self) -> Result<(), LuauSyntaxError> {
+        let start = self.line;
+        loop {
+            match self.peek(0) {
+                Some(b'\\') => self.escape(),
+                Some(b'`') => {
+                    self.pos += 1;
+                    self.expression_next = false;
+                    return Ok(());
+                }
+                Some(b'{') => {
+                    self.stack.push((Open::Interpolation, self.line));
+                    self.pos += 1;
+                    self.expression_next = true;
+                    return Ok(());
+                }
+                Some(b'\n') | None => {
+                    return self.error(start, "Malformed interpolated string; did you forget to add a '`'?")
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skip a number literal (`3`, `0.5`, `1e-3`, `0xFF`, `1_000`)
+    fn number(&mut self) {
+        let hex = self.peek(0) == Some(b'0') && matches!(self.peek(1), Some(b'x' | b'X'));
+        while let Some(c) = self.peek(0) {
+            let exponent_sign = matches!(c, b'+' | b'-') && !hex && matches!(self.source[self.pos - 1], b'e' | b'E');
+            if !(c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || exponent_sign) {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.expression_next = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> LuauSyntaxError {
+        check_luau_syntax(source).unwrap_err()
+    }
+
+    #[test]
+    fn test_valid_sources_pass() {
+        let sources = [
+            "",
+            "local x = 1",
+            "local function f(a, ...)\n\treturn a + select('#', ...)\nend",
+            "for i = 1, 10 do\n\tprint(i)\nend\nwhile true do break end\nrepeat x += 1 until x > 3",
+            "if a then\n\tb()\nelseif c then\n\td()\nelse\n\te()\nend",
+            "local t = { [1] = 'one', two = \"two\", nested = { f = function() end } }",
+            "local s = [[\nmulti\nline ]] .. [==[ with ]] inside ]==]",
+            "--[[ block\ncomment ]] local y = 2 -- line comment with end",
+            "local v = if a then 1 elseif b then 2 else 3",
+            "return if x then function() end else nil",
+            "local s = `hello {name} and {if a then `{b}` else 'c'}!`",
+            "type Point = { x: number, y: number }\nlocal p: Point = { x = 1e-3, y = 0xFF } :: Point",
+            "local n = 1_000 + .5 + 2.5e+10",
+            "print('it\\'s') print(\"a\\\nb\") print(\"x\\z\n   y\")",
+            "@native\nlocal function hot() end",
+            "function M.load(): Dump?\n\tif cached then return cached end\nend",
+            "local function f(): Map<string, number>\n\tif x then end\nend",
+            "local x = 5 if x then end",
+        ];
+        for source in sources {
+            assert_eq!(check_luau_syntax(source), Ok(()), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_missing_end() {
+        let e = error("local function f()\n\tif x then\n\t\treturn\n\tend\n");
+        assert_eq!(e.line, 5);
+        assert_eq!(e.message, "Expected 'end' (to close 'function' at line 1), got <eof>");
+    }
+
+    #[test]
+    fn test_extra_end() {
+        let e = error("local x = 1\nend");
+        assert_eq!(e.line, 2);
+        assert_eq!(e.message, "Expected <eof>, got 'end'");
+    }
+
+    #[test]
+    fn test_mismatched_brackets() {
+        let e = error("print(foo(1)\nlocal y = 2\nend");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "Expected ')' (to close '(' at line 1), got 'end'");
+
+        let e = error("local t = { 1, 2 )");
+        assert_eq!(e.message, "Expected '}' (to close '{' at line 1), got ')'");
+    }
+
+    #[test]
+    fn test_if_without_then() {
+        let e = error("if x\n\tprint(x)\nend");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "Expected 'then' (to close 'if' at line 1), got 'end'");
+        assert_eq!(error("else").message, "Unexpected 'else'");
+    }
+
+    #[test]
+    fn test_unfinished_strings_and_comments() {
+        let e = error("local a = 1\nlocal s = \"oops\nprint(s)");
+        assert_eq!((e.line, e.message.as_str()), (2, "Malformed string; did you forget to finish it?"));
+        assert_eq!(error("local s = [[ never closed").message, "Malformed string; did you forget to finish it?");
+        assert_eq!(error("\n--[==[ open ]] ").line, 2);
+        assert_eq!(error("print(`x {y}").message, "Malformed interpolated string; did you forget to add a '`'?");
+    }
+
+    #[test]
+    fn test_keywords_inside_strings_and_comments_are_ignored() {
+        assert_eq!(check_luau_syntax("print('end') -- end end\nlocal s = [[ function ]]"), Ok(()));
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let e = error("local x = 1\nlocal y = x $ 2");
+        assert_eq!((e.line, e.message.as_str()), (2, "Unexpected symbol '$'"));
+    }
+}
@@ -0,0 +1,487 @@
+//! Three-way merge of `.rbxjson` instance files
+//!
+//! Used as a git merge driver. Instead of merging lines, each property,
+//! attribute and top-level field is merged on its own, and tags as a set, so
+//! two branches editing different properties of the same instance merge
+//! cleanly. Values are compared the way [`crate::diff`] compares them, so
+//! formatting and number precision differences don't count as edits.
+//!
+//! Only a key both sides changed to different values is a conflict. It is
+//! written with git's conflict markers around that one entry, leaving the
+//! rest of the file merged. Commas are placed so that picking either side
+//! of every conflict leaves valid JSON; when an object holds nothing but
+//! keys one side deleted, the whole object is one conflict instead.
+//!
+//! Files are merged as JSON rather than through [`crate::InstanceMeta`]: the
+//! typed model requires `name` and `referenceId`, which most `.rbxjson` files
+//! leave out, and would drop fields it doesn't model. Single properties and
+//! attributes are compared through [`PropertyValue`] and [`AttributeValue`]
+//! when all three sides decode, so values that encode the same thing (an
+//! omitted optional field, precision beyond `f32`) aren't conflicts; a type
+//! the model doesn't know falls back to comparing the JSON.
+
+use std::collections::BTreeSet;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::diff::canonical;
+use crate::{AttributeValue, PropertyValue};
+
+/// Fields merged key by key rather than as a whole
+const KEYED_FIELDS: &[&str] = &["properties", "attributes"];
+
+/// How the three sides of a value are compared
+#[derive(Debug, Clone, Copy)]
+enum Compare {
+    /// As JSON, the way [`crate::diff`] compares it
+    Json,
+    /// Decoded as a [`PropertyValue`] if every side decodes
+    Property,
+    /// Decoded as an [`AttributeValue`] if every side decodes
+    Attribute,
+}
+
+impl Compare {
+    fn for_field(field: &str) -> Self {
+        match field {
+            "properties" => Compare::Property,
+            "attributes" => Compare::Attribute,
+            _ => Compare::Json,
+        }
+    }
+
+    /// Canonical text of each side (`None` where absent) for comparison
+    fn canonical_sides(self, sides: [Option<&Value>; 3]) -> [Option<String>; 3] {
+        let typed = match self {
+            Compare::Json => None,
+            Compare::Property => decoded::<PropertyValue>(sides),
+            Compare::Attribute => decoded::<AttributeValue>(sides),
+        };
+        typed.unwrap_or_else(|| sides.map(|side| side.map(canonical)))
+    }
+}
+
+/// Each side decoded as `T` and encoded again, if every present side decodes
+fn decoded<T: DeserializeOwned + Serialize>(sides: [Option<&Value>; 3]) -> Option<[Option<String>; 3]> {
+    let mut out = [None, None, None];
+    for (slot, side) in out.iter_mut().zip(sides) {
+        if let Some(value) = side {
+            let typed = T::deserialize(value).ok()?;
+            *slot = Some(canonical(&serde_json::to_value(typed).ok()?));
+        }
+    }
+    Some(out)
+}
+
+/// Result of [`merge_instance_files`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOutcome {
+    /// Merged file, with conflict markers if there are conflicts
+    pub content: String,
+    /// Conflicting keys, e.g. `properties.Anchored` or `className`
+    pub conflicts: Vec<String>,
+}
+
+impl MergeOutcome {
+    /// Whether the merge needs no manual resolution
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// A key both sides changed, with each side's value (`None` when deleted)
+struct Conflict {
+    ours: Option<Value>,
+    theirs: Option<Value>,
+}
+
+/// Merge `ours` and `theirs` from their common ancestor `base`
+///
+/// Fails if any side isn't a JSON object, e.g. because it already holds
+/// conflict markers; callers should fall back to a line-based merge.
+pub fn merge_instance_files(base: &str, ours: &str, theirs: &str) -> anyhow::Result<MergeOutcome> {
+    let parse = |label: &str, text: &str| -> anyhow::Result<Map<String, Value>> {
+        // A file added on both sides has an empty base
+        if label == "base" && text.trim().is_empty() {
+            return Ok(Map::new());
+        }
+        match serde_json::from_str(text)? {
+            Value::Object(map) => Ok(map),
+            _ => anyhow::bail!("{} is not a JSON object", label),
+        }
+    };
+    let base_map = parse("base", base)?;
+    let ours_map = parse("ours", ours)?;
+    let theirs_map = parse("theirs", theirs)?;
+
+    let mut conflicts = Vec::new();
+    let mut pending = Vec::new();
+    let mut merged = Map::new();
+    for key in keys(&base_map, &ours_map, &theirs_map) {
+        let (b, o, t) = (base_map.get(&key), ours_map.get(&key), theirs_map.get(&key));
+        if KEYED_FIELDS.contains(&key.as_str()) && [b, o, t].iter().flatten().all(|v| v.is_object()) {
+            let empty = Map::new();
+            let field = |v: Option<&Value>| v.and_then(|v| v.as_object()).unwrap_or(&empty).clone();
+            let map = merge_maps(&field(b), &field(o), &field(t), &key, &mut conflicts, &mut pending);
+            if !map.is_empty() || o.is_some() || t.is_some() {
+                merged.insert(key, Value::Object(map));
+            }
+        } else {
+            match merge_value(b, o, t, Compare::Json) {
+                Ok(Some(value)) => {
+                    merged.insert(key, value);
+                }
+                Ok(None) => {}
+                // Both sides changed the tags
+                Err(_) if key == "tags" => {
+                    merged.insert(key, merge_tags(b, o, t));
+                }
+                Err(conflict) => {
+                    merged.insert(key.clone(), placeholder(pending.len()));
+                    pending.push(conflict);
+                    conflicts.push(key);
+                }
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        // Keep a side's exact text when the merge didn't change it
+        let merged = Value::Object(merged.clone());
+        for (map, text) in [(ours_map, ours), (theirs_map, theirs)] {
+            if canonical(&Value::Object(map)) == canonical(&merged) {
+                return Ok(MergeOutcome { content: text.to_string(), conflicts });
+            }
+        }
+    }
+
+    let content = render(&merged, &pending)?;
+    Ok(MergeOutcome { content, conflicts })
+}
+
+/// Keys of all three sides, sorted
+fn keys(base: &Map<String, Value>, ours: &Map<String, Value>, theirs: &Map<String, Value>) -> BTreeSet<String> {
+    base.keys().chain(ours.keys()).chain(theirs.keys()).cloned().collect()
+}
+
+fn merge_maps(
+    base: &Map<String, Value>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    field: &str,
+    conflicts: &mut Vec<String>,
+    pending: &mut Vec<Conflict>,
+) -> Map<String, Value> {
+    let compare = Compare::for_field(field);
+    let mut merged = Map::new();
+    for key in keys(base, ours, theirs) {
+        match merge_value(base.get(&key), ours.get(&key), theirs.get(&key), compare) {
+            Ok(Some(value)) => {
+                merged.insert(key, value);
+            }
+            Ok(None) => {}
+            Err(conflict) => {
+                merged.insert(key.clone(), placeholder(pending.len()));
+                pending.push(conflict);
+                conflicts.push(format!("{}.{}", field, key));
+            }
+        }
+    }
+    merged
+}
+
+/// Three-way merge of one value; `Ok(None)` means deleted
+fn merge_value(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    compare: Compare,
+) -> Result<Option<Value>, Conflict> {
+    let [base_text, ours_text, theirs_text] = compare.canonical_sides([base, ours, theirs]);
+    if ours_text == theirs_text || base_text == theirs_text {
+        Ok(ours.cloned())
+    } else if base_text == ours_text {
+        Ok(theirs.cloned())
+    } else {
+        Err(Conflict {
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        })
+    }
+}
+
+/// Tags as a set: ours, plus tags theirs added, minus tags theirs removed
+fn merge_tags(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Value {
+    let tags = |v: Option<&Value>| -> Vec<String> {
+        v.and_then(|v| v.as_array())
+            .map(|t| t.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+    let (base, ours, theirs) = (tags(base), tags(ours), tags(theirs));
+    let mut merged: Vec<String> = ours.into_iter().filter(|t| !base.contains(t) || theirs.contains(t)).collect();
+    for tag in theirs {
+        if !base.contains(&tag) && !merged.contains(&tag) {
+            merged.push(tag);
+        }
+    }
+    Value::from(merged)
+}
+
+fn placeholder(index: usize) -> Value {
+    Value::String(format!("<<rbxsync-conflict-{}>>", index))
+}
+
+/// Pretty-print the merged object, expanding each conflict placeholder into
+/// both sides' entries between conflict markers
+fn render(merged: &Map<String, Value>, pending: &[Conflict]) -> anyhow::Result<String> {
+    if needs_whole_conflict(merged, pending) {
+        return Ok(format!(
+            "<<<<<<< ours\n{}\n=======\n{}\n>>>>>>> theirs\n",
+            serde_json::to_string_pretty(&pick(merged, pending, true))?,
+            serde_json::to_string_pretty(&pick(merged, pending, false))?
+        ));
+    }
+    Ok(format!("{}\n", render_object(merged, "", pending)?))
+}
+
+/// An object entry as text: the same on both sides, or each side's text
+/// (`None` where that side deleted it)
+enum Entry {
+    Plain(String),
+    Conflict(Option<String>, Option<String>),
+}
+
+fn render_object(map: &Map<String, Value>, indent: &str, pending: &[Conflict]) -> anyhow::Result<String> {
+    if map.is_empty() {
+        return Ok("{}".to_string());
+    }
+    let inner = format!("{}  ", indent);
+    let mut entries = Vec::new();
+    for (key, value) in map {
+        let head = format!("{}{}: ", inner, serde_json::to_string(key)?);
+        let text = |value: &Value| -> anyhow::Result<String> {
+            let pretty = serde_json::to_string_pretty(value)?.replace('\n', &format!("\n{}", inner));
+            Ok(format!("{}{}", head, pretty))
+        };
+        let entry = match (conflict_index(value), value) {
+            (Some(index), _) => {
+                let conflict = &pending[index];
+                Entry::Conflict(
+                    conflict.ours.as_ref().map(text).transpose()?,
+                    conflict.theirs.as_ref().map(text).transpose()?,
+                )
+            }
+            (None, Value::Object(child)) if needs_whole_conflict(child, pending) => Entry::Conflict(
+                Some(text(&Value::Object(pick(child, pending, true)))?),
+                Some(text(&Value::Object(pick(child, pending, false)))?),
+            ),
+            (None, Value::Object(child)) => Entry::Plain(format!("{}{}", head, render_object(child, &inner, pending)?)),
+            (None, value) => Entry::Plain(text(value)?),
+        };
+        entries.push(entry);
+    }
+
+    // Whichever side is picked, the surviving entries need a comma between
+    // them. An entry neither side deleted always survives, so a conflict
+    // before the last such entry carries the comma after it, and a conflict
+    // after it the comma before.
+    let last_kept = entries.iter().rposition(|entry| {
+        matches!(entry, Entry::Plain(_) | Entry::Conflict(Some(_), Some(_)))
+    });
+    let mut out = String::from("{\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let comma_after = last_kept.is_some_and(|last| i < last);
+        match entry {
+            Entry::Plain(text) => {
+                out.push_str(text);
+                out.push_str(if comma_after { ",\n" } else { "\n" });
+            }
+            Entry::Conflict(ours, theirs) => {
+                let side = |text: &Option<String>, out: &mut String| {
+                    let Some(text) = text else {
+                        return;
+                    };
+                    if comma_after {
+                        out.push_str(&format!("{},\n", text));
+                    } else if last_kept.is_some_and(|last| i > last) {
+                        out.push_str(&format!("{},\n{}\n", inner, text));
+                    } else {
+                        out.push_str(&format!("{}\n", text));
+                    }
+                };
+                out.push_str("<<<<<<< ours\n");
+                side(ours, &mut out);
+                out.push_str("=======\n");
+                side(theirs, &mut out);
+                out.push_str(">>>>>>> theirs\n");
+            }
+        }
+    }
+    out.push_str(indent);
+    out.push('}');
+    Ok(out)
+}
+
+fn conflict_index(value: &Value) -> Option<usize> {
+    value.as_str()?.strip_prefix("<<rbxsync-conflict-")?.strip_suffix(">>")?.parse().ok()
+}
+
+/// Whether every entry of the object is a conflict one side deleted: no
+/// placement of commas is valid for every choice, so the whole object
+/// becomes one conflict
+fn needs_whole_conflict(map: &Map<String, Value>, pending: &[Conflict]) -> bool {
+    map.len() > 1
+        && map.values().all(|value| {
+            conflict_index(value).is_some_and(|i| pending[i].ours.is_none() || pending[i].theirs.is_none())
+        })
+}
+
+/// The object with every conflict resolved to one side
+fn pick(map: &Map<String, Value>, pending: &[Conflict], ours: bool) -> Map<String, Value> {
+    map.iter()
+        .filter_map(|(key, value)| {
+            let value = match (conflict_index(value), value) {
+                (Some(index), _) if ours => pending[index].ours.clone()?,
+                (Some(index), _) => pending[index].theirs.clone()?,
+                (None, Value::Object(child)) => Value::Object(pick(child, pending, ours)),
+                (None, value) => value.clone(),
+            };
+            Some((key.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn part(props: Value) -> String {
+        serde_json::to_string_pretty(&json!({ "className": "Part", "properties": props, "tags": ["A"] })).unwrap()
+    }
+
+    #[test]
+    fn test_merges_different_properties() {
+        let base = part(json!({ "Anchored": { "type": "bool", "value": false }, "Transparency": { "type": "float", "value": 0 } }));
+        let ours = part(json!({ "Anchored": { "type": "bool", "value": true }, "Transparency": { "type": "float", "value": 0 } }));
+        let theirs = part(json!({ "Anchored": { "type": "bool", "value": false }, "Transparency": { "type": "float", "value": 0.5 } }));
+
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert!(outcome.is_clean());
+        let merged: Value = serde_json::from_str(&outcome.content).unwrap();
+        assert_eq!(merged["properties"]["Anchored"]["value"], true);
+        assert_eq!(merged["properties"]["Transparency"]["value"], 0.5);
+    }
+
+    #[test]
+    fn test_tags_merge_as_a_set() {
+        let base = json!({ "className": "Part", "tags": ["A", "B"] }).to_string();
+        let ours = json!({ "className": "Part", "tags": ["A", "B", "C"] }).to_string();
+        let theirs = json!({ "className": "Part", "tags": ["B", "D"] }).to_string();
+
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert!(outcome.is_clean());
+        let merged: Value = serde_json::from_str(&outcome.content).unwrap();
+        assert_eq!(merged["tags"], json!(["B", "C", "D"]));
+    }
+
+    #[test]
+    fn test_conflict_markers_only_around_conflicting_property() {
+        let base = part(json!({ "Anchored": { "type": "bool", "value": false }, "Transparency": { "type": "float", "value": 0 } }));
+        let ours = part(json!({ "Anchored": { "type": "bool", "value": true }, "Transparency": { "type": "float", "value": 0.25 } }));
+        let theirs = part(json!({ "Transparency": { "type": "float", "value": 0.5 } }));
+
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert_eq!(outcome.conflicts, vec!["properties.Anchored", "properties.Transparency"]);
+        assert_eq!(outcome.content.matches("<<<<<<< ours").count(), 2);
+        // Theirs deleted Anchored: nothing between the separator and the end marker
+        assert!(outcome.content.contains("=======\n>>>>>>> theirs"));
+        assert!(outcome.content.contains("\"tags\": ["));
+
+        // Picking one side of every conflict leaves valid JSON
+        assert_eq!(resolve(&outcome.content, true)["properties"]["Transparency"]["value"], 0.25);
+        assert_eq!(resolve(&outcome.content, false)["properties"]["Transparency"]["value"], 0.5);
+        assert!(resolve(&outcome.content, false)["properties"].get("Anchored").is_none());
+
+        // The deleted key is the object's last entry
+        let base = part(json!({ "Anchored": { "type": "bool", "value": true }, "Size": { "type": "float", "value": 1 } }));
+        let ours = part(json!({ "Anchored": { "type": "bool", "value": true }, "Size": { "type": "float", "value": 2 } }));
+        let theirs = part(json!({ "Anchored": { "type": "bool", "value": true } }));
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert_eq!(outcome.conflicts, vec!["properties.Size"]);
+        assert_eq!(resolve(&outcome.content, true)["properties"]["Size"]["value"], 2);
+        let resolved = resolve(&outcome.content, false);
+        assert!(resolved["properties"].get("Size").is_none());
+        assert_eq!(resolved["properties"]["Anchored"]["value"], true);
+
+        // Every entry is a conflict one side deleted: the whole object is one conflict
+        let base = part(json!({ "A": { "type": "float", "value": 1 }, "B": { "type": "float", "value": 1 } }));
+        let ours = part(json!({ "B": { "type": "float", "value": 2 } }));
+        let theirs = part(json!({ "A": { "type": "float", "value": 3 } }));
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert_eq!(outcome.conflicts, vec!["properties.A", "properties.B"]);
+        assert_eq!(outcome.content.matches("<<<<<<< ours").count(), 1);
+        assert_eq!(resolve(&outcome.content, true)["properties"], json!({ "B": { "type": "float", "value": 2 } }));
+        assert_eq!(resolve(&outcome.content, false)["properties"], json!({ "A": { "type": "float", "value": 3 } }));
+    }
+
+    /// Resolve every conflict to one side and parse the result
+    fn resolve(content: &str, ours: bool) -> Value {
+        let resolved: String = content
+            .split("<<<<<<< ours\n")
+            .enumerate()
+            .map(|(i, chunk)| {
+                if i == 0 {
+                    return chunk.to_string();
+                }
+                let (ours_text, rest) = chunk.split_once("=======\n").unwrap();
+                let (theirs_text, after) = rest.split_once(">>>>>>> theirs\n").unwrap();
+                format!("{}{}", if ours { ours_text } else { theirs_text }, after)
+            })
+            .collect();
+        serde_json::from_str(&resolved).unwrap_or_else(|e| panic!("{}\n{}", e, resolved))
+    }
+
+    #[test]
+    fn test_equivalent_typed_values_merge() {
+        let base = part(json!({ "Mesh": { "type": "SharedString", "value": { "hash": "a1", "file": null } } }));
+        let ours = part(json!({ "Mesh": { "type": "SharedString", "value": { "hash": "b2" } } }));
+        let theirs = part(json!({ "Mesh": { "type": "SharedString", "value": { "hash": "b2", "file": null } } }));
+        let outcome = merge_instance_files(&base, &ours, &theirs).unwrap();
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.content, ours);
+
+        // Same f32, so the same float
+        let base = part(json!({ "Size": { "type": "float", "value": 1 } }));
+        let ours = part(json!({ "Size": { "type": "float", "value": 16777216 } }));
+        let theirs = part(json!({ "Size": { "type": "float", "value": 16777217 } }));
+        assert!(merge_instance_files(&base, &ours, &theirs).unwrap().is_clean());
+
+        let attrs = |value: Value| json!({ "className": "Part", "attributes": { "Speed": value } }).to_string();
+        let base = attrs(json!({ "type": "number", "value": 1 }));
+        let ours = attrs(json!({ "type": "Vector3", "value": { "x": 1, "y": 2, "z": 3, "note": "ours" } }));
+        let theirs = attrs(json!({ "type": "Vector3", "value": { "x": 1.0, "y": 2.0, "z": 3.0 } }));
+        assert!(merge_instance_files(&base, &ours, &theirs).unwrap().is_clean());
+
+        // A type the model doesn't know is compared as JSON
+        let base = part(json!({ "Thing": { "type": "Future", "value": 1 } }));
+        let ours = part(json!({ "Thing": { "type": "Future", "value": { "a": 1 } } }));
+        let theirs = part(json!({ "Thing": { "type": "Future", "value": { "a": 1, "b": null } } }));
+        assert_eq!(merge_instance_files(&base, &ours, &theirs).unwrap().conflicts, vec!["properties.Thing"]);
+    }
+
+    #[test]
+    fn test_unchanged_side_keeps_formatting() {
+        let base = part(json!({ "Anchored": { "type": "bool", "value": false } }));
+        let ours = json!({ "className": "Part", "properties": { "Anchored": { "type": "bool", "value": true } } }).to_string();
+
+        // Only ours changed (Anchored, and tags dropped): result is ours verbatim
+        let outcome = merge_instance_files(&base, &ours, &base).unwrap();
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.content, ours);
+
+        assert!(merge_instance_files(&base, "<<<<<<< ours", &base).is_err());
+    }
+}
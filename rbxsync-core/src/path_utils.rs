@@ -29,6 +29,60 @@ pub fn pathbuf_with_suffix(path: &Path, suffix: &str) -> PathBuf {
     PathBuf::from(path_with_suffix(path, suffix))
 }
 
+/// Instance name and script class for a script file name (`.luau` or `.lua`)
+///
+/// `init` scripts come back with the name `init`; see [`file_instance_path`].
+pub fn script_file_info(filename: &str) -> Option<(&str, &'static str)> {
+    let stem = filename
+        .strip_suffix(".luau")
+        .or_else(|| filename.strip_suffix(".lua"))?;
+    Some(if let Some(name) = stem.strip_suffix(".server") {
+        (name, "Script")
+    } else if let Some(name) = stem.strip_suffix(".client") {
+        (name, "LocalScript")
+    } else {
+        (stem, "ModuleScript")
+    })
+}
+
+/// Whether a file is an `init` script that holds its folder's source
+pub fn is_init_script(filename: &str) -> bool {
+    script_file_info(filename).is_some_and(|(name, _)| name == "init")
+}
+
+/// Instance path of a synced file, from its path relative to the tree root
+///
+/// Follows the layout `rbxsync build` reads: `Name.server.luau` is a Script
+/// named `Name`, `Folder/init.luau` and `Folder/_meta.rbxjson` describe
+/// `Folder` itself, `Name.rbxjson` is an instance named `Name`, and data
+/// files such as `Name.json` or `Name.model.json` are named `Name` (see
+/// [`crate::data_files`]). Returns `None` for files that aren't synced.
+pub fn file_instance_path(rel_path: &str) -> Option<String> {
+    let rel_path = normalize_path(rel_path);
+    let (parent, filename) = match rel_path.rsplit_once('/') {
+        Some((parent, filename)) => (parent, filename),
+        None => ("", rel_path.as_str()),
+    };
+    let join = |name: &str| {
+        if parent.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", parent, name)
+        }
+    };
+
+    if filename == "_meta.rbxjson" || is_init_script(filename) {
+        return (!parent.is_empty()).then(|| parent.to_string());
+    }
+    if let Some((name, _)) = script_file_info(filename) {
+        return Some(join(name));
+    }
+    if let Some((name, _)) = crate::data_files::data_file_info(filename) {
+        return Some(join(name));
+    }
+    filename.strip_suffix(".rbxjson").map(join)
+}
+
 /// Sanitize filename for Windows compatibility
 pub fn sanitize_filename(name: &str) -> String {
     name.chars()
@@ -76,6 +130,42 @@ mod tests {
         assert!(result_str.ends_with(".rbxjson"));
     }
 
+    #[test]
+    fn test_script_file_info() {
+        assert_eq!(script_file_info("Main.server.luau"), Some(("Main", "Script")));
+        assert_eq!(script_file_info("Input.client.lua"), Some(("Input", "LocalScript")));
+        assert_eq!(script_file_info("Util.luau"), Some(("Util", "ModuleScript")));
+        assert_eq!(script_file_info("Part.rbxjson"), None);
+        assert!(is_init_script("init.server.luau"));
+        assert!(is_init_script("init.lua"));
+        assert!(!is_init_script("initialize.luau"));
+    }
+
+    #[test]
+    fn test_file_instance_path() {
+        assert_eq!(
+            file_instance_path("ServerScriptService/Main.server.luau").as_deref(),
+            Some("ServerScriptService/Main")
+        );
+        assert_eq!(
+            file_instance_path("ReplicatedStorage/Lib/init.lua").as_deref(),
+            Some("ReplicatedStorage/Lib")
+        );
+        assert_eq!(
+            file_instance_path("Workspace/Map/_meta.rbxjson").as_deref(),
+            Some("Workspace/Map")
+        );
+        assert_eq!(file_instance_path("Workspace/Part.rbxjson").as_deref(), Some("Workspace/Part"));
+        assert_eq!(file_instance_path("Workspace\\Part.rbxjson").as_deref(), Some("Workspace/Part"));
+        assert_eq!(
+            file_instance_path("ReplicatedStorage/Items.model.json").as_deref(),
+            Some("ReplicatedStorage/Items")
+        );
+        assert_eq!(file_instance_path("ReplicatedStorage/init.meta.json"), None);
+        assert_eq!(file_instance_path("init.luau"), None);
+        assert_eq!(file_instance_path("README.md"), None);
+    }
+
     #[test]
     fn test_sanitize_filename() {
         assert_eq!(sanitize_filename("normal_name"), "normal_name");
@@ -0,0 +1,328 @@
+//! Reading place and model files as instance data
+//!
+//! Converts `.rbxl`/`.rbxlx` places and `.rbxm`/`.rbxmx` models into the
+//! same JSON form the plugin produces for `.rbxjson` files (typed property
+//! values, `attributes`, `tags`, script `Source` in `properties`), so they can
+//! be compared with project trees by [`crate::diff_trees`].
+//!
+//! Place files store every property of every instance, including ones the
+//! plugin doesn't serialize, so compare them with [`crate::PropertyScope::Shared`].
+//! Instance references and binary data are left out: they're stored as
+//! file-local referents and can't be compared with a project.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use rbx_dom_weak::types::{Enum, Ref, Variant};
+use rbx_dom_weak::WeakDom;
+use rbx_reflection::DataType;
+use serde_json::{json, Value};
+
+use crate::types::SERVICES;
+
+/// Whether a path names a place or model file
+pub fn is_place_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("rbxl" | "rbxlx" | "rbxm" | "rbxmx")
+    )
+}
+
+/// Every instance in a place's [`SERVICES`], or in a model, with its
+/// `path` (`Workspace/Map/Wall`, or `Model/Part` for a model's top level)
+pub fn read_place_instances(path: &Path) -> Result<Vec<Value>> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let reader = BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path.display()))?);
+    let dom = match ext {
+        "rbxl" | "rbxm" => rbx_binary::from_reader(reader).map_err(anyhow::Error::from),
+        "rbxlx" | "rbxmx" => rbx_xml::from_reader_default(reader).map_err(anyhow::Error::from),
+        _ => bail!("{} is not a place or model file", path.display()),
+    }
+    .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let is_place = matches!(ext, "rbxl" | "rbxlx");
+    let mut instances = Vec::new();
+    for &child in dom.root().children() {
+        let Some(inst) = dom.get_by_ref(child) else {
+            continue;
+        };
+        if is_place && !SERVICES.contains(&inst.name.as_str()) {
+            continue;
+        }
+        collect(&dom, child, &inst.name, &mut instances);
+    }
+    Ok(instances)
+}
+
+fn collect(dom: &WeakDom, referent: Ref, path: &str, out: &mut Vec<Value>) {
+    let Some(inst) = dom.get_by_ref(referent) else {
+        return;
+    };
+    out.push(instance_json(&inst.class, &inst.properties, path));
+    for &child in inst.children() {
+        if let Some(child_inst) = dom.get_by_ref(child) {
+            let child_path = format!("{}/{}", path, child_inst.name);
+            collect(dom, child, &child_path, out);
+        }
+    }
+}
+
+fn instance_json<'a>(
+    class_name: &str,
+    props: impl IntoIterator<Item = (&'a String, &'a Variant)>,
+    path: &str,
+) -> Value {
+    let mut properties = serde_json::Map::new();
+    let mut attributes = serde_json::Map::new();
+    let mut tags = Vec::new();
+
+    for (name, value) in props {
+        match value {
+            Variant::Attributes(attrs) => {
+                for (attr, attr_value) in attrs.iter() {
+                    if let Some(encoded) = encode_attribute(attr_value) {
+                        attributes.insert(attr.clone(), encoded);
+                    }
+                }
+            }
+            Variant::Tags(t) => tags.extend(t.iter().map(str::to_string)),
+            _ => {
+                if let Some(encoded) = encode_property(class_name, name, value) {
+                    properties.insert(name.clone(), encoded);
+                }
+            }
+        }
+    }
+
+    let mut inst = json!({
+        "className": class_name,
+        "name": path.rsplit('/').next().unwrap_or(path),
+        "path": path,
+        "properties": properties,
+    });
+    if !attributes.is_empty() {
+        inst["attributes"] = Value::Object(attributes);
+    }
+    if !tags.is_empty() {
+        inst["tags"] = json!(tags);
+    }
+    inst
+}
+
+/// A property value as the plugin serializes it
+fn encode_property(class_name: &str, name: &str, value: &Variant) -> Option<Value> {
+    let typed = |ty: &str, value: Value| Some(json!({ "type": ty, "value": value }));
+    match value {
+        Variant::Int32(n) => typed("int", json!(n)),
+        Variant::Int64(n) => typed("int", json!(n)),
+        Variant::Float32(n) => typed("float", float(*n as f64)),
+        Variant::Float64(n) => typed("double", float(*n)),
+        Variant::Enum(e) => typed(
+            "Enum",
+            match enum_item(class_name, name, *e) {
+                Some((enum_type, item)) => json!({ "enumType": enum_type, "value": item }),
+                None => json!({ "enumType": "", "value": e.to_u32().to_string() }),
+            },
+        ),
+        Variant::Content(content) => typed("Content", json!(AsRef::<str>::as_ref(content))),
+        Variant::String(s) => typed("string", json!(s)),
+        Variant::Ref(_)
+        | Variant::BinaryString(_)
+        | Variant::SharedString(_)
+        | Variant::UniqueId(_)
+        | Variant::SecurityCapabilities(_)
+        | Variant::MaterialColors(_) => None,
+        other => encode_value(other),
+    }
+}
+
+/// An attribute value as the plugin serializes it (numbers are `int` when
+/// whole, like Luau numbers)
+fn encode_attribute(value: &Variant) -> Option<Value> {
+    match value {
+        Variant::Float64(n) if n.fract() == 0.0 => Some(json!({ "type": "int", "value": *n as i64 })),
+        Variant::Float64(n) => Some(json!({ "type": "float", "value": n })),
+        Variant::String(s) => Some(json!({ "type": "string", "value": s })),
+        other => encode_value(other),
+    }
+}
+
+/// Values encoded the same way for properties and attributes
+fn encode_value(value: &Variant) -> Option<Value> {
+    let (ty, value) = match value {
+        Variant::Bool(b) => ("bool", json!(b)),
+        Variant::Vector2(v) => ("Vector2", json!({ "x": v.x, "y": v.y })),
+        Variant::Vector2int16(v) => ("Vector2int16", json!({ "x": v.x, "y": v.y })),
+        Variant::Vector3(v) => ("Vector3", vector3(v)),
+        Variant::Vector3int16(v) => ("Vector3int16", json!({ "x": v.x, "y": v.y, "z": v.z })),
+        Variant::CFrame(cf) => ("CFrame", cframe(cf)),
+        Variant::OptionalCFrame(cf) => ("OptionalCFrame", cf.as_ref().map_or(Value::Null, cframe)),
+        Variant::Color3(c) => ("Color3", json!({ "r": c.r, "g": c.g, "b": c.b })),
+        // Studio reports colors stored as Color3uint8 (Part.Color) as Color3
+        Variant::Color3uint8(c) => (
+            "Color3",
+            json!({ "r": c.r as f32 / 255.0, "g": c.g as f32 / 255.0, "b": c.b as f32 / 255.0 }),
+        ),
+        Variant::BrickColor(b) => ("BrickColor", json!(*b as u16)),
+        Variant::UDim(u) => ("UDim", json!({ "scale": u.scale, "offset": u.offset })),
+        Variant::UDim2(u) => (
+            "UDim2",
+            json!({
+                "x": { "scale": u.x.scale, "offset": u.x.offset },
+                "y": { "scale": u.y.scale, "offset": u.y.offset }
+            }),
+        ),
+        Variant::Rect(r) => (
+            "Rect",
+            json!({ "min": { "x": r.min.x, "y": r.min.y }, "max": { "x": r.max.x, "y": r.max.y } }),
+        ),
+        Variant::NumberRange(r) => ("NumberRange", json!({ "min": r.min, "max": r.max })),
+        Variant::NumberSequence(s) => (
+            "NumberSequence",
+            json!({ "keypoints": s.keypoints.iter().map(|k| json!({
+                "time": k.time, "value": k.value, "envelope": k.envelope
+            })).collect::<Vec<_>>() }),
+        ),
+        Variant::ColorSequence(s) => (
+            "ColorSequence",
+            json!({ "keypoints": s.keypoints.iter().map(|k| json!({
+                "time": k.time, "color": { "r": k.color.r, "g": k.color.g, "b": k.color.b }
+            })).collect::<Vec<_>>() }),
+        ),
+        Variant::Font(f) => (
+            "Font",
+            json!({ "family": f.family, "weight": format!("{:?}", f.weight), "style": format!("{:?}", f.style) }),
+        ),
+        Variant::Faces(f) => {
+            use rbx_dom_weak::types::Faces;
+            let has = |face| f.contains(face);
+            (
+                "Faces",
+                json!({
+                    "top": has(Faces::TOP), "bottom": has(Faces::BOTTOM), "left": has(Faces::LEFT),
+                    "right": has(Faces::RIGHT), "front": has(Faces::FRONT), "back": has(Faces::BACK)
+                }),
+            )
+        }
+        Variant::Axes(a) => {
+            use rbx_dom_weak::types::Axes;
+            ("Axes", json!({ "x": a.contains(Axes::X), "y": a.contains(Axes::Y), "z": a.contains(Axes::Z) }))
+        }
+        Variant::PhysicalProperties(p) => (
+            "PhysicalProperties",
+            match p {
+                rbx_dom_weak::types::PhysicalProperties::Custom(c) => json!({
+                    "density": c.density,
+                    "friction": c.friction,
+                    "elasticity": c.elasticity,
+                    "frictionWeight": c.friction_weight,
+                    "elasticityWeight": c.elasticity_weight
+                }),
+                _ => Value::Null,
+            },
+        ),
+        Variant::Ray(r) => ("Ray", json!({ "origin": vector3(&r.origin), "direction": vector3(&r.direction) })),
+        Variant::Region3(r) => ("Region3", json!({ "min": vector3(&r.min), "max": vector3(&r.max) })),
+        Variant::Region3int16(r) => (
+            "Region3int16",
+            json!({
+                "min": { "x": r.min.x, "y": r.min.y, "z": r.min.z },
+                "max": { "x": r.max.x, "y": r.max.y, "z": r.max.z }
+            }),
+        ),
+        _ => return None,
+    };
+    Some(json!({ "type": ty, "value": value }))
+}
+
+/// NaN and infinities as the plugin writes them
+fn float(n: f64) -> Value {
+    if n.is_nan() {
+        json!("NaN")
+    } else if n.is_infinite() {
+        json!(if n > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        json!(n)
+    }
+}
+
+fn vector3(v: &rbx_dom_weak::types::Vector3) -> Value {
+    json!({ "x": v.x, "y": v.y, "z": v.z })
+}
+
+fn cframe(cf: &rbx_dom_weak::types::CFrame) -> Value {
+    let (p, m) = (cf.position, cf.orientation);
+    json!({
+        "position": [p.x, p.y, p.z],
+        "rotation": [m.x.x, m.x.y, m.x.z, m.y.x, m.y.y, m.y.z, m.z.x, m.z.y, m.z.z]
+    })
+}
+
+/// Enum type and item name of an enum property value
+fn enum_item(class_name: &str, property: &str, value: Enum) -> Option<(String, String)> {
+    let database = rbx_reflection_database::get();
+    let mut class = database.classes.get(class_name);
+    while let Some(descriptor) = class {
+        if let Some(prop) = descriptor.properties.get(property) {
+            let DataType::Enum(enum_name) = &prop.data_type else {
+                return None;
+            };
+            let item = database
+                .enums
+                .get(enum_name)?
+                .items
+                .iter()
+                .find(|(_, v)| **v == value.to_u32())?
+                .0;
+            return Some((enum_name.to_string(), item.to_string()));
+        }
+        class = descriptor.superclass.as_ref().and_then(|s| database.classes.get(s));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rbx_dom_weak::types::{Attributes, Color3uint8, Tags, Vector3};
+    use rbx_dom_weak::InstanceBuilder;
+
+    #[test]
+    fn test_reads_place_as_instance_data() {
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let root = dom.root_ref();
+        let workspace = dom.insert(root, InstanceBuilder::new("Workspace").with_name("Workspace"));
+        dom.insert(root, InstanceBuilder::new("Players").with_name("Players"));
+        let mut tags = Tags::new();
+        tags.push("Lava");
+        dom.insert(
+            workspace,
+            InstanceBuilder::new("Part")
+                .with_name("Floor")
+                .with_property("Anchored", true)
+                .with_property("Size", Vector3::new(4.0, 1.0, 2.0))
+                .with_property("Color", Color3uint8::new(255, 0, 0))
+                .with_property("Material", Enum::from_u32(256))
+                .with_property("Attributes", Attributes::new().with("Damage", 10.0f64))
+                .with_property("Tags", tags),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.rbxl");
+        let top: Vec<Ref> = dom.root().children().to_vec();
+        rbx_binary::to_writer(File::create(&path).unwrap(), &dom, &top).unwrap();
+
+        let instances = read_place_instances(&path).unwrap();
+        let paths: Vec<&str> = instances.iter().map(|i| i["path"].as_str().unwrap()).collect();
+        assert_eq!(paths, vec!["Workspace", "Workspace/Floor"]);
+
+        let floor = &instances[1];
+        assert_eq!(floor["properties"]["Anchored"], json!({ "type": "bool", "value": true }));
+        assert_eq!(floor["properties"]["Color"]["value"]["r"], 1.0);
+        assert_eq!(floor["properties"]["Material"]["value"], json!({ "enumType": "Material", "value": "Plastic" }));
+        assert_eq!(floor["attributes"]["Damage"], json!({ "type": "int", "value": 10 }));
+        assert_eq!(floor["tags"], json!(["Lava"]));
+    }
+}
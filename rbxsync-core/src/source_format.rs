@@ -0,0 +1,202 @@
+//! Line endings, trailing newline and BOM handling for script sources
+//!
+//! Studio keeps sources with LF line endings and no byte order mark, while
+//! files on disk may have CRLF (git `autocrlf` checkouts) or a BOM (some
+//! editors). Sources are normalized to Studio's form before they are sent to
+//! Studio or compared with it, and written to disk following the project's
+//! `scripts` settings in `rbxsync.json`:
+//!
+//! ```json
+//! { "scripts": { "lineEndings": "lf", "trailingNewline": "keep", "bom": "strip" } }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+const BOM: char = '\u{feff}';
+
+/// How script sources are written to disk
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFormat {
+    #[serde(default)]
+    pub line_endings: LineEndings,
+
+    #[serde(default)]
+    pub trailing_newline: TrailingNewline,
+
+    #[serde(default)]
+    pub bom: BomPolicy,
+}
+
+/// Line endings of script files
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEndings {
+    /// `\n`, as in Studio
+    #[default]
+    Lf,
+
+    /// `\r\n`
+    Crlf,
+
+    /// CRLF on Windows, LF elsewhere
+    Native,
+
+    /// Whatever the existing file uses (LF for new files)
+    Preserve,
+}
+
+/// Final newline of script files
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrailingNewline {
+    /// Exactly as in Studio
+    #[default]
+    Keep,
+
+    /// End every non-empty file with a newline
+    Ensure,
+
+    /// Remove trailing newlines
+    Strip,
+}
+
+/// Byte order mark of script files
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BomPolicy {
+    /// Never write a BOM
+    #[default]
+    Strip,
+
+    /// Keep a BOM the existing file has
+    Preserve,
+}
+
+impl SourceFormat {
+    /// Settings from a parsed `rbxsync.json` (defaults if absent or invalid)
+    pub fn from_project_config(config: Option<&serde_json::Value>) -> Self {
+        config
+            .and_then(|c| c.get("scripts"))
+            .and_then(|s| serde_json::from_value(s.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether [`SourceFormat::to_disk`] looks at the existing file
+    pub fn uses_existing(&self) -> bool {
+        self.line_endings == LineEndings::Preserve || self.bom == BomPolicy::Preserve
+    }
+
+    /// Source as Studio stores it: no BOM, LF line endings
+    pub fn to_studio(content: &str) -> String {
+        let content = content.strip_prefix(BOM).unwrap_or(content);
+        if content.contains('\r') {
+            content.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            content.to_string()
+        }
+    }
+
+    /// File content for a source from Studio; `existing` is the current file
+    pub fn to_disk(&self, source: &str, existing: Option<&str>) -> String {
+        let mut content = Self::to_studio(source);
+        match self.trailing_newline {
+            TrailingNewline::Keep => {}
+            TrailingNewline::Ensure => {
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+            }
+            TrailingNewline::Strip => {
+                let len = content.trim_end_matches('\n').len();
+                content.truncate(len);
+            }
+        }
+
+        let crlf = match self.line_endings {
+            LineEndings::Lf => false,
+            LineEndings::Crlf => true,
+            LineEndings::Native => cfg!(windows),
+            LineEndings::Preserve => existing.is_some_and(|e| e.contains("\r\n")),
+        };
+        if crlf {
+            content = content.replace('\n', "\r\n");
+        }
+
+        let bom = self.bom == BomPolicy::Preserve && existing.is_some_and(|e| e.starts_with(BOM));
+        if bom {
+            content.insert(0, BOM);
+        }
+        content
+    }
+
+    /// Whether two sources are the same script once normalized (ignoring
+    /// trailing newlines when the project normalizes them)
+    pub fn same_source(&self, a: &str, b: &str) -> bool {
+        let (a, b) = (Self::to_studio(a), Self::to_studio(b));
+        if self.trailing_newline == TrailingNewline::Keep {
+            a == b
+        } else {
+            a.trim_end_matches('\n') == b.trim_end_matches('\n')
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_studio_normalizes() {
+        assert_eq!(SourceFormat::to_studio("\u{feff}a\r\nb\rc\n"), "a\nb\nc\n");
+        assert_eq!(SourceFormat::to_studio("plain\n"), "plain\n");
+    }
+
+    #[test]
+    fn test_to_disk_policies() {
+        let crlf = SourceFormat {
+            line_endings: LineEndings::Crlf,
+            trailing_newline: TrailingNewline::Ensure,
+            bom: BomPolicy::Strip,
+        };
+        assert_eq!(crlf.to_disk("a\nb", None), "a\r\nb\r\n");
+        assert_eq!(crlf.to_disk("", None), "");
+
+        let preserve = SourceFormat {
+            line_endings: LineEndings::Preserve,
+            trailing_newline: TrailingNewline::Strip,
+            bom: BomPolicy::Preserve,
+        };
+        assert_eq!(preserve.to_disk("a\nb\n\n", Some("\u{feff}x\r\n")), "\u{feff}a\r\nb");
+        assert_eq!(preserve.to_disk("a\nb\n", Some("x\n")), "a\nb");
+        assert_eq!(preserve.to_disk("a\n", None), "a");
+
+        assert_eq!(SourceFormat::default().to_disk("\u{feff}a\r\n", Some("\u{feff}")), "a\n");
+    }
+
+    #[test]
+    fn test_same_source() {
+        let keep = SourceFormat::default();
+        assert!(keep.same_source("a\r\nb", "a\nb"));
+        assert!(!keep.same_source("a\n", "a"));
+
+        let ensure = SourceFormat {
+            trailing_newline: TrailingNewline::Ensure,
+            ..SourceFormat::default()
+        };
+        assert!(ensure.same_source("\u{feff}a\r\n", "a"));
+    }
+
+    #[test]
+    fn test_from_project_config() {
+        let config = serde_json::json!({ "scripts": { "lineEndings": "crlf", "bom": "preserve" } });
+        let format = SourceFormat::from_project_config(Some(&config));
+        assert_eq!(format.line_endings, LineEndings::Crlf);
+        assert_eq!(format.trailing_newline, TrailingNewline::Keep);
+        assert_eq!(format.bom, BomPolicy::Preserve);
+
+        let invalid = serde_json::json!({ "scripts": { "lineEndings": "mac" } });
+        assert_eq!(SourceFormat::from_project_config(Some(&invalid)), SourceFormat::default());
+        assert_eq!(SourceFormat::from_project_config(None), SourceFormat::default());
+    }
+}
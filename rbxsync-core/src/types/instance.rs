@@ -9,6 +9,26 @@ use uuid::Uuid;
 
 use super::{AttributeValue, PropertyValue};
 
+/// Services RbxSync syncs: the top-level containers of a place
+pub const SERVICES: &[&str] = &[
+    "Workspace",
+    "ReplicatedStorage",
+    "ReplicatedFirst",
+    "ServerScriptService",
+    "ServerStorage",
+    "StarterGui",
+    "StarterPack",
+    "StarterPlayer",
+    "Lighting",
+    "SoundService",
+    "Chat",
+    "LocalizationService",
+    "TestService",
+    "HttpService",
+    "Teams",
+    "TextChatService",
+];
+
 /// A serialized Roblox instance
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -88,25 +108,7 @@ impl Instance {
 
     /// Check if this is a service (top-level container)
     pub fn is_service(&self) -> bool {
-        matches!(
-            self.class_name.as_str(),
-            "Workspace"
-                | "ReplicatedStorage"
-                | "ReplicatedFirst"
-                | "ServerScriptService"
-                | "ServerStorage"
-                | "StarterGui"
-                | "StarterPack"
-                | "StarterPlayer"
-                | "Lighting"
-                | "SoundService"
-                | "Chat"
-                | "LocalizationService"
-                | "TestService"
-                | "HttpService"
-                | "Teams"
-                | "TextChatService"
-        )
+        SERVICES.contains(&self.class_name.as_str())
     }
 
     /// Get the appropriate file extension for scripts
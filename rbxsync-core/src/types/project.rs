@@ -41,6 +41,19 @@ pub struct ProjectConfig {
     /// Wally package configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub packages: Option<PackageConfig>,
+
+    /// Line endings, trailing newline and BOM of script files
+    #[serde(default)]
+    pub scripts: crate::source_format::SourceFormat,
+
+    /// Size guardrails for syncs and extractions
+    #[serde(default)]
+    pub limits: LimitsConfig,
+
+    /// Sync server the CLI talks to (e.g. "http://localhost:44800");
+    /// `--server` and `RBXSYNC_URL` take precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
 }
 
 fn default_tree_path() -> PathBuf {
@@ -62,6 +75,9 @@ impl Default for ProjectConfig {
             tree_mapping: HashMap::new(),
             license: None,
             packages: None,
+            scripts: crate::source_format::SourceFormat::default(),
+            limits: LimitsConfig::default(),
+            server: None,
         }
     }
 }
@@ -206,6 +222,14 @@ pub struct SyncConfig {
     /// Paths to watch for changes (relative to project root)
     #[serde(default)]
     pub watch_paths: Vec<PathBuf>,
+
+    /// Roll back a whole live-sync batch in Studio if any operation fails
+    #[serde(default)]
+    pub atomic: bool,
+
+    /// What live sync does after a checkout, pull or reset changes HEAD
+    #[serde(default)]
+    pub branch_switch: BranchSwitchMode,
 }
 
 impl Default for SyncConfig {
@@ -215,6 +239,49 @@ impl Default for SyncConfig {
             conflict_resolution: ConflictResolution::default(),
             auto_sync: false,
             watch_paths: vec![PathBuf::from("./src")],
+            atomic: false,
+            branch_switch: BranchSwitchMode::default(),
+        }
+    }
+}
+
+/// Size limits the server enforces on data from Studio and live sync
+///
+/// A limit of `0` disables it. Requests with `force: true` skip the checks.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitsConfig {
+    /// Largest script source or instance file, in bytes
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Most instances in one extraction chunk or sync batch
+    #[serde(default = "default_max_batch_instances")]
+    pub max_batch_instances: usize,
+
+    /// Most data one extraction may send, in bytes
+    #[serde(default = "default_max_extraction_size")]
+    pub max_extraction_size: u64,
+}
+
+fn default_max_file_size() -> u64 {
+    4 * 1024 * 1024
+}
+
+fn default_max_batch_instances() -> usize {
+    5000
+}
+
+fn default_max_extraction_size() -> u64 {
+    1024 * 1024 * 1024
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: default_max_file_size(),
+            max_batch_instances: default_max_batch_instances(),
+            max_extraction_size: default_max_extraction_size(),
         }
     }
 }
@@ -234,6 +301,18 @@ pub enum SyncMode {
     Bidirectional,
 }
 
+/// Handling of file changes made by git moving HEAD
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BranchSwitchMode {
+    /// Pause live sync and keep the changes until they are applied or discarded
+    #[default]
+    Review,
+
+    /// Send the changes to Studio straight away
+    Auto,
+}
+
 /// How to handle sync conflicts
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
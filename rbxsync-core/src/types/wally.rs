@@ -3,7 +3,7 @@
 //! Provides parsing for wally.toml manifests and wally.lock files
 //! to enable Wally package compatibility in RbxSync.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
@@ -89,11 +89,29 @@ pub struct WallyLockedPackage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checksum: Option<String>,
 
-    /// Dependencies of this package
-    #[serde(default)]
+    /// Dependencies of this package (`scope/name@version`)
+    #[serde(default, deserialize_with = "dependency_specs")]
     pub dependencies: Vec<String>,
 }
 
+/// Read lock dependencies, which the project's own entry writes as
+/// `["Alias", "scope/name@version"]` pairs
+fn dependency_specs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Dependency {
+        Spec(String),
+        Aliased(serde::de::IgnoredAny, String),
+    }
+
+    Ok(Vec::<Dependency>::deserialize(deserializer)?
+        .into_iter()
+        .map(|dependency| match dependency {
+            Dependency::Spec(spec) | Dependency::Aliased(_, spec) => spec,
+        })
+        .collect())
+}
+
 /// Errors that can occur when parsing Wally files
 #[derive(Debug, thiserror::Error)]
 pub enum WallyError {
@@ -162,6 +180,73 @@ impl WallyLock {
     pub fn package_names(&self) -> Vec<&str> {
         self.packages.iter().map(|p| p.name.as_str()).collect()
     }
+
+    /// Dependencies of `manifest` this lock doesn't satisfy: packages it
+    /// doesn't have, or has only at versions the manifest doesn't allow.
+    /// Empty when the lock is up to date.
+    pub fn check_manifest(&self, manifest: &WallyManifest) -> Vec<String> {
+        let mut dependencies: Vec<(String, String)> = manifest.all_dependencies().into_iter().collect();
+        dependencies.sort();
+
+        let mut problems = Vec::new();
+        for (alias, spec) in dependencies {
+            let Some((name, requirement)) = spec.split_once('@') else {
+                problems.push(format!("{}: '{}' is not a scope/name@version dependency", alias, spec));
+                continue;
+            };
+            let locked: Vec<&WallyLockedPackage> =
+                self.packages.iter().filter(|p| p.name.eq_ignore_ascii_case(name)).collect();
+            if locked.is_empty() {
+                problems.push(format!("{} ({}) is not in wally.lock", alias, spec));
+            } else if locked.iter().all(|p| version_matches(&p.version, requirement) == Some(false)) {
+                let versions: Vec<&str> = locked.iter().map(|p| p.version.as_str()).collect();
+                problems.push(format!(
+                    "{} requires {} {} but wally.lock has {}",
+                    alias,
+                    name,
+                    requirement,
+                    versions.join(", ")
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// Whether `version` meets a Wally version requirement: `1.2.3` or `^1.2.3`
+/// (same leftmost non-zero component, at least that version) or `=1.2.3`.
+/// `None` for requirements in other forms.
+fn version_matches(version: &str, requirement: &str) -> Option<bool> {
+    let requirement = requirement.trim();
+    let (exact, requirement) = match requirement.strip_prefix('=') {
+        Some(requirement) => (true, requirement),
+        None => (false, requirement.strip_prefix('^').unwrap_or(requirement)),
+    };
+    let wanted = parse_version(requirement)?;
+    let have = parse_version(version)?;
+    if exact {
+        return Some(have == wanted);
+    }
+    let compatible = match wanted {
+        (0, 0, _) => have == wanted,
+        (0, minor, _) => have.0 == 0 && have.1 == minor,
+        (major, _, _) => have.0 == major,
+    };
+    Some(compatible && have >= wanted)
+}
+
+/// `1.2.3`, `1.2` or `1`, ignoring pre-release and build metadata
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().split(['-', '+']).next()?;
+    let mut parts = version.split('.');
+    let mut next = || parts.next().map(|part| part.parse::<u64>().ok());
+    let major = next()??;
+    let minor = next().unwrap_or(Some(0))?;
+    let patch = next().unwrap_or(Some(0))?;
+    if next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
 }
 
 /// Find wally.toml in a project directory (checks root and common locations)
@@ -276,4 +361,77 @@ dependencies = ["sleitnick/signal"]
         assert_eq!(lock.packages.len(), 2);
         assert!(lock.find_package("sleitnick/signal").is_some());
     }
+
+    #[test]
+    fn test_check_manifest() {
+        let manifest: WallyManifest = toml::from_str(
+            r#"
+[package]
+name = "test/game"
+version = "0.1.0"
+
+[dependencies]
+Signal = "sleitnick/signal@1.5.0"
+Promise = "evaera/promise@^4.0.0"
+Janitor = "howmanysmall/janitor@=1.15.0"
+Fusion = "elttob/fusion@0.2.0"
+
+[server-dependencies]
+ProfileService = "madstudioroblox/profileservice@1.0.0"
+"#,
+        )
+        .unwrap();
+        // As wally writes it, with aliased pairs for the project's own entry
+        let lock: WallyLock = toml::from_str(
+            r#"
+registry = "test"
+
+[[package]]
+name = "test/game"
+version = "0.1.0"
+dependencies = [["Signal", "sleitnick/signal@1.5.0"], ["Promise", "evaera/promise@^4.0.0"]]
+
+[[package]]
+name = "sleitnick/signal"
+version = "1.5.2"
+dependencies = []
+
+[[package]]
+name = "evaera/promise"
+version = "4.0.0"
+dependencies = []
+
+[[package]]
+name = "howmanysmall/janitor"
+version = "1.15.1"
+dependencies = []
+
+[[package]]
+name = "elttob/fusion"
+version = "0.3.0"
+dependencies = []
+"#,
+        )
+        .unwrap();
+        assert_eq!(lock.packages[0].dependencies, ["sleitnick/signal@1.5.0", "evaera/promise@^4.0.0"]);
+        assert_eq!(
+            lock.check_manifest(&manifest),
+            [
+                "Fusion requires elttob/fusion 0.2.0 but wally.lock has 0.3.0",
+                "Janitor requires howmanysmall/janitor =1.15.0 but wally.lock has 1.15.1",
+                "ProfileService (madstudioroblox/profileservice@1.0.0) is not in wally.lock",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_matches() {
+        assert_eq!(version_matches("1.5.2", "1.5.0"), Some(true));
+        assert_eq!(version_matches("2.0.0", "1.5.0"), Some(false));
+        assert_eq!(version_matches("1.4.9", "^1.5"), Some(false));
+        assert_eq!(version_matches("0.2.9", "0.2.1"), Some(true));
+        assert_eq!(version_matches("0.0.4", "0.0.3"), Some(false));
+        assert_eq!(version_matches("1.0.0-rc.1", "=1.0.0"), Some(true));
+        assert_eq!(version_matches("1.0.0", ">=1.0.0, <2.0.0"), None);
+    }
 }
@@ -0,0 +1,395 @@
+//! Checks that a project tree is valid before it is built or committed
+//!
+//! Used by `rbxsync verify` and the pre-commit hook it installs. Reports
+//! `.rbxjson` files that don't parse, `Ref` properties pointing at instances
+//! that aren't in the tree, sibling files that would become instances with
+//! the same name, data files that fail to parse and Luau syntax errors.
+//!
+//! The whole tree can be checked, or only some changed files plus the files
+//! that depend on them: those referring to instances the changed files held,
+//! and the siblings of each changed file and of its folders.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::data_files::{data_file_info, parse_data_file};
+use crate::luau_syntax::check_luau_syntax;
+use crate::path_utils::{is_init_script, path_to_string, script_file_info};
+
+/// A problem found in a project file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VerifyIssue {
+    /// File relative to the tree root, with `/` separators
+    pub file: String,
+    /// 1-based line, when the problem is on a specific line
+    pub line: Option<usize>,
+    pub message: String,
+    pub kind: IssueKind,
+}
+
+/// What part of the checks found a [`VerifyIssue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// An instance or data file, a reference or clashing names
+    Tree,
+    /// A script that doesn't parse or can't be read
+    Luau,
+}
+
+impl fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Files to check instead of the whole tree, e.g. the ones staged for a commit
+#[derive(Debug, Clone, Default)]
+pub struct ChangedFiles {
+    /// Added, modified or deleted files, relative to the tree root
+    pub files: BTreeSet<String>,
+    /// `referenceId`s the changed files held before they changed, so files
+    /// referring to those instances are checked too
+    pub previous_ids: HashSet<String>,
+}
+
+/// What a directory entry becomes when the tree is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EntryKind {
+    Folder,
+    Instance,
+    Script,
+    DataFile,
+}
+
+/// `referenceId`s of an instance and its inline children
+pub fn reference_ids(instance: &Value) -> Vec<String> {
+    let mut ids = Vec::new();
+    if let Some(id) = instance.get("referenceId").and_then(|v| v.as_str()) {
+        ids.push(id.to_string());
+    }
+    for child in instance.get("children").and_then(|v| v.as_array()).into_iter().flatten() {
+        ids.extend(reference_ids(child));
+    }
+    ids
+}
+
+/// Non-nil `Ref` properties of an instance and its inline children, as
+/// (property, referenceId) pairs. Children's properties are prefixed with
+/// the child's name, e.g. `Handle.Attachment0`.
+fn references(instance: &Value, prefix: &str) -> Vec<(String, String)> {
+    let mut refs = Vec::new();
+    for (name, value) in instance.get("properties").and_then(|v| v.as_object()).into_iter().flatten() {
+        if value.get("type").and_then(|v| v.as_str()) != Some("Ref") {
+            continue;
+        }
+        if let Some(id) = value.get("value").and_then(|v| v.as_str()) {
+            refs.push((format!("{}{}", prefix, name), id.to_string()));
+        }
+    }
+    for child in instance.get("children").and_then(|v| v.as_array()).into_iter().flatten() {
+        let child_name = child.get("name").and_then(|v| v.as_str()).unwrap_or("?");
+        refs.extend(references(child, &format!("{}{}.", prefix, child_name)));
+    }
+    refs
+}
+
+/// Every file and folder under `root`, relative to it
+fn walk(root: &Path, dir: &Path, files: &mut Vec<String>, dirs: &mut BTreeSet<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let rel = path_to_string(path.strip_prefix(root).unwrap_or(&path));
+        if path.is_dir() {
+            dirs.insert(rel);
+            walk(root, &path, files, dirs);
+        } else {
+            files.push(rel);
+        }
+    }
+}
+
+fn issue(file: &str, line: Option<usize>, message: impl Into<String>) -> VerifyIssue {
+    VerifyIssue { file: file.to_string(), line, message: message.into(), kind: IssueKind::Tree }
+}
+
+/// File name part of a relative path
+fn file_name(rel: &str) -> &str {
+    rel.rsplit('/').next().unwrap_or(rel)
+}
+
+/// Parent folder of a relative path (`""` for the root)
+fn parent(rel: &str) -> &str {
+    rel.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+/// Check the tree under `root` (a project's `src` folder)
+///
+/// With `changed`, only the changed files and their dependents are checked,
+/// though the whole tree is read to resolve references.
+pub fn verify_tree(root: &Path, changed: Option<&ChangedFiles>) -> Vec<VerifyIssue> {
+    let mut files = Vec::new();
+    let mut dirs = BTreeSet::new();
+    walk(root, root, &mut files, &mut dirs);
+    files.sort();
+
+    // Every instance file is read, since any of them may hold a Ref target
+    let mut instances: HashMap<&str, Result<Value, serde_json::Error>> = HashMap::new();
+    let mut ids = HashSet::new();
+    for rel in files.iter().filter(|rel| rel.ends_with(".rbxjson")) {
+        let parsed = std::fs::read_to_string(root.join(rel))
+            .map_err(serde_json::Error::io)
+            .and_then(|content| serde_json::from_str::<Value>(&content));
+        if let Ok(value) = &parsed {
+            ids.extend(reference_ids(value));
+        }
+        instances.insert(rel.as_str(), parsed);
+    }
+
+    let selected: BTreeSet<&str> = match changed {
+        None => files.iter().map(String::as_str).collect(),
+        Some(changed) => files
+            .iter()
+            .map(String::as_str)
+            .filter(|rel| {
+                changed.files.contains(*rel)
+                    || matches!(instances.get(rel), Some(Ok(value))
+                        if references(value, "").iter().any(|(_, id)| changed.previous_ids.contains(id)))
+            })
+            .collect(),
+    };
+
+    let mut issues = Vec::new();
+    for &rel in &selected {
+        let name = file_name(rel);
+        if let Some(parsed) = instances.get(rel) {
+            match parsed {
+                Err(e) => {
+                    // Drop serde's " at line X column Y"; the line is reported separately
+                    let message = e.to_string();
+                    let message = message.split(" at line ").next().unwrap_or_default();
+                    let line = (e.line() > 0).then(|| e.line());
+                    issues.push(issue(rel, line, format!("Invalid JSON: {}", message)));
+                }
+                Ok(value) if !value.is_object() => issues.push(issue(rel, None, "Not a JSON object")),
+                Ok(value) => {
+                    if value.get("className").is_some_and(|c| !c.is_string()) {
+                        issues.push(issue(rel, None, "className must be a string"));
+                    }
+                    for (property, id) in references(value, "") {
+                        if !ids.contains(&id) {
+                            issues.push(issue(
+                                rel,
+                                None,
+                                format!("{} refers to instance {}, which isn't in the tree", property, id),
+                            ));
+                        }
+                    }
+                }
+            }
+        } else if script_file_info(name).is_some() {
+            match std::fs::read_to_string(root.join(rel)) {
+                Ok(source) => {
+                    if let Err(e) = check_luau_syntax(&source) {
+                        issues.push(VerifyIssue { kind: IssueKind::Luau, ..issue(rel, Some(e.line), e.message) });
+                    }
+                }
+                Err(e) => issues.push(VerifyIssue { kind: IssueKind::Luau, ..issue(rel, None, format!("Failed to read: {}", e)) }),
+            }
+        } else if data_file_info(name).is_some() {
+            let result = std::fs::read_to_string(root.join(rel))
+                .map_err(|e| e.to_string())
+                .and_then(|content| parse_data_file(name, &content).map(|_| ()).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                issues.push(issue(rel, None, e));
+            }
+        }
+    }
+
+    // Folders whose entries are compared for clashing names
+    let folders: BTreeSet<&str> = match changed {
+        None => std::iter::once("").chain(dirs.iter().map(String::as_str)).collect(),
+        Some(changed) => {
+            let mut folders = BTreeSet::new();
+            for rel in changed.files.iter().map(String::as_str).chain(selected.iter().copied()) {
+                let mut folder = parent(rel);
+                loop {
+                    folders.insert(folder);
+                    if folder.is_empty() {
+                        break;
+                    }
+                    folder = parent(folder);
+                }
+            }
+            folders
+        }
+    };
+
+    let mut entries: BTreeMap<(&str, &str), Vec<(EntryKind, &str)>> = BTreeMap::new();
+    for rel in &files {
+        let name = file_name(rel);
+        let kind_and_name = if name == "_meta.rbxjson" || is_init_script(name) {
+            None
+        } else if let Some((instance_name, _)) = script_file_info(name) {
+            Some((EntryKind::Script, instance_name))
+        } else if let Some(instance_name) = name.strip_suffix(".rbxjson") {
+            Some((EntryKind::Instance, instance_name))
+        } else {
+            data_file_info(name).map(|(instance_name, _)| (EntryKind::DataFile, instance_name))
+        };
+        if let Some((kind, instance_name)) = kind_and_name {
+            entries.entry((parent(rel), instance_name)).or_default().push((kind, rel));
+        }
+    }
+    for rel in &dirs {
+        entries.entry((parent(rel), file_name(rel))).or_default().push((EntryKind::Folder, rel));
+    }
+
+    for ((folder, instance_name), mut group) in entries {
+        if group.len() < 2 || !folders.contains(folder) {
+            continue;
+        }
+        group.sort();
+        // A script's other properties live in a .rbxjson next to it
+        if let [(EntryKind::Instance, _), (EntryKind::Script, _)] = group[..] {
+            continue;
+        }
+        let names: Vec<&str> = group.iter().map(|(_, rel)| file_name(rel)).collect();
+        issues.push(issue(
+            group[0].1,
+            None,
+            format!(
+                "{} and {} would both be an instance named '{}'; rename one, or merge them",
+                names[..names.len() - 1].join(", "),
+                names[names.len() - 1],
+                instance_name
+            ),
+        ));
+    }
+
+    issues.sort();
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn tree(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (rel, content) in files {
+            let path = dir.path().join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn messages(issues: &[VerifyIssue]) -> Vec<String> {
+        issues.iter().map(|i| i.to_string()).collect()
+    }
+
+    const DOOR: &str = r#"{"className": "Part", "referenceId": "door-id"}"#;
+    const HINGE: &str = r#"{"className": "HingeConstraint", "properties": {"Attachment0": {"type": "Ref", "value": "door-id"}, "Attachment1": {"type": "Ref", "value": null}}}"#;
+
+    #[test]
+    fn test_valid_tree_passes() {
+        let dir = tree(&[
+            ("Workspace/Door.rbxjson", DOOR),
+            ("Workspace/Hinge.rbxjson", HINGE),
+            ("Workspace/Model/_meta.rbxjson", r#"{"className": "Model"}"#),
+            ("ServerScriptService/Main.server.luau", "print('hi')\n"),
+            ("ServerScriptService/Main.rbxjson", r#"{"className": "Script"}"#),
+            ("ReplicatedStorage/Lib/init.luau", "return {}\n"),
+            ("ReplicatedStorage/Config.json", r#"{"speed": 5}"#),
+        ]);
+        assert_eq!(verify_tree(dir.path(), None), vec![]);
+    }
+
+    #[test]
+    fn test_reports_each_kind_of_problem() {
+        let dir = tree(&[
+            ("Workspace/Broken.rbxjson", "{\n  \"className\": \"Part\",\n}"),
+            ("Workspace/Hinge.rbxjson", HINGE),
+            ("Workspace/Script.luau", "local function f()\n"),
+            ("Workspace/Sign.rbxjson", r#"{"className": "Part"}"#),
+            ("Workspace/Sign/_meta.rbxjson", r#"{"className": "Model"}"#),
+            ("Workspace/Settings.toml", "speed = "),
+        ]);
+        let issues = verify_tree(dir.path(), None);
+        let messages = messages(&issues);
+        assert_eq!(messages.len(), 5, "{:#?}", messages);
+        assert_eq!(messages[0], "Workspace/Broken.rbxjson:3: Invalid JSON: trailing comma");
+        assert_eq!(
+            messages[1],
+            "Workspace/Hinge.rbxjson: Attachment0 refers to instance door-id, which isn't in the tree"
+        );
+        assert_eq!(messages[2], "Workspace/Script.luau:2: Expected 'end' (to close 'function' at line 1), got <eof>");
+        let kinds: Vec<IssueKind> = issues.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, [IssueKind::Tree, IssueKind::Tree, IssueKind::Luau, IssueKind::Tree, IssueKind::Tree]);
+        assert!(messages[3].starts_with("Workspace/Settings.toml: "));
+        assert_eq!(
+            messages[4],
+            "Workspace/Sign: Sign and Sign.rbxjson would both be an instance named 'Sign'; rename one, or merge them"
+        );
+    }
+
+    #[test]
+    fn test_clashing_scripts() {
+        let dir = tree(&[("Shared/Util.luau", "return 1"), ("Shared/Util.server.luau", "return 2")]);
+        let issues = verify_tree(dir.path(), None);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.starts_with("Util.luau and Util.server.luau"));
+    }
+
+    #[test]
+    fn test_changed_files_and_dependents() {
+        let dir = tree(&[
+            ("Workspace/Hinge.rbxjson", HINGE),
+            ("Workspace/Old.luau", "end"),
+            ("Workspace/New.luau", "end"),
+            ("Lighting/A.luau", "return 1"),
+            ("Lighting/A.server.luau", "return 2"),
+        ]);
+
+        // Only the changed script is checked, and only its folder for clashes
+        let changed = ChangedFiles {
+            files: BTreeSet::from(["Workspace/New.luau".to_string()]),
+            previous_ids: HashSet::new(),
+        };
+        assert_eq!(messages(&verify_tree(dir.path(), Some(&changed))), vec!["Workspace/New.luau:1: Expected <eof>, got 'end'"]);
+
+        // Deleting the Door makes the Hinge that refers to it a dependent
+        let changed = ChangedFiles {
+            files: BTreeSet::from(["Workspace/Door.rbxjson".to_string()]),
+            previous_ids: HashSet::from(["door-id".to_string()]),
+        };
+        let issues = verify_tree(dir.path(), Some(&changed));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].file, "Workspace/Hinge.rbxjson");
+    }
+
+    #[test]
+    fn test_inline_children() {
+        let model = r#"{"className": "Model", "referenceId": "m", "children": [
+            {"className": "Part", "name": "Handle", "referenceId": "h", "properties": {"Parent2": {"type": "Ref", "value": "m"}}},
+            {"className": "Weld", "name": "Weld", "properties": {"Part1": {"type": "Ref", "value": "gone"}}}
+        ]}"#;
+        let dir = tree(&[("Workspace/Tool.rbxjson", model)]);
+        assert_eq!(
+            messages(&verify_tree(dir.path(), None)),
+            vec!["Workspace/Tool.rbxjson: Weld.Part1 refers to instance gone, which isn't in the tree"]
+        );
+        let value: Value = serde_json::from_str(model).unwrap();
+        assert_eq!(reference_ids(&value), vec!["m", "h"]);
+    }
+}
@@ -1,7 +1,7 @@
 use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
     model::{ErrorData as McpError, *},
-    schemars, tool, tool_handler, tool_router, ServerHandler, ServiceExt,
+    schemars, tool, tool_router, ServerHandler, ServiceExt,
     transport::stdio,
 };
 use serde::Deserialize;
@@ -63,6 +63,14 @@ pub struct GitCommitParams {
     pub files: Option<Vec<String>>,
 }
 
+/// Parameters for diff_studio tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DiffParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+}
+
 /// Parameters for git_status tool
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct GitStatusParams {
@@ -71,6 +79,87 @@ pub struct GitStatusParams {
     pub project_dir: String,
 }
 
+/// Parameters for git_branches tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitBranchesParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+}
+
+/// Parameters for git_switch_branch tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitSwitchBranchParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+    /// Branch to check out
+    #[schemars(description = "Branch to check out")]
+    pub branch: String,
+    /// Create the branch from HEAD first
+    #[schemars(description = "Create the branch from the current commit (optional, default: false)")]
+    pub create: Option<bool>,
+}
+
+/// Parameters for git_stage tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitStageParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+    /// Instance paths whose files to stage
+    #[schemars(description = "Instance paths to stage with their descendants, e.g. [\"Workspace/Map/Spawn\"]")]
+    pub paths: Vec<String>,
+}
+
+/// Parameters for git_diff and git_blame tools
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitFileParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+    /// File relative to the project
+    #[schemars(description = "File relative to the project, e.g. src/ServerScriptService/Main.server.luau (or use instance_path)")]
+    pub file: Option<String>,
+    /// Instance path, translated to its files
+    #[schemars(description = "Instance path, e.g. ServerScriptService/Main (or use file)")]
+    pub instance_path: Option<String>,
+    /// Line number for blame
+    #[schemars(description = "Line number, 1-based (git_blame only, default: 1)")]
+    pub line: Option<usize>,
+}
+
+/// Parameters for git_stash tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitStashParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+    /// push, pop or list
+    #[schemars(description = "\"push\" (default), \"pop\" or \"list\"")]
+    pub action: Option<String>,
+    /// Stash message for push
+    #[schemars(description = "Message for the stash (push only, optional)")]
+    pub message: Option<String>,
+}
+
+/// Parameters for git_pull and git_push tools
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GitRemoteParams {
+    /// The project directory
+    #[schemars(description = "The project directory")]
+    pub project_dir: String,
+    /// Remote name or URL
+    #[schemars(description = "Remote name or URL (optional, default: the branch's upstream)")]
+    pub remote: Option<String>,
+    /// Branch on the remote
+    #[schemars(description = "Remote branch (optional)")]
+    pub branch: Option<String>,
+    /// For push: set the remote branch as upstream
+    #[schemars(description = "Set the remote branch as upstream (git_push only, default: false)")]
+    pub set_upstream: Option<bool>,
+}
+
 /// Parameters for run_code tool
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct RunCodeParams {
@@ -405,6 +494,9 @@ impl RbxSyncServer {
         // If delete flag is set, add delete operations for orphaned instances
         let delete_count = if params.delete.unwrap_or(false) {
             let diff = self.client.get_diff(&params.project_dir).await.map_err(|e| mcp_error(e.to_string()))?;
+            if let Some(error) = diff.error {
+                return Err(mcp_error(error));
+            }
             let removed_count = diff.removed.len();
             for entry in diff.removed {
                 operations.push(serde_json::json!({
@@ -463,6 +555,69 @@ impl RbxSyncServer {
         }
     }
 
+    /// Compare the project's files with Studio, down to property values and script sources.
+    #[tool(description = "Compare local files with Roblox Studio: instances only in files or only in Studio, and changed properties, attributes, tags and script sources (as unified diffs)")]
+    async fn diff_studio(
+        &self,
+        Parameters(params): Parameters<DiffParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let diff = self.client.get_diff(&params.project_dir).await.map_err(|e| mcp_error(e.to_string()))?;
+        if !diff.success {
+            return Err(mcp_error(diff.error.unwrap_or_else(|| "Diff failed".to_string())));
+        }
+
+        if diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Files and Studio are in sync ({} instances).",
+                diff.unchanged
+            ))]));
+        }
+
+        let mut lines = vec![format!(
+            "{} added, {} removed, {} modified, {} unchanged",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len(),
+            diff.unchanged
+        )];
+        for entry in &diff.added {
+            lines.push(format!("+ {} ({})", entry.path, entry.class_name.as_deref().unwrap_or("")));
+        }
+        for entry in &diff.removed {
+            lines.push(format!("- {} ({})", entry.path, entry.class_name.as_deref().unwrap_or("")));
+        }
+        for entry in &diff.modified {
+            lines.push(format!("~ {} ({})", entry.path, entry.class_name));
+            if let Some(old_class) = &entry.old_class_name {
+                lines.push(format!("    class: {} in Studio", old_class));
+            }
+            let show = |value: &Option<serde_json::Value>| match value {
+                Some(v) => v.get("value").unwrap_or(v).to_string(),
+                None => "(none)".to_string(),
+            };
+            for change in &entry.properties {
+                lines.push(format!("    {}: {} -> {}", change.name, show(&change.old), show(&change.new)));
+            }
+            for change in &entry.attributes {
+                lines.push(format!("    @{}: {} -> {}", change.name, show(&change.old), show(&change.new)));
+            }
+            for tag in &entry.tags_added {
+                lines.push(format!("    tag +{}", tag));
+            }
+            for tag in &entry.tags_removed {
+                lines.push(format!("    tag -{}", tag));
+            }
+            if let Some(source_diff) = &entry.source_diff {
+                lines.push(source_diff.trim_end().to_string());
+            }
+        }
+        if !diff.ambiguous.is_empty() {
+            lines.push(format!("Not compared (duplicate paths): {}", diff.ambiguous.join(", ")));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]))
+    }
+
     /// Get the git status of a project directory.
     #[tool(description = "Get git status of the project")]
     async fn git_status(
@@ -525,6 +680,198 @@ impl RbxSyncServer {
         }
     }
 
+    /// List local git branches.
+    #[tool(description = "List local git branches")]
+    async fn git_branches(
+        &self,
+        Parameters(params): Parameters<GitBranchesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let branches: Vec<tools::GitBranch> = self
+            .client
+            .git_request("branches", serde_json::json!({ "project_dir": params.project_dir }))
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+
+        let lines: Vec<String> = branches
+            .iter()
+            .map(|b| {
+                let upstream = b.upstream.as_deref().map(|u| format!(" → {}", u)).unwrap_or_default();
+                format!("{} {} ({}){}", if b.current { "*" } else { " " }, b.name, b.commit, upstream)
+            })
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]))
+    }
+
+    /// Check out a git branch.
+    #[tool(description = "Switch to a git branch, optionally creating it. Studio is not resynced automatically.")]
+    async fn git_switch_branch(
+        &self,
+        Parameters(params): Parameters<GitSwitchBranchParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let branch: String = self
+            .client
+            .git_request(
+                "switch",
+                serde_json::json!({
+                    "project_dir": params.project_dir,
+                    "branch": params.branch,
+                    "create": params.create.unwrap_or(false)
+                }),
+            )
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+        Ok(CallToolResult::success(vec![Content::text(format!("Switched to {}", branch))]))
+    }
+
+    /// Stage the files of instances by their paths.
+    #[tool(description = "Stage instances for commit by instance path (e.g. Workspace/Map/Spawn), including descendants and deletions")]
+    async fn git_stage(
+        &self,
+        Parameters(params): Parameters<GitStageParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let files: Vec<String> = self
+            .client
+            .git_request("stage", serde_json::json!({ "project_dir": params.project_dir, "paths": params.paths }))
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+
+        let mut lines = vec![format!("Staged {} file(s):", files.len())];
+        lines.extend(files.iter().map(|f| format!("  + {}", f)));
+        Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]))
+    }
+
+    /// Diff a file or instance against HEAD.
+    #[tool(description = "Show the git diff against HEAD for a file or instance path (all changes if neither is given)")]
+    async fn git_diff(
+        &self,
+        Parameters(params): Parameters<GitFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let diff: String = self
+            .client
+            .git_request(
+                "diff",
+                serde_json::json!({
+                    "project_dir": params.project_dir,
+                    "file": params.file,
+                    "instance_path": params.instance_path
+                }),
+            )
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+
+        if diff.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text("No changes against HEAD.")]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(diff)]))
+    }
+
+    /// Show who last changed a script line.
+    #[tool(description = "Show the commit and author that last changed a line of a script, by file or instance path")]
+    async fn git_blame(
+        &self,
+        Parameters(params): Parameters<GitFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let blame: tools::BlameLine = self
+            .client
+            .git_request(
+                "blame",
+                serde_json::json!({
+                    "project_dir": params.project_dir,
+                    "file": params.file,
+                    "instance_path": params.instance_path,
+                    "line": params.line.unwrap_or(1)
+                }),
+            )
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{}:{}\n  {}\n{} {} — {}",
+            blame.file, blame.line, blame.content, &blame.hash[..blame.hash.len().min(8)], blame.author, blame.summary
+        ))]))
+    }
+
+    /// Stash, restore or list local changes.
+    #[tool(description = "Stash local changes (push), restore the latest stash (pop) or list stashes")]
+    async fn git_stash(
+        &self,
+        Parameters(params): Parameters<GitStashParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let action = params.action.unwrap_or_else(|| "push".to_string());
+        let body = serde_json::json!({
+            "project_dir": params.project_dir,
+            "action": action,
+            "message": params.message
+        });
+
+        if action == "list" {
+            let stashes: Vec<tools::GitStash> = self
+                .client
+                .git_request("stash", body)
+                .await
+                .map_err(|e| mcp_error(e.to_string()))?;
+            if stashes.is_empty() {
+                return Ok(CallToolResult::success(vec![Content::text("No stashes.")]));
+            }
+            let lines: Vec<String> = stashes.iter().map(|s| format!("{}: {}", s.name, s.message)).collect();
+            return Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]));
+        }
+
+        let output: String = self
+            .client
+            .git_request("stash", body)
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+        Ok(CallToolResult::success(vec![Content::text(output.trim().to_string())]))
+    }
+
+    /// Pull from a git remote.
+    #[tool(description = "Pull from a git remote into the current branch")]
+    async fn git_pull(
+        &self,
+        Parameters(params): Parameters<GitRemoteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let output: String = self
+            .client
+            .git_request(
+                "pull",
+                serde_json::json!({
+                    "project_dir": params.project_dir,
+                    "remote": params.remote,
+                    "branch": params.branch
+                }),
+            )
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+        Ok(CallToolResult::success(vec![Content::text(output.trim().to_string())]))
+    }
+
+    /// Push to a git remote.
+    #[tool(description = "Push the current branch to a git remote")]
+    async fn git_push(
+        &self,
+        Parameters(params): Parameters<GitRemoteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let output: String = self
+            .client
+            .git_request(
+                "push",
+                serde_json::json!({
+                    "project_dir": params.project_dir,
+                    "remote": params.remote,
+                    "branch": params.branch,
+                    "set_upstream": params.set_upstream.unwrap_or(false)
+                }),
+            )
+            .await
+            .map_err(|e| mcp_error(e.to_string()))?;
+        Ok(CallToolResult::success(vec![Content::text(if output.is_empty() {
+            "Pushed.".to_string()
+        } else {
+            output
+        })]))
+    }
+
     /// Execute Luau code in Roblox Studio.
     #[tool(description = "Execute Luau code in Roblox Studio")]
     async fn run_code(
@@ -1335,8 +1682,36 @@ impl RbxSyncServer {
     }
 }
 
-#[tool_handler]
 impl ServerHandler for RbxSyncServer {
+    /// Run a tool call, abandoning it if the client cancels the request
+    ///
+    /// Dropping the tool future drops its in-flight HTTP request, which makes
+    /// the RbxSync server cancel the matching plugin command.
+    async fn call_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let ct = context.ct.clone();
+        let name = request.name.clone();
+        let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        tokio::select! {
+            result = self.tool_router.call(tcc) => result,
+            _ = ct.cancelled() => {
+                tracing::info!("Tool call {} cancelled by client", name);
+                Err(mcp_error(format!("Tool call {} cancelled", name)))
+            }
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<rmcp::model::ListToolsResult, McpError> {
+        Ok(rmcp::model::ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
@@ -170,6 +170,36 @@ pub struct GitStatusResponse {
     pub untracked: Vec<String>,
 }
 
+/// A local branch (matches server's GitBranch)
+#[derive(Debug, Deserialize)]
+pub struct GitBranch {
+    pub name: String,
+    #[serde(default)]
+    pub current: bool,
+    #[serde(default)]
+    pub upstream: Option<String>,
+    #[serde(default)]
+    pub commit: String,
+}
+
+/// A stash entry (matches server's GitStash)
+#[derive(Debug, Deserialize)]
+pub struct GitStash {
+    pub name: String,
+    pub message: String,
+}
+
+/// The commit that last changed a line (matches server's BlameLine)
+#[derive(Debug, Deserialize)]
+pub struct BlameLine {
+    pub file: String,
+    pub line: usize,
+    pub hash: String,
+    pub author: String,
+    pub summary: String,
+    pub content: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GitCommitResponse {
     pub success: bool,
@@ -282,16 +312,50 @@ pub struct InsertModelResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DiffEntry {
     pub path: String,
     pub class_name: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ValueChange {
+    pub name: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDiff {
+    pub path: String,
+    pub class_name: String,
+    pub old_class_name: Option<String>,
+    #[serde(default)]
+    pub properties: Vec<ValueChange>,
+    #[serde(default)]
+    pub attributes: Vec<ValueChange>,
+    #[serde(default)]
+    pub tags_added: Vec<String>,
+    #[serde(default)]
+    pub tags_removed: Vec<String>,
+    pub source_diff: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DiffResponse {
+    pub success: bool,
+    #[serde(default)]
     pub added: Vec<DiffEntry>,
+    #[serde(default)]
     pub removed: Vec<DiffEntry>,
+    #[serde(default)]
+    pub modified: Vec<InstanceDiff>,
+    #[serde(default)]
     pub unchanged: usize,
+    #[serde(default)]
+    pub ambiguous: Vec<String>,
+    pub error: Option<String>,
 }
 
 impl RbxSyncClient {
@@ -509,6 +573,28 @@ impl RbxSyncClient {
         Ok(resp)
     }
 
+    /// Call a `/git/...` endpoint, returning its `data` or its error
+    pub async fn git_request<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> anyhow::Result<T> {
+        let resp: CommandResponse<serde_json::Value> = self
+            .client
+            .post(format!("{}/git/{}", self.base_url, endpoint))
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if !resp.success {
+            anyhow::bail!("{}", resp.error.unwrap_or_else(|| format!("git {} failed", endpoint)));
+        }
+        let data = resp.data.ok_or_else(|| anyhow::anyhow!("Missing data in git {} response", endpoint))?;
+        Ok(serde_json::from_value(data)?)
+    }
+
     pub async fn run_code(&self, code: &str) -> anyhow::Result<String> {
         let resp: RunCodeResponse = self
             .client
@@ -0,0 +1,280 @@
+//! Crash-safe file writes
+//!
+//! Single files are written to a temp file next to the target, fsynced and
+//! renamed over it, so readers see either the old or the new content. Trees
+//! that are rebuilt as a whole (extraction's `src`) are built in a staging
+//! directory under `.rbxsync-staging` and swapped in with two renames; the
+//! tree they replace moves to `.rbxsync-backup`. Undo swaps the backup back
+//! the same way ([`restore_backup`]). [`recover`] finishes or discards a swap
+//! that a crash interrupted.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Scratch directory in the project for staged trees and extraction chunks
+pub const STAGING_DIR: &str = ".rbxsync-staging";
+
+/// Where a swapped-out tree is kept (undo extraction restores from here)
+pub const BACKUP_DIR: &str = ".rbxsync-backup";
+
+/// Written into a staged tree once it is complete; a staged tree without it
+/// is discarded by [`recover`]
+pub const COMPLETE_MARKER: &str = ".rbxsync-complete";
+
+/// Suffix of a tree set aside in the backup directory while undo restores
+/// the backup over it
+pub const REPLACED_SUFFIX: &str = ".replaced";
+
+/// Suffix of temp files; the file watcher ignores them
+pub const TEMP_SUFFIX: &str = ".rbxsync-tmp";
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a path is one of our temp files
+pub fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| name.ends_with(TEMP_SUFFIX))
+}
+
+/// Write a file atomically: temp file in the same directory, fsync, rename
+///
+/// Parent directories are created as needed.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp_path = parent.join(format!(
+        ".{}.{}-{}{}",
+        name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+        TEMP_SUFFIX
+    ));
+
+    let result = write_synced(&tmp_path, contents).and_then(|()| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return result;
+    }
+    sync_dir(parent)
+}
+
+/// Write and fsync a file in place (for files in a staged tree, which is
+/// swapped in as a whole)
+pub fn write_synced(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(contents.as_ref())?;
+    file.sync_all()
+}
+
+/// Flush a directory's entries (new names, renames) to disk
+pub fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::fs::File::open(dir)?.sync_all()
+    }
+    #[cfg(not(unix))]
+    {
+        // Directories can't be opened for syncing on Windows; NTFS journals renames
+        let _ = dir;
+        Ok(())
+    }
+}
+
+/// A tree being built in the staging directory to replace `<project>/<name>`
+#[derive(Debug)]
+pub struct StagedDir {
+    project_dir: PathBuf,
+    name: String,
+    staging: PathBuf,
+}
+
+impl StagedDir {
+    /// Start staging a replacement for `<project_dir>/<name>`, discarding any
+    /// earlier unfinished staging of it
+    pub fn begin(project_dir: impl Into<PathBuf>, name: &str) -> std::io::Result<Self> {
+        let project_dir = project_dir.into();
+        let staging = project_dir.join(STAGING_DIR).join(name);
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        std::fs::create_dir_all(&staging)?;
+        Ok(Self {
+            project_dir,
+            name: name.to_string(),
+            staging,
+        })
+    }
+
+    /// Directory to write the new tree into
+    pub fn path(&self) -> &Path {
+        &self.staging
+    }
+
+    /// Swap the staged tree in; the current tree moves to `.rbxsync-backup`
+    pub fn commit(self) -> std::io::Result<()> {
+        write_synced(&self.staging.join(COMPLETE_MARKER), b"")?;
+        sync_dir(&self.staging)?;
+        swap_in(&self.project_dir, &self.name)
+    }
+
+    /// Throw the staged tree away, leaving the current one untouched
+    pub fn abandon(self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.staging) {
+            tracing::warn!("Failed to remove staging directory {:?}: {}", self.staging, e);
+        }
+    }
+}
+
+/// Move `<project>/<name>` to the backup and the complete staged tree into its place
+fn swap_in(project_dir: &Path, name: &str) -> std::io::Result<()> {
+    let target = project_dir.join(name);
+    let staging = project_dir.join(STAGING_DIR).join(name);
+    let backup = project_dir.join(BACKUP_DIR).join(name);
+
+    if target.exists() {
+        if backup.exists() {
+            std::fs::remove_dir_all(&backup)?;
+        }
+        std::fs::create_dir_all(project_dir.join(BACKUP_DIR))?;
+        std::fs::rename(&target, &backup)?;
+    }
+    std::fs::rename(&staging, &target)?;
+    sync_dir(project_dir)?;
+    std::fs::remove_file(target.join(COMPLETE_MARKER))
+}
+
+/// Put the backup of `<project>/<name>` back in its place
+///
+/// The current tree is renamed aside within the backup directory, the backup
+/// renamed in and the set-aside tree deleted, so `<name>` is never missing for
+/// longer than [`recover`] can repair.
+pub fn restore_backup(project_dir: &Path, name: &str) -> std::io::Result<()> {
+    let target = project_dir.join(name);
+    let backup = project_dir.join(BACKUP_DIR).join(name);
+    let replaced = project_dir.join(BACKUP_DIR).join(format!("{}{}", name, REPLACED_SUFFIX));
+
+    if !backup.exists() {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "No backup found to restore"));
+    }
+    if replaced.exists() {
+        std::fs::remove_dir_all(&replaced)?;
+    }
+    if target.exists() {
+        std::fs::rename(&target, &replaced)?;
+    }
+    std::fs::rename(&backup, &target)?;
+    sync_dir(project_dir)?;
+    if replaced.exists() {
+        std::fs::remove_dir_all(&replaced)?;
+    }
+    Ok(())
+}
+
+/// Finish an undo interrupted by a crash: if the tree it set aside was not yet
+/// replaced, put the backup (or failing that, the set-aside tree) back in place
+fn recover_restores(project_dir: &Path, repaired: &mut Vec<String>) {
+    let backup_root = project_dir.join(BACKUP_DIR);
+    let Ok(entries) = std::fs::read_dir(&backup_root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name.strip_suffix(REPLACED_SUFFIX) else {
+            continue;
+        };
+        let replaced = entry.path();
+        let target = project_dir.join(name);
+        let backup = backup_root.join(name);
+
+        if !target.exists() {
+            let (from, repair) = if backup.exists() {
+                (&backup, format!("finished restoring {} from its backup", name))
+            } else {
+                (&replaced, format!("rolled back the restore of {}", name))
+            };
+            match std::fs::rename(from, &target).and_then(|()| sync_dir(project_dir)) {
+                Ok(()) => repaired.push(repair),
+                Err(e) => {
+                    tracing::error!("Failed to restore {:?} from {:?}: {}", target, from, e);
+                    continue;
+                }
+            }
+        }
+        if replaced.exists() {
+            match std::fs::remove_dir_all(&replaced) {
+                Ok(()) => repaired.push(format!("removed {}/{}", BACKUP_DIR, file_name)),
+                Err(e) => tracing::warn!("Failed to remove {:?}: {}", replaced, e),
+            }
+        }
+    }
+}
+
+/// Finish or discard swaps interrupted by a crash and remove stray temp files
+///
+/// Returns a description of each repair made.
+pub fn recover(project_dir: &Path) -> Vec<String> {
+    let mut repaired = Vec::new();
+    recover_restores(project_dir, &mut repaired);
+    let staging_root = project_dir.join(STAGING_DIR);
+
+    if let Ok(entries) = std::fs::read_dir(&staging_root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.join(COMPLETE_MARKER).exists() {
+                match swap_in(project_dir, &name) {
+                    Ok(()) => repaired.push(format!("finished replacing {} with its staged copy", name)),
+                    Err(e) => tracing::error!("Failed to finish staged swap of {:?}: {}", path, e),
+                }
+            } else {
+                let removed = if path.is_dir() {
+                    std::fs::remove_dir_all(&path)
+                } else {
+                    std::fs::remove_file(&path)
+                };
+                match removed {
+                    Ok(()) => repaired.push(format!("discarded incomplete {}/{}", STAGING_DIR, name)),
+                    Err(e) => tracing::warn!("Failed to remove {:?}: {}", path, e),
+                }
+            }
+        }
+        let _ = std::fs::remove_dir(&staging_root);
+    }
+
+    // A crash after the swap but before the marker was removed
+    if let Ok(entries) = std::fs::read_dir(project_dir) {
+        for entry in entries.flatten() {
+            let marker = entry.path().join(COMPLETE_MARKER);
+            if marker.exists() && std::fs::remove_file(&marker).is_ok() {
+                repaired.push(format!("removed leftover marker in {}", entry.file_name().to_string_lossy()));
+            }
+        }
+    }
+
+    let mut stack = vec![project_dir.join("src")];
+    let mut temp_files = 0;
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if is_temp_file(&path) && std::fs::remove_file(&path).is_ok() {
+                temp_files += 1;
+            }
+        }
+    }
+    if temp_files > 0 {
+        repaired.push(format!("removed {} partially written temp file(s)", temp_files));
+    }
+
+    for repair in &repaired {
+        tracing::warn!("Recovered {}: {}", project_dir.display(), repair);
+    }
+    repaired
+}
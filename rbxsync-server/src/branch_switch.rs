@@ -0,0 +1,454 @@
+//! Branch switches during live sync
+//!
+//! `git checkout`, `git pull` or `git reset` can rewrite hundreds of files at
+//! once. Sending them through the debounced file watcher floods Studio with
+//! batches and can miss deletions, so the watcher also watches the
+//! repository's `HEAD`, refs and index lock. While git is working, the
+//! project's file changes are held back. Once things settle, if HEAD moved
+//! other than through a commit, the held changes are dropped and the
+//! instance-level difference between the old commit and the working tree is
+//! computed instead. With `sync.branchSwitch: "review"` (the default) live
+//! sync pauses for the project until the switch is applied or discarded;
+//! with `"auto"` it is applied straight away. Applying sends the whole
+//! difference to Studio as one batch, so it can be undone in one step; the
+//! batch is exempt from `maxBatchInstances` and gets a longer timeout
+//! instead. The comparison runs in the background so live sync for other
+//! projects carries on meanwhile.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rbxsync_core::{PropertyScope, SourceFormat, TreeDiff};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::git::{self, GitError, HeadState};
+use crate::{events, file_watcher, live_sync, AppState, ConsoleMessage};
+
+/// Quiet time after the last git event before HEAD is checked
+pub const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// The repository of a watched project
+#[derive(Debug, Clone)]
+pub struct GitWatch {
+    pub git_dir: PathBuf,
+    /// HEAD as of the last check
+    pub head: HeadState,
+    /// Last event in the git directory that hasn't been checked yet
+    pub activity: Option<Instant>,
+}
+
+impl GitWatch {
+    /// `None` if the project isn't in a git repository
+    pub fn open(project_dir: &Path) -> Option<Self> {
+        let git_dir = git::git_dir(project_dir).ok()?;
+        let head = git::head(project_dir).ok()?;
+        Some(Self {
+            git_dir,
+            head,
+            activity: None,
+        })
+    }
+
+    /// Whether a path in the git directory signals a HEAD change: HEAD
+    /// itself, a ref, or the index lock git takes while updating files
+    pub fn is_head_event(&self, path: &Path) -> bool {
+        let Ok(rel) = path.strip_prefix(&self.git_dir) else {
+            return false;
+        };
+        let rel = rbxsync_core::path_to_string(rel);
+        matches!(rel.as_str(), "HEAD" | "HEAD.lock" | "ORIG_HEAD" | "index.lock" | "packed-refs")
+            || rel.starts_with("refs/")
+    }
+
+    /// Whether git is in the middle of something that moves HEAD or writes
+    /// files
+    fn busy(&self) -> bool {
+        ["index.lock", "HEAD.lock", "rebase-merge", "rebase-apply"]
+            .iter()
+            .any(|name| self.git_dir.join(name).exists())
+    }
+}
+
+/// A HEAD change whose file changes haven't been sent to Studio
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchSwitch {
+    pub project_dir: String,
+    pub from: HeadState,
+    pub to: HeadState,
+    /// What moved HEAD, from the reflog (`checkout: moving from main to feature`)
+    pub action: Option<String>,
+    pub detected_at: u64,
+    /// Instance changes from the old commit to the working tree
+    pub diff: TreeDiff,
+}
+
+impl BranchSwitch {
+    /// `main → feature: 3 added, 1 removed, 0 moved, 12 modified`
+    pub fn summary(&self) -> String {
+        format!(
+            "{} → {}: {} added, {} removed, {} moved, {} modified",
+            self.from.label(),
+            self.to.label(),
+            self.diff.added.len(),
+            self.diff.removed.len(),
+            self.diff.moved.len(),
+            self.diff.modified.len()
+        )
+    }
+}
+
+/// Branch switches waiting for review, per project directory
+#[derive(Debug, Default)]
+pub struct BranchSwitches {
+    pending: Mutex<HashMap<String, BranchSwitch>>,
+    /// Projects whose HEAD change is still being compared
+    comparing: Mutex<HashSet<String>>,
+}
+
+impl BranchSwitches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, BranchSwitch>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, project_dir: &str) -> Option<BranchSwitch> {
+        self.lock().get(project_dir).cloned()
+    }
+
+    /// Every pending switch, oldest first
+    pub fn list(&self) -> Vec<BranchSwitch> {
+        let mut switches: Vec<_> = self.lock().values().cloned().collect();
+        switches.sort_by_key(|s| s.detected_at);
+        switches
+    }
+
+    fn insert(&self, switch: BranchSwitch) {
+        self.lock().insert(switch.project_dir.clone(), switch);
+    }
+
+    fn remove(&self, project_dir: &str) -> Option<BranchSwitch> {
+        self.lock().remove(project_dir)
+    }
+
+    /// Whether a HEAD change in the project is still being compared
+    pub fn is_comparing(&self, project_dir: &str) -> bool {
+        self.comparing.lock().unwrap_or_else(|e| e.into_inner()).contains(project_dir)
+    }
+
+    fn set_comparing(&self, project_dir: &str, comparing: bool) {
+        let mut set = self.comparing.lock().unwrap_or_else(|e| e.into_inner());
+        if comparing {
+            set.insert(project_dir.to_string());
+        } else {
+            set.remove(project_dir);
+        }
+    }
+
+    /// Projects with a switch pending or being compared
+    fn project_dirs(&self) -> Vec<String> {
+        let mut dirs: Vec<String> = self.lock().keys().cloned().collect();
+        dirs.extend(self.comparing.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned());
+        dirs
+    }
+}
+
+/// What the file watcher should do with each project's changes, from [`poll`]
+#[derive(Debug, Default)]
+pub struct GitActivity {
+    /// Git is still working: keep the changes until it's done
+    pub held: HashSet<String>,
+    /// HEAD moved or a switch awaits review: drop the changes
+    pub paused: HashSet<String>,
+}
+
+/// Check projects with recent git activity for HEAD changes
+///
+/// Called from the live-sync loop before each round of file changes.
+pub async fn poll(state: &Arc<AppState>) -> GitActivity {
+    let mut activity = GitActivity::default();
+    let mut settled = Vec::new();
+    {
+        let watcher = state.file_watcher_state.read().await;
+        for (project_dir, git) in &watcher.git {
+            let Some(last) = git.activity else {
+                continue;
+            };
+            if last.elapsed() < SETTLE_TIME || git.busy() {
+                activity.held.insert(project_dir.clone());
+            } else {
+                settled.push((project_dir.clone(), git.clone()));
+            }
+        }
+    }
+
+    for (project_dir, git) in settled {
+        let dir = PathBuf::from(&project_dir);
+        let head = tokio::task::spawn_blocking(move || git::head(&dir))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or_else(|| git.head.clone());
+        {
+            let mut watcher = state.file_watcher_state.write().await;
+            if let Some(watch) = watcher.git.get_mut(&project_dir) {
+                // Newer events get their own settle time
+                if watch.activity == git.activity {
+                    watch.activity = None;
+                }
+                watch.head = head.clone();
+            }
+        }
+
+        // Nothing to compare with before the first commit
+        let (Some(from), Some(_)) = (&git.head.commit, &head.commit) else {
+            continue;
+        };
+        // Same commit: `git status`, a stash, or a branch created in place
+        if git.head.commit == head.commit {
+            continue;
+        }
+        let dir = PathBuf::from(&project_dir);
+        let action = tokio::task::spawn_blocking(move || git::last_head_update(&dir))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .flatten();
+        // A commit records files that were already synced
+        if action.as_deref().is_some_and(|a| a.starts_with("commit")) {
+            continue;
+        }
+
+        activity.paused.insert(project_dir.clone());
+        tracing::info!("HEAD moved in {}: {} -> {} ({:?})", project_dir, from, head.label(), action);
+        state.branch_switches.set_comparing(&project_dir, true);
+        let state = state.clone();
+        tokio::spawn(async move {
+            detected(&state, &project_dir, git.head, head, action).await;
+            state.branch_switches.set_comparing(&project_dir, false);
+        });
+    }
+
+    activity.paused.extend(state.branch_switches.project_dirs());
+    activity
+}
+
+/// Compare the old commit with the working tree and queue or apply the result
+async fn detected(state: &Arc<AppState>, project_dir: &str, from: HeadState, to: HeadState, action: Option<String>) {
+    let Some(from_commit) = from.commit.clone() else {
+        return;
+    };
+    let dir = project_dir.to_string();
+    let diff = match tokio::task::spawn_blocking(move || changes(&dir, &from_commit)).await {
+        Ok(Ok((diff, _))) => diff,
+        Ok(Err(e)) => {
+            console(state, "error", format!("Branch switch: failed to compare with {}: {}", from.label(), e)).await;
+            resume(state, project_dir).await;
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let switch = BranchSwitch {
+        project_dir: project_dir.to_string(),
+        from,
+        to,
+        action,
+        detected_at: crate::now_millis(),
+        diff,
+    };
+    state.events.publish(events::ServerEvent::BranchSwitchDetected {
+        project_dir: project_dir.to_string(),
+        from: switch.from.label(),
+        to: switch.to.label(),
+        added: switch.diff.added.len(),
+        removed: switch.diff.removed.len(),
+        moved: switch.diff.moved.len(),
+        modified: switch.diff.modified.len(),
+    });
+
+    if switch.diff.is_empty() {
+        console(state, "info", format!("Branch switch {}: no instance changes", switch.summary())).await;
+        resume(state, project_dir).await;
+        return;
+    }
+    let auto = crate::load_project_config(project_dir)
+        .and_then(|config| config.get("sync")?.get("branchSwitch")?.as_str().map(|m| m == "auto"))
+        .unwrap_or(false);
+    let summary = switch.summary();
+    state.branch_switches.insert(switch);
+    if auto {
+        console(state, "info", format!("Branch switch {}", summary)).await;
+        if let Err(e) = apply(state, project_dir).await {
+            console(state, "error", format!("Branch switch: {}", e)).await;
+        }
+    } else {
+        console(
+            state,
+            "warn",
+            format!(
+                "Branch switch {}. Live sync is paused until the changes are applied or discarded: rbxsync git branch-switch",
+                summary
+            ),
+        )
+        .await;
+    }
+}
+
+/// Send a pending switch to Studio as one batch and resume live sync
+///
+/// The difference is recomputed against the current working tree, so edits
+/// made while the switch was waiting are included. The batch isn't split at
+/// `maxBatchInstances`; see [`live_sync::queue_large_batch`]. Returns the
+/// batch ID, or `None` if there was nothing left to send.
+pub async fn apply(state: &Arc<AppState>, project_dir: &str) -> Result<Option<(Uuid, TreeDiff)>, String> {
+    let switch = state
+        .branch_switches
+        .get(project_dir)
+        .ok_or_else(|| format!("No branch switch pending for {}", project_dir))?;
+    let from = switch.from.commit.clone().unwrap_or_default();
+    let dir = project_dir.to_string();
+    let (diff, operations) = tokio::task::spawn_blocking(move || changes(&dir, &from))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    state.branch_switches.remove(project_dir);
+    resume(state, project_dir).await;
+    if operations.is_empty() {
+        return Ok(None);
+    }
+    console(
+        state,
+        "info",
+        format!("Applying branch switch {} in one batch", switch.summary()),
+    )
+    .await;
+    let id = live_sync::queue_large_batch(state, project_dir, operations).await;
+    state.events.publish(events::ServerEvent::BranchSwitchResolved {
+        project_dir: project_dir.to_string(),
+        applied: true,
+        request_id: Some(id.to_string()),
+    });
+    Ok(Some((id, diff)))
+}
+
+/// Drop a pending switch without touching Studio and resume live sync
+pub async fn discard(state: &Arc<AppState>, project_dir: &str) -> bool {
+    if state.branch_switches.remove(project_dir).is_none() {
+        return false;
+    }
+    resume(state, project_dir).await;
+    console(state, "info", format!("Branch switch discarded for {}; Studio was not changed", project_dir)).await;
+    state.events.publish(events::ServerEvent::BranchSwitchResolved {
+        project_dir: project_dir.to_string(),
+        applied: false,
+        request_id: None,
+    });
+    true
+}
+
+/// Instance changes from `from` to the working tree, and the sync operations
+/// that make them in Studio
+fn changes(project_dir: &str, from: &str) -> Result<(TreeDiff, Vec<Value>), GitError> {
+    let export = std::env::temp_dir().join(format!("rbxsync-switch-{}", Uuid::new_v4()));
+    let old = git::export_revision(Path::new(project_dir), from, &export)
+        .map(|()| crate::read_tree_instances(&export.to_string_lossy()));
+    let _ = std::fs::remove_dir_all(&export);
+    let old = old?;
+
+    let new = crate::read_tree_instances(project_dir);
+    let format = SourceFormat::from_project_config(crate::load_project_config(project_dir).as_ref());
+    let diff = rbxsync_core::diff_trees(&old, &new, &format, PropertyScope::All);
+    let operations = operations(&diff, &new);
+    Ok((diff, operations))
+}
+
+/// Sync operations that turn the old tree into `new`, in the shape the file
+/// watcher sends
+pub fn operations(diff: &TreeDiff, new: &[Value]) -> Vec<Value> {
+    let by_path: HashMap<&str, &Value> = new
+        .iter()
+        .filter_map(|inst| Some((inst.get("path")?.as_str()?, inst)))
+        .collect();
+    let data = |path: &str| {
+        by_path.get(path).map(|inst| {
+            let mut data = (*inst).clone();
+            if let Some(source) = data.pointer("/properties/Source/value").cloned() {
+                data["source"] = source;
+            }
+            data
+        })
+    };
+
+    let mut operations = Vec::new();
+    for entry in &diff.added {
+        if let Some(data) = data(&entry.path) {
+            operations.push(serde_json::json!({ "type": "create", "path": entry.path, "data": data }));
+        }
+    }
+    for moved in &diff.moved {
+        let name = moved.to.rsplit('/').next().unwrap_or(&moved.to);
+        let mut op = serde_json::json!({ "type": "move", "from": moved.from, "path": moved.to, "name": name });
+        if let Some(data) = data(&moved.to) {
+            op["data"] = data;
+        }
+        operations.push(op);
+    }
+    for changes in &diff.modified {
+        if let Some(data) = data(&changes.path) {
+            operations.push(serde_json::json!({ "type": "update", "path": changes.path, "data": data }));
+        }
+    }
+    for entry in &diff.removed {
+        operations.push(serde_json::json!({ "type": "delete", "path": entry.path }));
+    }
+    operations
+}
+
+/// Re-index the project's files for move detection, since the changes git
+/// made never went through the watcher
+async fn resume(state: &Arc<AppState>, project_dir: &str) {
+    let roots: Vec<PathBuf> = {
+        let watcher = state.file_watcher_state.read().await;
+        watcher
+            .roots
+            .get(project_dir)
+            .map(|roots| roots.iter().map(|root| root.path.clone()).collect())
+            .unwrap_or_default()
+    };
+    let index = {
+        let roots = roots.clone();
+        tokio::task::spawn_blocking(move || {
+            roots
+                .iter()
+                .flat_map(|root| file_watcher::index_directory(root))
+                .collect::<HashMap<_, _>>()
+        })
+        .await
+        .unwrap_or_default()
+    };
+    let mut watcher = state.file_watcher_state.write().await;
+    watcher
+        .fingerprints
+        .retain(|path, _| !roots.iter().any(|root| path.starts_with(root)));
+    watcher.fingerprints.extend(index);
+}
+
+async fn console(state: &AppState, message_type: &str, message: String) {
+    state
+        .push_console(ConsoleMessage {
+            timestamp: live_sync::clock_time(),
+            message_type: message_type.to_string(),
+            message,
+            source: Some("sync".to_string()),
+        })
+        .await;
+}
@@ -0,0 +1,470 @@
+//! Daemon mode
+//!
+//! Support for running the server as a managed background daemon: a PID/lock
+//! file so only one daemon runs per port, a size-rotated log file, status and
+//! config reload for every project the daemon serves, and (on Unix) a control
+//! socket that accepts line-delimited JSON commands.
+
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{file_watcher, AppState};
+
+/// Rotate the daemon log once it exceeds this size
+pub const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Number of rotated log files to keep (`.log.1` .. `.log.N`)
+pub const LOG_KEEP_FILES: usize = 3;
+
+/// File locations for a daemon listening on one port
+#[derive(Debug, Clone)]
+pub struct DaemonPaths {
+    pub pid_file: PathBuf,
+    pub log_file: PathBuf,
+    pub socket: PathBuf,
+}
+
+impl DaemonPaths {
+    /// Default daemon directory (`~/.rbxsync/daemon`)
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".rbxsync").join("daemon"))
+    }
+
+    /// Paths for the daemon on `port` inside `dir`
+    pub fn new(dir: &Path, port: u16) -> Self {
+        Self {
+            pid_file: dir.join(format!("rbxsync-{}.pid", port)),
+            log_file: dir.join(format!("rbxsync-{}.log", port)),
+            socket: dir.join(format!("rbxsync-{}.sock", port)),
+        }
+    }
+
+    /// Paths for the daemon on `port` in the default directory
+    pub fn for_port(port: u16) -> Option<Self> {
+        Self::default_dir().map(|dir| Self::new(&dir, port))
+    }
+
+    /// Remove the PID file and control socket
+    pub fn cleanup(&self) {
+        let _ = std::fs::remove_file(&self.pid_file);
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+/// Contents of the PID file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonInfo {
+    pub pid: u32,
+    pub port: u16,
+    /// Unix timestamp in millis
+    pub started_at: u64,
+    pub log_file: PathBuf,
+    pub socket: PathBuf,
+}
+
+impl DaemonInfo {
+    /// Read the PID file, returning `None` if it doesn't exist or is unreadable
+    pub fn read(pid_file: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(pid_file).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Whether the recorded process is still running
+    pub fn is_alive(&self) -> bool {
+        process_alive(self.pid)
+    }
+}
+
+/// Exclusive PID file, removed (with the control socket) when dropped
+///
+/// The file stays locked while the daemon runs, so of two daemons starting
+/// at once only one gets it, and a crashed daemon's lock goes away with it.
+#[derive(Debug)]
+pub struct PidLock {
+    paths: DaemonPaths,
+    _file: File,
+}
+
+impl PidLock {
+    /// Acquire the PID file for this process
+    ///
+    /// Fails if another live daemon holds it; a PID file left behind by a
+    /// crashed daemon is replaced.
+    pub fn acquire(paths: &DaemonPaths, port: u16) -> anyhow::Result<Self> {
+        if let Some(parent) = paths.pid_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let info = DaemonInfo {
+            pid: std::process::id(),
+            port,
+            started_at: crate::now_millis(),
+            log_file: paths.log_file.clone(),
+            socket: paths.socket.clone(),
+        };
+
+        for _ in 0..2 {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&paths.pid_file)?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(std::fs::TryLockError::WouldBlock) => match DaemonInfo::read(&paths.pid_file) {
+                    Some(existing) => anyhow::bail!(
+                        "Daemon already running on port {} (PID {})",
+                        existing.port,
+                        existing.pid
+                    ),
+                    None => anyhow::bail!("Daemon already running ({} is locked)", paths.pid_file.display()),
+                },
+                Err(std::fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+
+            // A daemon shutting down removes its PID file before unlocking
+            // it; if that's the file we locked, start over with a new one
+            if !is_same_file(&file, &paths.pid_file) {
+                continue;
+            }
+
+            // Written by a daemon that didn't lock it
+            if let Some(existing) = DaemonInfo::read(&paths.pid_file) {
+                if existing.pid != info.pid && existing.is_alive() {
+                    anyhow::bail!(
+                        "Daemon already running on port {} (PID {})",
+                        existing.port,
+                        existing.pid
+                    );
+                }
+                tracing::warn!("Replacing stale PID file: {}", paths.pid_file.display());
+            }
+
+            file.set_len(0)?;
+            file.write_all(serde_json::to_string_pretty(&info)?.as_bytes())?;
+            file.sync_all()?;
+            return Ok(Self {
+                paths: paths.clone(),
+                _file: file,
+            });
+        }
+
+        anyhow::bail!("Failed to acquire PID file: {}", paths.pid_file.display())
+    }
+}
+
+impl Drop for PidLock {
+    fn drop(&mut self) {
+        // Runs before the lock is released; leaves another daemon's files alone
+        if DaemonInfo::read(&self.paths.pid_file).is_some_and(|info| info.pid == std::process::id()) {
+            self.paths.cleanup();
+        }
+    }
+}
+
+/// Whether `path` still names the open `file`
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(named)) => open.dev() == named.dev() && open.ino() == named.ino(),
+        _ => false,
+    }
+}
+
+/// Whether `path` still names the open `file` (a file deleted while open
+/// can't be opened by name on Windows)
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+/// Append-only log file that rotates by size
+pub struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    /// Open (or create) the log at `path`
+    pub fn open(path: &Path, max_bytes: u64, keep: usize) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(self.rotated_path(self.keep));
+        for index in (1..self.keep).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A Studio place attached to a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachedPlace {
+    pub session_key: String,
+    pub place_id: u64,
+    pub place_name: String,
+}
+
+/// Everything the daemon knows about one project directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatus {
+    pub project_dir: String,
+    pub places: Vec<AttachedPlace>,
+    /// A VS Code workspace is registered for this directory
+    pub workspace: bool,
+    /// The live-sync file watcher is running for this directory
+    pub watching: bool,
+}
+
+/// Snapshot returned by `daemon status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub version: String,
+    pub uptime_secs: u64,
+    /// True when running with a PID file (`rbxsync daemon start`)
+    pub daemon: bool,
+    pub pid_file: Option<PathBuf>,
+    pub log_file: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+    pub state_file: Option<PathBuf>,
+    pub projects: Vec<ProjectStatus>,
+    /// Places registered without a project directory
+    pub unattached_places: Vec<AttachedPlace>,
+}
+
+/// Collect daemon status from the server state
+pub async fn status(state: &AppState) -> DaemonStatus {
+    let mut projects = BTreeMap::new();
+    let mut unattached_places = Vec::new();
+
+    for (key, place) in state.place_registry.read().await.iter() {
+        let attached = AttachedPlace {
+            session_key: key.clone(),
+            place_id: place.place_id,
+            place_name: place.place_name.clone(),
+        };
+        if place.project_dir.is_empty() {
+            unattached_places.push(attached);
+        } else {
+            project_entry(&mut projects, &place.project_dir).places.push(attached);
+        }
+    }
+    for dir in state.vscode_workspaces.read().await.keys() {
+        project_entry(&mut projects, dir).workspace = true;
+    }
+    for dir in state.file_watcher_state.read().await.watched_dirs.iter() {
+        project_entry(&mut projects, dir).watching = true;
+    }
+
+    let paths = state.daemon_paths.read().await.clone();
+    let state_file = state
+        .state_store
+        .read()
+        .await
+        .as_ref()
+        .map(|store| store.path().to_path_buf());
+
+    DaemonStatus {
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        daemon: paths.is_some(),
+        pid_file: paths.as_ref().map(|p| p.pid_file.clone()),
+        log_file: paths.as_ref().map(|p| p.log_file.clone()),
+        socket: paths.as_ref().map(|p| p.socket.clone()),
+        state_file,
+        projects: projects.into_values().collect(),
+        unattached_places,
+    }
+}
+
+fn project_entry<'a>(projects: &'a mut BTreeMap<String, ProjectStatus>, dir: &str) -> &'a mut ProjectStatus {
+    projects.entry(dir.to_string()).or_insert_with(|| ProjectStatus {
+        project_dir: dir.to_string(),
+        places: Vec::new(),
+        workspace: false,
+        watching: false,
+    })
+}
+
+/// Reload project configs by restarting the file watcher of every workspace
+///
+/// Returns the project directories whose watchers were restarted.
+pub async fn reload(state: &Arc<AppState>) -> Vec<String> {
+    let mut dirs: Vec<String> = state.vscode_workspaces.read().await.keys().cloned().collect();
+    for dir in state.file_watcher_state.read().await.watched_dirs.iter() {
+        if !dirs.contains(dir) {
+            dirs.push(dir.clone());
+        }
+    }
+    dirs.sort();
+
+    for dir in &dirs {
+        file_watcher::stop_file_watcher(dir, &state.file_watcher_state).await;
+        crate::spawn_workspace_watcher(state, dir.clone());
+    }
+
+    tracing::info!("Reloaded project configs for {} project(s)", dirs.len());
+    state.events.publish(crate::events::ServerEvent::ConfigReloaded {
+        project_dirs: dirs.clone(),
+    });
+    dirs
+}
+
+/// Control command sent over the daemon socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum ControlCommand {
+    Status,
+    Reload,
+    Stop,
+}
+
+/// Handle one control command
+pub async fn handle_control(state: &Arc<AppState>, command: ControlCommand) -> serde_json::Value {
+    match command {
+        ControlCommand::Status => serde_json::to_value(status(state).await)
+            .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() })),
+        ControlCommand::Reload => {
+            let reloaded = reload(state).await;
+            serde_json::json!({ "success": true, "reloaded": reloaded })
+        }
+        ControlCommand::Stop => {
+            tracing::info!("Stop requested via control socket");
+            state.shutdown.notify_one();
+            serde_json::json!({ "success": true, "status": "shutting_down" })
+        }
+    }
+}
+
+/// Serve the control socket until the server shuts down
+#[cfg(unix)]
+pub async fn serve_control_socket(path: PathBuf, state: Arc<AppState>) -> anyhow::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // A leftover socket from a crashed daemon would make bind fail
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    tracing::info!("Control socket listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let response = match serde_json::from_str::<ControlCommand>(&line) {
+                    Ok(command) => handle_control(&state, command).await,
+                    Err(e) => serde_json::json!({ "error": format!("Invalid command: {}", e) }),
+                };
+                let mut out = response.to_string();
+                out.push('\n');
+                if writer.write_all(out.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Send a command to a daemon's control socket and return its response
+#[cfg(unix)]
+pub async fn send_control_command(socket: &Path, command: ControlCommand) -> anyhow::Result<serde_json::Value> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let stream = tokio::net::UnixStream::connect(socket).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut request = serde_json::to_string(&command)?;
+    request.push('\n');
+    writer.write_all(request.as_bytes()).await?;
+
+    let mut lines = BufReader::new(reader).lines();
+    let line = lines
+        .next_line()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Control socket closed without a response"))?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // 0 and values that wrap to negative pids address process groups
+    if pid == 0 || pid > i32::MAX as u32 {
+        return false;
+    }
+    // Signal 0 only checks whether the process exists; EPERM means it
+    // exists but belongs to another user
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // Without a cheap liveness check, assume the PID file is stale; binding
+    // the port still fails if a daemon is really running.
+    false
+}
@@ -0,0 +1,129 @@
+//! Echo suppression for live sync
+//!
+//! Files the server writes itself (Studio→disk sync, terrain extraction) are
+//! picked up by the file watcher like any other change. Without a guard they
+//! would be pushed straight back to Studio as a `sync:batch`. Writers record
+//! the content they wrote here; watcher events whose current content still
+//! matches are dropped.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::file_watcher::{FileChange, FileChangeKind};
+
+/// How long a recorded write suppresses watcher events for its path
+///
+/// Covers the watcher debounce and slow filesystems (network drives, macOS
+/// FSEvents batching). Later edits with different content are never dropped.
+pub const ECHO_WINDOW: Duration = Duration::from_secs(10);
+
+/// What the server left at a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    /// A file with this content hash
+    File(u64),
+    /// A directory (created or renamed into place)
+    Directory,
+    /// Nothing (deleted or renamed away)
+    Removed,
+}
+
+/// Recent server-side writes, keyed by path
+#[derive(Debug, Default)]
+pub struct EchoFilter {
+    recent: Mutex<HashMap<PathBuf, (Expected, Instant)>>,
+}
+
+/// Hash file content for echo comparison
+pub fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl EchoFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, path: &Path, expected: Expected) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        recent.retain(|_, (_, at)| now.duration_since(*at) < ECHO_WINDOW);
+        recent.insert(path.to_path_buf(), (expected, now));
+    }
+
+    /// Record a file the server wrote
+    pub fn record_write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) {
+        self.record(path.as_ref(), Expected::File(content_hash(content.as_ref())));
+    }
+
+    /// Record a file or directory the server deleted or renamed away
+    pub fn record_delete(&self, path: impl AsRef<Path>) {
+        self.record(path.as_ref(), Expected::Removed);
+    }
+
+    /// Record a directory the server created or renamed into place, with
+    /// every file currently inside it
+    pub fn record_tree(&self, dir: impl AsRef<Path>) {
+        let mut stack = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            self.record(&dir, Expected::Directory);
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else if let Ok(content) = std::fs::read(&path) {
+                    self.record_write(&path, content);
+                }
+            }
+        }
+    }
+
+    /// Whether a path still looks exactly like the server left it
+    fn matches(&self, path: &Path) -> bool {
+        let recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        let Some((expected, at)) = recent.get(path) else {
+            return false;
+        };
+        if at.elapsed() >= ECHO_WINDOW {
+            return false;
+        }
+        match expected {
+            Expected::File(hash) => std::fs::read(path)
+                .map(|content| content_hash(&content) == *hash)
+                .unwrap_or(false),
+            Expected::Directory => path.is_dir(),
+            Expected::Removed => !path.exists(),
+        }
+    }
+
+    /// Whether a watcher event was caused by the server's own write
+    pub fn is_echo(&self, change: &FileChange) -> bool {
+        match &change.kind {
+            FileChangeKind::Move { from } => self.matches(from) && self.matches(&change.path),
+            _ => self.matches(&change.path),
+        }
+    }
+
+    /// Drop watcher events caused by the server's own writes
+    pub fn filter(&self, changes: Vec<FileChange>) -> Vec<FileChange> {
+        changes
+            .into_iter()
+            .filter(|change| {
+                let echo = self.is_echo(change);
+                if echo {
+                    tracing::debug!("Live sync: ignoring echo of server write {:?}", change.path);
+                }
+                !echo
+            })
+            .collect()
+    }
+}
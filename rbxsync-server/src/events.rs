@@ -0,0 +1,319 @@
+//! Server event stream
+//!
+//! Unified push channel for server state changes: operations, Studio and
+//! VS Code connections, live-sync batches, playtests, harness updates and
+//! console output. Every event carries a monotonically increasing sequence
+//! number so clients can reconnect and resume from the last event they saw.
+//! Sequence numbers start again when the server restarts, so events also
+//! carry the server run's epoch; a client resuming from another epoch is told
+//! to resync.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{ConsoleMessage, OperationType};
+
+/// Number of recent events kept for resume-from-sequence
+pub const EVENT_BUFFER_SIZE: usize = 2000;
+
+/// Typed server event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// A long-running operation (extract, sync, test) started
+    OperationStarted {
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+        operation: OperationType,
+        progress: Option<String>,
+    },
+    /// A long-running operation finished
+    OperationFinished {
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+        operation: OperationType,
+        success: bool,
+    },
+    /// A Studio place registered with the server
+    PlaceConnected {
+        #[serde(rename = "sessionKey")]
+        session_key: String,
+        #[serde(rename = "placeId")]
+        place_id: u64,
+        #[serde(rename = "placeName")]
+        place_name: String,
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+    },
+    /// A Studio place unregistered or timed out
+    PlaceDisconnected {
+        #[serde(rename = "sessionKey")]
+        session_key: String,
+        #[serde(rename = "placeId")]
+        place_id: u64,
+        #[serde(rename = "placeName")]
+        place_name: String,
+        /// "unregistered", "stale" or "replaced"
+        reason: String,
+    },
+    /// A VS Code workspace registered with the server
+    WorkspaceConnected {
+        #[serde(rename = "workspaceDir")]
+        workspace_dir: String,
+    },
+    /// A VS Code workspace stopped sending heartbeats
+    WorkspaceDisconnected {
+        #[serde(rename = "workspaceDir")]
+        workspace_dir: String,
+    },
+    /// The file watcher queued a live-sync batch for Studio
+    LiveSyncBatch {
+        #[serde(rename = "projectDir")]
+        project_dir: Option<String>,
+        #[serde(rename = "requestId")]
+        request_id: String,
+        operations: usize,
+        paths: Vec<String>,
+    },
+    /// The plugin finished (or gave up on) a live-sync batch
+    LiveSyncCompleted {
+        #[serde(rename = "projectDir")]
+        project_dir: Option<String>,
+        #[serde(rename = "requestId")]
+        request_id: String,
+        /// "applied", "partial", "failed", "rolled_back", "skipped" or "timed_out"
+        status: String,
+        applied: usize,
+        skipped: usize,
+        failed: usize,
+        /// Paths of the operations that failed
+        #[serde(rename = "failedPaths")]
+        failed_paths: Vec<String>,
+    },
+    /// Git moved HEAD (checkout, pull, reset) in a watched project
+    BranchSwitchDetected {
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+        /// Branch, or short hash when detached
+        from: String,
+        to: String,
+        added: usize,
+        removed: usize,
+        moved: usize,
+        modified: usize,
+    },
+    /// A pending branch switch was applied to Studio or discarded
+    BranchSwitchResolved {
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+        applied: bool,
+        /// Live-sync batch carrying the changes
+        #[serde(rename = "requestId")]
+        request_id: Option<String>,
+    },
+    /// A playtest started (bot connected)
+    PlaytestStarted,
+    /// A playtest stopped (bot disconnected or heartbeat timed out)
+    PlaytestStopped { reason: Option<String> },
+    /// Harness data changed for a project
+    HarnessUpdated {
+        #[serde(rename = "projectDir")]
+        project_dir: String,
+        /// "init", "session_start", "session_end" or "feature"
+        change: String,
+    },
+    /// Console output pushed by the plugin
+    Console { message: ConsoleMessage },
+    /// A plugin request was cancelled (timeout, client abort or explicit cancel)
+    RequestCancelled {
+        #[serde(rename = "requestId")]
+        request_id: String,
+        /// "timeout", "aborted" or "cancelled"
+        reason: String,
+    },
+    /// Project configs were reloaded and file watchers restarted
+    ConfigReloaded {
+        #[serde(rename = "projectDirs")]
+        project_dirs: Vec<String>,
+    },
+}
+
+impl ServerEvent {
+    /// Event name used for the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            ServerEvent::OperationStarted { .. } => "operation_started",
+            ServerEvent::OperationFinished { .. } => "operation_finished",
+            ServerEvent::PlaceConnected { .. } => "place_connected",
+            ServerEvent::PlaceDisconnected { .. } => "place_disconnected",
+            ServerEvent::WorkspaceConnected { .. } => "workspace_connected",
+            ServerEvent::WorkspaceDisconnected { .. } => "workspace_disconnected",
+            ServerEvent::LiveSyncBatch { .. } => "live_sync_batch",
+            ServerEvent::LiveSyncCompleted { .. } => "live_sync_completed",
+            ServerEvent::BranchSwitchDetected { .. } => "branch_switch_detected",
+            ServerEvent::BranchSwitchResolved { .. } => "branch_switch_resolved",
+            ServerEvent::PlaytestStarted => "playtest_started",
+            ServerEvent::PlaytestStopped { .. } => "playtest_stopped",
+            ServerEvent::HarnessUpdated { .. } => "harness_updated",
+            ServerEvent::Console { .. } => "console",
+            ServerEvent::RequestCancelled { .. } => "request_cancelled",
+            ServerEvent::ConfigReloaded { .. } => "config_reloaded",
+        }
+    }
+}
+
+/// Event with its sequence number and timestamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    /// The server run that published the event
+    pub epoch: String,
+    pub seq: u64,
+    /// Unix timestamp in millis
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+impl EventEnvelope {
+    /// `epoch:seq`, the SSE id a client resumes from
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.epoch, self.seq)
+    }
+}
+
+/// Where a client left off: an `epoch:seq` event id, or a bare sequence
+/// number from a client that doesn't track epochs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub epoch: Option<String>,
+    pub seq: u64,
+}
+
+impl Cursor {
+    pub fn parse(id: &str) -> Option<Self> {
+        match id.rsplit_once(':') {
+            Some((epoch, seq)) => Some(Self {
+                epoch: Some(epoch.to_string()),
+                seq: seq.parse().ok()?,
+            }),
+            None => Some(Self::from(id.parse::<u64>().ok()?)),
+        }
+    }
+}
+
+impl From<u64> for Cursor {
+    fn from(seq: u64) -> Self {
+        Self { epoch: None, seq }
+    }
+}
+
+/// Result of subscribing to the event bus
+pub struct EventSubscription {
+    /// Buffered events newer than the requested sequence
+    pub backlog: Vec<EventEnvelope>,
+    /// True if events between the requested sequence and the backlog were dropped
+    pub gap: bool,
+    /// Receiver for events published after the backlog
+    pub receiver: broadcast::Receiver<EventEnvelope>,
+}
+
+/// Broadcast bus with a replay buffer
+pub struct EventBus {
+    epoch: String,
+    seq: AtomicU64,
+    history: Mutex<VecDeque<EventEnvelope>>,
+    tx: broadcast::Sender<EventEnvelope>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self {
+            epoch: uuid::Uuid::new_v4().simple().to_string(),
+            seq: AtomicU64::new(0),
+            history: Mutex::new(VecDeque::with_capacity(EVENT_BUFFER_SIZE)),
+            tx,
+        }
+    }
+
+    /// Publish an event and return its sequence number
+    pub fn publish(&self, event: ServerEvent) -> u64 {
+        // Hold the history lock while sending so subscribers never see an
+        // event both in their backlog and on the live channel out of order
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        let envelope = EventEnvelope {
+            epoch: self.epoch.clone(),
+            seq,
+            timestamp: crate::now_millis(),
+            event,
+        };
+
+        if history.len() >= EVENT_BUFFER_SIZE {
+            history.pop_front();
+        }
+        history.push_back(envelope.clone());
+        let _ = self.tx.send(envelope);
+        seq
+    }
+
+    /// This server run's epoch
+    pub fn epoch(&self) -> &str {
+        &self.epoch
+    }
+
+    /// Sequence number of the most recent event (0 if none)
+    pub fn latest_seq(&self) -> u64 {
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    /// Buffered events after `since`
+    pub fn since(&self, since: impl Into<Cursor>) -> (Vec<EventEnvelope>, bool) {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        self.backlog(&history, &since.into())
+    }
+
+    /// Subscribe to live events, replaying buffered events after `since`
+    pub fn subscribe(&self, since: Option<Cursor>) -> EventSubscription {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.tx.subscribe();
+        let (backlog, gap) = match since {
+            Some(since) => self.backlog(&history, &since),
+            None => (Vec::new(), false),
+        };
+        EventSubscription {
+            backlog,
+            gap,
+            receiver,
+        }
+    }
+
+    fn backlog(&self, history: &VecDeque<EventEnvelope>, since: &Cursor) -> (Vec<EventEnvelope>, bool) {
+        // A client from an earlier run gets everything buffered. Without an
+        // epoch, a sequence ahead of the latest event is the only sign of one.
+        let latest = history.back().map_or(0, |newest| newest.seq);
+        let earlier_run = match &since.epoch {
+            Some(epoch) => *epoch != self.epoch,
+            None => since.seq > latest,
+        };
+        if earlier_run {
+            return (history.iter().cloned().collect(), true);
+        }
+        let since = since.seq;
+        let gap = history
+            .front()
+            .is_some_and(|oldest| oldest.seq > since.saturating_add(1));
+        let events = history.iter().filter(|e| e.seq > since).cloned().collect();
+        (events, gap)
+    }
+}
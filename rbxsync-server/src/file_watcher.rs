@@ -1,10 +1,16 @@
 //! File watcher module for live sync
 //!
 //! Watches project directories for file changes and pushes updates to Studio.
+//! A project can have several watch roots: `src`, `sync.watchPaths`,
+//! `treeMapping` targets and, when package sync is on, the Wally `Packages`
+//! folder. Roots are re-read when `rbxsync.json` changes.
 //! Supports Wally package exclusion to prevent package files from being synced.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -14,6 +20,8 @@ use tokio::sync::{mpsc, RwLock};
 
 use rbxsync_core::is_package_path;
 
+use crate::branch_switch::GitWatch;
+
 /// File change event
 #[derive(Debug, Clone)]
 pub struct FileChange {
@@ -28,6 +36,19 @@ pub enum FileChangeKind {
     Create,
     Modify,
     Delete,
+    /// A delete and create paired by `coalesce_moves`; `path` is the new location
+    Move { from: PathBuf },
+}
+
+/// Identity of a synced file, used to recognise a delete + create as a move
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Fingerprint {
+    /// `referenceId` of an `.rbxjson` instance
+    Reference(String),
+    /// Hash of script class and source (or `.rbxjson` content without a referenceId)
+    Content(u64),
+    /// Hash of the relative paths and fingerprints of every file in a folder
+    Folder(u64),
 }
 
 /// File watcher state
@@ -38,6 +59,32 @@ pub struct FileWatcherState {
     pub pending_changes: HashMap<PathBuf, (Instant, FileChangeKind)>,
     /// Channel to send file changes
     pub change_tx: mpsc::UnboundedSender<FileChange>,
+    /// Running flag per watched directory (cleared to stop the watcher)
+    pub running: HashMap<String, Arc<AtomicBool>>,
+    /// Last known fingerprint of every watched file, so deleted files can be
+    /// matched against newly created ones
+    pub fingerprints: HashMap<PathBuf, Fingerprint>,
+    /// Active watch roots per project directory
+    pub roots: HashMap<String, Vec<WatchRoot>>,
+    /// Repository of each watched project that is in one, for branch switches
+    pub git: HashMap<String, GitWatch>,
+}
+
+/// A directory watched recursively for one project
+#[derive(Debug, Clone)]
+pub struct WatchRoot {
+    pub path: PathBuf,
+    /// File changes forwarded from this root
+    pub events: Arc<AtomicU64>,
+}
+
+impl WatchRoot {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            events: Arc::new(AtomicU64::new(0)),
+        }
+    }
 }
 
 impl FileWatcherState {
@@ -46,10 +93,112 @@ impl FileWatcherState {
             watched_dirs: HashSet::new(),
             pending_changes: HashMap::new(),
             change_tx,
+            running: HashMap::new(),
+            fingerprints: HashMap::new(),
+            roots: HashMap::new(),
+            git: HashMap::new(),
         }
     }
 }
 
+/// Whether Wally package changes are synced (`packages.excludeFromWatch: false`)
+pub fn sync_packages_enabled(config: &Option<serde_json::Value>) -> bool {
+    config
+        .as_ref()
+        .and_then(|c| c.get("packages"))
+        .and_then(|p| p.get("excludeFromWatch"))
+        .and_then(|v| v.as_bool())
+        .map(|exclude| !exclude)  // Invert: excludeFromWatch=false means sync_packages=true
+        .unwrap_or(false)  // Default: don't sync packages (for backwards compatibility)
+}
+
+/// Directories to watch for a project
+///
+/// `src`, every `sync.watchPaths` entry, every `treeMapping` target (relative
+/// to the project, or to `src` as `resolve_instance_path` allows) and, if
+/// `sync_packages`, the Wally packages folders. Only existing directories are
+/// returned, and roots nested inside another root are dropped.
+pub fn watch_roots(
+    project_dir: &Path,
+    config: &Option<serde_json::Value>,
+    sync_packages: bool,
+) -> Vec<PathBuf> {
+    let mut candidates = vec![project_dir.join("src")];
+
+    let strings = |value: Option<&serde_json::Value>| -> Vec<String> {
+        match value {
+            Some(serde_json::Value::Array(items)) => {
+                items.iter().filter_map(|v| v.as_str().map(String::from)).collect()
+            }
+            Some(serde_json::Value::Object(map)) => {
+                map.values().filter_map(|v| v.as_str().map(String::from)).collect()
+            }
+            _ => Vec::new(),
+        }
+    };
+    let config_value = config.as_ref();
+    for path in strings(config_value.and_then(|c| c.get("sync")).and_then(|s| s.get("watchPaths"))) {
+        candidates.push(project_dir.join(path));
+    }
+    for target in strings(config_value.and_then(|c| c.get("treeMapping"))) {
+        let in_project = project_dir.join(&target);
+        candidates.push(if in_project.is_dir() {
+            in_project
+        } else {
+            project_dir.join("src").join(&target)
+        });
+    }
+    if sync_packages {
+        candidates.extend(package_folders(config).into_iter().map(|(folder, _)| project_dir.join(folder)));
+    }
+
+    // Normalize `./src` and `src/` so duplicates compare equal
+    let mut roots: Vec<PathBuf> = candidates
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .map(|path| path.components().collect())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    let nested: Vec<PathBuf> = roots
+        .iter()
+        .filter(|path| roots.iter().any(|other| other != *path && path.starts_with(other)))
+        .cloned()
+        .collect();
+    roots.retain(|path| !nested.contains(path));
+    roots
+}
+
+/// Wally packages folders and the instance paths they sync to
+///
+/// `packagesFolder` (default `Packages`) maps to `sharedPackagesPath` and
+/// `ServerPackages` to `serverPackagesPath`.
+fn package_folders(config: &Option<serde_json::Value>) -> Vec<(String, String)> {
+    let packages = config.as_ref().and_then(|c| c.get("packages"));
+    let setting = |key: &str, default: &str| {
+        packages
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or(default)
+            .to_string()
+    };
+    vec![
+        (setting("packagesFolder", "Packages"), setting("sharedPackagesPath", "ReplicatedStorage/Packages")),
+        ("ServerPackages".to_string(), setting("serverPackagesPath", "ServerScriptService/Packages")),
+    ]
+}
+
+/// `treeMapping` plus the packages folders, for resolving watched paths
+pub fn live_sync_tree_mapping(config: &Option<serde_json::Value>) -> HashMap<String, String> {
+    let mut mapping = crate::get_tree_mapping(config);
+    for (folder, instance_path) in package_folders(config) {
+        if !mapping.values().any(|target| target.trim_start_matches("./").trim_end_matches('/') == folder) {
+            mapping.entry(instance_path).or_insert(folder);
+        }
+    }
+    mapping
+}
+
 /// Start the file watcher for a project directory
 ///
 /// If `sync_packages` is true, Wally package changes will be included in file sync.
@@ -68,18 +217,41 @@ pub async fn start_file_watcher(
         }
     }
 
-    let src_dir = PathBuf::from(&project_dir).join("src");
-    if !src_dir.exists() {
-        tracing::warn!("Source directory does not exist: {:?}", src_dir);
+    let project_path = PathBuf::from(&project_dir);
+    if !project_path.is_dir() {
+        tracing::warn!("Project directory does not exist: {:?}", project_path);
         return Ok(());
     }
+    let config_path = project_path.join("rbxsync.json");
+    let root_paths = watch_roots(&project_path, &crate::load_project_config(&project_dir), sync_packages);
+    if root_paths.is_empty() {
+        tracing::warn!("No directories to watch in {:?} (waiting for rbxsync.json changes)", project_path);
+    }
 
-    tracing::info!("Starting file watcher for: {:?}", src_dir);
+    tracing::info!("Starting file watcher for {}: {:?}", project_dir, root_paths);
 
-    // Mark as watching
+    // Mark as watching and index existing files for move detection
+    let running = Arc::new(AtomicBool::new(true));
+    let (index, git) = {
+        let root_paths = root_paths.clone();
+        let project_path = project_path.clone();
+        tokio::task::spawn_blocking(move || {
+            let index = root_paths.iter().flat_map(|root| index_directory(root)).collect::<HashMap<_, _>>();
+            (index, GitWatch::open(&project_path))
+        })
+        .await
+        .unwrap_or_default()
+    };
+    let mut roots: Vec<WatchRoot> = root_paths.into_iter().map(WatchRoot::new).collect();
     {
         let mut state = state.write().await;
         state.watched_dirs.insert(project_dir.clone());
+        state.running.insert(project_dir.clone(), running.clone());
+        state.roots.insert(project_dir.clone(), roots.clone());
+        state.fingerprints.extend(index);
+        if let Some(git) = &git {
+            state.git.insert(project_dir.clone(), git.clone());
+        }
     }
 
     let project_dir_clone = project_dir.clone();
@@ -88,6 +260,7 @@ pub async fn start_file_watcher(
     // Start watcher in a separate task
     tokio::task::spawn_blocking(move || {
         let rt = tokio::runtime::Handle::current();
+        let mut sync_packages = sync_packages;
 
         let (tx, rx) = std::sync::mpsc::channel();
 
@@ -106,19 +279,83 @@ pub async fn start_file_watcher(
             }
         };
 
-        if let Err(e) = watcher.watch(&src_dir, RecursiveMode::Recursive) {
-            tracing::error!("Failed to watch directory: {}", e);
-            return;
+        // The project directory itself, for rbxsync.json changes
+        if let Err(e) = watcher.watch(&project_path, RecursiveMode::NonRecursive) {
+            tracing::warn!("Failed to watch {:?} for config changes: {}", project_path, e);
         }
+        // HEAD, the index lock and local branches, for branch switches
+        if let Some(git) = &git {
+            if let Err(e) = watcher.watch(&git.git_dir, RecursiveMode::NonRecursive) {
+                tracing::warn!("Failed to watch {:?} for branch switches: {}", git.git_dir, e);
+            }
+            let _ = watcher.watch(&git.git_dir.join("refs/heads"), RecursiveMode::Recursive);
+        }
+        roots.retain(|root| match watcher.watch(&root.path, RecursiveMode::Recursive) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Failed to watch directory {:?}: {}", root.path, e);
+                false
+            }
+        });
+        rt.block_on(async {
+            state_clone.write().await.roots.insert(project_dir_clone.clone(), roots.clone());
+        });
 
-        tracing::info!("File watcher active for: {:?}", src_dir);
+        tracing::info!("File watcher active for: {}", project_dir_clone);
 
         // Process events
         loop {
+            if !running.load(Ordering::SeqCst) {
+                tracing::info!("File watcher stopped for: {}", project_dir_clone);
+                break;
+            }
+
             match rx.recv_timeout(Duration::from_secs(1)) {
                 Ok(event) => {
+                    // Git is moving HEAD or writing files: hold live sync
+                    // until it settles (see branch_switch)
+                    if let Some(git) = &git {
+                        if event.paths.iter().any(|p| git.is_head_event(p)) {
+                            rt.block_on(async {
+                                if let Some(watch) = state_clone.write().await.git.get_mut(&project_dir_clone) {
+                                    watch.activity = Some(Instant::now());
+                                }
+                            });
+                            continue;
+                        }
+                    }
+
+                    // rbxsync.json changed: add and remove roots to match it
+                    if event.paths.iter().any(|p| p == &config_path) {
+                        if config_path.exists() && crate::load_project_config(&project_dir_clone).is_none() {
+                            tracing::warn!("Ignoring unreadable {:?}, keeping current watch roots", config_path);
+                            continue;
+                        }
+                        let config = crate::load_project_config(&project_dir_clone);
+                        sync_packages = sync_packages_enabled(&config);
+                        let wanted = watch_roots(&project_path, &config, sync_packages);
+                        update_roots(&mut watcher, &mut roots, wanted, &project_dir_clone, &state_clone, &rt);
+                        continue;
+                    }
+
                     // Process each path in the event with macOS-aware kind detection
                     for path in event.paths.iter() {
+                        // Events from the project directory watch outside any root
+                        let Some(root) = roots.iter().find(|root| path.starts_with(&root.path)) else {
+                            continue;
+                        };
+                        // The root itself was replaced (extraction swaps in a
+                        // staged src); the old watch followed the moved directory
+                        if path == &root.path {
+                            if path.is_dir() {
+                                rewatch_root(&mut watcher, path, &state_clone, &rt);
+                            }
+                            continue;
+                        }
+                        // Temp files of atomic writes; the rename is what counts
+                        if crate::atomic_fs::is_temp_file(path) {
+                            continue;
+                        }
                         // Determine the event kind using Argon's macOS approach:
                         // - Create: only if path exists
                         // - Modify(Name): check path existence (deletion on macOS comes as rename)
@@ -177,23 +414,35 @@ pub async fn start_file_watcher(
                             let path = path.clone();
                             // Check if it's a directory that was created (for undo operations)
                             if kind == FileChangeKind::Create && path.is_dir() {
+                                // Send the folder itself so a moved folder can be paired with its delete
+                                let change = FileChange {
+                                    path: path.clone(),
+                                    project_dir: project_dir_clone.clone(),
+                                    kind: FileChangeKind::Create,
+                                };
+                                root.events.fetch_add(1, Ordering::Relaxed);
+                                let state = state_clone.clone();
+                                rt.spawn(async move {
+                                    let state = state.read().await;
+                                    let _ = state.change_tx.send(change);
+                                });
+
                                 // Scan directory for script files and send Create events for each
                                 if let Ok(entries) = std::fs::read_dir(&path) {
                                     for entry in entries.flatten() {
                                         let entry_path = entry.path();
-                                        if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                                            if ext == "luau" || ext == "rbxjson" {
-                                                let change = FileChange {
-                                                    path: entry_path,
-                                                    project_dir: project_dir_clone.clone(),
-                                                    kind: FileChangeKind::Create,
-                                                };
-                                                let state = state_clone.clone();
-                                                rt.spawn(async move {
-                                                    let state = state.read().await;
-                                                    let _ = state.change_tx.send(change);
-                                                });
-                                            }
+                                        if is_synced_file(&entry_path) {
+                                            let change = FileChange {
+                                                path: entry_path,
+                                                project_dir: project_dir_clone.clone(),
+                                                kind: FileChangeKind::Create,
+                                            };
+                                            root.events.fetch_add(1, Ordering::Relaxed);
+                                            let state = state_clone.clone();
+                                            rt.spawn(async move {
+                                                let state = state.read().await;
+                                                let _ = state.change_tx.send(change);
+                                            });
                                         }
                                     }
                                 }
@@ -207,18 +456,18 @@ pub async fn start_file_watcher(
                             }
 
                             // Check if it's a file we care about
-                            let should_process = if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                                ext == "luau" || ext == "rbxjson"
+                            let should_process = if path.extension().is_some() {
+                                is_synced_file(&path)
                             } else {
                                 // For deletions, also handle directories (no extension)
-                                // Check that path is inside src (has at least one segment after src)
+                                // Check that path is inside the root (has at least one segment after it)
                                 // and doesn't have a dot in the filename (not a file without extension)
                                 if kind == FileChangeKind::Delete {
-                                    // Make sure we're deleting something INSIDE src, not src itself
-                                    let is_inside_src = path.strip_prefix(&src_dir)
+                                    // Make sure we're deleting something INSIDE the root, not the root itself
+                                    let is_inside_root = path.strip_prefix(&root.path)
                                         .map(|rel| !rel.as_os_str().is_empty())
                                         .unwrap_or(false);
-                                    is_inside_src && path.file_name()
+                                    is_inside_root && path.file_name()
                                         .and_then(|n| n.to_str())
                                         .map(|n| !n.contains('.'))
                                         .unwrap_or(false)
@@ -233,6 +482,7 @@ pub async fn start_file_watcher(
                                     project_dir: project_dir_clone.clone(),
                                     kind: kind.clone(),
                                 };
+                                root.events.fetch_add(1, Ordering::Relaxed);
 
                                 // Send to async handler
                                 let state = state_clone.clone();
@@ -258,40 +508,153 @@ pub async fn start_file_watcher(
     Ok(())
 }
 
+/// Add and remove watch roots to match `wanted`
+fn update_roots(
+    watcher: &mut RecommendedWatcher,
+    roots: &mut Vec<WatchRoot>,
+    wanted: Vec<PathBuf>,
+    project_dir: &str,
+    state: &Arc<RwLock<FileWatcherState>>,
+    rt: &tokio::runtime::Handle,
+) {
+    let removed: Vec<PathBuf> = roots
+        .iter()
+        .filter(|root| !wanted.contains(&root.path))
+        .map(|root| root.path.clone())
+        .collect();
+    for path in &removed {
+        let _ = watcher.unwatch(path);
+        if path.as_path() == Path::new(project_dir) {
+            // Still needed for rbxsync.json changes
+            let _ = watcher.watch(path, RecursiveMode::NonRecursive);
+        }
+    }
+    roots.retain(|root| !removed.contains(&root.path));
+
+    let mut added = Vec::new();
+    for path in wanted {
+        if roots.iter().any(|root| root.path == path) {
+            continue;
+        }
+        match watcher.watch(&path, RecursiveMode::Recursive) {
+            Ok(()) => {
+                roots.push(WatchRoot::new(path.clone()));
+                added.push(path);
+            }
+            Err(e) => tracing::error!("Failed to watch directory {:?}: {}", path, e),
+        }
+    }
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    tracing::info!("Watch roots for {} changed: added {:?}, removed {:?}", project_dir, added, removed);
+    let index: HashMap<PathBuf, Fingerprint> = added.iter().flat_map(|root| index_directory(root)).collect();
+    rt.block_on(async {
+        let mut state = state.write().await;
+        state
+            .fingerprints
+            .retain(|path, _| !removed.iter().any(|root| path.starts_with(root)));
+        state.fingerprints.extend(index);
+        state.roots.insert(project_dir.to_string(), roots.clone());
+    });
+}
+
+/// Watch a root directory again after it was replaced, and re-index it
+fn rewatch_root(
+    watcher: &mut RecommendedWatcher,
+    path: &Path,
+    state: &Arc<RwLock<FileWatcherState>>,
+    rt: &tokio::runtime::Handle,
+) {
+    let _ = watcher.unwatch(path);
+    if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+        tracing::error!("Failed to watch replaced directory {:?}: {}", path, e);
+        return;
+    }
+    tracing::info!("Watching replaced directory {:?}", path);
+    let index = index_directory(path);
+    rt.block_on(async {
+        let mut state = state.write().await;
+        state.fingerprints.retain(|file, _| !file.starts_with(path));
+        state.fingerprints.extend(index);
+    });
+}
+
+/// Stop the file watcher for a project directory
+///
+/// Returns false if the directory was not being watched. The watcher thread
+/// exits within its one-second poll interval.
+pub async fn stop_file_watcher(project_dir: &str, state: &Arc<RwLock<FileWatcherState>>) -> bool {
+    let mut state = state.write().await;
+    state.watched_dirs.remove(project_dir);
+    let roots = state.roots.remove(project_dir).unwrap_or_default();
+    state.git.remove(project_dir);
+    state
+        .fingerprints
+        .retain(|path, _| !roots.iter().any(|root| path.starts_with(&root.path)));
+    match state.running.remove(project_dir) {
+        Some(running) => {
+            running.store(false, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
 /// Process a file change and prepare sync operation
+///
+/// `tree_mapping` is the project's [`live_sync_tree_mapping`], loaded once
+/// per batch of changes.
 pub fn process_file_change(
     change: &FileChange,
+    tree_mapping: &HashMap<String, String>,
 ) -> Option<serde_json::Value> {
     let path = &change.path;
     let project_dir = PathBuf::from(&change.project_dir);
-    let src_dir = project_dir.join("src");
-
-    // Get relative path from src directory
-    let rel_path = match path.strip_prefix(&src_dir) {
-        Ok(p) => p,
-        Err(_) => return None,
-    };
 
-    // Convert to instance path (e.g., "ServerScriptService/MyScript")
-    // Handle _meta.rbxjson specially - it represents the parent folder
+    let inst_path = resolve_instance_path(&project_dir, path, tree_mapping)?;
     let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-    let inst_path = if filename == "_meta.rbxjson" {
-        // _meta.rbxjson represents the parent folder
-        rel_path
-            .parent()
-            .map(rbxsync_core::path_to_string)
-            .unwrap_or_default()
-    } else {
-        rbxsync_core::path_to_string(rel_path)
-            .trim_end_matches(".server.luau")
-            .trim_end_matches(".client.luau")
-            .trim_end_matches(".luau")
-            .trim_end_matches(".rbxjson")
-            .to_string()
-    };
 
-    match change.kind {
+    match &change.kind {
+        FileChangeKind::Move { from } => {
+            let from_path = resolve_instance_path(&project_dir, from, tree_mapping)?;
+            let name = inst_path.rsplit('/').next().unwrap_or(&inst_path).to_string();
+
+            // Carry the new file's data so the plugin can apply edits made
+            // alongside the move, or create the instance if it isn't in Studio
+            let data = if path.is_dir() {
+                None
+            } else {
+                let create = FileChange {
+                    path: path.clone(),
+                    project_dir: change.project_dir.clone(),
+                    kind: FileChangeKind::Create,
+                };
+                process_file_change(&create, tree_mapping).and_then(|op| op.get("data").cloned())
+            };
+
+            let mut op = serde_json::json!({
+                "type": "move",
+                "from": from_path,
+                "path": inst_path,
+                "name": name,
+                "isFolder": path.is_dir(),
+            });
+            if let Some(data) = data {
+                op["data"] = data;
+            }
+            Some(op)
+        }
         FileChangeKind::Delete => {
+            // An init script or _meta.rbxjson describes its folder; removing it
+            // while the folder stays must not delete the folder's instance
+            let describes_folder = filename == "_meta.rbxjson" || rbxsync_core::is_init_script(filename);
+            if describes_folder && path.parent().is_some_and(|dir| dir.is_dir()) {
+                tracing::debug!("Live sync: {:?} removed, keeping folder {}", path, inst_path);
+                return None;
+            }
+
             // For folder deletions, the path won't have an extension
             // The inst_path will be the folder path in the instance tree
             Some(serde_json::json!({
@@ -313,26 +676,17 @@ pub fn process_file_change(
             // Read the file content
             let file_ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
 
-            if file_ext == "luau" {
-                // Script file
+            if let Some((_, class_name)) = rbxsync_core::script_file_info(filename) {
+                // Script file (init scripts carry their folder's path)
+                // Studio gets LF line endings and no BOM whatever the checkout has
                 let source = match std::fs::read_to_string(path) {
-                    Ok(s) => s,
+                    Ok(s) => rbxsync_core::SourceFormat::to_studio(&s),
                     Err(e) => {
                         tracing::warn!("Failed to read file {:?}: {}", path, e);
                         return None;
                     }
                 };
 
-                // Determine script type from filename
-                let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                let class_name = if filename.ends_with(".server.luau") {
-                    "Script"
-                } else if filename.ends_with(".client.luau") {
-                    "LocalScript"
-                } else {
-                    "ModuleScript"
-                };
-
                 // Extract instance name from path (last segment)
                 let instance_name = inst_path.rsplit('/').next().unwrap_or(&inst_path);
 
@@ -381,6 +735,30 @@ pub fn process_file_change(
                     }
                 }
 
+                Some(serde_json::json!({
+                    "type": if change.kind == FileChangeKind::Create { "create" } else { "update" },
+                    "path": inst_path,
+                    "data": data
+                }))
+            } else if rbxsync_core::data_file_info(filename).is_some() {
+                // Data file (.json, .toml, .yaml, .txt, .csv, .model.json)
+                let content = match std::fs::read_to_string(path) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::warn!("Failed to read file {:?}: {}", path, e);
+                        return None;
+                    }
+                };
+
+                let mut data = match rbxsync_core::parse_data_file(filename, &content) {
+                    Ok(d) => d,
+                    Err(e) => {
+                        tracing::warn!("Failed to parse data file {:?}: {}", path, e);
+                        return None;
+                    }
+                };
+                data["path"] = serde_json::Value::String(inst_path.clone());
+
                 Some(serde_json::json!({
                     "type": if change.kind == FileChangeKind::Create { "create" } else { "update" },
                     "path": inst_path,
@@ -392,3 +770,271 @@ pub fn process_file_change(
         }
     }
 }
+
+/// Resolve a watched path to its instance path, applying `treeMapping`
+///
+/// Mapping targets may be written relative to the project (`src/server`, as
+/// in Rojo projects) or to `src` (as extraction writes them); both are tried.
+/// Files follow the same naming rules as `rbxsync build`.
+pub fn resolve_instance_path(
+    project_dir: &Path,
+    path: &Path,
+    tree_mapping: &HashMap<String, String>,
+) -> Option<String> {
+    let fs_instance_path = |rel: &Path| {
+        let rel = rbxsync_core::path_to_string(rel);
+        if path.extension().is_none() {
+            // Folder
+            (!rel.is_empty()).then_some(rel)
+        } else {
+            rbxsync_core::file_instance_path(&rel)
+        }
+    };
+
+    if let Some(rel) = path.strip_prefix(project_dir).ok().and_then(fs_instance_path) {
+        if let Some(mapped) = crate::reverse_tree_mapping_match(&rel, tree_mapping) {
+            return Some(mapped);
+        }
+    }
+    let rel = fs_instance_path(path.strip_prefix(project_dir.join("src")).ok()?)?;
+    Some(crate::apply_reverse_tree_mapping(&rel, tree_mapping))
+}
+
+/// Whether a file is synced by the watcher (scripts, `.rbxjson` and data files)
+fn is_synced_file(path: &Path) -> bool {
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("luau") | Some("lua") | Some("rbxjson")
+    ) || rbxsync_core::data_file_info(filename).is_some()
+}
+
+/// Fingerprint a synced file on disk
+pub fn fingerprint_file(path: &Path) -> Option<Fingerprint> {
+    if !is_synced_file(path) {
+        return None;
+    }
+    let content = std::fs::read_to_string(path).ok()?;
+
+    if path.extension().and_then(|e| e.to_str()) == Some("rbxjson") {
+        let reference = serde_json::from_str::<serde_json::Value>(&content)
+            .ok()
+            .and_then(|v| v.get("referenceId").and_then(|r| r.as_str()).map(String::from));
+        if let Some(reference) = reference {
+            return Some(Fingerprint::Reference(reference));
+        }
+    }
+
+    // Renaming foo.server.luau to foo.client.luau changes the class, which is not a move
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let class = rbxsync_core::script_file_info(filename)
+        .map(|(_, class)| class)
+        .or_else(|| {
+            rbxsync_core::data_file_info(filename).map(|(_, kind)| kind.class_name().unwrap_or("model"))
+        })
+        .unwrap_or("rbxjson");
+
+    let mut hasher = DefaultHasher::new();
+    class.hash(&mut hasher);
+    content.hash(&mut hasher);
+    Some(Fingerprint::Content(hasher.finish()))
+}
+
+/// Fingerprint every synced file under a directory
+pub fn index_directory(dir: &Path) -> HashMap<PathBuf, Fingerprint> {
+    let mut index = HashMap::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Some(fingerprint) = fingerprint_file(&path) {
+                index.insert(path, fingerprint);
+            }
+        }
+    }
+    index
+}
+
+/// Fingerprint a folder from the fingerprints of the files inside it
+///
+/// Returns `None` for empty folders, which can't be told apart.
+fn folder_fingerprint<'a>(
+    dir: &Path,
+    files: impl Iterator<Item = (&'a PathBuf, &'a Fingerprint)>,
+) -> Option<Fingerprint> {
+    let mut entries: Vec<(PathBuf, &Fingerprint)> = files
+        .filter_map(|(path, fp)| path.strip_prefix(dir).ok().map(|rel| (rel.to_path_buf(), fp)))
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    Some(Fingerprint::Folder(hasher.finish()))
+}
+
+/// Fingerprints of a batch's created and modified paths, read from disk
+/// before [`coalesce_moves`] takes the watcher lock
+#[derive(Debug, Default)]
+pub struct DiskFingerprints {
+    files: HashMap<PathBuf, Fingerprint>,
+    /// Every file under each created folder
+    folders: HashMap<PathBuf, HashMap<PathBuf, Fingerprint>>,
+}
+
+impl DiskFingerprints {
+    /// Read the batch's paths from disk (blocking)
+    pub fn read(changes: &[FileChange]) -> Self {
+        let mut disk = Self::default();
+        for change in changes {
+            if change.kind == FileChangeKind::Delete {
+                continue;
+            }
+            if change.path.is_dir() {
+                disk.folders.insert(change.path.clone(), index_directory(&change.path));
+            } else if let Some(fingerprint) = fingerprint_file(&change.path) {
+                disk.files.insert(change.path.clone(), fingerprint);
+            }
+        }
+        disk
+    }
+}
+
+/// Pair deletes with creates of the same file or folder into moves, and keep
+/// the fingerprint index up to date
+///
+/// A delete is paired only when exactly one delete and one create in the batch
+/// share a fingerprint; ambiguous matches (e.g. two identical empty modules)
+/// stay a delete plus a create. Changes inside a moved folder are dropped since
+/// the folder move carries its children along, except edits that may have
+/// changed a file, which follow the move.
+pub fn coalesce_moves(
+    changes: Vec<FileChange>,
+    index: &mut HashMap<PathBuf, Fingerprint>,
+    mut disk: DiskFingerprints,
+) -> Vec<FileChange> {
+    // _meta.rbxjson and init scripts describe their folder and are never moved on their own
+    let is_meta = |path: &Path| {
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        filename == "_meta.rbxjson" || rbxsync_core::is_init_script(filename)
+    };
+
+    // Fingerprints of deleted paths come from the index, created ones from disk
+    let mut deleted: HashMap<Fingerprint, Vec<usize>> = HashMap::new();
+    let mut created: HashMap<Fingerprint, Vec<usize>> = HashMap::new();
+    for (i, change) in changes.iter().enumerate() {
+        if is_meta(&change.path) {
+            continue;
+        }
+        let fingerprint = match change.kind {
+            FileChangeKind::Delete if is_synced_file(&change.path) => index.get(&change.path).cloned(),
+            FileChangeKind::Delete => folder_fingerprint(&change.path, index.iter()),
+            FileChangeKind::Create => match disk.folders.get(&change.path) {
+                Some(files) => folder_fingerprint(&change.path, files.iter()),
+                None => disk.files.get(&change.path).cloned(),
+            },
+            _ => None,
+        };
+        if let Some(fingerprint) = fingerprint {
+            let side = if change.kind == FileChangeKind::Delete { &mut deleted } else { &mut created };
+            side.entry(fingerprint).or_default().push(i);
+        }
+    }
+
+    // (delete index, create index) of each unambiguous pair
+    let mut moves: Vec<(usize, usize)> = deleted
+        .iter()
+        .filter_map(|(fingerprint, deletes)| {
+            let creates = created.get(fingerprint)?;
+            match (deletes.as_slice(), creates.as_slice()) {
+                ([d], [c]) if changes[*d].project_dir == changes[*c].project_dir => Some((*d, *c)),
+                _ => None,
+            }
+        })
+        .collect();
+    moves.sort();
+
+    let mut consumed: HashSet<usize> = HashSet::new();
+    let mut moved_dirs: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (d, c) in &moves {
+        consumed.insert(*d);
+        consumed.insert(*c);
+        if disk.folders.contains_key(&changes[*c].path) {
+            moved_dirs.push((changes[*d].path.clone(), changes[*c].path.clone()));
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut after_moves = Vec::new();
+    for (i, change) in changes.iter().enumerate() {
+        if consumed.contains(&i) {
+            continue;
+        }
+        let moved = moved_dirs.iter().find(|(from, to)| {
+            change.path.starts_with(to) || (change.kind == FileChangeKind::Delete && change.path.starts_with(from))
+        });
+        let Some((from, to)) = moved else {
+            result.push(change.clone());
+            continue;
+        };
+        // An edit in the same window still has to reach Studio. A file is
+        // known unchanged only when its content hash matches its old one; a
+        // referenceId says nothing about the properties.
+        if change.kind == FileChangeKind::Modify {
+            let old = change.path.strip_prefix(to).ok().and_then(|rel| index.get(&from.join(rel)));
+            let new = disk.folders.get(to).and_then(|files| files.get(&change.path));
+            let unchanged = matches!(old, Some(Fingerprint::Content(_))) && old == new;
+            if !unchanged {
+                after_moves.push(change.clone());
+            }
+        }
+    }
+    for (d, c) in moves {
+        tracing::info!("Live sync: detected move {:?} -> {:?}", changes[d].path, changes[c].path);
+        result.push(FileChange {
+            path: changes[c].path.clone(),
+            project_dir: changes[c].project_dir.clone(),
+            kind: FileChangeKind::Move { from: changes[d].path.clone() },
+        });
+    }
+    result.extend(after_moves);
+
+    // Update the index
+    for change in &result {
+        match &change.kind {
+            FileChangeKind::Delete => {
+                index.retain(|path, _| !path.starts_with(&change.path));
+            }
+            FileChangeKind::Move { from } => {
+                index.retain(|path, _| !path.starts_with(from));
+                match disk.folders.remove(&change.path) {
+                    Some(files) => index.extend(files),
+                    None => {
+                        if let Some(fingerprint) = disk.files.remove(&change.path) {
+                            index.insert(change.path.clone(), fingerprint);
+                        }
+                    }
+                }
+            }
+            FileChangeKind::Create if disk.folders.contains_key(&change.path) => {
+                if let Some(files) = disk.folders.remove(&change.path) {
+                    index.extend(files);
+                }
+            }
+            FileChangeKind::Create | FileChangeKind::Modify => {
+                if let Some(fingerprint) = disk.files.get(&change.path) {
+                    index.insert(change.path.clone(), fingerprint.clone());
+                }
+            }
+        }
+    }
+
+    result
+}
@@ -1,12 +1,63 @@
 //! Git integration module
 //!
-//! Provides git commands for the RbxSync plugin to display status,
-//! commit changes, and view history.
+//! Provides git commands for the RbxSync plugin, VS Code and MCP agents:
+//! status, history, commits, branches, staging, diffs, stashes, remotes
+//! and blame, plus the merge driver and pre-commit hook. Instances can be
+//! named by their DataModel path (e.g. `Workspace/Map/Spawn`) and are
+//! translated to the files that hold them.
 
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Errors from git operations
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("Not a git repository")]
+    NotARepository,
+
+    #[error("Failed to run git: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("git {command} failed: {message}")]
+    Failed { command: String, message: String },
+
+    #[error("Unknown revision '{0}'")]
+    UnknownRevision(String),
+
+    #[error("No files found for '{0}'")]
+    PathNotFound(String),
+
+    #[error("'{0}' is not a script")]
+    NotAScript(String),
+
+    #[error("{0} already exists and wasn't installed by rbxsync")]
+    HookExists(PathBuf),
+
+    #[error("No Studio changes to commit")]
+    NothingToCommit,
+
+    #[error("Invalid {what} '{value}'")]
+    InvalidArgument { what: &'static str, value: String },
+}
+
+impl GitError {
+    /// Short machine-readable name of the error, for API responses
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GitError::NotARepository => "not_a_repository",
+            GitError::Io(_) => "io",
+            GitError::Failed { .. } => "failed",
+            GitError::UnknownRevision(_) => "unknown_revision",
+            GitError::PathNotFound(_) => "path_not_found",
+            GitError::NotAScript(_) => "not_a_script",
+            GitError::HookExists(_) => "hook_exists",
+            GitError::NothingToCommit => "nothing_to_commit",
+            GitError::InvalidArgument { .. } => "invalid_argument",
+        }
+    }
+}
+
 /// Git repository status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitStatus {
@@ -37,32 +88,77 @@ pub struct GitCommit {
     pub date: String,
 }
 
-/// Get git status for a project directory
-pub fn get_status(project_dir: &Path) -> Result<GitStatus, String> {
-    // Check if directory is a git repo
-    if !project_dir.join(".git").exists() {
-        return Err("Not a git repository".to_string());
+/// A local branch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitBranch {
+    pub name: String,
+    /// Whether this is the checked-out branch
+    pub current: bool,
+    /// Upstream branch, e.g. `origin/main`
+    pub upstream: Option<String>,
+    /// Short hash of the branch's commit
+    pub commit: String,
+}
+
+/// A stash entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStash {
+    /// Stash reference, e.g. `stash@{0}`
+    pub name: String,
+    pub message: String,
+}
+
+/// The commit that last changed a line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// File the line is in, relative to the project
+    pub file: String,
+    pub line: usize,
+    pub hash: String,
+    pub author: String,
+    /// Author time as a Unix timestamp
+    pub timestamp: i64,
+    pub summary: String,
+    pub content: String,
+}
+
+/// Run git in the project directory, returning stdout
+fn run(project_dir: &Path, args: &[&str]) -> Result<String, GitError> {
+    let output = Command::new("git").args(args).current_dir(project_dir).output()?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
     }
+    // Some failures (e.g. "nothing to commit") are only reported on stdout
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let message = if stderr.is_empty() {
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    } else {
+        stderr
+    };
+    Err(GitError::Failed {
+        command: args.first().unwrap_or(&"").to_string(),
+        message,
+    })
+}
+
+/// Fail unless the project is inside a git work tree
+fn ensure_repo(project_dir: &Path) -> Result<(), GitError> {
+    match run(project_dir, &["rev-parse", "--is-inside-work-tree"]) {
+        Ok(out) if out.trim() == "true" => Ok(()),
+        Ok(_) | Err(GitError::Failed { .. }) => Err(GitError::NotARepository),
+        Err(e) => Err(e),
+    }
+}
+
+/// Get git status for a project directory
+pub fn get_status(project_dir: &Path) -> Result<GitStatus, GitError> {
+    ensure_repo(project_dir)?;
 
     // Get current branch
-    let branch_output = Command::new("git")
-        .args(["branch", "--show-current"])
-        .current_dir(project_dir)
-        .output()
-        .map_err(|e| format!("Failed to get branch: {}", e))?;
-
-    let branch = String::from_utf8_lossy(&branch_output.stdout)
-        .trim()
-        .to_string();
+    let branch = run(project_dir, &["branch", "--show-current"])?.trim().to_string();
 
     // Get status with -uall to show all untracked files (not just directories)
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain", "-uall"])
-        .current_dir(project_dir)
-        .output()
-        .map_err(|e| format!("Failed to get status: {}", e))?;
-
-    let status_str = String::from_utf8_lossy(&status_output.stdout);
+    let status_str = run(project_dir, &["status", "--porcelain", "-uall"])?;
 
     let mut staged_count = 0;
     let mut unstaged_count = 0;
@@ -130,19 +226,12 @@ pub fn get_status(project_dir: &Path) -> Result<GitStatus, String> {
     let mut ahead = 0;
     let mut behind = 0;
 
-    let rev_output = Command::new("git")
-        .args(["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])
-        .current_dir(project_dir)
-        .output();
-
-    if let Ok(output) = rev_output {
-        if output.status.success() {
-            let counts = String::from_utf8_lossy(&output.stdout);
-            let parts: Vec<&str> = counts.trim().split('\t').collect();
-            if parts.len() == 2 {
-                ahead = parts[0].parse().unwrap_or(0);
-                behind = parts[1].parse().unwrap_or(0);
-            }
+    // Fails without an upstream, leaving both at 0
+    if let Ok(counts) = run(project_dir, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"]) {
+        let parts: Vec<&str> = counts.trim().split('\t').collect();
+        if parts.len() == 2 {
+            ahead = parts[0].parse().unwrap_or(0);
+            behind = parts[1].parse().unwrap_or(0);
         }
     }
 
@@ -161,27 +250,13 @@ pub fn get_status(project_dir: &Path) -> Result<GitStatus, String> {
 }
 
 /// Get recent commit history
-pub fn get_log(project_dir: &Path, limit: usize) -> Result<Vec<GitCommit>, String> {
-    // Check if directory is a git repo
-    if !project_dir.join(".git").exists() {
-        return Err("Not a git repository".to_string());
-    }
-
-    let output = Command::new("git")
-        .args([
-            "log",
-            &format!("-{}", limit),
-            "--pretty=format:%h|%s|%an|%ar",
-        ])
-        .current_dir(project_dir)
-        .output()
-        .map_err(|e| format!("Failed to get log: {}", e))?;
+pub fn get_log(project_dir: &Path, limit: usize) -> Result<Vec<GitCommit>, GitError> {
+    ensure_repo(project_dir)?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
-    }
-
-    let log_str = String::from_utf8_lossy(&output.stdout);
+    let log_str = run(
+        project_dir,
+        &["log", &format!("-{}", limit), "--pretty=format:%h|%s|%an|%ar"],
+    )?;
     let commits: Vec<GitCommit> = log_str
         .lines()
         .filter_map(|line| {
@@ -203,50 +278,520 @@ pub fn get_log(project_dir: &Path, limit: usize) -> Result<Vec<GitCommit>, Strin
 }
 
 /// Commit all changes with a message
-pub fn commit(project_dir: &Path, message: &str, add_all: bool) -> Result<String, String> {
-    // Check if directory is a git repo
-    if !project_dir.join(".git").exists() {
-        return Err("Not a git repository".to_string());
-    }
+pub fn commit(project_dir: &Path, message: &str, add_all: bool) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
 
     // Add all changes if requested
     if add_all {
-        let add_output = Command::new("git")
-            .args(["add", "-A"])
-            .current_dir(project_dir)
-            .output()
-            .map_err(|e| format!("Failed to stage changes: {}", e))?;
-
-        if !add_output.status.success() {
-            return Err(String::from_utf8_lossy(&add_output.stderr).to_string());
-        }
+        run(project_dir, &["add", "-A"])?;
+    }
+
+    run(project_dir, &["commit", "-m", message])
+}
+
+/// Initialize a new git repository
+pub fn init(project_dir: &Path) -> Result<String, GitError> {
+    run(project_dir, &["init"])
+}
+
+/// List local branches
+pub fn list_branches(project_dir: &Path) -> Result<Vec<GitBranch>, GitError> {
+    ensure_repo(project_dir)?;
+
+    let output = run(
+        project_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)|%(HEAD)|%(upstream:short)|%(objectname:short)",
+            "refs/heads",
+        ],
+    )?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('|').collect();
+            (parts.len() == 4).then(|| GitBranch {
+                name: parts[0].to_string(),
+                current: parts[1] == "*",
+                upstream: (!parts[2].is_empty()).then(|| parts[2].to_string()),
+                commit: parts[3].to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Refuse a branch, remote or revision that git would read as an option
+/// (a remote of `--upload-pack=...` runs a command)
+fn check_arg(what: &'static str, value: &str) -> Result<(), GitError> {
+    if value.starts_with('-') {
+        return Err(GitError::InvalidArgument {
+            what,
+            value: value.to_string(),
+        });
     }
+    Ok(())
+}
 
-    // Commit
-    let commit_output = Command::new("git")
-        .args(["commit", "-m", message])
-        .current_dir(project_dir)
-        .output()
-        .map_err(|e| format!("Failed to commit: {}", e))?;
+/// Check out a branch, creating it from HEAD if `create` is set
+pub fn switch_branch(project_dir: &Path, branch: &str, create: bool) -> Result<(), GitError> {
+    ensure_repo(project_dir)?;
+    check_arg("branch", branch)?;
 
-    if commit_output.status.success() {
-        Ok(String::from_utf8_lossy(&commit_output.stdout).to_string())
+    if create {
+        run(project_dir, &["switch", "-c", branch])?;
     } else {
-        Err(String::from_utf8_lossy(&commit_output.stderr).to_string())
+        run(project_dir, &["switch", branch])?;
     }
+    Ok(())
 }
 
-/// Initialize a new git repository
-pub fn init(project_dir: &Path) -> Result<String, String> {
-    let output = Command::new("git")
-        .args(["init"])
-        .current_dir(project_dir)
-        .output()
-        .map_err(|e| format!("Failed to init: {}", e))?;
+/// Files that hold an instance and its descendants, relative to the project
+///
+/// Looks at both the working tree and the index, so instances whose files
+/// were deleted are still found.
+pub fn instance_files(project_dir: &Path, instance_path: &str) -> Result<Vec<String>, GitError> {
+    ensure_repo(project_dir)?;
+
+    let config = crate::load_project_config(&project_dir.to_string_lossy());
+    let tree_mapping = crate::get_tree_mapping(&config);
+    let target = crate::normalize_path_for_comparison(&crate::apply_tree_mapping(
+        instance_path.trim_matches('/'),
+        &tree_mapping,
+    ));
+
+    // Only the directory the instance's files can be in
+    let parent = target.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("");
+    let listed_dir = if parent.is_empty() { "src".to_string() } else { format!("src/{}", parent) };
+    let listing = run(
+        project_dir,
+        &["ls-files", "--cached", "--others", "--exclude-standard", "--full-name", "--", &listed_dir],
+    )?;
+
+    // ls-files names paths from the repo root; strip the project's prefix
+    let prefix = run(project_dir, &["rev-parse", "--show-prefix"])?.trim().to_string();
+    let mut files: Vec<String> = listing
+        .lines()
+        .filter_map(|file| file.strip_prefix(prefix.as_str()))
+        .filter(|file| {
+            let Some(rel) = file.strip_prefix("src/") else {
+                return false;
+            };
+            let Some(path) = rbxsync_core::file_instance_path(rel) else {
+                return false;
+            };
+            let path = crate::normalize_path_for_comparison(&path);
+            path == target || path.starts_with(&format!("{}/", target))
+        })
+        .map(String::from)
+        .collect();
+    files.sort();
+    files.dedup();
+
+    if files.is_empty() {
+        return Err(GitError::PathNotFound(instance_path.to_string()));
+    }
+    Ok(files)
+}
+
+/// The script file of a script instance, relative to the project
+pub fn script_file(project_dir: &Path, instance_path: &str) -> Result<String, GitError> {
+    let files = instance_files(project_dir, instance_path)?;
+    let depth = files.iter().map(|f| f.matches('/').count()).min().unwrap_or(0);
+    // The instance's own script: the shallowest one, or its folder's init script
+    files
+        .into_iter()
+        .find(|file| {
+            let filename = file.rsplit('/').next().unwrap_or(file);
+            let is_script = rbxsync_core::script_file_info(filename).is_some();
+            let own = if rbxsync_core::is_init_script(filename) {
+                file.matches('/').count() == depth + 1
+            } else {
+                file.matches('/').count() == depth
+            };
+            is_script && own
+        })
+        .ok_or_else(|| GitError::NotAScript(instance_path.to_string()))
+}
+
+/// Stage the files of the given instances, including deletions
+///
+/// Returns the staged files, relative to the project.
+pub fn stage(project_dir: &Path, instance_paths: &[String]) -> Result<Vec<String>, GitError> {
+    let mut files = Vec::new();
+    for instance_path in instance_paths {
+        files.extend(instance_files(project_dir, instance_path)?);
+    }
+    files.sort();
+    files.dedup();
+
+    let mut args = vec!["add", "-A", "--"];
+    args.extend(files.iter().map(String::as_str));
+    run(project_dir, &args)?;
+    Ok(files)
+}
+
+/// Stage project files and directories as they are on disk, additions and
+/// deletions alike; returns the files that now differ from HEAD
+pub fn stage_files(project_dir: &Path, paths: &[String]) -> Result<Vec<String>, GitError> {
+    ensure_repo(project_dir)?;
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
 
+    // git add rejects pathspecs that match nothing, such as a file that was
+    // created and removed again before ever being added
+    let mut args = vec!["ls-files", "-z", "--cached", "--others", "--exclude-standard", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let out = run(project_dir, &args)?;
+    let mut known: Vec<&str> = out.split('\0').filter(|f| !f.is_empty()).collect();
+    known.sort();
+    known.dedup();
+    if known.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec!["add", "-A", "--"];
+    args.extend(known.iter().copied());
+    run(project_dir, &args)?;
+
+    let mut args = vec!["diff", "--cached", "--name-only", "--no-renames", "--relative", "-z", "--"];
+    args.extend(known.iter().copied());
+    let out = run(project_dir, &args)?;
+    Ok(out.split('\0').filter(|f| !f.is_empty()).map(String::from).collect())
+}
+
+/// Commit only `files`, leaving anything else that is staged for a later
+/// commit; returns the new commit's hash
+pub fn commit_files(project_dir: &Path, message: &str, files: &[String]) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+
+    let mut args = vec!["commit", "-m", message, "--"];
+    args.extend(files.iter().map(String::as_str));
+    run(project_dir, &args)?;
+    Ok(run(project_dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Unified diff of files against HEAD, staged and unstaged changes together
+pub fn diff_head(project_dir: &Path, files: &[String]) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+
+    let mut args = vec!["diff", "HEAD", "--"];
+    args.extend(files.iter().map(String::as_str));
+    run(project_dir, &args)
+}
+
+/// Stash local changes, returning git's output
+pub fn stash_push(project_dir: &Path, message: Option<&str>, include_untracked: bool) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if let Some(message) = message {
+        args.extend(["-m", message]);
+    }
+    run(project_dir, &args)
+}
+
+/// Apply and drop the latest stash, returning git's output
+pub fn stash_pop(project_dir: &Path) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+    run(project_dir, &["stash", "pop"])
+}
+
+/// List stashes, newest first
+pub fn stash_list(project_dir: &Path) -> Result<Vec<GitStash>, GitError> {
+    ensure_repo(project_dir)?;
+
+    let output = run(project_dir, &["stash", "list", "--format=%gd|%gs"])?;
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once('|'))
+        .map(|(name, message)| GitStash {
+            name: name.to_string(),
+            message: message.to_string(),
+        })
+        .collect())
+}
+
+/// Pull from a remote, merging into the current branch
+///
+/// Defaults to the branch's upstream when `remote` is `None`.
+pub fn pull(project_dir: &Path, remote: Option<&str>, branch: Option<&str>) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+    remote.map_or(Ok(()), |remote| check_arg("remote", remote))?;
+    branch.map_or(Ok(()), |branch| check_arg("branch", branch))?;
+
+    let mut args = vec!["pull", "--no-rebase", "--no-edit"];
+    args.extend(remote);
+    args.extend(branch);
+    run(project_dir, &args)
+}
+
+/// Push to a remote, optionally setting it as the branch's upstream
+///
+/// Defaults to the branch's upstream when `remote` is `None`.
+pub fn push(project_dir: &Path, remote: Option<&str>, branch: Option<&str>, set_upstream: bool) -> Result<String, GitError> {
+    ensure_repo(project_dir)?;
+    remote.map_or(Ok(()), |remote| check_arg("remote", remote))?;
+    branch.map_or(Ok(()), |branch| check_arg("branch", branch))?;
+
+    let mut args = vec!["push"];
+    if set_upstream {
+        args.push("--set-upstream");
+    }
+    args.extend(remote);
+    args.extend(branch);
+    // git push reports progress on stderr
+    let output = Command::new("git").args(&args).current_dir(project_dir).output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        Ok(stderr)
+    } else {
+        Err(GitError::Failed {
+            command: "push".to_string(),
+            message: stderr,
+        })
+    }
+}
+
+/// Who last changed a line (1-based) of a file relative to the project
+pub fn blame_line(project_dir: &Path, file: &str, line: usize) -> Result<BlameLine, GitError> {
+    ensure_repo(project_dir)?;
+
+    let range = format!("{},{}", line, line);
+    let output = run(project_dir, &["blame", "--porcelain", "-L", &range, "--", file])?;
+
+    let mut lines = output.lines();
+    let hash = lines
+        .next()
+        .and_then(|header| header.split(' ').next())
+        .unwrap_or_default()
+        .to_string();
+    let mut blame = BlameLine {
+        file: file.to_string(),
+        line,
+        hash,
+        author: String::new(),
+        timestamp: 0,
+        summary: String::new(),
+        content: String::new(),
+    };
+    for entry in lines {
+        if let Some(content) = entry.strip_prefix('\t') {
+            blame.content = content.to_string();
+        } else if let Some(author) = entry.strip_prefix("author ") {
+            blame.author = author.to_string();
+        } else if let Some(time) = entry.strip_prefix("author-time ") {
+            blame.timestamp = time.parse().unwrap_or(0);
+        } else if let Some(summary) = entry.strip_prefix("summary ") {
+            blame.summary = summary.to_string();
+        }
+    }
+    Ok(blame)
+}
+
+/// Where HEAD points
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadState {
+    /// Full commit hash; `None` before the first commit
+    pub commit: Option<String>,
+    /// Checked-out branch; `None` when HEAD is detached
+    pub branch: Option<String>,
+}
+
+impl HeadState {
+    /// Branch name, or the short commit hash when detached
+    pub fn label(&self) -> String {
+        match (&self.branch, &self.commit) {
+            (Some(branch), _) => branch.clone(),
+            (None, Some(commit)) => commit.chars().take(7).collect(),
+            (None, None) => "(no commit)".to_string(),
+        }
+    }
+}
+
+/// The repository's git directory (`.git`, or a worktree's own directory)
+pub fn git_dir(project_dir: &Path) -> Result<PathBuf, GitError> {
+    ensure_repo(project_dir)?;
+    let dir = run(project_dir, &["rev-parse", "--absolute-git-dir"])?;
+    Ok(PathBuf::from(dir.trim()))
+}
+
+/// Current commit and branch
+pub fn head(project_dir: &Path) -> Result<HeadState, GitError> {
+    ensure_repo(project_dir)?;
+    let commit = run(project_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).ok();
+    let branch = run(project_dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+    let trimmed = |out: Option<String>| out.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    Ok(HeadState {
+        commit: trimmed(commit),
+        branch: trimmed(branch),
+    })
+}
+
+/// Reflog message of the last HEAD update, e.g. `checkout: moving from main
+/// to feature` or `commit: Fix spawn`
+pub fn last_head_update(project_dir: &Path) -> Result<Option<String>, GitError> {
+    let out = run(project_dir, &["reflog", "-1", "--format=%gs"])?;
+    Ok(Some(out.trim().to_string()).filter(|s| !s.is_empty()))
+}
+
+/// Files staged for the next commit, relative to the project
+///
+/// Renames are listed as the deleted old path and the added new one.
+pub fn staged_files(project_dir: &Path) -> Result<Vec<String>, GitError> {
+    ensure_repo(project_dir)?;
+    let out = run(project_dir, &["diff", "--cached", "--name-only", "--no-renames", "--relative", "-z"])?;
+    Ok(out.split('\0').filter(|f| !f.is_empty()).map(String::from).collect())
+}
+
+/// Content of a project file at `revision`, or `None` if it didn't exist
+/// (or there is no such revision, as before the first commit)
+pub fn file_at(project_dir: &Path, revision: &str, file: &str) -> Result<Option<String>, GitError> {
+    check_arg("revision", revision)?;
+    match run(project_dir, &["show", &format!("{}:./{}", revision, file)]) {
+        Ok(content) => Ok(Some(content)),
+        Err(GitError::Failed { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the project as it was at `revision` into `dest`
+///
+/// Only the part of the repository under `project_dir` is exported, so a
+/// project nested inside a larger repo lands at the root of `dest`.
+pub fn export_revision(project_dir: &Path, revision: &str, dest: &Path) -> Result<(), GitError> {
+    check_arg("revision", revision)?;
+    if run(project_dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)]).is_err() {
+        return Err(GitError::UnknownRevision(revision.to_string()));
+    }
+    export_tree(project_dir, revision, dest)
+}
+
+/// Write the project as it is staged in the index into `dest`
+///
+/// Fails while the index has unresolved merge conflicts.
+pub fn export_index(project_dir: &Path, dest: &Path) -> Result<(), GitError> {
+    ensure_repo(project_dir)?;
+    let tree = run(project_dir, &["write-tree"])?;
+    export_tree(project_dir, tree.trim(), dest)
+}
+
+/// Extract the project's part of a commit or tree into `dest`
+fn export_tree(project_dir: &Path, revision: &str, dest: &Path) -> Result<(), GitError> {
+    // git archive resolves paths against the working directory, so run it
+    // from the top level with the project's prefix
+    let location = run(project_dir, &["rev-parse", "--show-toplevel", "--show-prefix"])?;
+    let mut lines = location.lines();
+    let toplevel = lines.next().unwrap_or_default().to_string();
+    let prefix = lines.next().unwrap_or_default().trim_end_matches('/').to_string();
+    let tree = if prefix.is_empty() {
+        revision.to_string()
     } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
+        format!("{}:{}", revision, prefix)
+    };
+
+    std::fs::create_dir_all(dest)?;
+    let mut archive = Command::new("git")
+        .args(["archive", "--format=tar", &tree])
+        .current_dir(&toplevel)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let archive_stdout = archive.stdout.take().ok_or_else(|| GitError::Failed {
+        command: "archive".to_string(),
+        message: "no output".to_string(),
+    })?;
+    let extract = Command::new("tar").arg("-x").arg("-C").arg(dest).stdin(archive_stdout).output()?;
+    let archive_output = archive.wait_with_output()?;
+
+    if !archive_output.status.success() {
+        return Err(GitError::Failed {
+            command: "archive".to_string(),
+            message: String::from_utf8_lossy(&archive_output.stderr).trim().to_string(),
+        });
+    }
+    if !extract.status.success() {
+        return Err(GitError::Failed {
+            command: "tar".to_string(),
+            message: String::from_utf8_lossy(&extract.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Attribute line routing `.rbxjson` files to the rbxsync merge driver
+pub const MERGE_ATTRIBUTE: &str = "*.rbxjson merge=rbxsync";
+
+/// Register `rbxsync merge-driver` for `.rbxjson` files
+///
+/// The driver command goes in the repo's local git config, which isn't
+/// shared, so each clone needs this once. The `.gitattributes` entry is
+/// added next to the project and should be committed. Returns whether
+/// `.gitattributes` was changed.
+pub fn install_merge_driver(project_dir: &Path) -> Result<bool, GitError> {
+    ensure_repo(project_dir)?;
+
+    run(project_dir, &["config", "merge.rbxsync.name", "RbxSync instance-aware .rbxjson merge"])?;
+    run(project_dir, &["config", "merge.rbxsync.driver", "rbxsync merge-driver %O %A %B"])?;
+
+    let attributes_path = project_dir.join(".gitattributes");
+    let existing = std::fs::read_to_string(&attributes_path).unwrap_or_default();
+    if existing.lines().any(|line| line.trim() == MERGE_ATTRIBUTE) {
+        return Ok(false);
     }
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(MERGE_ATTRIBUTE);
+    content.push('\n');
+    crate::atomic_fs::write(&attributes_path, content)?;
+    Ok(true)
+}
+
+/// First lines of the pre-commit hook, which mark it as installed by rbxsync
+const PRE_COMMIT_HEADER: &str = "#!/bin/sh\n# rbxsync pre-commit hook\n";
+
+/// Install a pre-commit hook that runs `rbxsync verify --staged`
+///
+/// The hook goes where git looks for hooks, which honors `core.hooksPath`.
+/// An existing hook that rbxsync didn't write is only replaced with
+/// `force`. Returns the hook's path.
+pub fn install_pre_commit_hook(project_dir: &Path, force: bool) -> Result<PathBuf, GitError> {
+    ensure_repo(project_dir)?;
+    let hooks_dir = PathBuf::from(run(project_dir, &["rev-parse", "--path-format=absolute", "--git-path", "hooks"])?.trim());
+    let hook_path = hooks_dir.join("pre-commit");
+
+    if let Ok(existing) = std::fs::read_to_string(&hook_path) {
+        if !existing.starts_with(PRE_COMMIT_HEADER) && !force {
+            return Err(GitError::HookExists(hook_path));
+        }
+    }
+
+    // Hooks run from the top of the work tree
+    let prefix = run(project_dir, &["rev-parse", "--show-prefix"])?;
+    let prefix = prefix.trim().trim_end_matches('/');
+    let project = if prefix.is_empty() { "." } else { prefix };
+    let script = format!(
+        "{}# Checks that the staged project tree builds; skip with `git commit --no-verify`\nexec rbxsync verify --staged --path {}\n",
+        PRE_COMMIT_HEADER,
+        shell_quote(project)
+    );
+
+    std::fs::create_dir_all(&hooks_dir)?;
+    crate::atomic_fs::write(&hook_path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(hook_path)
+}
+
+/// Single-quote a word for `sh`
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
 }
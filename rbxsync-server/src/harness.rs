@@ -104,6 +104,14 @@ fn get_harness_dir(project_dir: &str) -> PathBuf {
     PathBuf::from(project_dir).join(HARNESS_DIR)
 }
 
+/// Notify event stream subscribers that harness data changed
+fn publish_harness_update(state: &AppState, project_dir: &str, change: &str) {
+    state.events.publish(crate::events::ServerEvent::HarnessUpdated {
+        project_dir: project_dir.to_string(),
+        change: change.to_string(),
+    });
+}
+
 /// Request to initialize a harness for a project
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -145,7 +153,7 @@ pub struct HarnessInitResponse {
 
 /// Initialize harness directory structure for a project
 pub async fn handle_harness_init(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<HarnessInitRequest>,
 ) -> impl IntoResponse {
     tracing::info!("Initializing harness for project: {}", req.project_dir);
@@ -188,7 +196,7 @@ pub async fn handle_harness_init(
     let game_path = harness_dir.join("game.yaml");
     match serde_yaml::to_string(&game) {
         Ok(yaml) => {
-            if let Err(e) = std::fs::write(&game_path, yaml) {
+            if let Err(e) = crate::atomic_fs::write(&game_path, yaml) {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(HarnessInitResponse {
@@ -286,7 +294,7 @@ pub async fn handle_harness_init(
     let features_path = harness_dir.join("features.yaml");
     match serde_yaml::to_string(&features) {
         Ok(yaml) => {
-            if let Err(e) = std::fs::write(&features_path, yaml) {
+            if let Err(e) = crate::atomic_fs::write(&features_path, yaml) {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(HarnessInitResponse {
@@ -329,6 +337,7 @@ pub async fn handle_harness_init(
         harness_dir.display(),
         template_applied
     );
+    publish_harness_update(&state, &req.project_dir, "init");
 
     (
         StatusCode::OK,
@@ -367,7 +376,7 @@ pub struct SessionStartResponse {
 
 /// Start a new development session
 pub async fn handle_session_start(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<SessionStartRequest>,
 ) -> impl IntoResponse {
     tracing::info!("Starting new session for project: {}", req.project_dir);
@@ -414,7 +423,7 @@ pub async fn handle_session_start(
     // Write session file
     match serde_yaml::to_string(&session) {
         Ok(yaml) => {
-            if let Err(e) = std::fs::write(&session_path, yaml) {
+            if let Err(e) = crate::atomic_fs::write(&session_path, yaml) {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(SessionStartResponse {
@@ -440,6 +449,7 @@ pub async fn handle_session_start(
     }
 
     tracing::info!("Session started: {}", session_id);
+    publish_harness_update(&state, &req.project_dir, "session_start");
 
     (
         StatusCode::OK,
@@ -481,7 +491,7 @@ pub struct SessionEndResponse {
 
 /// End a development session
 pub async fn handle_session_end(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<SessionEndRequest>,
 ) -> impl IntoResponse {
     tracing::info!("Ending session {} for project: {}", req.session_id, req.project_dir);
@@ -535,7 +545,7 @@ pub async fn handle_session_end(
     // Write updated session
     match serde_yaml::to_string(&session) {
         Ok(yaml) => {
-            if let Err(e) = std::fs::write(&session_path, yaml) {
+            if let Err(e) = crate::atomic_fs::write(&session_path, yaml) {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(SessionEndResponse {
@@ -557,6 +567,7 @@ pub async fn handle_session_end(
     }
 
     tracing::info!("Session ended: {}", req.session_id);
+    publish_harness_update(&state, &req.project_dir, "session_end");
 
     (
         StatusCode::OK,
@@ -638,7 +649,7 @@ pub struct FeatureUpdateResponse {
 
 /// Update or create a feature
 pub async fn handle_feature_update(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<FeatureUpdateRequest>,
 ) -> impl IntoResponse {
     tracing::info!("Feature update for project: {}", req.project_dir);
@@ -802,7 +813,7 @@ pub async fn handle_feature_update(
     // Write updated features
     match serde_yaml::to_string(&features_file) {
         Ok(yaml) => {
-            if let Err(e) = std::fs::write(&features_path, yaml) {
+            if let Err(e) = crate::atomic_fs::write(&features_path, yaml) {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(FeatureUpdateResponse {
@@ -827,6 +838,7 @@ pub async fn handle_feature_update(
 
     let action = if is_new { "created" } else { "updated" };
     tracing::info!("Feature {}: {}", action, feature_id);
+    publish_harness_update(&state, &req.project_dir, "feature");
 
     (
         StatusCode::OK,
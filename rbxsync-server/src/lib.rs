@@ -3,14 +3,28 @@
 //! HTTP server that communicates with the Roblox Studio plugin
 //! for game extraction and synchronization.
 
+pub mod atomic_fs;
+pub mod branch_switch;
+pub mod echo;
+pub mod events;
 pub mod git;
 pub mod file_watcher;
+pub mod daemon;
 pub mod harness;
+pub mod limits;
+pub mod live_sync;
+pub mod metrics;
+pub mod plugin_requests;
+pub mod state_store;
+pub mod studio_commit;
+pub mod studio_diff;
+pub mod studio_stream;
+pub mod writer;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use axum::{
     extract::{DefaultBodyLimit, Path, Query, State},
@@ -23,6 +37,8 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, watch, Mutex, RwLock};
 use uuid::Uuid;
 
+use plugin_requests::PendingRequest;
+
 /// Normalize Windows paths by converting backslashes to forward slashes.
 /// This ensures consistent path handling across platforms and prevents issues
 /// with backslash escape sequences in JSON/strings.
@@ -32,7 +48,7 @@ fn normalize_path(path: &str) -> String {
 }
 
 /// Load project config from rbxsync.json
-fn load_project_config(project_dir: &str) -> Option<serde_json::Value> {
+pub fn load_project_config(project_dir: &str) -> Option<serde_json::Value> {
     let config_path = PathBuf::from(project_dir).join("rbxsync.json");
     if config_path.exists() {
         if let Ok(content) = std::fs::read_to_string(&config_path) {
@@ -44,6 +60,18 @@ fn load_project_config(project_dir: &str) -> Option<serde_json::Value> {
     None
 }
 
+/// Milliseconds since the Unix epoch, the timestamp format clients get
+pub(crate) fn now_millis() -> u64 {
+    system_time_to_millis(SystemTime::now())
+}
+
+/// A `SystemTime` in milliseconds since the Unix epoch (0 before it)
+pub(crate) fn system_time_to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 /// Apply tree mapping to convert DataModel path to filesystem path
 fn apply_tree_mapping(datamodel_path: &str, tree_mapping: &HashMap<String, String>) -> String {
     // Try to find longest matching prefix
@@ -87,29 +115,31 @@ fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> std::io::Result<()> {
 }
 
 /// Apply reverse tree mapping to convert filesystem path to DataModel path
-#[allow(dead_code)]
 fn apply_reverse_tree_mapping(fs_path: &str, tree_mapping: &HashMap<String, String>) -> String {
+    reverse_tree_mapping_match(fs_path, tree_mapping).unwrap_or_else(|| fs_path.to_string())
+}
+
+/// Reverse tree mapping lookup; `None` if no mapping covers the path
+fn reverse_tree_mapping_match(fs_path: &str, tree_mapping: &HashMap<String, String>) -> Option<String> {
     // Try to find longest matching prefix (reverse lookup)
     let mut best_match: Option<(&str, &str)> = None;
     let mut best_len = 0;
 
     for (dm_prefix, fs_prefix) in tree_mapping {
+        let fs_prefix = fs_prefix.strip_prefix("./").unwrap_or(fs_prefix).trim_end_matches('/');
         if (fs_path == fs_prefix || fs_path.starts_with(&format!("{}/", fs_prefix)))
             && fs_prefix.len() > best_len {
-                best_match = Some((dm_prefix.as_str(), fs_prefix.as_str()));
+                best_match = Some((dm_prefix.as_str(), fs_prefix));
                 best_len = fs_prefix.len();
             }
     }
 
-    if let Some((dm_prefix, fs_prefix)) = best_match {
-        if fs_path == fs_prefix {
-            dm_prefix.to_string()
-        } else {
-            let suffix = &fs_path[fs_prefix.len() + 1..]; // Skip the '/'
-            format!("{}/{}", dm_prefix, suffix)
-        }
+    let (dm_prefix, fs_prefix) = best_match?;
+    if fs_path == fs_prefix {
+        Some(dm_prefix.to_string())
     } else {
-        fs_path.to_string()
+        let suffix = &fs_path[fs_prefix.len() + 1..]; // Skip the '/'
+        Some(format!("{}/{}", dm_prefix, suffix))
     }
 }
 
@@ -159,6 +189,12 @@ fn normalize_path_for_comparison(path: &str) -> String {
 pub struct ServerConfig {
     pub port: u16,
     pub host: String,
+    /// Directory for persisted server state (None disables persistence)
+    pub state_dir: Option<PathBuf>,
+    /// Daemon PID file, log and control socket (None when not running as a daemon)
+    pub daemon: Option<daemon::DaemonPaths>,
+    /// Plugin response timeouts per command
+    pub command_timeouts: plugin_requests::CommandTimeouts,
 }
 
 impl Default for ServerConfig {
@@ -166,6 +202,9 @@ impl Default for ServerConfig {
         Self {
             port: 44755,
             host: "127.0.0.1".to_string(),
+            state_dir: None,
+            daemon: None,
+            command_timeouts: plugin_requests::CommandTimeouts::default(),
         }
     }
 }
@@ -248,6 +287,18 @@ pub struct AppState {
     /// Channel to receive file changes
     pub file_change_rx: Mutex<mpsc::UnboundedReceiver<file_watcher::FileChange>>,
 
+    /// Files the server wrote itself, so the watcher doesn't sync them back
+    pub echo_filter: echo::EchoFilter,
+
+    /// Recent live-sync batches and their results (served at /sync/batches)
+    pub live_sync_batches: live_sync::BatchLog,
+
+    /// HEAD changes waiting to be applied to Studio (see branch_switch)
+    pub branch_switches: branch_switch::BranchSwitches,
+
+    /// Studio changes waiting to be written to disk (see studio_stream)
+    pub studio_stream: studio_stream::StudioStream,
+
     /// Track which VS Code workspaces we've logged (to prevent spam)
     pub logged_vscode_workspaces: RwLock<HashSet<String>>,
 
@@ -287,6 +338,30 @@ pub struct AppState {
     /// Current operation state per project (RBXSYNC-77)
     /// Allows VS Code to display server-initiated operations (CLI/MCP)
     pub operation_state: RwLock<HashMap<String, OperationInfo>>,
+
+    /// Unified event stream for all state changes (served at /events)
+    pub events: events::EventBus,
+
+    /// On-disk state store (set when persistence is enabled)
+    pub state_store: RwLock<Option<state_store::StateStore>>,
+
+    /// When the server started (for daemon status uptime)
+    pub started_at: Instant,
+
+    /// Daemon file locations (set when running as a daemon)
+    pub daemon_paths: RwLock<Option<daemon::DaemonPaths>>,
+
+    /// Requests a handler is waiting on (for deadlines and cancellation)
+    pub pending_requests: RwLock<HashMap<Uuid, plugin_requests::PendingInfo>>,
+
+    /// Plugin response timeouts per command
+    pub command_timeouts: RwLock<plugin_requests::CommandTimeouts>,
+
+    /// Notified to trigger graceful shutdown (daemon control socket)
+    pub shutdown: tokio::sync::Notify,
+
+    /// Counters and histograms served at /metrics
+    pub metrics: metrics::Metrics,
 }
 
 impl AppState {
@@ -307,6 +382,10 @@ impl AppState {
             live_sync_paused: std::sync::atomic::AtomicBool::new(false),
             file_watcher_state: Arc::new(RwLock::new(file_watcher::FileWatcherState::new(file_change_tx))),
             file_change_rx: Mutex::new(file_change_rx),
+            echo_filter: echo::EchoFilter::new(),
+            live_sync_batches: live_sync::BatchLog::new(),
+            branch_switches: branch_switch::BranchSwitches::new(),
+            studio_stream: studio_stream::StudioStream::new(),
             logged_vscode_workspaces: RwLock::new(HashSet::new()),
             logged_studio_places: RwLock::new(HashSet::new()),
             console_buffer: RwLock::new(VecDeque::with_capacity(CONSOLE_BUFFER_SIZE)),
@@ -320,8 +399,87 @@ impl AppState {
             playtest_started: RwLock::new(None),
             playtest_ended: RwLock::new(None),
             operation_state: RwLock::new(HashMap::new()),
+            events: events::EventBus::new(),
+            state_store: RwLock::new(None),
+            started_at: Instant::now(),
+            daemon_paths: RwLock::new(None),
+            pending_requests: RwLock::new(HashMap::new()),
+            command_timeouts: RwLock::new(plugin_requests::CommandTimeouts::default()),
+            shutdown: tokio::sync::Notify::new(),
+            metrics: metrics::Metrics::new(),
         })
     }
+
+    /// Plugin response timeout for a command
+    pub async fn timeout_for(&self, command: &str) -> Duration {
+        self.command_timeouts.read().await.get(command)
+    }
+
+    /// Add a message to the console history and stream it to subscribers
+    pub async fn push_console(&self, msg: ConsoleMessage) {
+        // Broadcast to any active subscribers
+        let _ = self.console_tx.send(msg.clone());
+        self.events.publish(events::ServerEvent::Console { message: msg.clone() });
+
+        // Add to buffer (ring buffer behavior)
+        let mut buffer = self.console_buffer.write().await;
+        if buffer.len() >= CONSOLE_BUFFER_SIZE {
+            buffer.pop_front();
+        }
+        buffer.push_back(msg);
+    }
+
+    /// Record the start of an operation for VS Code UI sync (RBXSYNC-77)
+    pub async fn start_operation(&self, project_dir: &str, op_type: OperationType, progress: Option<String>) {
+        if project_dir.is_empty() {
+            return;
+        }
+        let mut ops = self.operation_state.write().await;
+        ops.insert(project_dir.to_string(), OperationInfo {
+            op_type,
+            project_dir: project_dir.to_string(),
+            start_time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            progress: progress.clone(),
+        });
+        drop(ops);
+
+        self.events.publish(events::ServerEvent::OperationStarted {
+            project_dir: project_dir.to_string(),
+            operation: op_type,
+            progress,
+        });
+    }
+
+    /// Clear the operation state for a project once it completes
+    pub async fn finish_operation(&self, project_dir: &str, success: bool) {
+        let removed = self.operation_state.write().await.remove(project_dir);
+        if let Some(op) = removed {
+            self.events.publish(events::ServerEvent::OperationFinished {
+                project_dir: project_dir.to_string(),
+                operation: op.op_type,
+                success,
+            });
+        }
+    }
+
+    /// Mark a playtest as started, emitting an event on transition
+    fn mark_playtest_active(&self) {
+        if !self.playtest_active.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            self.events.publish(events::ServerEvent::PlaytestStarted);
+        }
+    }
+
+    /// Mark a playtest as stopped, emitting an event on transition
+    fn mark_playtest_inactive(&self, reason: Option<&str>) {
+        if self.playtest_active.swap(false, std::sync::atomic::Ordering::Relaxed) {
+            self.events.publish(events::ServerEvent::PlaytestStopped {
+                reason: reason.map(String::from),
+            });
+        }
+    }
 }
 
 /// Request to send to the Studio plugin
@@ -330,6 +488,20 @@ pub struct PluginRequest {
     pub id: Uuid,
     pub command: String,
     pub payload: serde_json::Value,
+    /// Unix timestamp in millis after which the server stops waiting (set on dispatch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+}
+
+impl PluginRequest {
+    pub fn new(id: Uuid, command: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self {
+            id,
+            command: command.into(),
+            payload,
+            deadline: None,
+        }
+    }
 }
 
 /// Response from the Studio plugin
@@ -352,10 +524,31 @@ pub struct ExtractionSession {
     pub data: Vec<serde_json::Value>,
     /// Whether finalize has been called (extraction complete even if 0 chunks)
     pub finalized: bool,
+    /// Data files in `src` when the extraction started, keyed by instance path
+    pub data_files: HashMap<String, ExtractedDataFile>,
+    /// Chunk and terrain bytes received, checked against `limits.maxExtractionSize`
+    pub bytes_received: u64,
+    /// Skip the size limits (`force: true` on `/extract/start`)
+    pub force: bool,
+    /// The project's size limits, read once when the session starts
+    pub limits: rbxsync_core::LimitsConfig,
+    /// Why a chunk was refused; finalize refuses to swap in a partial tree
+    pub limit_error: Option<String>,
+    /// Terrain received in this session, staged until finalize
+    pub terrain_file: Option<PathBuf>,
+}
+
+/// A data file (.json, .txt, .csv, .model.json, ...) from the tree an
+/// extraction replaces, kept so finalize can write it back in its own format
+#[derive(Debug, Clone)]
+pub struct ExtractedDataFile {
+    /// Path relative to `src`, with forward slashes
+    pub rel_path: String,
+    pub content: String,
 }
 
 /// Connected Studio place information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaceInfo {
     pub place_id: u64,
     pub place_name: String,
@@ -372,6 +565,8 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         // RbxSync plugin communication endpoints (separate from roblox-mcp)
         .route("/rbxsync/request", get(handle_request_poll))
         .route("/rbxsync/response", post(handle_response))
+        .route("/rbxsync/cancel/:id", post(handle_cancel_request))
+        .route("/rbxsync/timeouts", get(handle_get_timeouts))
         .route("/rbxsync/register", post(handle_register))
         .route("/rbxsync/unregister", post(handle_unregister))
         .route("/rbxsync/register-vscode", post(handle_register_vscode))
@@ -394,9 +589,14 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         // Sync endpoints
         .route("/sync/command", post(handle_sync_command))
         .route("/sync/batch", post(handle_sync_batch))
+        .route("/sync/batches", get(handle_sync_batches))
+        .route("/sync/batches/:id", get(handle_sync_batch_status))
+        .route("/sync/branch-switch", get(handle_branch_switches).post(handle_branch_switch))
+        .route("/watch/status", get(handle_watch_status))
         .route("/sync/read-tree", post(handle_sync_read_tree))
         .route("/sync/read-terrain", post(handle_sync_read_terrain))
         .route("/sync/from-studio", post(handle_sync_from_studio))
+        .route("/sync/studio-stream", get(handle_studio_stream_status).post(handle_studio_stream))
         .route("/sync/pending-changes", post(handle_sync_pending_changes))
         .route("/sync/incremental", post(handle_sync_incremental))
         // Diff endpoints
@@ -406,7 +606,16 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/git/status", post(handle_git_status))
         .route("/git/log", post(handle_git_log))
         .route("/git/commit", post(handle_git_commit))
+        .route("/git/commit-studio", post(handle_git_commit_studio))
         .route("/git/init", post(handle_git_init))
+        .route("/git/branches", post(handle_git_branches))
+        .route("/git/switch", post(handle_git_switch))
+        .route("/git/stage", post(handle_git_stage))
+        .route("/git/diff", post(handle_git_diff))
+        .route("/git/blame", post(handle_git_blame))
+        .route("/git/stash", post(handle_git_stash))
+        .route("/git/pull", post(handle_git_pull))
+        .route("/git/push", post(handle_git_push))
         // Test runner endpoints (for AI-powered development workflows)
         .route("/test/start", post(handle_test_start))
         .route("/test/status", get(handle_test_status))
@@ -430,6 +639,9 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/console/push", post(handle_console_push))
         .route("/console/subscribe", get(handle_console_subscribe))
         .route("/console/history", get(handle_console_history))
+        // Unified event stream (operations, connections, live sync, playtests, harness)
+        .route("/events", get(handle_events_subscribe))
+        .route("/events/history", get(handle_events_history))
         // Run arbitrary Luau code (for MCP)
         .route("/run", post(handle_run_code))
         // Read instance properties (for MCP)
@@ -442,6 +654,11 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/insert-model", post(handle_insert_model))
         // Health check
         .route("/health", get(handle_health))
+        // Prometheus metrics
+        .route("/metrics", get(handle_metrics))
+        // Daemon status and config reload
+        .route("/daemon/status", get(handle_daemon_status))
+        .route("/daemon/reload", post(handle_daemon_reload))
         // Shutdown endpoint
         .route("/shutdown", post(handle_shutdown))
         // Harness system for multi-session AI development
@@ -463,9 +680,22 @@ async fn handle_health() -> impl IntoResponse {
     }))
 }
 
+/// Prometheus metrics in the text exposition format
+async fn handle_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        metrics::render(&state).await,
+    )
+}
+
 /// Shutdown endpoint - gracefully stops the server
-async fn handle_shutdown() -> impl IntoResponse {
+async fn handle_shutdown(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     tracing::info!("Shutdown requested via API");
+    persist_state(&state).await;
+    // process::exit skips destructors, so release the daemon PID file here
+    if let Some(paths) = state.daemon_paths.read().await.as_ref() {
+        paths.cleanup();
+    }
     // Spawn a task to exit after response is sent
     tokio::spawn(async {
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -476,6 +706,20 @@ async fn handle_shutdown() -> impl IntoResponse {
     }))
 }
 
+/// Daemon status: attached places, workspaces and watchers per project
+async fn handle_daemon_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(daemon::status(&state).await)
+}
+
+/// Reload project configs and restart file watchers
+async fn handle_daemon_reload(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let reloaded = daemon::reload(&state).await;
+    Json(serde_json::json!({
+        "success": true,
+        "reloaded": reloaded
+    }))
+}
+
 /// Register request from Studio plugin
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -519,11 +763,18 @@ async fn handle_register(
                     info.session_id.unwrap_or_default(),
                     stale_key
                 );
+                state.events.publish(events::ServerEvent::PlaceDisconnected {
+                    session_key: stale_key,
+                    place_id: info.place_id,
+                    place_name: info.place_name,
+                    reason: "replaced".to_string(),
+                });
             }
         }
     }
 
     // Register/update this place (replaces any existing entry for this session)
+    let is_new_session = !registry.contains_key(&key);
     registry.insert(key.clone(), PlaceInfo {
         place_id: req.place_id,
         place_name: req.place_name.clone(),
@@ -533,6 +784,15 @@ async fn handle_register(
     });
     drop(registry); // Release lock before acquiring another
 
+    if is_new_session {
+        state.events.publish(events::ServerEvent::PlaceConnected {
+            session_key: key.clone(),
+            place_id: req.place_id,
+            place_name: req.place_name.clone(),
+            project_dir: project_dir.clone(),
+        });
+    }
+
     // Create project queue if it doesn't exist
     {
         let mut queues = state.project_queues.write().await;
@@ -607,6 +867,12 @@ async fn handle_unregister(
             req.session_id,
             req.project_dir
         );
+        state.events.publish(events::ServerEvent::PlaceDisconnected {
+            session_key: key,
+            place_id: req.place_id,
+            place_name: req.place_name.clone(),
+            reason: "unregistered".to_string(),
+        });
     }
 
     Json(serde_json::json!({
@@ -635,6 +901,12 @@ async fn cleanup_stale_registrations(state: &Arc<AppState>) {
     for key in &stale_keys {
         if let Some(info) = registry.remove(key) {
             tracing::info!("Removed stale registration: {} ({})", info.place_name, key);
+            state.events.publish(events::ServerEvent::PlaceDisconnected {
+                session_key: key.clone(),
+                place_id: info.place_id,
+                place_name: info.place_name,
+                reason: "stale".to_string(),
+            });
         }
     }
 }
@@ -684,6 +956,12 @@ async fn handle_register_vscode(
     });
     drop(workspaces); // Release lock before acquiring another
 
+    if is_new {
+        state.events.publish(events::ServerEvent::WorkspaceConnected {
+            workspace_dir: workspace_dir.clone(),
+        });
+    }
+
     // Only log and start file watcher if this is a new workspace this session
     // Use a separate set to prevent spam from heartbeat registrations
     let mut logged = state.logged_vscode_workspaces.write().await;
@@ -739,26 +1017,7 @@ async fn handle_register_vscode(
 
         // Start file watcher for new workspaces
         if is_new {
-            let watcher_state = state.file_watcher_state.clone();
-            let dir = workspace_dir.clone();
-
-            // Load config to check package sync settings
-            let config = load_project_config(&dir);
-            let packages_config = config.as_ref().and_then(|c| c.get("packages"));
-
-            // Check if packages should sync (excludeFromWatch: false means sync packages)
-            // Also enable if packages.enabled is true and excludeFromWatch is not explicitly set
-            let sync_packages = packages_config
-                .and_then(|p| p.get("excludeFromWatch"))
-                .and_then(|v| v.as_bool())
-                .map(|exclude| !exclude)  // Invert: excludeFromWatch=false means sync_packages=true
-                .unwrap_or(false);  // Default: don't sync packages (for backwards compatibility)
-
-            tokio::spawn(async move {
-                if let Err(e) = file_watcher::start_file_watcher(dir, watcher_state, sync_packages).await {
-                    tracing::error!("Failed to start file watcher: {}", e);
-                }
-            });
+            spawn_workspace_watcher(&state, workspace_dir.clone());
         }
     }
 
@@ -768,6 +1027,59 @@ async fn handle_register_vscode(
     }))
 }
 
+/// Start the live-sync file watcher for a VS Code workspace
+fn spawn_workspace_watcher(state: &Arc<AppState>, dir: String) {
+    let watcher_state = state.file_watcher_state.clone();
+
+    // Load config to check package sync settings
+    let sync_packages = file_watcher::sync_packages_enabled(&load_project_config(&dir));
+
+    tokio::spawn(async move {
+        if let Err(e) = file_watcher::start_file_watcher(dir, watcher_state, sync_packages).await {
+            tracing::error!("Failed to start file watcher: {}", e);
+        }
+    });
+}
+
+/// File watcher roots and event counts per project
+async fn handle_watch_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let watcher = state.file_watcher_state.read().await;
+    let mut dirs: Vec<&String> = watcher.watched_dirs.iter().collect();
+    dirs.sort();
+
+    let projects: Vec<serde_json::Value> = dirs
+        .into_iter()
+        .map(|dir| {
+            let roots: Vec<serde_json::Value> = watcher
+                .roots
+                .get(dir)
+                .map(|roots| {
+                    roots
+                        .iter()
+                        .map(|root| {
+                            serde_json::json!({
+                                "path": rbxsync_core::path_to_string(&root.path),
+                                "events": root.events.load(std::sync::atomic::Ordering::Relaxed)
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let events: u64 = roots.iter().filter_map(|r| r["events"].as_u64()).sum();
+            serde_json::json!({
+                "projectDir": dir,
+                "roots": roots,
+                "events": events
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "projects": projects,
+        "trackedFiles": watcher.fingerprints.len()
+    }))
+}
+
 /// Request to update Studio project path
 #[derive(Debug, Deserialize)]
 pub struct UpdateProjectPathRequest {
@@ -986,37 +1298,19 @@ struct UndoExtractRequest {
 async fn handle_undo_extract(
     Json(req): Json<UndoExtractRequest>,
 ) -> impl IntoResponse {
-    let src_dir = PathBuf::from(&req.project_dir).join("src");
-    let backup_dir = PathBuf::from(&req.project_dir).join(".rbxsync-backup");
-    let backup_src = backup_dir.join("src");
-
-    if !backup_src.exists() {
+    let project_dir = PathBuf::from(&req.project_dir);
+    if !project_dir.join(atomic_fs::BACKUP_DIR).join("src").exists() {
         return Json(serde_json::json!({
             "success": false,
             "error": "No backup found to restore"
         }));
     }
 
-    // Remove current src
-    if src_dir.exists() {
-        if let Err(e) = std::fs::remove_dir_all(&src_dir) {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Failed to remove current src: {}", e)
-            }));
-        }
-    }
-
-    // Restore from backup
-    if std::fs::rename(&backup_src, &src_dir).is_err() {
-        // If rename fails, try copy
-        if let Err(e) = copy_dir_recursive(&backup_src, &src_dir) {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": format!("Failed to restore from backup: {}", e)
-            }));
-        }
-        let _ = std::fs::remove_dir_all(&backup_src);
+    if let Err(e) = atomic_fs::restore_backup(&project_dir, "src") {
+        return Json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to restore from backup: {}", e)
+        }));
     }
 
     tracing::info!("Restored src from backup for {}", req.project_dir);
@@ -1046,6 +1340,9 @@ async fn cleanup_stale_vscode_workspaces(state: &Arc<AppState>) {
     for key in &stale_keys {
         workspaces.remove(key);
         tracing::info!("Removed stale VS Code workspace: {}", key);
+        state.events.publish(events::ServerEvent::WorkspaceDisconnected {
+            workspace_dir: key.clone(),
+        });
     }
 }
 
@@ -1142,21 +1439,27 @@ async fn handle_request_poll(
     ) -> Option<PluginRequest> {
         // First try project-specific queue if projectDir provided
         if let Some(ref dir) = project_dir {
-            let mut queues = state.project_queues.write().await;
-            if let Some(queue) = queues.get_mut(dir) {
-                if let Some(request) = queue.pop_front() {
-                    return Some(request);
-                }
+            let popped = {
+                let mut queues = state.project_queues.write().await;
+                queues.get_mut(dir).and_then(|queue| queue.pop_front())
+            };
+            if let Some(mut request) = popped {
+                plugin_requests::mark_dispatched(state, &mut request, Some(dir.clone())).await;
+                return Some(request);
             }
         }
 
         // Fall back to global queue (legacy support)
-        let mut queue = state.request_queue.lock().await;
-        queue.pop_front()
+        let popped = state.request_queue.lock().await.pop_front();
+        let mut request = popped?;
+        plugin_requests::mark_dispatched(state, &mut request, None).await;
+        Some(request)
     }
 
     // First check if there's already a request
+    let poll_start = Instant::now();
     if let Some(request) = try_pop_request(&state, &params.project_dir).await {
+        record_poll(&state, poll_start, Some(&request));
         return (StatusCode::OK, Json(serde_json::to_value(&request).unwrap()));
     }
 
@@ -1177,26 +1480,48 @@ async fn handle_request_poll(
     tokio::select! {
         _ = tokio::time::sleep(timeout) => {
             // Timeout - return empty response
+            record_poll(&state, poll_start, None);
             (StatusCode::NO_CONTENT, Json(serde_json::json!(null)))
         }
         _ = trigger_rx.changed() => {
             // Check if there's a request
             if let Some(request) = try_pop_request(&state, &params.project_dir).await {
+                record_poll(&state, poll_start, Some(&request));
                 (StatusCode::OK, Json(serde_json::to_value(&request).unwrap()))
             } else {
+                record_poll(&state, poll_start, None);
                 (StatusCode::NO_CONTENT, Json(serde_json::json!(null)))
             }
         }
     }
 }
 
+/// Record poll metrics and the dispatch time of any returned request
+fn record_poll(state: &AppState, started: Instant, request: Option<&PluginRequest>) {
+    state.metrics.poll_wait_seconds.observe(started.elapsed().as_secs_f64());
+    match request {
+        // Cancel commands are fire-and-forget, the plugin doesn't answer them
+        Some(request) if request.command == plugin_requests::CANCEL_COMMAND => {
+            state.metrics.polls.inc("request");
+        }
+        Some(request) => {
+            state.metrics.polls.inc("request");
+            state.metrics.request_dispatched(request.id, &request.command);
+        }
+        None => state.metrics.polls.inc("empty"),
+    }
+}
+
 /// Handle response from plugin
 async fn handle_response(
     State(state): State<Arc<AppState>>,
     Json(response): Json<PluginResponse>,
 ) -> impl IntoResponse {
     tracing::info!("Received response for request {}: success={}", response.id, response.success);
+    plugin_requests::mark_answered(&state, response.id).await;
     let channels = state.response_channels.read().await;
+    let delivered = channels.contains_key(&response.id);
+    state.metrics.response_received(response.id, response.success, delivered);
     if let Some(sender) = channels.get(&response.id) {
         tracing::info!("Found channel for request {}, sending response", response.id);
         let _ = sender.send(response);
@@ -1206,6 +1531,35 @@ async fn handle_response(
     Json(serde_json::json!({"ok": true}))
 }
 
+/// Cancel a plugin request by ID
+///
+/// Queued requests are dropped; dispatched ones get a `cancel` command so the
+/// plugin can abort or discard them. The waiting handler returns immediately.
+async fn handle_cancel_request(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let outcome = plugin_requests::cancel_request(&state, id, "cancelled").await;
+    let status = if outcome == plugin_requests::CancelOutcome::Unknown {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::OK
+    };
+    (
+        status,
+        Json(serde_json::json!({
+            "success": status == StatusCode::OK,
+            "requestId": id,
+            "stage": outcome
+        })),
+    )
+}
+
+/// Current plugin response timeouts (seconds)
+async fn handle_get_timeouts(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::to_value(&*state.command_timeouts.read().await).unwrap_or_default())
+}
+
 /// Start extraction request
 #[derive(Debug, Deserialize)]
 pub struct ExtractStartRequest {
@@ -1217,6 +1571,9 @@ pub struct ExtractStartRequest {
     pub include_terrain: Option<bool>,
     /// Include binary assets
     pub include_assets: Option<bool>,
+    /// Skip the project's size limits
+    #[serde(default)]
+    pub force: bool,
 }
 
 async fn handle_extract_start(
@@ -1227,7 +1584,16 @@ async fn handle_extract_start(
     let session_uuid = Uuid::new_v4();
     let session_id = session_uuid.to_string();
 
+    // Remember the data files in the tree this extraction replaces
+    let data_files = req
+        .project_dir
+        .as_deref()
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| index_data_files(&PathBuf::from(dir).join("src")))
+        .unwrap_or_default();
+
     // Create extraction session
+    state.metrics.extraction_reset();
     {
         let mut session = state.extraction_session.write().await;
         *session = Some(ExtractionSession {
@@ -1236,23 +1602,18 @@ async fn handle_extract_start(
             total_chunks: None,
             data: Vec::new(),
             finalized: false,
+            data_files,
+            bytes_received: 0,
+            force: req.force,
+            limits: limits::for_project(req.project_dir.as_deref()),
+            limit_error: None,
+            terrain_file: None,
         });
     }
 
     // Set operation state for VS Code UI (RBXSYNC-77)
     if let Some(ref project_dir) = req.project_dir {
-        if !project_dir.is_empty() {
-            let mut ops = state.operation_state.write().await;
-            ops.insert(project_dir.clone(), OperationInfo {
-                op_type: OperationType::Extract,
-                project_dir: project_dir.clone(),
-                start_time: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_millis() as u64)
-                    .unwrap_or(0),
-                progress: Some("Starting extraction...".to_string()),
-            });
-        }
+        state.start_operation(project_dir, OperationType::Extract, Some("Starting extraction...".to_string())).await;
     }
 
     // Pause live sync during extraction to avoid syncing back files we just extracted
@@ -1295,53 +1656,27 @@ async fn handle_extract_start(
         }
     }
 
-    // Clear existing src folder before extraction to remove stale files (Fixes RBXSYNC-27)
+    // src stays in place until finalize swaps in the extracted tree, so stale
+    // files don't survive (RBXSYNC-27) and an aborted extraction changes nothing.
+    // Finish or discard whatever an earlier crash left half done first.
     if let Some(ref project_dir) = req.project_dir {
         if !project_dir.is_empty() {
-            let src_dir = PathBuf::from(project_dir).join("src");
-
-            if src_dir.exists() {
-                let backup_dir = PathBuf::from(project_dir).join(".rbxsync-backup");
-                let backup_src = backup_dir.join("src");
-
-                // Remove old backup if exists
-                if backup_src.exists() {
-                    let _ = std::fs::remove_dir_all(&backup_src);
-                }
-
-                // Create backup directory
-                let _ = std::fs::create_dir_all(&backup_dir);
-
-                // Move src to backup (rename is atomic and fast)
-                if let Err(e) = std::fs::rename(&src_dir, &backup_src) {
-                    // If rename fails (cross-device), fall back to copy+delete
-                    tracing::warn!("Rename failed, falling back to copy: {}", e);
-                    if let Err(e) = copy_dir_recursive(&src_dir, &backup_src) {
-                        tracing::warn!("Failed to backup src directory: {}", e);
-                    }
-                    // Delete original src after backup
-                    let _ = std::fs::remove_dir_all(&src_dir);
-                }
-
-                tracing::info!("Cleared src folder before extraction (backed up to .rbxsync-backup/src)");
-            }
-
-            // Create fresh src directory
-            let _ = std::fs::create_dir_all(&src_dir);
+            let project_path = PathBuf::from(project_dir);
+            let _ = tokio::task::spawn_blocking(move || atomic_fs::recover(&project_path)).await;
         }
     }
 
     // Queue request to plugin
-    let plugin_request = PluginRequest {
-        id: session_uuid,
-        command: "extract:start".to_string(),
-        payload: serde_json::json!({
+    let plugin_request = PluginRequest::new(
+        session_uuid,
+        "extract:start",
+        serde_json::json!({
             "project_dir": req.project_dir,
             "services": req.services.unwrap_or_default(),
             "extractTerrain": req.include_terrain.unwrap_or(false),
             "includeAssets": req.include_assets.unwrap_or(true),
         }),
-    };
+    );
 
     {
         let mut queue = state.request_queue.lock().await;
@@ -1367,14 +1702,14 @@ pub struct ExtractChunkRequest {
 
 async fn handle_extract_chunk(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<ExtractChunkRequest>,
+    Json(mut req): Json<ExtractChunkRequest>,
 ) -> impl IntoResponse {
     let mut session_guard = state.extraction_session.write().await;
 
-    // Determine output directory: use project_dir/src if provided, otherwise fallback
+    // Determine output directory: the project's staging area if provided, otherwise fallback
     let output_dir = if let Some(ref project_dir) = req.project_dir {
         if !project_dir.is_empty() {
-            format!("{}/src", project_dir)
+            format!("{}/{}/chunks", project_dir, atomic_fs::STAGING_DIR)
         } else {
             format!(".rbxsync/extract_{}", &req.session_id)
         }
@@ -1395,6 +1730,12 @@ async fn handle_extract_chunk(
             total_chunks: None,
             data: Vec::new(),
             finalized: false,
+            data_files: HashMap::new(),
+            bytes_received: 0,
+            force: false,
+            limits: limits::for_project(req.project_dir.as_deref()),
+            limit_error: None,
+            terrain_file: None,
         });
     }
 
@@ -1405,17 +1746,60 @@ async fn handle_extract_chunk(
             session.id = req.session_id.clone();
             session.chunks_received = 0;
             session.data.clear();
+            session.bytes_received = 0;
+            session.limits = limits::for_project(req.project_dir.as_deref());
+            session.limit_error = None;
+            session.terrain_file = None;
 
             // Create new output directory
             let _ = std::fs::create_dir_all(&output_dir);
         }
 
+        if let Some(ref error) = session.limit_error {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(serde_json::json!({ "success": false, "error": error })),
+            );
+        }
+        // An oversized instance is left out of the extraction rather than failing it
+        let mut skipped = Vec::new();
+        if !session.force {
+            let limits = session.limits;
+            let count = req.data.as_array().map_or(0, Vec::len);
+            // Not kept on the session: the plugin resends the chunk in smaller pieces
+            if let Err(err) = limits::check_count(&limits, count, "instances") {
+                drop(session_guard);
+                limits::report(&state, "extract", "warn", &err).await;
+                return err.response();
+            }
+            if let Some(instances) = req.data.as_array_mut() {
+                instances.retain(|inst| match limits::check_instance(&limits, inst) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        skipped.push(err);
+                        false
+                    }
+                });
+            }
+            let chunk_size = serde_json::to_string(&req.data).map_or(0, |s| s.len() as u64);
+            if let Err(err) = limits::check_extraction_size(&limits, session.bytes_received + chunk_size) {
+                session.limit_error = Some(err.message.clone());
+                drop(session_guard);
+                limits::report(&state, "extract", "error", &err).await;
+                return err.response();
+            }
+        }
+        let chunk_json = serde_json::to_string(&req.data).unwrap_or_default();
+        session.bytes_received += chunk_json.len() as u64;
+
         session.total_chunks = Some(req.total_chunks);
         session.chunks_received += 1;
 
         // Save chunk to disk immediately
         let chunk_path = format!("{}/chunk_{:06}.json", output_dir, session.chunks_received);
-        if let Err(e) = std::fs::write(&chunk_path, serde_json::to_string(&req.data).unwrap_or_default()) {
+        state.metrics.extraction_chunk(&session.id, chunk_json.len());
+        let _ = std::fs::create_dir_all(&output_dir);
+        if let Err(e) = std::fs::write(&chunk_path, chunk_json) {
             tracing::warn!("Failed to save chunk to disk: {}", e);
         }
 
@@ -1424,11 +1808,18 @@ async fn handle_extract_chunk(
 
         tracing::info!("Received chunk {}/{}", session.chunks_received, req.total_chunks);
 
+        let received = session.chunks_received;
+        drop(session_guard);
+        for err in &skipped {
+            limits::report(&state, "extract", "warn", err).await;
+        }
+
         (
             StatusCode::OK,
             Json(serde_json::json!({
-                "received": session.chunks_received,
-                "total": req.total_chunks
+                "received": received,
+                "total": req.total_chunks,
+                "skipped": skipped.iter().map(|err| &err.message).collect::<Vec<_>>()
             })),
         )
     } else {
@@ -1491,7 +1882,7 @@ async fn handle_extract_export(
             "instances": all_instances,
         });
 
-        match std::fs::write(&req.output_path, serde_json::to_string_pretty(&output).unwrap()) {
+        match atomic_fs::write(std::path::Path::new(&req.output_path), serde_json::to_string_pretty(&output).unwrap()) {
             Ok(_) => {
                 tracing::info!("Export complete: {}", req.output_path);
                 (
@@ -1591,7 +1982,7 @@ fn generate_tooling_files(project_dir: &str, service_folders: &HashSet<String>,
     let project_json_path = project_path.join("default.project.json");
     if !project_json_path.exists() && src_dir.exists() {
         if let Ok(project_json) = generate_project_json(&project_name, &src_dir, service_folders) {
-            match std::fs::write(&project_json_path, project_json) {
+            match atomic_fs::write(&project_json_path, project_json) {
                 Ok(_) => tracing::info!("Generated default.project.json"),
                 Err(e) => tracing::warn!("Failed to write default.project.json: {}", e),
             }
@@ -1603,7 +1994,7 @@ fn generate_tooling_files(project_dir: &str, service_folders: &HashSet<String>,
     if !selene_toml_path.exists() {
         let selene_content = r#"std = "roblox"
 "#;
-        match std::fs::write(&selene_toml_path, selene_content) {
+        match atomic_fs::write(&selene_toml_path, selene_content) {
             Ok(_) => tracing::info!("Generated selene.toml"),
             Err(e) => tracing::warn!("Failed to write selene.toml: {}", e),
         }
@@ -1629,7 +2020,7 @@ realm = "shared"
 "#,
             sanitized_name
         );
-        match std::fs::write(&wally_toml_path, wally_content) {
+        match atomic_fs::write(&wally_toml_path, wally_content) {
             Ok(_) => tracing::info!("Generated wally.toml"),
             Err(e) => tracing::warn!("Failed to write wally.toml: {}", e),
         }
@@ -1726,12 +2117,26 @@ async fn handle_extract_finalize(
     }
 
     let session = session_guard.as_ref().unwrap();
+    let session_id = session.id.clone();
     let src_dir = PathBuf::from(&req.project_dir).join("src");
 
+    // A refused chunk means the extracted tree is incomplete
+    if let Some(error) = session.limit_error.clone() {
+        drop(session_guard);
+        return finalize_failed(
+            &state,
+            &req.project_dir,
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("{}; src was not changed", error),
+        )
+        .await;
+    }
+
     // Load project config and tree mapping
     let config = load_project_config(&req.project_dir);
     let tree_mapping = get_tree_mapping(&config);
     tracing::info!("Tree mapping loaded: {:?}", tree_mapping);
+    let source_format = rbxsync_core::SourceFormat::from_project_config(config.as_ref());
 
     // Check package preservation settings from config JSON
     let (preserve_packages, packages_folder) = if let Some(ref cfg) = config {
@@ -1757,46 +2162,37 @@ async fn handle_extract_finalize(
         tracing::info!("Package preservation enabled - Packages folder: {}", packages_folder);
     }
 
-    // Backup existing src directory before clearing (for undo support)
-    let backup_dir = PathBuf::from(&req.project_dir).join(".rbxsync-backup");
-    let backup_src = backup_dir.join("src");
-
-    // IMPORTANT: Back up terrain data BEFORE any directory operations
-    // Terrain is saved during extraction and must survive the src clear
-    let terrain_file = src_dir.join("Workspace").join("Terrain").join("terrain.rbxjson");
-    let terrain_data = if terrain_file.exists() {
-        tracing::info!("Backing up terrain.rbxjson before finalize");
-        std::fs::read_to_string(&terrain_file).ok()
+    // Data files are written back in their own format. Sessions started
+    // without a project dir never indexed them, so look at src now.
+    let data_files = if session.data_files.is_empty() {
+        index_data_files(&src_dir)
     } else {
-        None
+        session.data_files.clone()
     };
+    let model_roots: Vec<String> = data_files
+        .iter()
+        .filter(|(_, file)| file.rel_path.ends_with(".model.json"))
+        .map(|(inst_path, _)| format!("{}/", inst_path))
+        .collect();
 
-    if src_dir.exists() {
-        // Remove old backup if exists
-        if backup_src.exists() {
-            let _ = std::fs::remove_dir_all(&backup_src);
-        }
-        // Create backup directory
-        let _ = std::fs::create_dir_all(&backup_dir);
-        // Move src to backup (rename is atomic and fast)
-        if let Err(e) = std::fs::rename(&src_dir, &backup_src) {
-            // If rename fails (cross-device), fall back to copy+delete
-            tracing::warn!("Rename failed, falling back to copy: {}", e);
-            if let Err(e) = copy_dir_recursive(&src_dir, &backup_src) {
-                tracing::warn!("Failed to backup src directory: {}", e);
-            }
-
-            for entry in std::fs::read_dir(&src_dir).unwrap_or_else(|_| std::fs::read_dir(".").unwrap()).flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    let _ = std::fs::remove_dir_all(&path);
-                } else {
-                    let _ = std::fs::remove_file(&path);
-                }
-            }
+    // The new tree is built in .rbxsync-staging/src and swapped in only once
+    // every file is on disk, so a crash or full disk leaves src untouched.
+    // The current src then moves to .rbxsync-backup/src (for undo support).
+    let staged = match atomic_fs::StagedDir::begin(&req.project_dir, "src") {
+        Ok(staged) => staged,
+        Err(e) => {
+            drop(session_guard);
+            return finalize_failed(&state, &req.project_dir, StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create staging directory: {}", e)).await;
         }
-        tracing::info!("Backed up src to .rbxsync-backup/src");
-    }
+    };
+    let stage_dir = staged.path().to_path_buf();
+
+    // Only terrain received in this session goes into the new tree; an
+    // extraction without terrain drops the old terrain like any other file
+    let terrain_data = session
+        .terrain_file
+        .as_ref()
+        .and_then(|file| std::fs::read_to_string(file).ok());
 
     // Flatten all chunks into a single array of instances
     let mut all_instances: Vec<serde_json::Value> = Vec::new();
@@ -1806,18 +2202,14 @@ async fn handle_extract_finalize(
         }
     }
 
-    tracing::info!("Finalizing {} instances to {}", all_instances.len(), src_dir.display());
+    tracing::info!("Finalizing {} instances to {} (staged in {})", all_instances.len(), src_dir.display(), stage_dir.display());
 
-    // Create src directory
-    let _ = std::fs::create_dir_all(&src_dir);
-
-    // Restore terrain data that was backed up before clearing
+    let mut terrain_failed = false;
     if let Some(data) = terrain_data {
-        let terrain_dir = src_dir.join("Workspace").join("Terrain");
-        let _ = std::fs::create_dir_all(&terrain_dir);
-        if std::fs::write(terrain_dir.join("terrain.rbxjson"), &data).is_ok() {
-            tracing::info!("Restored terrain.rbxjson after finalize");
-        }
+        let terrain_dir = stage_dir.join("Workspace").join("Terrain");
+        terrain_failed = std::fs::create_dir_all(&terrain_dir)
+            .and_then(|()| atomic_fs::write_synced(&terrain_dir.join("terrain.rbxjson"), &data))
+            .is_err();
     }
 
     // Track which services we've seen to create folders for them
@@ -1902,11 +2294,8 @@ async fn handle_extract_finalize(
     // Maximum concurrent file writes to prevent overwhelming the filesystem
     const MAX_CONCURRENT_WRITES: usize = 64;
 
-    // Struct to hold pending write operations
-    struct WriteOp {
-        path: PathBuf,
-        content: String,
-    }
+    // Pending write operations
+    use writer::FileWrite as WriteOp;
 
     // First pass: Collect all directories needed and prepare write operations
     let mut directories_needed: HashSet<PathBuf> = HashSet::new();
@@ -1917,8 +2306,6 @@ async fn handle_extract_finalize(
     let prep_start = std::time::Instant::now();
 
     for inst in &all_instances {
-        let class_name = inst.get("className").and_then(|v| v.as_str()).unwrap_or("Unknown");
-
         // Use disambiguated path from ref_to_path map to handle duplicate instance names
         let ref_id = inst.get("referenceId").and_then(|v| v.as_str()).unwrap_or("");
         let inst_path = if !ref_id.is_empty() {
@@ -1937,68 +2324,53 @@ async fn handle_extract_finalize(
         let fs_path = apply_tree_mapping(&inst_path, &tree_mapping);
 
         // Use mapped path for filesystem operations
-        let full_path = src_dir.join(&fs_path);
+        let full_path = stage_dir.join(&fs_path);
 
         // Track service name (first segment of mapped path) for folder creation
         if let Some(service_name) = fs_path.split('/').next() {
             service_folders.insert(service_name.to_string());
         }
 
+        // A .model.json holds its whole subtree
+        let fs_key = rbxsync_core::normalize_path(&fs_path);
+        if model_roots.iter().any(|root| fs_key.starts_with(root)) {
+            continue;
+        }
+
         // Collect parent directory instead of creating immediately
         if let Some(parent) = full_path.parent() {
             directories_needed.insert(parent.to_path_buf());
         }
 
-        // Check if this instance has children (use normalized path)
-        let is_container = has_children(&inst_path);
-
-        // Check if this is a script with source
-        let is_script = matches!(class_name, "Script" | "LocalScript" | "ModuleScript");
-
-        if is_script {
-            // Prepare script source write operation
-            if let Some(props) = inst.get("properties") {
-                if let Some(source) = props.get("Source").and_then(|v| v.get("value")).and_then(|v| v.as_str()) {
-                    let extension = match class_name {
-                        "Script" => ".server.luau",
-                        "LocalScript" => ".client.luau",
-                        _ => ".luau",
-                    };
-                    let script_path = rbxsync_core::path_with_suffix(&full_path, extension);
-                    script_write_ops.push(WriteOp {
-                        path: PathBuf::from(script_path),
-                        content: source.to_string(),
-                    });
-                }
+        // Keep data files in their own format
+        if let Some(file) = data_files.get(&fs_key) {
+            if let Some(content) = extracted_data_file_content(inst, file) {
+                script_write_ops.push(WriteOp {
+                    path: stage_dir.join(&file.rel_path),
+                    content,
+                });
+                continue;
             }
         }
 
-        // Prepare .rbxjson file write operation
-        let json_path = if is_container {
-            // Container: folder will be created, put _meta.rbxjson inside
+        // Check if this instance has children (use normalized path)
+        let is_container = has_children(&inst_path);
+        if is_container {
+            // Container: folder will be created, _meta.rbxjson goes inside
             directories_needed.insert(full_path.clone());
-            full_path.join("_meta.rbxjson")
-        } else {
-            // Leaf: write as sibling .rbxjson
-            rbxsync_core::pathbuf_with_suffix(&full_path, ".rbxjson")
-        };
-
-        // Create a clean instance object without source (for scripts)
-        let mut clean_inst = inst.clone();
-        if is_script {
-            if let Some(props) = clean_inst.get_mut("properties") {
-                if let Some(obj) = props.as_object_mut() {
-                    obj.remove("Source");
-                }
-            }
         }
 
-        if let Ok(json) = serde_json::to_string_pretty(&clean_inst) {
-            json_write_ops.push(WriteOp {
-                path: json_path,
-                content: json,
-            });
+        let mut files = writer::instance_files(inst, &full_path, is_container);
+        if let Some(source) = files.source.as_mut() {
+            // Preserved line endings / BOM come from the file being replaced
+            let existing = source_format
+                .uses_existing()
+                .then(|| std::fs::read_to_string(src_dir.join(source.path.strip_prefix(&stage_dir).unwrap_or(&source.path))).ok())
+                .flatten();
+            source.content = source_format.to_disk(&source.content, existing.as_deref());
         }
+        script_write_ops.extend(files.source);
+        json_write_ops.extend(files.json);
     }
 
     tracing::info!(
@@ -2014,12 +2386,11 @@ async fn handle_extract_finalize(
     let dir_count = dirs_to_create.len();
 
     let dir_start = std::time::Instant::now();
-    tokio::task::spawn_blocking(move || {
-        for dir in dirs_to_create {
-            let _ = std::fs::create_dir_all(&dir);
-        }
+    let dir_failures = tokio::task::spawn_blocking(move || {
+        dirs_to_create.iter().filter(|dir| std::fs::create_dir_all(dir).is_err()).count()
     }).await.unwrap_or_else(|e| {
         tracing::error!("Failed to create directories: {}", e);
+        dir_count
     });
     tracing::info!("Created {} directories in {:?}", dir_count, dir_start.elapsed());
 
@@ -2030,7 +2401,9 @@ async fn handle_extract_finalize(
     let script_count = script_write_ops.len();
     let script_results: Vec<bool> = stream::iter(script_write_ops)
         .map(|op| async move {
-            tokio::fs::write(&op.path, &op.content).await.is_ok()
+            tokio::task::spawn_blocking(move || atomic_fs::write_synced(&op.path, &op.content).is_ok())
+                .await
+                .unwrap_or(false)
         })
         .buffer_unordered(MAX_CONCURRENT_WRITES)
         .collect()
@@ -2041,7 +2414,9 @@ async fn handle_extract_finalize(
     let json_count = json_write_ops.len();
     let json_results: Vec<bool> = stream::iter(json_write_ops)
         .map(|op| async move {
-            tokio::fs::write(&op.path, &op.content).await.is_ok()
+            tokio::task::spawn_blocking(move || atomic_fs::write_synced(&op.path, &op.content).is_ok())
+                .await
+                .unwrap_or(false)
         })
         .buffer_unordered(MAX_CONCURRENT_WRITES)
         .collect()
@@ -2053,39 +2428,36 @@ async fn handle_extract_finalize(
         scripts_written, files_written, write_start.elapsed(), MAX_CONCURRENT_WRITES
     );
 
-    // Log if there were any failures
+    // Any failure (e.g. a full disk) keeps the current src
     let script_failures = script_count - scripts_written;
     let json_failures = json_count - files_written;
-    if script_failures > 0 || json_failures > 0 {
-        tracing::warn!(
-            "Write failures: {} scripts, {} json files",
-            script_failures, json_failures
-        );
-    }
-
-    // Clean up chunk files
-    if let Ok(entries) = std::fs::read_dir(&src_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with("chunk_") && name.ends_with(".json") {
-                    let _ = std::fs::remove_file(path);
-                }
-            }
-        }
+    if script_failures > 0 || json_failures > 0 || dir_failures > 0 || terrain_failed {
+        staged.abandon();
+        drop(session_guard);
+        return finalize_failed(
+            &state,
+            &req.project_dir,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Write failures: {} scripts, {} json files, {} directories{}; src was not changed",
+                script_failures,
+                json_failures,
+                dir_failures,
+                if terrain_failed { ", terrain" } else { "" }
+            ),
+        )
+        .await;
     }
 
     // Create service folders even if they're empty
     for service in &service_folders {
-        let service_folder = src_dir.join(service);
-        // Create the folder if it doesn't exist
-        let _ = std::fs::create_dir_all(&service_folder);
+        let _ = std::fs::create_dir_all(stage_dir.join(service));
     }
 
-    // Restore Packages folder from backup if preservation is enabled
+    // Carry the Packages folder over from the current src if preservation is enabled
     let mut packages_preserved = false;
     if preserve_packages {
-        // Look for Packages folders in common locations within backup
+        // Look for Packages folders in common locations within the current src
         let package_restore_locations: Vec<(String, String)> = vec![
             ("ReplicatedStorage/Packages".to_string(), "ReplicatedStorage/Packages".to_string()),
             ("ServerScriptService/Packages".to_string(), "ServerScriptService/Packages".to_string()),
@@ -2094,11 +2466,11 @@ async fn handle_extract_finalize(
             (packages_folder.clone(), packages_folder.clone()),
         ];
 
-        for (backup_rel, dest_rel) in &package_restore_locations {
-            let backup_packages = backup_src.join(backup_rel);
-            let dest_packages = src_dir.join(dest_rel);
+        for (current_rel, dest_rel) in &package_restore_locations {
+            let current_packages = src_dir.join(current_rel);
+            let dest_packages = stage_dir.join(dest_rel);
 
-            if backup_packages.exists() && backup_packages.is_dir() {
+            if current_packages.is_dir() {
                 // Remove any extracted packages (from Studio) to replace with local
                 if dest_packages.exists() {
                     let _ = std::fs::remove_dir_all(&dest_packages);
@@ -2109,17 +2481,29 @@ async fn handle_extract_finalize(
                     let _ = std::fs::create_dir_all(parent);
                 }
 
-                // Restore packages from backup
-                if let Err(e) = copy_dir_recursive(&backup_packages, &dest_packages) {
-                    tracing::warn!("Failed to restore packages from {}: {}", backup_rel, e);
+                // Copy local packages into the new tree
+                if let Err(e) = copy_dir_recursive(&current_packages, &dest_packages) {
+                    tracing::warn!("Failed to preserve packages from {}: {}", current_rel, e);
                 } else {
-                    tracing::info!("Restored Wally packages from backup: {}", backup_rel);
+                    tracing::info!("Preserved Wally packages: {}", current_rel);
                     packages_preserved = true;
                 }
             }
         }
     }
 
+    let swap = tokio::task::spawn_blocking(move || staged.commit())
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|result| result);
+    if let Err(e) = swap {
+        drop(session_guard);
+        return finalize_failed(&state, &req.project_dir, StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to swap in extracted src: {}", e)).await;
+    }
+    let _ = std::fs::remove_dir_all(PathBuf::from(&req.project_dir).join(atomic_fs::STAGING_DIR).join("chunks"));
+    let _ = std::fs::remove_dir_all(PathBuf::from(&req.project_dir).join(atomic_fs::STAGING_DIR).join("terrain"));
+    tracing::info!("Swapped in extracted src (previous tree in {}/src)", atomic_fs::BACKUP_DIR);
+
     tracing::info!(
         "Finalize complete: {} .rbxjson files, {} .luau scripts, {} services{}",
         files_written,
@@ -2169,10 +2553,8 @@ async fn handle_extract_finalize(
     }
 
     // Clear operation state for VS Code UI (RBXSYNC-77)
-    {
-        let mut ops = state.operation_state.write().await;
-        ops.remove(&req.project_dir);
-    }
+    state.metrics.extraction_finished(&session_id, all_instances.len());
+    state.finish_operation(&req.project_dir, true).await;
 
     (
         StatusCode::OK,
@@ -2185,6 +2567,26 @@ async fn handle_extract_finalize(
     )
 }
 
+/// End a failed finalize: src is left as it was and live sync resumes. The
+/// session is kept so finalize can be retried.
+async fn finalize_failed(
+    state: &Arc<AppState>,
+    project_dir: &str,
+    status: StatusCode,
+    error: String,
+) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!("Finalize failed for {}: {}", project_dir, error);
+    state.live_sync_paused.store(false, std::sync::atomic::Ordering::Relaxed);
+    state.finish_operation(project_dir, false).await;
+    (
+        status,
+        Json(serde_json::json!({
+            "success": false,
+            "error": error
+        })),
+    )
+}
+
 /// Terrain extraction request
 #[derive(Debug, Deserialize)]
 pub struct TerrainRequest {
@@ -2196,10 +2598,14 @@ pub struct TerrainRequest {
 }
 
 /// Handle terrain data from extraction (supports batched uploads)
-async fn handle_extract_terrain(Json(req): Json<TerrainRequest>) -> impl IntoResponse {
+async fn handle_extract_terrain(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<TerrainRequest>,
+) -> impl IntoResponse {
     tracing::info!("Received terrain data for project: {}", req.project_dir);
-    let terrain_dir = PathBuf::from(&req.project_dir).join("src").join("Workspace").join("Terrain");
-    tracing::info!("Terrain directory: {}", terrain_dir.display());
+    // Batches are staged and only reach src when finalize swaps in the new tree
+    let terrain_dir = PathBuf::from(&req.project_dir).join(atomic_fs::STAGING_DIR).join("terrain");
+    tracing::info!("Terrain staging directory: {}", terrain_dir.display());
 
     // Create terrain directory
     if let Err(e) = std::fs::create_dir_all(&terrain_dir) {
@@ -2216,8 +2622,41 @@ async fn handle_extract_terrain(Json(req): Json<TerrainRequest>) -> impl IntoRes
     let batch_index = req.batch_index.unwrap_or(1);
     let total_batches = req.total_batches.unwrap_or(1);
 
-    // For batched uploads, merge with existing data
-    let final_terrain = if batch_index == 1 {
+    // Terrain counts toward the extraction's size; each batch is capped like a file
+    {
+        let mut session_guard = state.extraction_session.write().await;
+        let batch_size = serde_json::to_string(&req.terrain).map(|s| s.len() as u64).unwrap_or(0);
+        let force = session_guard.as_ref().is_some_and(|s| s.force);
+        if !force {
+            let limits = match *session_guard {
+                Some(ref session) => session.limits,
+                None => limits::for_project(Some(&req.project_dir)),
+            };
+            let received = session_guard.as_ref().map_or(0, |s| s.bytes_received);
+            let checked = limits::check_file_size(&limits, &format!("Terrain batch {}", batch_index), batch_size)
+                .and_then(|()| limits::check_extraction_size(&limits, received + batch_size));
+            if let Err(err) = checked {
+                if let Some(ref mut session) = *session_guard {
+                    session.limit_error = Some(err.message.clone());
+                }
+                drop(session_guard);
+                limits::report(&state, "extract", "error", &err).await;
+                return err.response();
+            }
+        }
+        if let Some(ref mut session) = *session_guard {
+            session.bytes_received += batch_size;
+        }
+    }
+
+    // For batched uploads, merge with the batches of this session
+    let staged_earlier = state
+        .extraction_session
+        .read()
+        .await
+        .as_ref()
+        .is_some_and(|session| session.terrain_file.as_ref() == Some(&terrain_file));
+    let final_terrain = if batch_index == 1 || !staged_earlier {
         // First batch - use as base
         req.terrain.clone()
     } else {
@@ -2256,7 +2695,7 @@ async fn handle_extract_terrain(Json(req): Json<TerrainRequest>) -> impl IntoRes
         }
     };
 
-    if let Err(e) = std::fs::write(&terrain_file, terrain_json) {
+    if let Err(e) = atomic_fs::write(&terrain_file, &terrain_json) {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({
@@ -2265,6 +2704,9 @@ async fn handle_extract_terrain(Json(req): Json<TerrainRequest>) -> impl IntoRes
             })),
         );
     }
+    if let Some(ref mut session) = *state.extraction_session.write().await {
+        session.terrain_file = Some(terrain_file.clone());
+    }
 
     let chunk_count = final_terrain.get("chunks")
         .and_then(|c| c.as_array())
@@ -2307,11 +2749,9 @@ async fn handle_sync_command(
     }
 
     // Queue request to plugin
-    let plugin_request = PluginRequest {
-        id: request_id,
-        command: req.command.clone(),
-        payload: req.payload,
-    };
+    let plugin_request = PluginRequest::new(request_id, req.command.clone(), req.payload);
+    let timeout = state.timeout_for(&req.command).await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     {
         let mut queue = state.request_queue.lock().await;
@@ -2321,8 +2761,7 @@ async fn handle_sync_command(
 
     tracing::info!("Sent sync command: {} ({})", req.command, request_id);
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(30);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     let result = tokio::time::timeout(timeout, rx.recv()).await;
 
     // Clean up channel
@@ -2344,6 +2783,7 @@ async fn handle_sync_command(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             tracing::warn!("Timeout waiting for response: {}", request_id);
             (
                 StatusCode::GATEWAY_TIMEOUT,
@@ -2360,6 +2800,12 @@ pub struct SyncBatchRequest {
     /// Optional project directory for operation tracking (RBXSYNC-77)
     #[serde(rename = "projectDir")]
     pub project_dir: Option<String>,
+    /// Roll back the whole batch in Studio if any operation fails
+    #[serde(default)]
+    pub atomic: bool,
+    /// Skip the project's size limits
+    #[serde(default)]
+    pub force: bool,
 }
 
 /// Handle sync batch - sends batch of operations to plugin
@@ -2367,22 +2813,24 @@ async fn handle_sync_batch(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SyncBatchRequest>,
 ) -> impl IntoResponse {
+    if !req.force {
+        let limits = limits::for_project(req.project_dir.as_deref());
+        let checked = limits::check_count(&limits, req.operations.len(), "operations").and_then(|()| {
+            req.operations
+                .iter()
+                .try_for_each(|op| limits::check_instance(&limits, op.get("data").unwrap_or(op)))
+        });
+        if let Err(err) = checked {
+            limits::report(&state, "sync", "error", &err).await;
+            return err.response();
+        }
+    }
+
     let request_id = Uuid::new_v4();
 
     // Set operation state for VS Code UI (RBXSYNC-77)
     if let Some(ref project_dir) = req.project_dir {
-        if !project_dir.is_empty() {
-            let mut ops = state.operation_state.write().await;
-            ops.insert(project_dir.clone(), OperationInfo {
-                op_type: OperationType::Sync,
-                project_dir: project_dir.clone(),
-                start_time: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_millis() as u64)
-                    .unwrap_or(0),
-                progress: Some(format!("Syncing {} operations...", req.operations.len())),
-            });
-        }
+        state.start_operation(project_dir, OperationType::Sync, Some(format!("Syncing {} operations...", req.operations.len()))).await;
     }
 
     // Create response channel
@@ -2393,13 +2841,16 @@ async fn handle_sync_batch(
     }
 
     // Queue batch request to plugin
-    let plugin_request = PluginRequest {
-        id: request_id,
-        command: "sync:batch".to_string(),
-        payload: serde_json::json!({
-            "operations": req.operations
+    let plugin_request = PluginRequest::new(
+        request_id,
+        "sync:batch",
+        serde_json::json!({
+            "operations": req.operations,
+            "atomic": req.atomic
         }),
-    };
+    );
+    let timeout = state.timeout_for("sync:batch").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     {
         let mut queue = state.request_queue.lock().await;
@@ -2409,8 +2860,7 @@ async fn handle_sync_batch(
 
     tracing::info!("Sent sync batch with {} operations ({})", req.operations.len(), request_id);
 
-    // Wait for response with longer timeout for batch operations
-    let timeout = tokio::time::Duration::from_secs(300); // 5 minutes for large batches
+    // Wait for response (cancelled on timeout or if the client disconnects)
     let result = tokio::time::timeout(timeout, rx.recv()).await;
 
     // Clean up channel
@@ -2421,8 +2871,8 @@ async fn handle_sync_batch(
 
     // Clear operation state for VS Code UI (RBXSYNC-77)
     if let Some(ref project_dir) = req.project_dir {
-        let mut ops = state.operation_state.write().await;
-        ops.remove(project_dir);
+        let success = matches!(&result, Ok(Some(response)) if response.success);
+        state.finish_operation(project_dir, success).await;
     }
 
     match result {
@@ -2437,6 +2887,7 @@ async fn handle_sync_batch(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             (
                 StatusCode::GATEWAY_TIMEOUT,
                 Json(serde_json::json!({"error": "Timeout waiting for plugin response"})),
@@ -2445,12 +2896,191 @@ async fn handle_sync_batch(
     }
 }
 
+/// Recent live-sync batches from the file watcher, newest first
+async fn handle_sync_batches(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SyncBatchesQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(20).min(live_sync::BATCH_HISTORY);
+    let batches = state.live_sync_batches.recent(limit);
+    Json(serde_json::json!({
+        "batches": batches,
+        "count": batches.len()
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncBatchesQuery {
+    limit: Option<usize>,
+}
+
+/// A single live-sync batch with per-operation results
+async fn handle_sync_batch_status(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.live_sync_batches.get(id) {
+        Some(batch) => (StatusCode::OK, Json(serde_json::to_value(&batch).unwrap_or_default())),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("Unknown batch {}", id) })),
+        ),
+    }
+}
+
+/// HEAD changes waiting to be applied to Studio
+async fn handle_branch_switches(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let switches = state.branch_switches.list();
+    Json(serde_json::json!({
+        "switches": switches,
+        "count": switches.len()
+    }))
+}
+
+/// Apply or discard a pending branch switch
+#[derive(Debug, Deserialize)]
+pub struct BranchSwitchRequest {
+    pub project_dir: String,
+    /// "apply" (default) or "discard"
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+async fn handle_branch_switch(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BranchSwitchRequest>,
+) -> impl IntoResponse {
+    match req.action.as_deref().unwrap_or("apply") {
+        "apply" => match branch_switch::apply(&state, &req.project_dir).await {
+            Ok(Some((request_id, diff))) => (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "success": true,
+                    "data": { "requestId": request_id, "diff": diff }
+                })),
+            ),
+            Ok(None) => (
+                StatusCode::OK,
+                Json(serde_json::json!({ "success": true, "data": { "requestId": null } })),
+            ),
+            Err(e) => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "success": false, "error": e })),
+            ),
+        },
+        "discard" => {
+            if branch_switch::discard(&state, &req.project_dir).await {
+                (StatusCode::OK, Json(serde_json::json!({ "success": true })))
+            } else {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({
+                        "success": false,
+                        "error": format!("No branch switch pending for {}", req.project_dir)
+                    })),
+                )
+            }
+        }
+        other => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "success": false,
+                "error": format!("Unknown action '{}' (expected apply or discard)", other)
+            })),
+        ),
+    }
+}
+
+/// Live Studio→disk changes from the plugin's ChangeTracker
+#[derive(Debug, Deserialize)]
+pub struct StudioStreamRequest {
+    pub operations: Vec<StudioChangeOperation>,
+    #[serde(rename = "projectDir")]
+    pub project_dir: String,
+    /// Write everything pending now instead of waiting for changes to settle
+    #[serde(default)]
+    pub flush: bool,
+    /// Skip the project's size limits
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Queue Studio changes to be written to disk
+async fn handle_studio_stream(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<StudioStreamRequest>,
+) -> impl IntoResponse {
+    if !studio_stream::accepts_studio_changes(&load_project_config(&req.project_dir)) {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "success": false,
+                "error": "sync.mode is \"push\"; Studio changes are not written to disk"
+            })),
+        );
+    }
+    if !PathBuf::from(&req.project_dir).join("src").is_dir() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "success": false,
+                "error": format!("No src directory in {}", req.project_dir)
+            })),
+        );
+    }
+
+    if !req.force {
+        if let Err(err) = check_studio_changes(&req.project_dir, &req.operations) {
+            limits::report(&state, "sync", "error", &err).await;
+            return err.response();
+        }
+    }
+
+    let queued = req.operations.len();
+    let outcome = studio_stream::receive(&state, &req.project_dir, req.operations, req.flush).await;
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "success": outcome.errors.is_empty(),
+            "queued": queued,
+            "pending": state.studio_stream.pending_count(&req.project_dir),
+            "filesWritten": outcome.files_written,
+            "errors": outcome.errors
+        })),
+    )
+}
+
+/// Studio changes still waiting to be written, per project
+async fn handle_studio_stream_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "projects": state.studio_stream.status(),
+        "changes": state.metrics.studio_stream_changes.get(),
+        "coalesced": state.metrics.studio_stream_coalesced.get(),
+        "filesWritten": state.metrics.studio_stream_files_written.get()
+    }))
+}
+
 /// Sync changes from Studio back to files
 #[derive(Debug, Deserialize)]
 pub struct SyncFromStudioRequest {
     pub operations: Vec<StudioChangeOperation>,
     #[serde(rename = "projectDir")]
     pub project_dir: String,
+    /// Skip the project's size limits
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Check Studio changes against the project's size limits
+fn check_studio_changes(project_dir: &str, operations: &[StudioChangeOperation]) -> Result<(), limits::LimitExceeded> {
+    let limits = limits::for_project(Some(project_dir));
+    limits::check_count(&limits, operations.len(), "changes")?;
+    for op in operations {
+        if let Some(ref data) = op.data {
+            limits::check_instance(&limits, data)?;
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Deserialize)]
@@ -2464,7 +3094,10 @@ pub struct StudioChangeOperation {
 }
 
 /// Handle changes from Studio and write them to files
-async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl IntoResponse {
+async fn handle_sync_from_studio(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SyncFromStudioRequest>,
+) -> impl IntoResponse {
     tracing::info!("handle_sync_from_studio called with {} operations", req.operations.len());
     for (i, op) in req.operations.iter().enumerate() {
         tracing::info!("  Op {}: type={}, path={}, className={:?}, has_data={}",
@@ -2472,6 +3105,13 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
     }
     let src_dir = PathBuf::from(&req.project_dir).join("src");
 
+    if !req.force {
+        if let Err(err) = check_studio_changes(&req.project_dir, &req.operations) {
+            limits::report(&state, "sync", "error", &err).await;
+            return err.response();
+        }
+    }
+
     if !src_dir.exists() {
         return (
             StatusCode::BAD_REQUEST,
@@ -2485,6 +3125,7 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
     // Load project config and tree mapping
     let config = load_project_config(&req.project_dir);
     let tree_mapping = get_tree_mapping(&config);
+    let source_format = rbxsync_core::SourceFormat::from_project_config(config.as_ref());
 
     let mut files_written = 0;
     let mut errors: Vec<String> = Vec::new();
@@ -2503,12 +3144,14 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
                 for ext in luau_extensions {
                     let script_path = rbxsync_core::path_with_suffix(&full_path, ext);
                     if std::fs::remove_file(&script_path).is_ok() {
+                        state.echo_filter.record_delete(&script_path);
                         deleted_any = true;
                         tracing::info!("Studio sync: deleted {}", script_path);
                     }
                 }
                 let json_path = rbxsync_core::path_with_suffix(&full_path, ".rbxjson");
                 if std::fs::remove_file(&json_path).is_ok() {
+                    state.echo_filter.record_delete(&json_path);
                     deleted_any = true;
                     tracing::info!("Studio sync: deleted {}", json_path);
                 }
@@ -2516,6 +3159,7 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
                 // Try to delete as a directory (for Folder instances)
                 if full_path.is_dir()
                     && std::fs::remove_dir_all(&full_path).is_ok() {
+                        state.echo_filter.record_delete(&full_path);
                         deleted_any = true;
                         tracing::info!("Studio sync: deleted folder {:?}", full_path);
                     }
@@ -2547,6 +3191,8 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
                         if old_full_path.is_dir() {
                             match std::fs::rename(&old_full_path, &new_full_path) {
                                 Ok(_) => {
+                                    state.echo_filter.record_delete(&old_full_path);
+                                    state.echo_filter.record_tree(&new_full_path);
                                     tracing::info!("Studio sync: renamed folder {:?} -> {:?}", old_full_path, new_full_path);
                                     files_written += 1;
                                 }
@@ -2566,6 +3212,10 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
                                 if old_file.exists() {
                                     match std::fs::rename(&old_file, &new_file) {
                                         Ok(_) => {
+                                            state.echo_filter.record_delete(&old_file);
+                                            if let Ok(content) = std::fs::read(&new_file) {
+                                                state.echo_filter.record_write(&new_file, content);
+                                            }
                                             tracing::info!("Studio sync: renamed {:?} -> {:?}", old_file, new_file);
                                             renamed_any = true;
                                         }
@@ -2620,9 +3270,15 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
                                 _ => ".luau",
                             };
                             let script_path = rbxsync_core::path_with_suffix(&full_path, extension);
+                            let existing = source_format
+                                .uses_existing()
+                                .then(|| std::fs::read_to_string(&script_path).ok())
+                                .flatten();
+                            let source = source_format.to_disk(source, existing.as_deref());
 
-                            match std::fs::write(&script_path, source) {
+                            match atomic_fs::write(std::path::Path::new(&script_path), &source) {
                                 Ok(_) => {
+                                    state.echo_filter.record_write(&script_path, &source);
                                     tracing::info!("Studio sync: wrote {}", script_path);
                                     files_written += 1;
                                 }
@@ -2649,8 +3305,9 @@ async fn handle_sync_from_studio(Json(req): Json<SyncFromStudioRequest>) -> impl
 
                     let json_path = rbxsync_core::path_with_suffix(&full_path, ".rbxjson");
                     if let Ok(json) = serde_json::to_string_pretty(&clean_data) {
-                        match std::fs::write(&json_path, json) {
+                        match atomic_fs::write(std::path::Path::new(&json_path), &json) {
                             Ok(_) => {
+                                state.echo_filter.record_write(&json_path, &json);
                                 files_written += 1;
                             }
                             Err(e) => {
@@ -2685,10 +3342,7 @@ pub struct ReadTreeRequest {
 }
 
 async fn handle_sync_read_tree(Json(req): Json<ReadTreeRequest>) -> impl IntoResponse {
-    let project_dir = PathBuf::from(&req.project_dir);
-    let src_dir = project_dir.join("src");
-
-    if !src_dir.exists() {
+    if !PathBuf::from(&req.project_dir).join("src").exists() {
         return (
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
@@ -2698,8 +3352,27 @@ async fn handle_sync_read_tree(Json(req): Json<ReadTreeRequest>) -> impl IntoRes
         );
     }
 
+    let instances = read_tree_instances(&req.project_dir);
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "success": true,
+            "instances": instances,
+            "count": instances.len()
+        })),
+    )
+}
+
+/// Every instance described by the project's files (`src` plus Wally
+/// packages), with script sources merged into `properties.Source` and paths
+/// as they appear in Studio
+pub fn read_tree_instances(project_dir_str: &str) -> Vec<serde_json::Value> {
+    let project_dir = PathBuf::from(project_dir_str);
+    let src_dir = project_dir.join("src");
+
     // Load project config for package settings
-    let config = load_project_config(&req.project_dir);
+    let config = load_project_config(project_dir_str);
     let packages_config = config.as_ref().and_then(|c| c.get("packages"));
     let packages_folder = packages_config
         .and_then(|p| p.get("packagesFolder"))
@@ -2791,17 +3464,16 @@ async fn handle_sync_read_tree(Json(req): Json<ReadTreeRequest>) -> impl IntoRes
                                 instances.push(inst);
                             }
                         }
-                    } else if ext == "luau" {
+                    } else if ext == "luau" || ext == "lua" {
                         // Read script source
                         let rel_path = path.strip_prefix(base).unwrap_or(&path);
                         let path_str = rbxsync_core::path_to_string(rel_path);
                         // Keep '/' as delimiter (matches instance path format)
                         // e.g., "ServerScriptService/MyScript.server.luau" -> "ServerScriptService/MyScript"
-                        let rel_inst_path = path_str
-                            .trim_end_matches(".server.luau")
-                            .trim_end_matches(".client.luau")
-                            .trim_end_matches(".luau")
-                            .to_string();
+                        // e.g., "ReplicatedStorage/Lib/init.luau" -> "ReplicatedStorage/Lib"
+                        let Some(rel_inst_path) = rbxsync_core::file_instance_path(&path_str) else {
+                            continue;
+                        };
 
                         // Apply path prefix (for packages mapping to DataModel paths)
                         let inst_path = if path_prefix.is_empty() {
@@ -2813,8 +3485,24 @@ async fn handle_sync_read_tree(Json(req): Json<ReadTreeRequest>) -> impl IntoRes
                         // Normalize path to strip disambiguation suffixes (RBXSYNC-68)
                         let normalized_inst_path = normalize_path_for_comparison(&inst_path);
                         if let Ok(source) = std::fs::read_to_string(&path) {
-                            scripts.insert(normalized_inst_path, source);
+                            scripts.insert(normalized_inst_path, rbxsync_core::SourceFormat::to_studio(&source));
                         }
+                    } else {
+                        // Data files (.json, .toml, .yaml, .txt, .csv, .model.json)
+                        let rel_path = path.strip_prefix(base).unwrap_or(&path);
+                        let path_str = rbxsync_core::path_to_string(rel_path);
+                        let is_data_file = path.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| rbxsync_core::data_file_info(n).is_some());
+                        let Some(rel_inst_path) = rbxsync_core::file_instance_path(&path_str).filter(|_| is_data_file) else {
+                            continue;
+                        };
+                        let inst_path = if path_prefix.is_empty() {
+                            rel_inst_path
+                        } else {
+                            format!("{}/{}", path_prefix, rel_inst_path)
+                        };
+                        instances.extend(read_data_file_instances(&path, &normalize_path_for_comparison(&inst_path)));
                     }
                 }
             }
@@ -2856,14 +3544,105 @@ async fn handle_sync_read_tree(Json(req): Json<ReadTreeRequest>) -> impl IntoRes
 
     tracing::info!("Read {} instances from {}", instances.len(), src_dir.display());
 
-    (
-        StatusCode::OK,
-        Json(serde_json::json!({
-            "success": true,
-            "instances": instances,
-            "count": instances.len()
-        })),
-    )
+    instances
+}
+
+/// Read a data file (.json, .toml, .yaml, .txt, .csv, .model.json) as
+/// instances rooted at `inst_path`, with model children flattened
+fn read_data_file_instances(path: &std::path::Path, inst_path: &str) -> Vec<serde_json::Value> {
+    let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let parsed = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| rbxsync_core::parse_data_file(filename, &content).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(inst) => rbxsync_core::flatten_instance(&inst, inst_path)
+            .into_iter()
+            .map(|(path, mut inst)| {
+                inst["path"] = serde_json::Value::String(path);
+                inst
+            })
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Skipping data file {:?}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Data files under `src_dir`, keyed by the instance path they describe
+/// (relative to `src`, before tree mapping)
+fn index_data_files(src_dir: &std::path::Path) -> HashMap<String, ExtractedDataFile> {
+    let mut files = HashMap::new();
+    let mut stack = vec![src_dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if rbxsync_core::data_file_info(filename).is_none() {
+                continue;
+            }
+            let rel_path = rbxsync_core::path_to_string(path.strip_prefix(src_dir).unwrap_or(&path));
+            let (Some(inst_path), Ok(content)) = (
+                rbxsync_core::file_instance_path(&rel_path),
+                std::fs::read_to_string(&path),
+            ) else {
+                continue;
+            };
+            files.insert(inst_path, ExtractedDataFile { rel_path, content });
+        }
+    }
+    files
+}
+
+/// Content to write back to a data file for an extracted instance
+///
+/// Returns `None` when Studio's version can't be kept in the file's format (a
+/// different class, or a data module whose source was edited in Studio); the
+/// instance is then written as `.luau`/`.rbxjson` like any other.
+fn extracted_data_file_content(inst: &serde_json::Value, file: &ExtractedDataFile) -> Option<String> {
+    use rbxsync_core::DataFileKind;
+
+    let filename = file.rel_path.rsplit('/').next().unwrap_or(&file.rel_path);
+    let (_, kind) = rbxsync_core::data_file_info(filename)?;
+    let class_name = inst.get("className").and_then(|v| v.as_str()).unwrap_or("");
+    if kind.class_name().is_some_and(|expected| expected != class_name) {
+        return None;
+    }
+    let property = |name: &str| {
+        inst.get("properties")
+            .and_then(|p| p.get(name))
+            .and_then(|p| p.get("value"))
+            .and_then(|v| v.as_str())
+    };
+
+    match kind {
+        DataFileKind::Text => Some(property("Value").unwrap_or_default().to_string()),
+        DataFileKind::Csv => {
+            // Studio may not report Contents; keep the file as it was then
+            let entries = property("Contents")
+                .and_then(|c| serde_json::from_str::<Vec<serde_json::Value>>(c).ok());
+            Some(match entries {
+                Some(entries) => rbxsync_core::localization_csv(&entries),
+                None => file.content.clone(),
+            })
+        }
+        DataFileKind::Json | DataFileKind::Toml | DataFileKind::Yaml => {
+            let parsed = rbxsync_core::parse_data_file(filename, &file.content).ok()?;
+            let unchanged = parsed["source"].as_str() == property("Source");
+            if !unchanged {
+                tracing::info!("{} was edited in Studio, extracting it as a script", file.rel_path);
+            }
+            unchanged.then(|| file.content.clone())
+        }
+        DataFileKind::Model => Some(file.content.clone()),
+    }
 }
 
 /// Read terrain data for sync
@@ -3074,16 +3853,23 @@ async fn handle_sync_incremental(
                                 instances.push(inst);
                             }
                         }
-                    } else if ext == "luau" {
+                    } else if ext == "luau" || ext == "lua" {
                         let rel_path = path.strip_prefix(base).unwrap_or(&path);
                         let path_str = rbxsync_core::path_to_string(rel_path);
-                        let inst_path = path_str
-                            .trim_end_matches(".server.luau")
-                            .trim_end_matches(".client.luau")
-                            .trim_end_matches(".luau")
-                            .to_string();
+                        let Some(inst_path) = rbxsync_core::file_instance_path(&path_str) else {
+                            continue;
+                        };
                         if let Ok(source) = std::fs::read_to_string(&path) {
-                            scripts.insert(inst_path, source);
+                            scripts.insert(inst_path, rbxsync_core::SourceFormat::to_studio(&source));
+                        }
+                    } else {
+                        let rel_path = path.strip_prefix(base).unwrap_or(&path);
+                        let path_str = rbxsync_core::path_to_string(rel_path);
+                        let is_data_file = path.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| rbxsync_core::data_file_info(n).is_some());
+                        if let Some(inst_path) = rbxsync_core::file_instance_path(&path_str).filter(|_| is_data_file) {
+                            instances.extend(read_data_file_instances(&path, &inst_path));
                         }
                     }
                 }
@@ -3204,11 +3990,9 @@ async fn handle_studio_paths(
     }
 
     // Queue request to plugin
-    let plugin_request = PluginRequest {
-        id: request_id,
-        command: "studio:paths".to_string(),
-        payload: serde_json::json!({}),
-    };
+    let plugin_request = PluginRequest::new(request_id, "studio:paths", serde_json::json!({}));
+    let timeout = state.timeout_for("studio:paths").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     {
         let mut queue = state.request_queue.lock().await;
@@ -3218,8 +4002,7 @@ async fn handle_studio_paths(
 
     tracing::info!("Requesting Studio paths ({})", request_id);
 
-    // Wait for response with timeout (60s for large games)
-    let timeout = tokio::time::Duration::from_secs(60);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     let result = tokio::time::timeout(timeout, rx.recv()).await;
 
     // Clean up channel
@@ -3241,6 +4024,7 @@ async fn handle_studio_paths(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             tracing::warn!("Timeout waiting for Studio paths: {}", request_id);
             (
                 StatusCode::GATEWAY_TIMEOUT,
@@ -3272,14 +4056,13 @@ pub struct DiffResult {
     pub common: usize,              // In both
 }
 
-/// Handle diff request - compares files with Studio
+/// Handle diff request - compares files with Studio, down to properties,
+/// attributes, tags and script sources
 async fn handle_diff(
     State(state): State<Arc<AppState>>,
     Json(req): Json<DiffRequest>,
 ) -> impl IntoResponse {
-    // 1. Read file tree
-    let src_dir = PathBuf::from(&req.project_dir).join("src");
-    if !src_dir.exists() {
+    if !PathBuf::from(&req.project_dir).join("src").exists() {
         return (
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
@@ -3289,153 +4072,14 @@ async fn handle_diff(
         );
     }
 
-    // Collect file paths
-    let mut file_paths: HashSet<String> = HashSet::new();
-    let mut file_classes: HashMap<String, String> = HashMap::new();
-
-    fn collect_file_paths(
-        dir: &std::path::Path,
-        base: &std::path::Path,
-        paths: &mut HashSet<String>,
-        classes: &mut HashMap<String, String>,
-    ) {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    collect_file_paths(&path, base, paths, classes);
-                } else if let Some(ext) = path.extension() {
-                    if ext == "rbxjson" {
-                        if let Ok(content) = std::fs::read_to_string(&path) {
-                            if let Ok(inst) = serde_json::from_str::<serde_json::Value>(&content) {
-                                let rel_path = path.strip_prefix(base).unwrap_or(&path);
-                                let path_str = rbxsync_core::path_to_string(rel_path);
-                                let is_meta = path_str.ends_with("/_meta.rbxjson") || path_str.ends_with("\\_meta.rbxjson");
-                                let inst_path = if is_meta {
-                                    path_str.replace("/_meta.rbxjson", "").replace("\\_meta.rbxjson", "")
-                                } else {
-                                    path_str.replace(".rbxjson", "")
-                                };
-                                // Normalize path separators
-                                let inst_path = inst_path.replace('\\', "/");
-                                // Strip disambiguation suffixes for comparison with Studio paths
-                                // (RBXSYNC-68: extract adds _refId suffixes, Studio paths don't have them)
-                                let normalized_path = normalize_path_for_comparison(&inst_path);
-                                paths.insert(normalized_path.clone());
-                                if let Some(class) = inst.get("className").and_then(|v| v.as_str()) {
-                                    classes.insert(normalized_path, class.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    match studio_diff::diff_project(&state, &req.project_dir).await {
+        Ok(diff) => {
+            let mut body = serde_json::to_value(&diff).unwrap_or_default();
+            body["success"] = serde_json::Value::Bool(true);
+            (StatusCode::OK, Json(body))
         }
+        Err((status, error)) => (status, Json(serde_json::json!({"success": false, "error": error}))),
     }
-
-    collect_file_paths(&src_dir, &src_dir, &mut file_paths, &mut file_classes);
-    tracing::info!("Read {} file paths from {}", file_paths.len(), src_dir.display());
-
-    // 2. Get Studio paths via plugin
-    let request_id = Uuid::new_v4();
-    let (tx, mut rx) = mpsc::unbounded_channel();
-    {
-        let mut channels = state.response_channels.write().await;
-        channels.insert(request_id, tx);
-    }
-
-    let plugin_request = PluginRequest {
-        id: request_id,
-        command: "studio:paths".to_string(),
-        payload: serde_json::json!({}),
-    };
-
-    {
-        let mut queue = state.request_queue.lock().await;
-        queue.push_back(plugin_request);
-    }
-    let _ = state.trigger.send(());
-
-    let timeout = tokio::time::Duration::from_secs(60);
-    let result = tokio::time::timeout(timeout, rx.recv()).await;
-
-    {
-        let mut channels = state.response_channels.write().await;
-        channels.remove(&request_id);
-    }
-
-    let studio_response = match result {
-        Ok(Some(response)) if response.success => response.data,
-        Ok(Some(response)) => {
-            return (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": false,
-                    "error": response.error.unwrap_or_else(|| "Plugin returned error".to_string())
-                })),
-            );
-        }
-        Ok(None) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"success": false, "error": "Channel closed"})),
-            );
-        }
-        Err(_) => {
-            return (
-                StatusCode::GATEWAY_TIMEOUT,
-                Json(serde_json::json!({"success": false, "error": "Timeout waiting for Studio paths"})),
-            );
-        }
-    };
-
-    // Parse studio paths
-    let mut studio_paths: HashSet<String> = HashSet::new();
-    let mut studio_classes: HashMap<String, String> = HashMap::new();
-
-    if let Some(paths) = studio_response.get("paths").and_then(|v| v.as_array()) {
-        for entry in paths {
-            if let Some(path) = entry.get("path").and_then(|v| v.as_str()) {
-                studio_paths.insert(path.to_string());
-                if let Some(class) = entry.get("className").and_then(|v| v.as_str()) {
-                    studio_classes.insert(path.to_string(), class.to_string());
-                }
-            }
-        }
-    }
-
-    tracing::info!("Got {} Studio paths", studio_paths.len());
-
-    // 3. Compute diff
-    let added: Vec<DiffEntry> = file_paths
-        .difference(&studio_paths)
-        .map(|path| DiffEntry {
-            path: path.clone(),
-            class_name: file_classes.get(path).cloned().unwrap_or_default(),
-        })
-        .collect();
-
-    let removed: Vec<DiffEntry> = studio_paths
-        .difference(&file_paths)
-        .map(|path| DiffEntry {
-            path: path.clone(),
-            class_name: studio_classes.get(path).cloned().unwrap_or_default(),
-        })
-        .collect();
-
-    let common = file_paths.intersection(&studio_paths).count();
-
-    (
-        StatusCode::OK,
-        Json(serde_json::json!({
-            "success": true,
-            "added": added,
-            "removed": removed,
-            "common": common,
-            "file_count": file_paths.len(),
-            "studio_count": studio_paths.len()
-        })),
-    )
 }
 
 // ============================================================================
@@ -3450,32 +4094,38 @@ pub struct GitProjectRequest {
 
 /// Git status request
 #[derive(Debug, Deserialize)]
-pub struct GitStatusRequest {
-    pub project_dir: String,
-}
-
-/// Handle git status request
-async fn handle_git_status(Json(req): Json<GitStatusRequest>) -> impl IntoResponse {
-    let project_path = PathBuf::from(&req.project_dir);
+pub struct GitStatusRequest {
+    pub project_dir: String,
+}
 
-    match git::get_status(&project_path) {
-        Ok(status) => (
+/// Response for a git operation: `data` on success, `error` and its `kind`
+/// on failure
+fn git_response<T: Serialize>(result: Result<T, git::GitError>) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(data) => (
             StatusCode::OK,
             Json(serde_json::json!({
                 "success": true,
-                "data": status
+                "data": data
             })),
         ),
         Err(e) => (
             StatusCode::OK,
             Json(serde_json::json!({
                 "success": false,
-                "error": e
+                "error": e.to_string(),
+                "kind": e.kind()
             })),
         ),
     }
 }
 
+/// Handle git status request
+async fn handle_git_status(Json(req): Json<GitStatusRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    git_response(git::get_status(&project_path))
+}
+
 /// Git log request
 #[derive(Debug, Deserialize)]
 pub struct GitLogRequest {
@@ -3487,23 +4137,7 @@ pub struct GitLogRequest {
 async fn handle_git_log(Json(req): Json<GitLogRequest>) -> impl IntoResponse {
     let project_path = PathBuf::from(&req.project_dir);
     let limit = req.limit.unwrap_or(5);
-
-    match git::get_log(&project_path, limit) {
-        Ok(commits) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "success": true,
-                "data": commits
-            })),
-        ),
-        Err(e) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            })),
-        ),
-    }
+    git_response(git::get_log(&project_path, limit))
 }
 
 /// Git commit request
@@ -3519,55 +4153,171 @@ async fn handle_git_commit(Json(req): Json<GitCommitRequest>) -> impl IntoRespon
     let project_path = PathBuf::from(&req.project_dir);
     let add_all = req.add_all.unwrap_or(true);
 
-    match git::commit(&project_path, &req.message, add_all) {
-        Ok(output) => {
-            tracing::info!("Git commit successful in {}", req.project_dir);
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": true,
-                    "data": output
-                })),
-            )
-        }
-        Err(e) => {
-            tracing::warn!("Git commit failed: {}", e);
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": false,
-                    "error": e
-                })),
-            )
-        }
+    let result = git::commit(&project_path, &req.message, add_all);
+    match &result {
+        Ok(_) => tracing::info!("Git commit successful in {}", req.project_dir),
+        Err(e) => tracing::warn!("Git commit failed: {}", e),
+    }
+    git_response(result)
+}
+
+/// Git commit-studio request
+#[derive(Debug, Deserialize)]
+pub struct GitCommitStudioRequest {
+    pub project_dir: String,
+    /// Replaces the generated subject line
+    pub message: Option<String>,
+}
+
+/// Handle git commit-studio request: commit pending Studio changes with a
+/// generated message
+async fn handle_git_commit_studio(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GitCommitStudioRequest>,
+) -> impl IntoResponse {
+    let result = studio_commit::commit(&state, &req.project_dir, req.message.as_deref()).await;
+    if let Err(e) = &result {
+        tracing::warn!("Git commit-studio failed: {}", e);
     }
+    git_response(result)
 }
 
 /// Handle git init request
 async fn handle_git_init(Json(req): Json<GitProjectRequest>) -> impl IntoResponse {
     let project_path = PathBuf::from(&req.project_dir);
 
-    match git::init(&project_path) {
-        Ok(output) => {
-            tracing::info!("Git init successful in {}", req.project_dir);
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "success": true,
-                    "data": output
-                })),
+    let result = git::init(&project_path);
+    if result.is_ok() {
+        tracing::info!("Git init successful in {}", req.project_dir);
+    }
+    git_response(result)
+}
+
+/// Handle git branch listing request
+async fn handle_git_branches(Json(req): Json<GitProjectRequest>) -> impl IntoResponse {
+    git_response(git::list_branches(&PathBuf::from(&req.project_dir)))
+}
+
+/// Git branch switch request
+#[derive(Debug, Deserialize)]
+pub struct GitSwitchRequest {
+    pub project_dir: String,
+    pub branch: String,
+    /// Create the branch from HEAD first
+    #[serde(default)]
+    pub create: bool,
+}
+
+/// Handle git branch switch request
+async fn handle_git_switch(Json(req): Json<GitSwitchRequest>) -> impl IntoResponse {
+    let result = git::switch_branch(&PathBuf::from(&req.project_dir), &req.branch, req.create);
+    if result.is_ok() {
+        tracing::info!("Switched to branch {} in {}", req.branch, req.project_dir);
+    }
+    git_response(result.map(|_| req.branch))
+}
+
+/// Git stage request
+#[derive(Debug, Deserialize)]
+pub struct GitStageRequest {
+    pub project_dir: String,
+    /// Instance paths, e.g. `Workspace/Map/Spawn`
+    pub paths: Vec<String>,
+}
+
+/// Handle git stage request: stage the files of instances
+async fn handle_git_stage(Json(req): Json<GitStageRequest>) -> impl IntoResponse {
+    git_response(git::stage(&PathBuf::from(&req.project_dir), &req.paths))
+}
+
+/// Git diff or blame target: a file relative to the project, or an
+/// instance path translated to its files
+#[derive(Debug, Deserialize)]
+pub struct GitFileRequest {
+    pub project_dir: String,
+    pub file: Option<String>,
+    pub instance_path: Option<String>,
+    /// Line number for blame (1-based)
+    pub line: Option<usize>,
+}
+
+/// Handle git diff request: diff against HEAD
+async fn handle_git_diff(Json(req): Json<GitFileRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    let files = match (&req.file, &req.instance_path) {
+        (Some(file), _) => Ok(vec![file.clone()]),
+        (None, Some(instance_path)) => git::instance_files(&project_path, instance_path),
+        (None, None) => Ok(Vec::new()),
+    };
+    git_response(files.and_then(|files| git::diff_head(&project_path, &files)))
+}
+
+/// Handle git blame request for one line of a script
+async fn handle_git_blame(Json(req): Json<GitFileRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    let file = match (&req.file, &req.instance_path) {
+        (Some(file), _) => Ok(file.clone()),
+        (None, Some(instance_path)) => git::script_file(&project_path, instance_path),
+        (None, None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"success": false, "error": "file or instance_path is required"})),
             )
         }
-        Err(e) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "success": false,
-                "error": e
-            })),
+    };
+    let line = req.line.unwrap_or(1);
+    git_response(file.and_then(|file| git::blame_line(&project_path, &file, line)))
+}
+
+/// Git stash request
+#[derive(Debug, Deserialize)]
+pub struct GitStashRequest {
+    pub project_dir: String,
+    /// "push" (default), "pop" or "list"
+    pub action: Option<String>,
+    pub message: Option<String>,
+    #[serde(default)]
+    pub include_untracked: bool,
+}
+
+/// Handle git stash request
+async fn handle_git_stash(Json(req): Json<GitStashRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    match req.action.as_deref().unwrap_or("push") {
+        "push" => git_response(git::stash_push(&project_path, req.message.as_deref(), req.include_untracked).map(serde_json::Value::from)),
+        "pop" => git_response(git::stash_pop(&project_path).map(serde_json::Value::from)),
+        "list" => git_response(git::stash_list(&project_path).map(|stashes| serde_json::json!(stashes))),
+        other => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"success": false, "error": format!("Unknown stash action '{}'", other)})),
         ),
     }
 }
 
+/// Git pull/push request
+#[derive(Debug, Deserialize)]
+pub struct GitRemoteRequest {
+    pub project_dir: String,
+    /// Remote name or URL (default: the branch's upstream)
+    pub remote: Option<String>,
+    pub branch: Option<String>,
+    /// For push: make the remote branch the upstream
+    #[serde(default)]
+    pub set_upstream: bool,
+}
+
+/// Handle git pull request
+async fn handle_git_pull(Json(req): Json<GitRemoteRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    git_response(git::pull(&project_path, req.remote.as_deref(), req.branch.as_deref()))
+}
+
+/// Handle git push request
+async fn handle_git_push(Json(req): Json<GitRemoteRequest>) -> impl IntoResponse {
+    let project_path = PathBuf::from(&req.project_dir);
+    git_response(git::push(&project_path, req.remote.as_deref(), req.branch.as_deref(), req.set_upstream))
+}
+
 // =============================================================================
 // Test Runner Endpoints
 // =============================================================================
@@ -3606,7 +4356,7 @@ async fn clear_stale_playtest_state(state: &Arc<AppState>) -> bool {
 
     if stale && is_active {
         tracing::info!("Clearing stale playtest state (heartbeat timeout)");
-        state.playtest_active.store(false, std::sync::atomic::Ordering::Relaxed);
+        state.mark_playtest_inactive(Some("heartbeat_timeout"));
         *state.playtest_ended.write().await = Some(std::time::Instant::now());
         *state.bot_state.write().await = None;
         return true;
@@ -3622,11 +4372,9 @@ async fn handle_test_start(State(state): State<Arc<AppState>>) -> impl IntoRespo
 
     // Send command to plugin to start capture
     let request_id = Uuid::new_v4();
-    let request = PluginRequest {
-        id: request_id,
-        command: "test:start".to_string(),
-        payload: serde_json::json!({}),
-    };
+    let request = PluginRequest::new(request_id, "test:start", serde_json::json!({}));
+    let timeout = state.timeout_for("test:start").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -3636,8 +4384,7 @@ async fn handle_test_start(State(state): State<Arc<AppState>>) -> impl IntoRespo
     state.request_queue.lock().await.push_back(request);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(30);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -3660,6 +4407,7 @@ async fn handle_test_start(State(state): State<Arc<AppState>>) -> impl IntoRespo
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -3676,11 +4424,9 @@ async fn handle_test_start(State(state): State<Arc<AppState>>) -> impl IntoRespo
 async fn handle_test_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     // Send command to plugin to get current output
     let request_id = Uuid::new_v4();
-    let request = PluginRequest {
-        id: request_id,
-        command: "test:output".to_string(),
-        payload: serde_json::json!({}),
-    };
+    let request = PluginRequest::new(request_id, "test:output", serde_json::json!({}));
+    let timeout = state.timeout_for("test:output").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -3690,8 +4436,7 @@ async fn handle_test_status(State(state): State<Arc<AppState>>) -> impl IntoResp
     state.request_queue.lock().await.push_back(request);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(10);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -3710,6 +4455,7 @@ async fn handle_test_status(State(state): State<Arc<AppState>>) -> impl IntoResp
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -3728,11 +4474,9 @@ async fn handle_test_status(State(state): State<Arc<AppState>>) -> impl IntoResp
 async fn handle_test_stop(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     // Send command to plugin to stop capture
     let request_id = Uuid::new_v4();
-    let request = PluginRequest {
-        id: request_id,
-        command: "test:stop".to_string(),
-        payload: serde_json::json!({}),
-    };
+    let request = PluginRequest::new(request_id, "test:stop", serde_json::json!({}));
+    let timeout = state.timeout_for("test:stop").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -3742,8 +4486,7 @@ async fn handle_test_stop(State(state): State<Arc<AppState>>) -> impl IntoRespon
     state.request_queue.lock().await.push_back(request);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(30);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -3762,6 +4505,7 @@ async fn handle_test_stop(State(state): State<Arc<AppState>>) -> impl IntoRespon
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -3932,11 +4676,9 @@ async fn send_bot_command(
     payload: serde_json::Value,
 ) -> Result<serde_json::Value, (StatusCode, Json<serde_json::Value>)> {
     let request_id = Uuid::new_v4();
-    let request = PluginRequest {
-        id: request_id,
-        command: command.to_string(),
-        payload,
-    };
+    let request = PluginRequest::new(request_id, command, payload);
+    let timeout = state.timeout_for(command).await;
+    let _pending = PendingRequest::register(state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -3946,13 +4688,7 @@ async fn send_bot_command(
     state.request_queue.lock().await.push_back(request);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout (longer timeout for movement commands)
-    let timeout = if command == "bot:move" {
-        tokio::time::Duration::from_secs(60)
-    } else {
-        tokio::time::Duration::from_secs(30)
-    };
-
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -3969,6 +4705,7 @@ async fn send_bot_command(
             ))
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             Err((
                 StatusCode::REQUEST_TIMEOUT,
@@ -4136,7 +4873,7 @@ async fn handle_bot_state_update(
     *bot_state = Some(body);
 
     // Mark playtest as active and update heartbeat
-    state.playtest_active.store(true, std::sync::atomic::Ordering::Relaxed);
+    state.mark_playtest_active();
     let mut heartbeat = state.last_bot_heartbeat.write().await;
     *heartbeat = Some(std::time::Instant::now());
 
@@ -4199,7 +4936,7 @@ async fn handle_bot_result_post(
             results.insert(id, body.clone());
 
             // Also update playtest heartbeat
-            state.playtest_active.store(true, std::sync::atomic::Ordering::Relaxed);
+            state.mark_playtest_active();
             let mut heartbeat = state.last_bot_heartbeat.write().await;
             *heartbeat = Some(std::time::Instant::now());
 
@@ -4254,7 +4991,7 @@ async fn handle_bot_playtest_status(State(state): State<Arc<AppState>>) -> impl
 
     // Mark as inactive if stale
     if stale && is_active {
-        state.playtest_active.store(false, std::sync::atomic::Ordering::Relaxed);
+        state.mark_playtest_inactive(Some("heartbeat_timeout"));
     }
 
     // Check explicit lifecycle events
@@ -4287,7 +5024,7 @@ async fn handle_bot_lifecycle(
     match event {
         "hello" => {
             // Bot connected - playtest started
-            state.playtest_active.store(true, std::sync::atomic::Ordering::Relaxed);
+            state.mark_playtest_active();
             *state.playtest_started.write().await = Some(std::time::Instant::now());
             *state.playtest_ended.write().await = None;
             *state.last_bot_heartbeat.write().await = Some(std::time::Instant::now());
@@ -4301,7 +5038,7 @@ async fn handle_bot_lifecycle(
         }
         "goodbye" => {
             // Bot disconnected - playtest ended
-            state.playtest_active.store(false, std::sync::atomic::Ordering::Relaxed);
+            state.mark_playtest_inactive(reason.or(Some("goodbye")));
             *state.playtest_ended.write().await = Some(std::time::Instant::now());
             tracing::info!("Playtest ended - bot disconnected (reason: {:?})", reason);
 
@@ -4335,18 +5072,11 @@ async fn handle_console_push(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ConsolePushRequest>,
 ) -> impl IntoResponse {
-    let mut buffer = state.console_buffer.write().await;
     let count = req.messages.len();
+    state.metrics.console_messages.add(count as u64);
 
     for msg in req.messages {
-        // Broadcast to any active subscribers
-        let _ = state.console_tx.send(msg.clone());
-
-        // Add to buffer (ring buffer behavior)
-        if buffer.len() >= CONSOLE_BUFFER_SIZE {
-            buffer.pop_front();
-        }
-        buffer.push_back(msg);
+        state.push_console(msg).await;
     }
 
     Json(serde_json::json!({
@@ -4386,6 +5116,7 @@ async fn handle_console_subscribe(
     use std::convert::Infallible;
 
     let mut rx = state.console_tx.subscribe();
+    let state_for_stream = state.clone();
 
     let stream = async_stream::stream! {
         loop {
@@ -4394,8 +5125,9 @@ async fn handle_console_subscribe(
                     let json = serde_json::to_string(&msg).unwrap_or_default();
                     yield Ok::<_, Infallible>(Event::default().data(json));
                 }
-                Err(broadcast::error::RecvError::Lagged(_)) => {
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
                     // Client fell behind, continue
+                    state_for_stream.metrics.console_dropped.add(skipped);
                     continue;
                 }
                 Err(broadcast::error::RecvError::Closed) => {
@@ -4412,6 +5144,118 @@ async fn handle_console_subscribe(
     )
 }
 
+// ============================================================================
+// Event Stream Endpoints
+// ============================================================================
+
+/// Query params for the event stream
+#[derive(Debug, Deserialize)]
+struct EventsQuery {
+    /// Resume after this event id, `epoch:seq` or a bare sequence number
+    /// (replays buffered events)
+    since: Option<String>,
+    /// Max events to return from /events/history
+    limit: Option<usize>,
+}
+
+/// Subscribe to all server events via Server-Sent Events
+///
+/// Resume with `?since=<epoch:seq>` or the standard `Last-Event-ID` header.
+async fn handle_events_subscribe(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EventsQuery>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    use axum::response::sse::{Event, Sse};
+    use std::convert::Infallible;
+
+    let last_event_id = headers.get("last-event-id").and_then(|v| v.to_str().ok());
+    let since = params.since.as_deref().or(last_event_id).and_then(events::Cursor::parse);
+    let since_seq = since.as_ref().map_or(0, |cursor| cursor.seq);
+
+    let events::EventSubscription { backlog, gap, mut receiver } = state.events.subscribe(since);
+    let state_for_stream = state.clone();
+
+    fn to_sse(envelope: &events::EventEnvelope) -> Event {
+        Event::default()
+            .id(envelope.id())
+            .event(envelope.event.name())
+            .data(serde_json::to_string(envelope).unwrap_or_default())
+    }
+
+    let stream = async_stream::stream! {
+        let mut last_seq = since_seq;
+
+        if gap {
+            // Buffer no longer holds everything after `since`, or `since` is
+            // from before a restart; client should refetch state
+            yield Ok::<_, Infallible>(Event::default().event("resync").data(
+                serde_json::json!({ "since": last_seq, "epoch": state_for_stream.events.epoch() }).to_string()
+            ));
+            last_seq = 0;
+        }
+
+        for envelope in backlog {
+            last_seq = envelope.seq;
+            yield Ok(to_sse(&envelope));
+        }
+
+        loop {
+            match receiver.recv().await {
+                Ok(envelope) => {
+                    if envelope.seq <= last_seq {
+                        continue;
+                    }
+                    last_seq = envelope.seq;
+                    yield Ok(to_sse(&envelope));
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // Fell behind the live channel - catch up from the replay buffer
+                    state_for_stream.metrics.events_lagged.add(skipped);
+                    let (missed, gap) = state_for_stream.events.since(last_seq);
+                    if gap {
+                        yield Ok(Event::default().event("resync").data(
+                            serde_json::json!({ "since": last_seq, "epoch": state_for_stream.events.epoch() }).to_string()
+                        ));
+                    }
+                    for envelope in missed {
+                        last_seq = envelope.seq;
+                        yield Ok(to_sse(&envelope));
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    break;
+                }
+            }
+        }
+    };
+
+    Sse::new(stream).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(std::time::Duration::from_secs(15))
+            .text("keepalive")
+    )
+}
+
+/// Get buffered events after a sequence number (polling alternative to /events)
+async fn handle_events_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EventsQuery>,
+) -> impl IntoResponse {
+    let since = params.since.as_deref().and_then(events::Cursor::parse).unwrap_or(events::Cursor::from(0));
+    let (mut events, gap) = state.events.since(since);
+    if let Some(limit) = params.limit {
+        events.truncate(limit);
+    }
+
+    Json(serde_json::json!({
+        "events": events,
+        "epoch": state.events.epoch(),
+        "latestSeq": state.events.latest_seq(),
+        "gap": gap
+    }))
+}
+
 // ============================================================================
 // Run Code Endpoint
 // ============================================================================
@@ -4429,13 +5273,15 @@ async fn handle_run_code(
 ) -> impl IntoResponse {
     let request_id = Uuid::new_v4();
     tracing::info!("run:code request {} - queuing command", request_id);
-    let request = PluginRequest {
-        id: request_id,
-        command: "run:code".to_string(),
-        payload: serde_json::json!({
+    let request = PluginRequest::new(
+        request_id,
+        "run:code",
+        serde_json::json!({
             "code": req.code
         }),
-    };
+    );
+    let timeout = state.timeout_for("run:code").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -4450,8 +5296,7 @@ async fn handle_run_code(
     tracing::info!("run:code request {} - queued (queue length: {})", request_id, queue_len);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(30);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -4473,6 +5318,7 @@ async fn handle_run_code(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -4503,13 +5349,15 @@ async fn handle_read_properties(
 ) -> impl IntoResponse {
     let request_id = Uuid::new_v4();
     tracing::info!("read-properties:get request {} - path: {}", request_id, req.path);
-    let request = PluginRequest {
-        id: request_id,
-        command: "read-properties:get".to_string(),
-        payload: serde_json::json!({
+    let request = PluginRequest::new(
+        request_id,
+        "read-properties:get",
+        serde_json::json!({
             "path": req.path
         }),
-    };
+    );
+    let timeout = state.timeout_for("read-properties:get").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -4524,8 +5372,7 @@ async fn handle_read_properties(
     tracing::info!("read-properties:get request {} - queued (queue length: {})", request_id, queue_len);
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout
-    let timeout = tokio::time::Duration::from_secs(30);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -4549,6 +5396,7 @@ async fn handle_read_properties(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -4586,14 +5434,16 @@ async fn handle_explore_hierarchy(
         req.path,
         depth
     );
-    let request = PluginRequest {
-        id: request_id,
-        command: "explore-hierarchy:get".to_string(),
-        payload: serde_json::json!({
+    let request = PluginRequest::new(
+        request_id,
+        "explore-hierarchy:get",
+        serde_json::json!({
             "path": req.path,
             "depth": depth
         }),
-    };
+    );
+    let timeout = state.timeout_for("explore-hierarchy:get").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -4612,8 +5462,7 @@ async fn handle_explore_hierarchy(
     );
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout (longer for deep hierarchies)
-    let timeout = tokio::time::Duration::from_secs(60);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -4640,6 +5489,7 @@ async fn handle_explore_hierarchy(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -4682,16 +5532,18 @@ async fn handle_find_instances(
         req.parent,
         limit
     );
-    let request = PluginRequest {
-        id: request_id,
-        command: "find-instances:search".to_string(),
-        payload: serde_json::json!({
+    let request = PluginRequest::new(
+        request_id,
+        "find-instances:search",
+        serde_json::json!({
             "className": req.class_name,
             "name": req.name,
             "parent": req.parent,
             "limit": limit
         }),
-    };
+    );
+    let timeout = state.timeout_for("find-instances:search").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -4710,8 +5562,7 @@ async fn handle_find_instances(
     );
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout (longer for searching large hierarchies)
-    let timeout = tokio::time::Duration::from_secs(60);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -4738,6 +5589,7 @@ async fn handle_find_instances(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -4775,14 +5627,16 @@ async fn handle_insert_model(
         req.asset_id,
         req.parent
     );
-    let request = PluginRequest {
-        id: request_id,
-        command: "insert:model".to_string(),
-        payload: serde_json::json!({
+    let request = PluginRequest::new(
+        request_id,
+        "insert:model",
+        serde_json::json!({
             "assetId": req.asset_id,
             "parent": req.parent
         }),
-    };
+    );
+    let timeout = state.timeout_for("insert:model").await;
+    let _pending = PendingRequest::register(&state, request_id, timeout).await;
 
     // Create response channel
     let (tx, mut rx) = mpsc::unbounded_channel();
@@ -4801,8 +5655,7 @@ async fn handle_insert_model(
     );
     state.trigger.send(()).ok();
 
-    // Wait for response with timeout (marketplace fetch may take time)
-    let timeout = tokio::time::Duration::from_secs(60);
+    // Wait for response (cancelled on timeout or if the client disconnects)
     match tokio::time::timeout(timeout, rx.recv()).await {
         Ok(Some(response)) => {
             state.response_channels.write().await.remove(&request_id);
@@ -4831,6 +5684,7 @@ async fn handle_insert_model(
             )
         }
         Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
             state.response_channels.write().await.remove(&request_id);
             (
                 StatusCode::REQUEST_TIMEOUT,
@@ -4850,6 +5704,96 @@ async fn handle_insert_model(
 pub async fn run_server(config: ServerConfig) -> anyhow::Result<()> {
     let state = AppState::new();
     let router = create_router(state.clone());
+    *state.command_timeouts.write().await = config.command_timeouts.clone();
+
+    // Take the daemon PID file first so a second daemon fails fast
+    let _pid_lock = match config.daemon {
+        Some(ref paths) => {
+            let lock = daemon::PidLock::acquire(paths, config.port)?;
+            *state.daemon_paths.write().await = Some(paths.clone());
+            tracing::info!("Daemon started (PID {}), pid file {}", std::process::id(), paths.pid_file.display());
+
+            #[cfg(unix)]
+            {
+                let socket = paths.socket.clone();
+                let state_for_socket = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = daemon::serve_control_socket(socket, state_for_socket).await {
+                        tracing::error!("Control socket failed: {}", e);
+                    }
+                });
+
+                // SIGHUP reloads project configs, like most daemons
+                let state_for_reload = state.clone();
+                tokio::spawn(async move {
+                    use tokio::signal::unix::{signal, SignalKind};
+                    let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+                        return;
+                    };
+                    while hangup.recv().await.is_some() {
+                        daemon::reload(&state_for_reload).await;
+                    }
+                });
+            }
+
+            Some(lock)
+        }
+        None => None,
+    };
+
+    // Restore persisted state before accepting connections
+    if let Some(ref state_dir) = config.state_dir {
+        let store = state_store::StateStore::new(state_dir, config.port);
+        match store.load() {
+            Ok(Some(persisted)) => {
+                let workspaces = persisted.vscode_workspaces.clone();
+                tracing::info!(
+                    "Restoring server state from {} ({} places, {} workspaces)",
+                    store.path().display(),
+                    persisted.places.len(),
+                    workspaces.len()
+                );
+                persisted.apply(&state).await;
+                for dir in workspaces {
+                    state.logged_vscode_workspaces.write().await.insert(dir.clone());
+                    spawn_workspace_watcher(&state, dir);
+                }
+            }
+            Ok(None) => {
+                tracing::info!("No persisted state at {}", store.path().display());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load persisted state from {}: {}", store.path().display(), e);
+            }
+        }
+
+        *state.state_store.write().await = Some(store.clone());
+        tokio::spawn(store.run_autosave(state.clone(), std::time::Duration::from_secs(5)));
+    }
+
+    // Finish or discard file swaps a crash interrupted in known projects
+    {
+        let mut project_dirs: HashSet<String> = state
+            .place_registry
+            .read()
+            .await
+            .values()
+            .map(|place| place.project_dir.clone())
+            .filter(|dir| !dir.is_empty())
+            .collect();
+        project_dirs.extend(state.vscode_workspaces.read().await.keys().cloned());
+        if let Ok(cwd) = std::env::current_dir() {
+            if cwd.join("rbxsync.json").exists() {
+                project_dirs.insert(cwd.to_string_lossy().to_string());
+            }
+        }
+        let _ = tokio::task::spawn_blocking(move || {
+            for dir in project_dirs {
+                atomic_fs::recover(std::path::Path::new(&dir));
+            }
+        })
+        .await;
+    }
 
     // Start background task to process file changes for live sync
     let state_for_watcher = state.clone();
@@ -4857,14 +5801,52 @@ pub async fn run_server(config: ServerConfig) -> anyhow::Result<()> {
         process_file_changes(state_for_watcher).await;
     });
 
+    // Write streamed Studio changes once they settle
+    tokio::spawn(studio_stream::run(state.clone()));
+
     let addr = format!("{}:{}", config.host, config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     tracing::info!("RbxSync server listening on {}", addr);
-    axum::serve(listener, router).await?;
+    let state_for_shutdown = state.clone();
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate_signal() => {}
+                _ = state_for_shutdown.shutdown.notified() => {}
+            }
+            tracing::info!("Shutting down");
+            persist_state(&state_for_shutdown).await;
+        })
+        .await?;
 
     Ok(())
 }
 
+/// Resolves when the process receives SIGTERM (never on non-Unix platforms)
+async fn terminate_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            term.recv().await;
+            return;
+        }
+    }
+    std::future::pending::<()>().await
+}
+
+/// Save server state to disk if persistence is enabled
+async fn persist_state(state: &Arc<AppState>) {
+    let store = state.state_store.read().await.clone();
+    if let Some(store) = store {
+        match store.save_state(state).await {
+            Ok(()) => tracing::info!("Saved server state to {}", store.path().display()),
+            Err(e) => tracing::warn!("Failed to save server state: {}", e),
+        }
+    }
+}
+
 /// Background task to process file changes and send sync commands to the plugin
 async fn process_file_changes(state: Arc<AppState>) {
     use std::collections::HashMap;
@@ -4883,12 +5865,19 @@ async fn process_file_changes(state: Arc<AppState>) {
             }
         }
 
+        // Changes git makes on checkout or pull are held while it works and
+        // dropped once HEAD moves; branch_switch sends the difference instead
+        let git = branch_switch::poll(&state).await;
+        pending.retain(|_, (change, _)| !git.paused.contains(&change.project_dir));
+
         // Process changes that have passed debounce period
         let now = Instant::now();
         let mut ready_changes: Vec<file_watcher::FileChange> = Vec::new();
 
         pending.retain(|_, (change, time)| {
-            if now.duration_since(*time) >= debounce_duration {
+            if git.held.contains(&change.project_dir) {
+                true
+            } else if now.duration_since(*time) >= debounce_duration {
                 ready_changes.push(change.clone());
                 false
             } else {
@@ -4898,58 +5887,76 @@ async fn process_file_changes(state: Arc<AppState>) {
 
         // Send ready changes to plugin (skip if live sync is paused during extraction)
         if !ready_changes.is_empty() {
+            // Turn delete + create pairs into moves so Studio keeps instance identity.
+            // The files are read first, without holding the watcher lock.
+            let read = tokio::task::spawn_blocking(move || {
+                let disk = file_watcher::DiskFingerprints::read(&ready_changes);
+                (ready_changes, disk)
+            })
+            .await;
+            let Ok((ready_changes, disk)) = read else {
+                tracing::error!("Live sync: failed to read changed files");
+                continue;
+            };
+            let ready_changes = {
+                let mut watcher = state.file_watcher_state.write().await;
+                file_watcher::coalesce_moves(ready_changes, &mut watcher.fingerprints, disk)
+            };
+
+            // Drop changes the server made itself (Studio→disk sync)
+            let total = ready_changes.len();
+            let ready_changes = state.echo_filter.filter(ready_changes);
+            state.metrics.live_sync_echoes_suppressed.add((total - ready_changes.len()) as u64);
+
             // Check if live sync is paused (during extraction)
             if state.live_sync_paused.load(std::sync::atomic::Ordering::Relaxed) {
                 tracing::debug!("Live sync paused, skipping {} file changes", ready_changes.len());
                 continue;
             }
 
-            let mut operations = Vec::new();
-
-            for change in &ready_changes {
-                if let Some(op) = file_watcher::process_file_change(change) {
-                    tracing::info!("Live sync: {:?} -> {:?}", change.kind, change.path);
-                    operations.push(op);
+            // Each project's changes go out with its own settings, read once per round
+            let mut by_project: Vec<(String, Vec<file_watcher::FileChange>)> = Vec::new();
+            for change in ready_changes {
+                match by_project.iter_mut().find(|(dir, _)| *dir == change.project_dir) {
+                    Some((_, changes)) => changes.push(change),
+                    None => by_project.push((change.project_dir.clone(), vec![change])),
                 }
             }
 
-            if !operations.is_empty() {
-                // Find project dir from first change
-                let project_dir = ready_changes.first().map(|c| c.project_dir.clone());
-
-                // Queue batch sync request to plugin
-                let request_id = Uuid::new_v4();
-                let plugin_request = PluginRequest {
-                    id: request_id,
-                    command: "sync:batch".to_string(),
-                    payload: serde_json::json!({
-                        "operations": operations,
-                        "source": "file_watcher"  // Mark as from file watcher
-                    }),
-                };
-
-                // Send to project-specific queue if we know the project
-                // Only fall back to global queue if project queue doesn't exist
-                let mut sent = false;
-                if let Some(ref dir) = project_dir {
-                    let mut queues = state.project_queues.write().await;
-                    if let Some(queue) = queues.get_mut(dir) {
-                        tracing::info!("Queued {} operations for project {}", operations.len(), dir);
-                        queue.push_back(plugin_request.clone());
-                        sent = true;
-                    } else {
-                        tracing::warn!("No queue for project {}, available queues: {:?}", dir, queues.keys().collect::<Vec<_>>());
+            for (project_dir, changes) in by_project {
+                let config = load_project_config(&project_dir);
+                let limits = limits::from_config(&config);
+                let tree_mapping = file_watcher::live_sync_tree_mapping(&config);
+                let mut operations = Vec::new();
+
+                for change in &changes {
+                    // Oversized files stay out of Studio
+                    let size = std::fs::metadata(&change.path).ok().filter(|m| m.is_file()).map(|m| m.len());
+                    let name = change.path.display().to_string();
+                    if let Some(Err(err)) = size.map(|size| limits::check_file_size(&limits, &name, size)) {
+                        let err = limits::LimitExceeded {
+                            message: format!("{}; not synced to Studio", err.message),
+                            ..err
+                        };
+                        limits::report(&state, "sync", "warn", &err).await;
+                        continue;
+                    }
+                    if let Some(op) = file_watcher::process_file_change(change, &tree_mapping) {
+                        tracing::info!("Live sync: {:?} -> {:?}", change.kind, change.path);
+                        operations.push(op);
                     }
                 }
 
-                // Only use global queue as fallback if project queue wasn't available
-                if !sent {
-                    let mut queue = state.request_queue.lock().await;
-                    queue.push_back(plugin_request);
+                // Large change sets go out as several batches
+                let batch_size = match limits.max_batch_instances {
+                    0 => operations.len().max(1),
+                    max => max,
+                };
+                while !operations.is_empty() {
+                    let rest = operations.split_off(operations.len().min(batch_size));
+                    live_sync::queue_batch(&state, Some(project_dir.clone()), &config, operations).await;
+                    operations = rest;
                 }
-
-                // Trigger long-polling requests to wake up
-                let _ = state.trigger.send(());
             }
         }
 
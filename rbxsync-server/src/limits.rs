@@ -0,0 +1,150 @@
+//! Size guardrails for syncs and extractions
+//!
+//! A runaway script in Studio can produce multi-megabyte sources or thousands
+//! of instances a second. Data from Studio and from the file watcher is
+//! checked against the project's `limits` in `rbxsync.json` before anything is
+//! written or queued. Requests over a limit are refused with `413 Payload Too
+//! Large`, and the reason is pushed to the console. Requests with
+//! `force: true` skip the checks for intentional large operations.
+
+use axum::http::StatusCode;
+use axum::Json;
+use rbxsync_core::LimitsConfig;
+
+use crate::{AppState, ConsoleMessage};
+
+/// A request, file or extraction over one of the project's limits
+#[derive(Debug, Clone)]
+pub struct LimitExceeded {
+    /// The `limits` key that was hit (`maxFileSize`, ...)
+    pub limit: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl LimitExceeded {
+    /// `413` response for a refused request
+    pub fn response(&self) -> (StatusCode, Json<serde_json::Value>) {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(serde_json::json!({
+                "success": false,
+                "error": self.message,
+                "limit": self.limit,
+                "hint": format!("Raise limits.{} in rbxsync.json or send \"force\": true", self.limit)
+            })),
+        )
+    }
+}
+
+/// Limits for a project (defaults if it has no valid `limits`)
+pub fn for_project(project_dir: Option<&str>) -> LimitsConfig {
+    from_config(&project_dir.filter(|dir| !dir.is_empty()).and_then(crate::load_project_config))
+}
+
+/// Limits from an already loaded `rbxsync.json`
+pub fn from_config(config: &Option<serde_json::Value>) -> LimitsConfig {
+    config
+        .as_ref()
+        .and_then(|config| serde_json::from_value(config.get("limits")?.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Check how many instances or operations one request carries
+pub fn check_count(limits: &LimitsConfig, count: usize, what: &str) -> Result<(), LimitExceeded> {
+    if limits.max_batch_instances > 0 && count > limits.max_batch_instances {
+        return Err(LimitExceeded {
+            limit: "maxBatchInstances",
+            message: format!("{} {} in one batch (limit {})", count, what, limits.max_batch_instances),
+        });
+    }
+    Ok(())
+}
+
+/// Check the size of a single file
+pub fn check_file_size(limits: &LimitsConfig, name: &str, bytes: u64) -> Result<(), LimitExceeded> {
+    if limits.max_file_size > 0 && bytes > limits.max_file_size {
+        return Err(LimitExceeded {
+            limit: "maxFileSize",
+            message: format!(
+                "{} is {} (limit {})",
+                name,
+                format_bytes(bytes),
+                format_bytes(limits.max_file_size)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Check the files an instance from Studio would produce: its script source
+/// and its `.rbxjson`
+pub fn check_instance(limits: &LimitsConfig, inst: &serde_json::Value) -> Result<(), LimitExceeded> {
+    if limits.max_file_size == 0 {
+        return Ok(());
+    }
+    let name = inst.get("path").and_then(|v| v.as_str()).unwrap_or("An instance");
+    let source = rbxsync_core::diff::instance_source(inst).map_or(0, str::len) as u64;
+    let properties = serialized_len(inst).saturating_sub(source);
+    check_file_size(limits, name, source.max(properties))
+}
+
+/// Check the running total of data received by an extraction
+pub fn check_extraction_size(limits: &LimitsConfig, bytes: u64) -> Result<(), LimitExceeded> {
+    if limits.max_extraction_size > 0 && bytes > limits.max_extraction_size {
+        return Err(LimitExceeded {
+            limit: "maxExtractionSize",
+            message: format!(
+                "Extraction has sent {} (limit {})",
+                format_bytes(bytes),
+                format_bytes(limits.max_extraction_size)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Push a limit hit to the console and count it
+pub async fn report(state: &AppState, source: &str, message_type: &str, err: &LimitExceeded) {
+    tracing::warn!("Size limit {} hit: {}", err.limit, err.message);
+    state.metrics.size_limit_hits.inc(err.limit);
+    state
+        .push_console(ConsoleMessage {
+            timestamp: crate::live_sync::clock_time(),
+            message_type: message_type.to_string(),
+            message: format!("{} (limits.{})", err.message, err.limit),
+            source: Some(source.to_string()),
+        })
+        .await;
+}
+
+/// JSON size of a value without building the string
+fn serialized_len(value: &serde_json::Value) -> u64 {
+    struct Count(u64);
+    impl std::io::Write for Count {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut count = Count(0);
+    let _ = serde_json::to_writer(&mut count, value);
+    count.0
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= MB {
+        format!("{:.1} MB", bytes as f64 / MB)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
@@ -0,0 +1,434 @@
+//! Live-sync batch tracking
+//!
+//! The file watcher sends changes to Studio as `sync:batch` requests. Each
+//! batch is recorded here and a task waits for the plugin's response, so
+//! partial failures are not silent: per-operation results are kept for
+//! `/sync/batches`, failures are pushed to the console and event stream, and
+//! outcomes are counted in the metrics. Projects with `sync.atomic` set ask
+//! the plugin to revert the whole batch when any operation fails.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::plugin_requests::PendingRequest;
+use crate::{events, AppState, ConsoleMessage, PluginRequest, PluginResponse};
+
+/// Completed batches kept for `/sync/batches`
+pub const BATCH_HISTORY: usize = 100;
+
+/// Outcome of a live-sync batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    /// Waiting for the plugin
+    Pending,
+    /// Every operation succeeded (or needed no change)
+    Applied,
+    /// Some operations failed, the rest were left applied
+    Partial,
+    /// Every operation failed, or the plugin reported an error
+    Failed,
+    /// Operations failed and the plugin reverted the whole batch
+    RolledBack,
+    /// The plugin declined the batch (e.g. files→Studio sync disabled)
+    Skipped,
+    /// No response before the `sync:batch` timeout
+    TimedOut,
+}
+
+impl BatchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchStatus::Pending => "pending",
+            BatchStatus::Applied => "applied",
+            BatchStatus::Partial => "partial",
+            BatchStatus::Failed => "failed",
+            BatchStatus::RolledBack => "rolled_back",
+            BatchStatus::Skipped => "skipped",
+            BatchStatus::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// Result of one operation as reported by the plugin
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationResult {
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub op_type: Option<String>,
+    pub success: bool,
+    pub skipped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl OperationResult {
+    fn from_json(value: &serde_json::Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self {
+            path: string("path"),
+            op_type: string("type"),
+            success: value.get("success").and_then(|v| v.as_bool()).unwrap_or(false),
+            skipped: value.get("skipped").and_then(|v| v.as_bool()).unwrap_or(false),
+            action: string("action"),
+            error: string("error"),
+        }
+    }
+}
+
+/// A live-sync batch and, once answered, its outcome
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRecord {
+    pub request_id: Uuid,
+    pub project_dir: Option<String>,
+    pub atomic: bool,
+    pub status: BatchStatus,
+    /// Number of operations sent
+    pub operations: usize,
+    pub applied: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Unix millis when the batch was queued
+    pub queued_at: u64,
+    /// Unix millis when the response arrived (or the wait timed out)
+    pub completed_at: Option<u64>,
+    pub error: Option<String>,
+    pub results: Vec<OperationResult>,
+}
+
+impl BatchRecord {
+    pub fn new(request_id: Uuid, project_dir: Option<String>, operations: usize, atomic: bool) -> Self {
+        Self {
+            request_id,
+            project_dir,
+            atomic,
+            status: BatchStatus::Pending,
+            operations,
+            applied: 0,
+            skipped: 0,
+            failed: 0,
+            queued_at: crate::now_millis(),
+            completed_at: None,
+            error: None,
+            results: Vec::new(),
+        }
+    }
+
+    /// Fill in the outcome from the plugin's `sync:batch` response
+    pub fn apply_response(&mut self, response: &PluginResponse) {
+        let data = &response.data;
+        let count = |key: &str| data.get(key).and_then(|v| v.as_u64()).map(|n| n as usize);
+
+        self.completed_at = Some(crate::now_millis());
+        self.error = response.error.clone();
+        self.results = data
+            .get("results")
+            .and_then(|v| v.as_array())
+            .map(|results| results.iter().map(OperationResult::from_json).collect())
+            .unwrap_or_default();
+
+        // A declined batch answers `{skipped: true, reason}` rather than counts
+        if data.get("skipped").and_then(|v| v.as_bool()) == Some(true) {
+            self.status = BatchStatus::Skipped;
+            self.skipped = self.operations;
+            if self.error.is_none() {
+                self.error = data.get("reason").and_then(|v| v.as_str()).map(String::from);
+            }
+            return;
+        }
+        if data.get("cancelled").and_then(|v| v.as_bool()) == Some(true) {
+            self.status = BatchStatus::Failed;
+            return;
+        }
+
+        let failed_results = self.results.iter().filter(|r| !r.success).count();
+        self.applied = count("applied").unwrap_or(0);
+        self.skipped = count("skipped").unwrap_or(0);
+        self.failed = count("failed").unwrap_or(failed_results);
+
+        self.status = if data.get("rolledBack").and_then(|v| v.as_bool()) == Some(true) {
+            BatchStatus::RolledBack
+        } else if response.success && self.failed == 0 {
+            BatchStatus::Applied
+        } else if self.applied > 0 || self.skipped > 0 {
+            BatchStatus::Partial
+        } else {
+            BatchStatus::Failed
+        };
+    }
+
+    /// Mark the batch as abandoned after the response timeout
+    pub fn time_out(&mut self, timeout: Duration) {
+        self.completed_at = Some(crate::now_millis());
+        self.status = BatchStatus::TimedOut;
+        self.error = Some(format!("No response from Studio within {}s", timeout.as_secs()));
+    }
+
+    /// Operations the plugin reported as failed
+    pub fn failures(&self) -> impl Iterator<Item = &OperationResult> {
+        self.results.iter().filter(|r| !r.success)
+    }
+}
+
+/// Recent live-sync batches, newest last
+#[derive(Debug, Default)]
+pub struct BatchLog {
+    batches: Mutex<VecDeque<BatchRecord>>,
+}
+
+impl BatchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, record: BatchRecord) {
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        if batches.len() >= BATCH_HISTORY {
+            batches.pop_front();
+        }
+        batches.push_back(record);
+    }
+
+    /// Update a batch in place, returning the updated copy
+    pub fn update(&self, id: Uuid, f: impl FnOnce(&mut BatchRecord)) -> Option<BatchRecord> {
+        let mut batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        let record = batches.iter_mut().find(|r| r.request_id == id)?;
+        f(record);
+        Some(record.clone())
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<BatchRecord> {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.iter().find(|r| r.request_id == id).cloned()
+    }
+
+    /// The last `limit` batches, newest first
+    pub fn recent(&self, limit: usize) -> Vec<BatchRecord> {
+        let batches = self.batches.lock().unwrap_or_else(|e| e.into_inner());
+        batches.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// Queue a `sync:batch` for Studio and track it to completion
+///
+/// `config` is the project's `rbxsync.json`, loaded once by the caller.
+/// Returns the request ID; the outcome shows up in `/sync/batches` once the
+/// plugin answers or the `sync:batch` timeout passes.
+pub async fn queue_batch(
+    state: &Arc<AppState>,
+    project_dir: Option<String>,
+    config: &Option<serde_json::Value>,
+    operations: Vec<serde_json::Value>,
+) -> Uuid {
+    let timeout = state.timeout_for("sync:batch").await;
+    queue_batch_with_timeout(state, project_dir, config, operations, timeout).await
+}
+
+/// Queue a `sync:batch` that must reach Studio whole, however large
+///
+/// The batch isn't held to `maxBatchInstances`. Instead the `sync:batch`
+/// timeout is multiplied by the number of batches of that size it would
+/// have been split into.
+pub async fn queue_large_batch(
+    state: &Arc<AppState>,
+    project_dir: &str,
+    operations: Vec<serde_json::Value>,
+) -> Uuid {
+    let config = crate::load_project_config(project_dir);
+    let max = crate::limits::from_config(&config).max_batch_instances.max(1);
+    let parts = u32::try_from(operations.len().div_ceil(max)).unwrap_or(u32::MAX).max(1);
+    let timeout = state.timeout_for("sync:batch").await.saturating_mul(parts);
+    queue_batch_with_timeout(state, Some(project_dir.to_string()), &config, operations, timeout).await
+}
+
+async fn queue_batch_with_timeout(
+    state: &Arc<AppState>,
+    project_dir: Option<String>,
+    config: &Option<serde_json::Value>,
+    operations: Vec<serde_json::Value>,
+    timeout: Duration,
+) -> Uuid {
+    let atomic = config
+        .as_ref()
+        .and_then(|config| config.get("sync")?.get("atomic")?.as_bool())
+        .unwrap_or(false);
+
+    let request_id = Uuid::new_v4();
+    let count = operations.len();
+    let paths: Vec<String> = operations
+        .iter()
+        .filter_map(|op| op.get("path").and_then(|v| v.as_str()).map(String::from))
+        .collect();
+    let plugin_request = PluginRequest::new(
+        request_id,
+        "sync:batch",
+        serde_json::json!({
+            "operations": operations,
+            "source": "file_watcher",  // Mark as from file watcher
+            "atomic": atomic
+        }),
+    );
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    state.response_channels.write().await.insert(request_id, tx);
+    let pending = PendingRequest::register(state, request_id, timeout).await;
+    state
+        .live_sync_batches
+        .insert(BatchRecord::new(request_id, project_dir.clone(), count, atomic));
+
+    // Send to project-specific queue if we know the project
+    // Only fall back to global queue if project queue doesn't exist
+    let mut sent = false;
+    if let Some(ref dir) = project_dir {
+        let mut queues = state.project_queues.write().await;
+        if let Some(queue) = queues.get_mut(dir) {
+            tracing::info!("Queued {} operations for project {}", count, dir);
+            queue.push_back(plugin_request.clone());
+            sent = true;
+        } else {
+            tracing::warn!("No queue for project {}, available queues: {:?}", dir, queues.keys().collect::<Vec<_>>());
+        }
+    }
+    if !sent {
+        state.request_queue.lock().await.push_back(plugin_request);
+    }
+
+    state.metrics.live_sync_batches.inc();
+    state.metrics.live_sync_batch_operations.observe(count as f64);
+    state.events.publish(events::ServerEvent::LiveSyncBatch {
+        project_dir,
+        request_id: request_id.to_string(),
+        operations: count,
+        paths,
+    });
+
+    // Trigger long-polling requests to wake up
+    let _ = state.trigger.send(());
+
+    tokio::spawn(track_batch(state.clone(), request_id, rx, timeout, pending));
+    request_id
+}
+
+/// Wait for a batch's response, record it and report failures
+async fn track_batch(
+    state: Arc<AppState>,
+    request_id: Uuid,
+    mut rx: mpsc::UnboundedReceiver<PluginResponse>,
+    timeout: Duration,
+    pending: PendingRequest,
+) {
+    let response = tokio::time::timeout(timeout, rx.recv()).await;
+    state.response_channels.write().await.remove(&request_id);
+
+    let record = match response {
+        Ok(Some(response)) => state
+            .live_sync_batches
+            .update(request_id, |record| record.apply_response(&response)),
+        _ => {
+            state.metrics.plugin_response_timeouts.inc();
+            state.live_sync_batches.update(request_id, |record| record.time_out(timeout))
+        }
+    };
+    // Cancels the request in the plugin if it never answered
+    drop(pending);
+
+    if let Some(record) = record {
+        report(&state, &record).await;
+    }
+}
+
+/// Surface a finished batch in the metrics, console and event stream
+async fn report(state: &AppState, record: &BatchRecord) {
+    let status = record.status.as_str();
+    state.metrics.live_sync_batch_results.inc(status);
+    state.metrics.live_sync_operations_failed.add(record.failed as u64);
+
+    match record.status {
+        BatchStatus::Applied | BatchStatus::Pending => {
+            tracing::debug!("Live sync batch {} {}", record.request_id, status);
+        }
+        _ => tracing::warn!(
+            "Live sync batch {} {}: {} applied, {} failed{}",
+            record.request_id,
+            status,
+            record.applied,
+            record.failed,
+            record.error.as_deref().map(|e| format!(" ({})", e)).unwrap_or_default()
+        ),
+    }
+
+    for failure in record.failures() {
+        console(
+            state,
+            "error",
+            format!(
+                "Live sync failed: {} {} - {}",
+                failure.op_type.as_deref().unwrap_or("sync"),
+                failure.path.as_deref().unwrap_or("unknown"),
+                failure.error.as_deref().unwrap_or("unknown error")
+            ),
+        )
+        .await;
+    }
+    let summary = match record.status {
+        BatchStatus::RolledBack => Some((
+            "warn",
+            format!("Live sync batch rolled back: {} of {} operations failed", record.failed, record.operations),
+        )),
+        BatchStatus::Skipped => Some((
+            "warn",
+            format!(
+                "Live sync batch skipped by Studio: {}",
+                record.error.as_deref().unwrap_or("no reason given")
+            ),
+        )),
+        BatchStatus::TimedOut | BatchStatus::Failed if record.results.is_empty() => Some((
+            "error",
+            format!(
+                "Live sync batch of {} operations {}: {}",
+                record.operations,
+                if record.status == BatchStatus::TimedOut { "timed out" } else { "failed" },
+                record.error.as_deref().unwrap_or("unknown error")
+            ),
+        )),
+        _ => None,
+    };
+    if let Some((message_type, message)) = summary {
+        console(state, message_type, message).await;
+    }
+
+    state.events.publish(events::ServerEvent::LiveSyncCompleted {
+        project_dir: record.project_dir.clone(),
+        request_id: record.request_id.to_string(),
+        status: status.to_string(),
+        applied: record.applied,
+        skipped: record.skipped,
+        failed: record.failed,
+        failed_paths: record.failures().filter_map(|r| r.path.clone()).collect(),
+    });
+}
+
+async fn console(state: &AppState, message_type: &str, message: String) {
+    state
+        .push_console(ConsoleMessage {
+            timestamp: clock_time(),
+            message_type: message_type.to_string(),
+            message,
+            source: Some("sync".to_string()),
+        })
+        .await;
+}
+
+/// `HH:MM:SS` (UTC), matching the plugin's console timestamps
+pub(crate) fn clock_time() -> String {
+    let secs = crate::now_millis() / 1000;
+    format!("{:02}:{:02}:{:02}", secs / 3600 % 24, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,376 @@
+//! Server metrics
+//!
+//! Counters and histograms for the plugin request path, live sync,
+//! extraction and console streaming, rendered in the Prometheus text
+//! exposition format at `/metrics`. Gauges (queue depth, connections) are
+//! read from `AppState` at scrape time instead of being tracked here.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::AppState;
+
+/// Buckets for latency histograms (seconds)
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Buckets for long-poll wait times (seconds, polls time out at 15s)
+const POLL_BUCKETS: &[f64] = &[0.01, 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 15.0];
+
+/// Buckets for batch sizes (operations)
+const SIZE_BUCKETS: &[f64] = &[1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 1000.0];
+
+/// Buckets for extraction durations (seconds)
+const EXTRACTION_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Dispatched requests older than this are forgotten (never answered)
+const INFLIGHT_TTL: Duration = Duration::from_secs(600);
+
+/// Monotonic counter
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counter with a single label
+#[derive(Debug, Default)]
+pub struct LabeledCounter(Mutex<BTreeMap<String, u64>>);
+
+impl LabeledCounter {
+    pub fn inc(&self, label: &str) {
+        let mut values = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        *values.entry(label.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, label: &str) -> u64 {
+        let values = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        values.get(label).copied().unwrap_or(0)
+    }
+
+    fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+#[derive(Debug)]
+struct HistogramData {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Cumulative histogram with fixed buckets
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: &'static [f64],
+    data: Mutex<HistogramData>,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            data: Mutex::new(HistogramData {
+                counts: vec![0; buckets.len()],
+                sum: 0.0,
+                count: 0,
+            }),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        for (i, bound) in self.buckets.iter().enumerate() {
+            if value <= *bound {
+                data.counts[i] += 1;
+            }
+        }
+        data.sum += value;
+        data.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).sum
+    }
+}
+
+/// All server metrics
+#[derive(Debug)]
+pub struct Metrics {
+    /// Requests handed to the plugin via long-poll, by command
+    pub plugin_requests: LabeledCounter,
+    /// Plugin responses by outcome ("success" or "failure")
+    pub plugin_responses: LabeledCounter,
+    /// Time from dispatch to the plugin until its response arrived
+    pub plugin_response_seconds: Histogram,
+    /// Handlers that gave up waiting for a plugin response
+    pub plugin_response_timeouts: Counter,
+    /// Responses that arrived after their handler stopped waiting
+    pub plugin_responses_dropped: Counter,
+    /// Requests cancelled (timeout, client abort or explicit cancel)
+    pub plugin_requests_cancelled: Counter,
+    /// Long-poll results ("request" or "empty")
+    pub polls: LabeledCounter,
+    /// How long each long-poll was held open
+    pub poll_wait_seconds: Histogram,
+    /// Live-sync batches queued by the file watcher
+    pub live_sync_batches: Counter,
+    /// Operations per live-sync batch
+    pub live_sync_batch_operations: Histogram,
+    /// Watcher events dropped because the server wrote the file itself
+    pub live_sync_echoes_suppressed: Counter,
+    /// Live-sync batch results by status ("applied", "partial", "failed", ...)
+    pub live_sync_batch_results: LabeledCounter,
+    /// Live-sync operations the plugin reported as failed
+    pub live_sync_operations_failed: Counter,
+    /// Instance changes streamed from Studio
+    pub studio_stream_changes: Counter,
+    /// Streamed changes merged into a change already pending for the instance
+    pub studio_stream_coalesced: Counter,
+    /// Files written, deleted or renamed by Studio streaming
+    pub studio_stream_files_written: Counter,
+    /// Requests, files and extractions over a size limit, by limit
+    pub size_limit_hits: LabeledCounter,
+    /// Extraction chunks received from the plugin
+    pub extraction_chunks: Counter,
+    /// Extraction chunk payload bytes received
+    pub extraction_bytes: Counter,
+    /// Instances written by finalized extractions
+    pub extraction_instances: Counter,
+    /// Duration from a session's first chunk to finalize
+    pub extraction_seconds: Histogram,
+    /// Console messages pushed by the plugin
+    pub console_messages: Counter,
+    /// Console messages skipped by slow `/console/subscribe` clients
+    pub console_dropped: Counter,
+    /// Events skipped by slow `/events` clients (replayed from history when possible)
+    pub events_lagged: Counter,
+
+    inflight: Mutex<HashMap<Uuid, Instant>>,
+    /// Session id of the extraction being timed, and when its first chunk came
+    extraction_started: Mutex<Option<(String, Instant)>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            plugin_requests: LabeledCounter::default(),
+            plugin_responses: LabeledCounter::default(),
+            plugin_response_seconds: Histogram::new(LATENCY_BUCKETS),
+            plugin_response_timeouts: Counter::default(),
+            plugin_responses_dropped: Counter::default(),
+            plugin_requests_cancelled: Counter::default(),
+            polls: LabeledCounter::default(),
+            poll_wait_seconds: Histogram::new(POLL_BUCKETS),
+            live_sync_batches: Counter::default(),
+            live_sync_batch_operations: Histogram::new(SIZE_BUCKETS),
+            live_sync_echoes_suppressed: Counter::default(),
+            live_sync_batch_results: LabeledCounter::default(),
+            live_sync_operations_failed: Counter::default(),
+            studio_stream_changes: Counter::default(),
+            studio_stream_coalesced: Counter::default(),
+            studio_stream_files_written: Counter::default(),
+            size_limit_hits: LabeledCounter::default(),
+            extraction_chunks: Counter::default(),
+            extraction_bytes: Counter::default(),
+            extraction_instances: Counter::default(),
+            extraction_seconds: Histogram::new(EXTRACTION_BUCKETS),
+            console_messages: Counter::default(),
+            console_dropped: Counter::default(),
+            events_lagged: Counter::default(),
+            inflight: Mutex::new(HashMap::new()),
+            extraction_started: Mutex::new(None),
+        }
+    }
+
+    /// Record a request handed to the plugin
+    pub fn request_dispatched(&self, id: Uuid, command: &str) {
+        self.plugin_requests.inc(command);
+        let mut inflight = self.inflight.lock().unwrap_or_else(|e| e.into_inner());
+        if inflight.len() >= 1000 {
+            inflight.retain(|_, sent| sent.elapsed() < INFLIGHT_TTL);
+        }
+        inflight.insert(id, Instant::now());
+    }
+
+    /// Record a plugin response; `delivered` is false if no handler was waiting
+    pub fn response_received(&self, id: Uuid, success: bool, delivered: bool) {
+        self.plugin_responses.inc(if success { "success" } else { "failure" });
+        if !delivered {
+            self.plugin_responses_dropped.inc();
+        }
+        let sent = self.inflight.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        if let Some(sent) = sent {
+            self.plugin_response_seconds.observe(sent.elapsed().as_secs_f64());
+        }
+    }
+
+    /// Forget the start of an extraction that never finalized
+    pub fn extraction_reset(&self) {
+        *self.extraction_started.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Record an extraction chunk; the first chunk of a session starts its timer
+    pub fn extraction_chunk(&self, session_id: &str, bytes: usize) {
+        self.extraction_chunks.inc();
+        self.extraction_bytes.add(bytes as u64);
+        let mut started = self.extraction_started.lock().unwrap_or_else(|e| e.into_inner());
+        if started.as_ref().is_none_or(|(id, _)| id != session_id) {
+            *started = Some((session_id.to_string(), Instant::now()));
+        }
+    }
+
+    /// Record a finalized extraction
+    pub fn extraction_finished(&self, session_id: &str, instances: usize) {
+        self.extraction_instances.add(instances as u64);
+        let started = self
+            .extraction_started
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take_if(|(id, _)| id == session_id);
+        if let Some((_, started)) = started {
+            self.extraction_seconds.observe(started.elapsed().as_secs_f64());
+        }
+    }
+}
+
+/// Render all metrics in the Prometheus text format
+pub async fn render(state: &AppState) -> String {
+    let m = &state.metrics;
+    let mut out = String::new();
+
+    // Gauges read from server state
+    let global_depth = state.request_queue.lock().await.len();
+    let project_depths: BTreeMap<String, usize> = state
+        .project_queues
+        .read()
+        .await
+        .iter()
+        .map(|(dir, queue)| (dir.clone(), queue.len()))
+        .collect();
+    write_header(&mut out, "rbxsync_queue_depth", "gauge", "Requests waiting for the plugin to poll");
+    let _ = writeln!(out, "rbxsync_queue_depth{{queue=\"global\"}} {}", global_depth);
+    for (dir, depth) in &project_depths {
+        let _ = writeln!(out, "rbxsync_queue_depth{{queue=\"{}\"}} {}", escape_label(dir), depth);
+    }
+    write_gauge(
+        &mut out,
+        "rbxsync_pending_responses",
+        "Handlers waiting for a plugin response",
+        state.response_channels.read().await.len() as f64,
+    );
+    write_gauge(
+        &mut out,
+        "rbxsync_connected_places",
+        "Registered Studio places",
+        state.place_registry.read().await.len() as f64,
+    );
+    write_gauge(
+        &mut out,
+        "rbxsync_connected_workspaces",
+        "Registered VS Code workspaces",
+        state.vscode_workspaces.read().await.len() as f64,
+    );
+    write_gauge(
+        &mut out,
+        "rbxsync_playtest_active",
+        "Whether a playtest is running",
+        if state.playtest_active.load(Ordering::SeqCst) { 1.0 } else { 0.0 },
+    );
+    write_gauge(
+        &mut out,
+        "rbxsync_uptime_seconds",
+        "Seconds since the server started",
+        state.started_at.elapsed().as_secs_f64(),
+    );
+
+    write_labeled(&mut out, "rbxsync_plugin_requests_total", "Requests dispatched to the plugin", "command", &m.plugin_requests);
+    write_labeled(&mut out, "rbxsync_plugin_responses_total", "Responses received from the plugin", "outcome", &m.plugin_responses);
+    write_histogram(&mut out, "rbxsync_plugin_response_seconds", "Time from dispatch to plugin response", &m.plugin_response_seconds);
+    write_counter(&mut out, "rbxsync_plugin_response_timeouts_total", "Requests that timed out waiting for the plugin", &m.plugin_response_timeouts);
+    write_counter(&mut out, "rbxsync_plugin_responses_dropped_total", "Responses that arrived after the handler stopped waiting", &m.plugin_responses_dropped);
+    write_counter(&mut out, "rbxsync_plugin_requests_cancelled_total", "Requests cancelled by timeout, client abort or explicit cancel", &m.plugin_requests_cancelled);
+    write_labeled(&mut out, "rbxsync_polls_total", "Plugin long-polls by result", "result", &m.polls);
+    write_histogram(&mut out, "rbxsync_poll_wait_seconds", "Time each plugin long-poll was held open", &m.poll_wait_seconds);
+    write_counter(&mut out, "rbxsync_live_sync_batches_total", "Live-sync batches queued", &m.live_sync_batches);
+    write_histogram(&mut out, "rbxsync_live_sync_batch_operations", "Operations per live-sync batch", &m.live_sync_batch_operations);
+    write_counter(&mut out, "rbxsync_live_sync_echoes_suppressed_total", "Watcher events dropped as echoes of server writes", &m.live_sync_echoes_suppressed);
+    write_labeled(&mut out, "rbxsync_live_sync_batch_results_total", "Live-sync batches completed, by status", "status", &m.live_sync_batch_results);
+    write_counter(&mut out, "rbxsync_live_sync_operations_failed_total", "Live-sync operations that failed in Studio", &m.live_sync_operations_failed);
+    write_counter(&mut out, "rbxsync_studio_stream_changes_total", "Instance changes streamed from Studio", &m.studio_stream_changes);
+    write_counter(&mut out, "rbxsync_studio_stream_coalesced_total", "Streamed Studio changes merged into a pending write", &m.studio_stream_coalesced);
+    write_counter(&mut out, "rbxsync_studio_stream_files_written_total", "Files written by Studio streaming", &m.studio_stream_files_written);
+    write_labeled(&mut out, "rbxsync_size_limit_hits_total", "Requests, files and extractions over a size limit", "limit", &m.size_limit_hits);
+    write_counter(&mut out, "rbxsync_extraction_chunks_total", "Extraction chunks received", &m.extraction_chunks);
+    write_counter(&mut out, "rbxsync_extraction_bytes_total", "Extraction chunk bytes received", &m.extraction_bytes);
+    write_counter(&mut out, "rbxsync_extraction_instances_total", "Instances written by extractions", &m.extraction_instances);
+    write_histogram(&mut out, "rbxsync_extraction_seconds", "Extraction duration from first chunk to finalize", &m.extraction_seconds);
+    write_counter(&mut out, "rbxsync_console_messages_total", "Console messages pushed by the plugin", &m.console_messages);
+    write_counter(&mut out, "rbxsync_console_dropped_total", "Console messages dropped for slow subscribers", &m.console_dropped);
+    write_counter(&mut out, "rbxsync_events_lagged_total", "Events skipped on the live channel for slow subscribers", &m.events_lagged);
+
+    out
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    write_header(out, name, "gauge", help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, counter: &Counter) {
+    write_header(out, name, "counter", help);
+    let _ = writeln!(out, "{} {}", name, counter.get());
+}
+
+fn write_labeled(out: &mut String, name: &str, help: &str, label: &str, counter: &LabeledCounter) {
+    write_header(out, name, "counter", help);
+    for (value, count) in counter.snapshot() {
+        let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, escape_label(&value), count);
+    }
+}
+
+fn write_histogram(out: &mut String, name: &str, help: &str, histogram: &Histogram) {
+    write_header(out, name, "histogram", help);
+    let data = histogram.data.lock().unwrap_or_else(|e| e.into_inner());
+    for (bound, count) in histogram.buckets.iter().zip(&data.counts) {
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+    }
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, data.count);
+    let _ = writeln!(out, "{}_sum {}", name, data.sum);
+    let _ = writeln!(out, "{}_count {}", name, data.count);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
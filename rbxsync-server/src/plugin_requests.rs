@@ -0,0 +1,257 @@
+//! Plugin request deadlines and cancellation
+//!
+//! Handlers that wait on a plugin response register the request here with
+//! their timeout. The deadline is stamped onto the request when the plugin
+//! polls it, so the plugin can skip work the server has already given up on.
+//! When a handler times out or its HTTP client goes away (an aborted MCP tool
+//! call or CLI command), the request is cancelled: removed from the queue if
+//! it was never dispatched, otherwise a `cancel` command is sent to the plugin.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{events, AppState, PluginRequest, PluginResponse};
+
+/// Command sent to the plugin to abort a dispatched request
+pub const CANCEL_COMMAND: &str = "cancel";
+
+/// Plugin response timeouts by command
+///
+/// Lookup order: exact command (`run:code`), command group (`run`), default.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandTimeouts {
+    #[serde(serialize_with = "serialize_secs")]
+    pub default: Duration,
+    #[serde(serialize_with = "serialize_secs_map")]
+    pub overrides: HashMap<String, Duration>,
+}
+
+impl Default for CommandTimeouts {
+    fn default() -> Self {
+        let overrides = [
+            ("sync:batch", 300),
+            ("studio:paths", 60),
+            ("studio:hashes", 300),
+            ("studio:read", 120),
+            ("test:output", 10),
+            ("explore-hierarchy:get", 60),
+            ("find-instances:search", 60),
+            ("insert:model", 60),
+            ("bot:move", 60),
+        ]
+        .into_iter()
+        .map(|(command, secs)| (command.to_string(), Duration::from_secs(secs)))
+        .collect();
+
+        Self {
+            default: Duration::from_secs(30),
+            overrides,
+        }
+    }
+}
+
+impl CommandTimeouts {
+    /// Timeout for a command
+    pub fn get(&self, command: &str) -> Duration {
+        if let Some(timeout) = self.overrides.get(command) {
+            return *timeout;
+        }
+        if let Some((group, _)) = command.split_once(':') {
+            if let Some(timeout) = self.overrides.get(group) {
+                return *timeout;
+            }
+        }
+        self.default
+    }
+
+    /// Set the timeout for a command, command group or `default`
+    pub fn set(&mut self, key: &str, timeout: Duration) {
+        if key == "default" {
+            self.default = timeout;
+        } else {
+            self.overrides.insert(key.to_string(), timeout);
+        }
+    }
+
+    /// Apply a `COMMAND=SECONDS` override (as passed to `--timeout`)
+    pub fn apply_override(&mut self, spec: &str) -> Result<(), String> {
+        let (key, secs) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid timeout '{}', expected COMMAND=SECONDS", spec))?;
+        let secs: u64 = secs
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid timeout seconds in '{}'", spec))?;
+        if secs == 0 {
+            return Err(format!("Timeout must be positive in '{}'", spec));
+        }
+        self.set(key.trim(), Duration::from_secs(secs));
+        Ok(())
+    }
+}
+
+fn serialize_secs<S: serde::Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(value.as_secs())
+}
+
+fn serialize_secs_map<S: serde::Serializer>(
+    value: &HashMap<String, Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let secs: std::collections::BTreeMap<&String, u64> =
+        value.iter().map(|(k, v)| (k, v.as_secs())).collect();
+    secs.serialize(serializer)
+}
+
+/// A request a handler is waiting on
+#[derive(Debug, Clone)]
+pub struct PendingInfo {
+    /// Unix timestamp in millis after which the handler stops waiting
+    pub deadline: u64,
+    /// Queue the plugin polled it from (`None` = global queue), once dispatched
+    pub dispatched_from: Option<Option<String>>,
+}
+
+/// Where a cancelled request was when it was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CancelOutcome {
+    /// Removed from a queue before the plugin saw it
+    Queued,
+    /// Already sent to the plugin; a cancel command was queued
+    Dispatched,
+    /// Not queued or pending (already answered or never existed)
+    Unknown,
+}
+
+/// Registration for a pending request; cancels the request when dropped
+///
+/// Dropping after the response arrived is a no-op. Dropping early (timeout,
+/// or the handler future dropped because the client disconnected) cancels.
+pub struct PendingRequest {
+    state: Arc<AppState>,
+    id: Uuid,
+    expires: Instant,
+}
+
+impl PendingRequest {
+    /// Register a request before queueing it
+    pub async fn register(state: &Arc<AppState>, id: Uuid, timeout: Duration) -> Self {
+        let deadline = SystemTime::now()
+            .checked_add(timeout)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        state.pending_requests.write().await.insert(
+            id,
+            PendingInfo {
+                deadline,
+                dispatched_from: None,
+            },
+        );
+        Self {
+            state: state.clone(),
+            id,
+            expires: Instant::now() + timeout,
+        }
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        let id = self.id;
+        let reason = if Instant::now() >= self.expires { "timeout" } else { "aborted" };
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                cancel_request(&state, id, reason).await;
+            });
+        }
+    }
+}
+
+/// Stamp the deadline on a request the plugin just polled
+pub async fn mark_dispatched(state: &AppState, request: &mut PluginRequest, queue: Option<String>) {
+    if let Some(info) = state.pending_requests.write().await.get_mut(&request.id) {
+        request.deadline = Some(info.deadline);
+        info.dispatched_from = Some(queue);
+    }
+}
+
+/// Forget a request once its response has arrived
+pub async fn mark_answered(state: &AppState, id: Uuid) {
+    state.pending_requests.write().await.remove(&id);
+}
+
+/// Cancel a request wherever it is
+pub async fn cancel_request(state: &AppState, id: Uuid, reason: &str) -> CancelOutcome {
+    let mut outcome = CancelOutcome::Unknown;
+
+    // Not dispatched yet - just drop it from its queue
+    if remove_from_queues(state, id).await {
+        outcome = CancelOutcome::Queued;
+    }
+
+    let pending = state.pending_requests.write().await.remove(&id);
+    if outcome == CancelOutcome::Unknown {
+        if let Some(PendingInfo { dispatched_from: Some(queue), .. }) = pending {
+            // Tell the plugin to abort (or drop the result of) the request
+            let cancel = PluginRequest::new(
+                Uuid::new_v4(),
+                CANCEL_COMMAND,
+                serde_json::json!({ "requestId": id, "reason": reason }),
+            );
+            match queue {
+                Some(dir) => {
+                    let mut queues = state.project_queues.write().await;
+                    queues.entry(dir).or_default().push_back(cancel);
+                }
+                None => state.request_queue.lock().await.push_back(cancel),
+            }
+            let _ = state.trigger.send(());
+            outcome = CancelOutcome::Dispatched;
+        }
+    }
+
+    // Wake the waiting handler, if it is still waiting
+    if let Some(sender) = state.response_channels.write().await.remove(&id) {
+        let _ = sender.send(PluginResponse {
+            id,
+            success: false,
+            data: serde_json::json!({ "cancelled": true, "reason": reason }),
+            error: Some(format!("Request cancelled ({})", reason)),
+        });
+    }
+
+    if outcome != CancelOutcome::Unknown {
+        tracing::info!("Cancelled request {} ({}, {:?})", id, reason, outcome);
+        state.metrics.plugin_requests_cancelled.inc();
+        state.events.publish(events::ServerEvent::RequestCancelled {
+            request_id: id.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+    outcome
+}
+
+async fn remove_from_queues(state: &AppState, id: Uuid) -> bool {
+    {
+        let mut queue = state.request_queue.lock().await;
+        if let Some(pos) = queue.iter().position(|r| r.id == id) {
+            queue.remove(pos);
+            return true;
+        }
+    }
+    let mut queues = state.project_queues.write().await;
+    for queue in queues.values_mut() {
+        if let Some(pos) = queue.iter().position(|r| r.id == id) {
+            queue.remove(pos);
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,204 @@
+//! Persisted server state
+//!
+//! Opt-in on-disk store for the durable parts of `AppState` so that
+//! `rbxsync stop`/`serve` or a crash doesn't forget connected places,
+//! VS Code workspaces, incremental sync timestamps, bot results and the
+//! console buffer. In-flight work (request queues, response channels,
+//! extraction sessions, operation state) is intentionally not persisted.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{AppState, ConsoleMessage, PlaceInfo, VsCodeWorkspace, CONSOLE_BUFFER_SIZE};
+
+/// Bump when the persisted format changes incompatibly
+const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Durable snapshot of server state
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedState {
+    pub version: u32,
+    /// Unix timestamp in millis when the snapshot was taken
+    pub saved_at: u64,
+    /// Connected Studio places (registry key -> place)
+    #[serde(default)]
+    pub places: HashMap<String, PlaceInfo>,
+    /// Registered VS Code workspace directories
+    #[serde(default)]
+    pub vscode_workspaces: Vec<String>,
+    /// Last incremental sync time per project (Unix millis)
+    #[serde(default)]
+    pub sync_state: HashMap<String, u64>,
+    /// Bot command results not yet collected
+    #[serde(default)]
+    pub bot_command_results: HashMap<Uuid, serde_json::Value>,
+    /// Recent console messages
+    #[serde(default)]
+    pub console_buffer: Vec<ConsoleMessage>,
+}
+
+impl PersistedState {
+    /// Snapshot the durable parts of the server state
+    pub async fn capture(state: &AppState) -> Self {
+        let places = state.place_registry.read().await.clone();
+        let mut vscode_workspaces: Vec<String> = state
+            .vscode_workspaces
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect();
+        vscode_workspaces.sort();
+        let sync_state = state
+            .sync_state
+            .read()
+            .await
+            .iter()
+            .map(|(dir, time)| (dir.clone(), crate::system_time_to_millis(*time)))
+            .collect();
+        let bot_command_results = state.bot_command_results.read().await.clone();
+        let console_buffer = state.console_buffer.read().await.iter().cloned().collect();
+
+        Self {
+            version: STATE_FORMAT_VERSION,
+            saved_at: crate::now_millis(),
+            places,
+            vscode_workspaces,
+            sync_state,
+            bot_command_results,
+            console_buffer,
+        }
+    }
+
+    /// Restore a snapshot into a fresh server state
+    ///
+    /// Restored places and workspaces get a fresh heartbeat so they survive
+    /// the normal stale-cleanup window while Studio and VS Code reconnect.
+    pub async fn apply(self, state: &AppState) {
+        let now = Instant::now();
+
+        {
+            let mut registry = state.place_registry.write().await;
+            let mut queues = state.project_queues.write().await;
+            for (key, mut place) in self.places {
+                place.last_heartbeat = Some(now);
+                if !place.project_dir.is_empty() {
+                    queues.entry(place.project_dir.clone()).or_default();
+                }
+                registry.insert(key, place);
+            }
+        }
+
+        {
+            let mut workspaces = state.vscode_workspaces.write().await;
+            for dir in self.vscode_workspaces {
+                workspaces.insert(dir.clone(), VsCodeWorkspace {
+                    workspace_dir: dir,
+                    last_heartbeat: Some(now),
+                });
+            }
+        }
+
+        {
+            let mut sync_state = state.sync_state.write().await;
+            for (dir, millis) in self.sync_state {
+                sync_state.insert(dir, UNIX_EPOCH + Duration::from_millis(millis));
+            }
+        }
+
+        state.bot_command_results.write().await.extend(self.bot_command_results);
+
+        {
+            let mut buffer = state.console_buffer.write().await;
+            let skip = self.console_buffer.len().saturating_sub(CONSOLE_BUFFER_SIZE);
+            buffer.extend(self.console_buffer.into_iter().skip(skip));
+        }
+    }
+}
+
+/// On-disk location of persisted state for one server
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    /// Default state directory (`~/.rbxsync/state`)
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".rbxsync").join("state"))
+    }
+
+    /// Store for the server listening on `port` (one file per port)
+    pub fn new(dir: &Path, port: u16) -> Self {
+        Self {
+            path: dir.join(format!("server-{}.json", port)),
+        }
+    }
+
+    /// Path of the state file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load persisted state, returning `None` if nothing has been saved yet
+    pub fn load(&self) -> anyhow::Result<Option<PersistedState>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&self.path)?;
+        let persisted: PersistedState = serde_json::from_str(&content)?;
+        if persisted.version != STATE_FORMAT_VERSION {
+            tracing::warn!(
+                "Ignoring persisted state with unsupported version {} at {}",
+                persisted.version,
+                self.path.display()
+            );
+            return Ok(None);
+        }
+        Ok(Some(persisted))
+    }
+
+    /// Save state atomically so a crash never leaves a torn file
+    pub fn save(&self, persisted: &PersistedState) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(persisted)?;
+        crate::atomic_fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    /// Snapshot and save the current server state
+    pub async fn save_state(&self, state: &AppState) -> anyhow::Result<()> {
+        let persisted = PersistedState::capture(state).await;
+        self.save(&persisted)
+    }
+
+    /// Periodically save state until the task is dropped
+    ///
+    /// Skips the write when nothing but the timestamp changed.
+    pub async fn run_autosave(self, state: std::sync::Arc<AppState>, interval: Duration) {
+        let mut last_written = String::new();
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let mut persisted = PersistedState::capture(&state).await;
+            let saved_at = persisted.saved_at;
+            persisted.saved_at = 0;
+            let Ok(fingerprint) = serde_json::to_string(&persisted) else {
+                continue;
+            };
+            if fingerprint == last_written {
+                continue;
+            }
+            persisted.saved_at = saved_at;
+
+            match self.save(&persisted) {
+                Ok(()) => last_written = fingerprint,
+                Err(e) => tracing::warn!("Failed to persist server state: {}", e),
+            }
+        }
+    }
+}
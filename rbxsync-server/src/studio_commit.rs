@@ -0,0 +1,212 @@
+//! Committing Studio changes
+//!
+//! `/git/commit-studio` writes whatever Studio changes are still pending,
+//! stages only the files those changes touched and commits them with a
+//! message summarizing the instance-level changes, e.g.
+//! `Workspace: moved 3 parts; ServerScriptService/Combat: edited`. Anything
+//! else in the working tree or index is left alone.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::git::{self, GitError};
+use crate::studio_stream::{self, ChangeAction, StudioChange};
+use crate::AppState;
+
+/// Longest generated subject before it falls back to a count per service
+pub const MAX_SUBJECT_LEN: usize = 72;
+
+/// Result of `/git/commit-studio`
+#[derive(Debug, Clone, Serialize)]
+pub struct StudioCommit {
+    pub commit: String,
+    pub message: String,
+    /// Committed files, relative to the project
+    pub files: Vec<String>,
+    pub changes: Vec<StudioChange>,
+}
+
+/// Write pending Studio changes and commit the files they touched
+///
+/// `message` replaces the generated subject line; the list of changes is
+/// still added as the body.
+pub async fn commit(state: &Arc<AppState>, project_dir: &str, message: Option<&str>) -> Result<StudioCommit, GitError> {
+    let project_path = Path::new(project_dir);
+    git::git_dir(project_path)?;
+
+    // Not while a checkout is replacing src
+    if !state.live_sync_paused.load(std::sync::atomic::Ordering::Relaxed) {
+        studio_stream::flush_project(state, project_dir, true).await;
+    }
+
+    let journal = state.studio_stream.journal(project_dir);
+    let mut paths: Vec<String> = journal
+        .iter()
+        .flat_map(|change| &change.files)
+        .filter_map(|file| file.strip_prefix(project_path).ok())
+        .map(rbxsync_core::path_to_string)
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let files = git::stage_files(project_path, &paths)?;
+    let changes: Vec<StudioChange> = journal
+        .iter()
+        .filter(|change| {
+            change.files.iter().any(|file| {
+                let Ok(rel) = file.strip_prefix(project_path) else {
+                    return false;
+                };
+                let rel = rbxsync_core::path_to_string(rel);
+                files
+                    .iter()
+                    .any(|f| *f == rel || f.strip_prefix(&rel).is_some_and(|rest| rest.starts_with('/')))
+            })
+        })
+        .cloned()
+        .collect();
+    if files.is_empty() {
+        // Whatever is left in the journal matches HEAD already
+        state.studio_stream.forget(project_dir, &journal);
+        return Err(GitError::NothingToCommit);
+    }
+
+    let message = commit_message(&changes, message);
+    let commit = git::commit_files(project_path, &message, &files)?;
+    state.studio_stream.forget(project_dir, &journal);
+    tracing::info!("Committed {} Studio change(s) in {} as {}", changes.len(), project_dir, commit);
+
+    Ok(StudioCommit {
+        commit,
+        message,
+        files,
+        changes,
+    })
+}
+
+/// Commit message for a set of Studio changes
+///
+/// Changes with the same parent, action and class are counted together
+/// (`Workspace: moved 3 parts`) and a lone change names its instance
+/// (`ServerScriptService/Combat: edited`). The body lists every change.
+pub fn commit_message(changes: &[StudioChange], subject: Option<&str>) -> String {
+    let subject = match subject.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(subject) => subject.to_string(),
+        None => summary(changes),
+    };
+
+    let mut lines: Vec<String> = changes
+        .iter()
+        .map(|change| match &change.old_path {
+            Some(old_path) => format!("- {} {} → {}", verb(change.action), old_path, change.path),
+            None => format!("- {} {}", verb(change.action), change.path),
+        })
+        .collect();
+    lines.sort();
+    if lines.is_empty() {
+        return subject;
+    }
+    format!("{}\n\n{}", subject, lines.join("\n"))
+}
+
+/// One-line summary of the changes
+fn summary(changes: &[StudioChange]) -> String {
+    if changes.is_empty() {
+        return "Studio changes".to_string();
+    }
+
+    let mut groups: BTreeMap<(&str, u8, Option<&str>), Vec<&StudioChange>> = BTreeMap::new();
+    for change in changes {
+        let key = (parent_path(&change.path), action_order(change.action), change.class_name.as_deref());
+        groups.entry(key).or_default().push(change);
+    }
+
+    let mut segments: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for ((parent, _, class_name), group) in groups {
+        let (location, text) = match group.as_slice() {
+            [change] => (change.path.clone(), verb(change.action).to_string()),
+            _ => {
+                let location = if parent.is_empty() { "game" } else { parent };
+                let text = format!("{} {} {}", verb(group[0].action), group.len(), plural_class(class_name));
+                (location.to_string(), text)
+            }
+        };
+        segments.entry(location).or_default().push(text);
+    }
+
+    let subject = segments
+        .iter()
+        .map(|(location, texts)| format!("{}: {}", location, texts.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ");
+    if subject.chars().count() <= MAX_SUBJECT_LEN {
+        return subject;
+    }
+
+    let mut services: Vec<&str> = changes
+        .iter()
+        .map(|change| change.path.split('/').next().unwrap_or(""))
+        .collect();
+    services.sort();
+    services.dedup();
+    format!("Studio: {} changes in {}", changes.len(), services.join(", "))
+}
+
+fn verb(action: ChangeAction) -> &'static str {
+    match action {
+        ChangeAction::Added => "added",
+        ChangeAction::Edited => "edited",
+        ChangeAction::Removed => "removed",
+        ChangeAction::Moved => "moved",
+        ChangeAction::Renamed => "renamed",
+    }
+}
+
+fn action_order(action: ChangeAction) -> u8 {
+    match action {
+        ChangeAction::Added => 0,
+        ChangeAction::Edited => 1,
+        ChangeAction::Moved => 2,
+        ChangeAction::Renamed => 3,
+        ChangeAction::Removed => 4,
+    }
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+/// `MeshPart` → `mesh parts`
+fn plural_class(class_name: Option<&str>) -> String {
+    let Some(class_name) = class_name.filter(|c| !c.is_empty()) else {
+        return "instances".to_string();
+    };
+
+    // Split at case changes, keeping acronyms together (UIListLayout → UI List Layout)
+    let chars: Vec<char> = class_name.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let starts_word = i > 0
+            && c.is_uppercase()
+            && (chars[i - 1].is_lowercase()
+                || (chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|next| next.is_lowercase())));
+        if starts_word || words.is_empty() {
+            words.push(String::new());
+        }
+        words.last_mut().unwrap().extend(c.to_lowercase());
+    }
+
+    let last = words.last_mut().unwrap();
+    if last.ends_with('s') || last.ends_with('x') || last.ends_with("ch") || last.ends_with("sh") {
+        last.push_str("es");
+    } else if last.ends_with('y') && !last.ends_with("ay") && !last.ends_with("ey") && !last.ends_with("oy") {
+        last.pop();
+        last.push_str("ies");
+    } else {
+        last.push('s');
+    }
+    words.join(" ")
+}
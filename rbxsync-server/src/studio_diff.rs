@@ -0,0 +1,189 @@
+//! Diff between the project's files and Studio
+//!
+//! The plugin hashes every tracked instance (`studio:hashes`) and the files
+//! are hashed the same way, so matching instances are settled without sending
+//! their properties. Only instances whose hashes differ are read in full
+//! (`studio:read`) and compared value by value.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use rbxsync_core::{diff_instance, InstanceDiff, InstanceHashes, PropertyScope, SourceFormat};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::plugin_requests::PendingRequest;
+use crate::{AppState, DiffEntry, PluginRequest};
+
+/// Paths per `studio:read` request
+const READ_BATCH: usize = 500;
+
+/// Result of comparing the files with Studio
+#[derive(Debug, Serialize)]
+pub struct ProjectDiff {
+    /// In files, not in Studio (would be created)
+    pub added: Vec<DiffEntry>,
+    /// In Studio, not in files (would be deleted)
+    pub removed: Vec<DiffEntry>,
+    /// In both but different (old is Studio, new is the files)
+    pub modified: Vec<InstanceDiff>,
+    /// In both and identical
+    pub unchanged: usize,
+    /// Paths shared by several instances on one side, which can't be matched
+    pub ambiguous: Vec<String>,
+    /// In both (`modified` + `unchanged` + ambiguous paths on both sides)
+    pub common: usize,
+    pub file_count: usize,
+    pub studio_count: usize,
+}
+
+/// An instance as reported by `studio:hashes`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StudioHashes {
+    path: String,
+    #[serde(default)]
+    class_name: String,
+    hashes: InstanceHashes,
+}
+
+/// Compare the project's files with the open place
+pub async fn diff_project(state: &Arc<AppState>, project_dir: &str) -> Result<ProjectDiff, (StatusCode, String)> {
+    let format = SourceFormat::from_project_config(crate::load_project_config(project_dir).as_ref());
+
+    let mut files: HashMap<String, serde_json::Value> = HashMap::new();
+    let mut ambiguous: HashSet<String> = HashSet::new();
+    for inst in crate::read_tree_instances(project_dir) {
+        let Some(path) = inst.get("path").and_then(|v| v.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if files.insert(path.clone(), inst).is_some() {
+            ambiguous.insert(path);
+        }
+    }
+
+    let data = plugin_command(state, "studio:hashes", serde_json::json!({})).await?;
+    let entries: Vec<StudioHashes> = serde_json::from_value(data.get("instances").cloned().unwrap_or_default())
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Invalid studio:hashes response: {}", e)))?;
+    let studio_count = entries.len();
+    tracing::info!("Comparing {} file instances with {} Studio instances", files.len(), studio_count);
+
+    let mut studio: HashMap<String, StudioHashes> = HashMap::new();
+    for entry in entries {
+        let path = entry.path.clone();
+        if studio.insert(path.clone(), entry).is_some() {
+            ambiguous.insert(path);
+        }
+    }
+
+    let class_of = |inst: &serde_json::Value| {
+        inst.get("className").and_then(|v| v.as_str()).unwrap_or_default().to_string()
+    };
+    let mut added: Vec<DiffEntry> = files
+        .iter()
+        .filter(|(path, _)| !studio.contains_key(*path))
+        .map(|(path, inst)| DiffEntry { path: path.clone(), class_name: class_of(inst) })
+        .collect();
+    let mut removed: Vec<DiffEntry> = studio
+        .iter()
+        .filter(|(path, _)| !files.contains_key(*path))
+        .map(|(path, entry)| DiffEntry { path: path.clone(), class_name: entry.class_name.clone() })
+        .collect();
+    added.sort_by(|a, b| a.path.cmp(&b.path));
+    removed.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let common = files.keys().filter(|path| studio.contains_key(*path)).count();
+    let mut mismatched: Vec<String> = files
+        .iter()
+        .filter(|(path, _)| !ambiguous.contains(*path))
+        .filter(|(path, inst)| {
+            studio.get(*path).is_some_and(|entry| entry.hashes != InstanceHashes::of(inst))
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    mismatched.sort();
+
+    let mut modified = Vec::new();
+    let ambiguous_common = ambiguous
+        .iter()
+        .filter(|path| files.contains_key(*path) && studio.contains_key(*path))
+        .count();
+    let mut unchanged = common - ambiguous_common - mismatched.len();
+    for batch in mismatched.chunks(READ_BATCH) {
+        let data = plugin_command(state, "studio:read", serde_json::json!({ "paths": batch })).await?;
+        let instances = data.get("instances").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        for studio_inst in &instances {
+            let Some(path) = studio_inst.get("path").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let Some(file_inst) = files.get(path) else {
+                continue;
+            };
+            match diff_instance(path, studio_inst, file_inst, &format, PropertyScope::New) {
+                Some(diff) => modified.push(diff),
+                // Hashes can differ on values the comparison treats as equal
+                // (trailing newlines, properties only Studio reports)
+                None => unchanged += 1,
+            }
+        }
+        if let Some(missing) = data.get("missing").and_then(|v| v.as_array()) {
+            for path in missing.iter().filter_map(|v| v.as_str()) {
+                tracing::warn!("{} disappeared from Studio during the diff", path);
+            }
+        }
+    }
+
+    modified.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut ambiguous: Vec<String> = ambiguous.into_iter().collect();
+    ambiguous.sort();
+
+    Ok(ProjectDiff {
+        added,
+        removed,
+        modified,
+        unchanged,
+        ambiguous,
+        common,
+        file_count: files.len(),
+        studio_count,
+    })
+}
+
+/// Send a command to the plugin and wait for its data
+async fn plugin_command(
+    state: &Arc<AppState>,
+    command: &str,
+    payload: serde_json::Value,
+) -> Result<serde_json::Value, (StatusCode, String)> {
+    let request_id = Uuid::new_v4();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    state.response_channels.write().await.insert(request_id, tx);
+
+    let timeout = state.timeout_for(command).await;
+    let _pending = PendingRequest::register(state, request_id, timeout).await;
+    state
+        .request_queue
+        .lock()
+        .await
+        .push_back(PluginRequest::new(request_id, command, payload));
+    let _ = state.trigger.send(());
+
+    // Wait for response (cancelled on timeout or if the client disconnects)
+    let result = tokio::time::timeout(timeout, rx.recv()).await;
+    state.response_channels.write().await.remove(&request_id);
+
+    match result {
+        Ok(Some(response)) if response.success => Ok(response.data),
+        Ok(Some(response)) => Err((
+            StatusCode::BAD_GATEWAY,
+            response.error.unwrap_or_else(|| "Plugin returned error".to_string()),
+        )),
+        Ok(None) => Err((StatusCode::INTERNAL_SERVER_ERROR, "Channel closed".to_string())),
+        Err(_) => {
+            state.metrics.plugin_response_timeouts.inc();
+            Err((StatusCode::GATEWAY_TIMEOUT, format!("Timeout waiting for {}", command)))
+        }
+    }
+}
@@ -0,0 +1,805 @@
+//! Live Studio→disk streaming
+//!
+//! The plugin's ChangeTracker posts instance changes to `/sync/studio-stream`
+//! as they happen. Creates and property changes are coalesced per instance
+//! path and written once the instance has been quiet for a moment, and no
+//! file is rewritten more often than `MIN_WRITE_INTERVAL`, so dragging a part
+//! rewrites its `.rbxjson` a few times rather than on every frame. Deletes and
+//! renames are applied straight away. Files are laid out by the extraction
+//! writer and recorded with the echo filter so the file watcher doesn't send
+//! them back. Projects whose `sync.mode` is `push` don't accept changes.
+//!
+//! Every change that reaches disk is also recorded in a per-project journal
+//! of instance-level changes, which `/git/commit-studio` turns into a commit.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rbxsync_core::SourceFormat;
+use serde::Serialize;
+
+use crate::{writer, AppState, StudioChangeOperation};
+
+/// A pending change is written once no new change arrived for this long
+pub const QUIET_PERIOD: Duration = Duration::from_millis(150);
+
+/// ...or once it has been pending this long, even if changes keep coming
+pub const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Minimum time between two writes of the same instance
+pub const MIN_WRITE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A create or property change waiting to be written
+#[derive(Debug, Clone)]
+struct PendingWrite {
+    class_name: Option<String>,
+    data: serde_json::Value,
+    first_seen: Instant,
+    last_seen: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ProjectStream {
+    pending: HashMap<String, PendingWrite>,
+    last_write: HashMap<String, Instant>,
+    journal: Vec<StudioChange>,
+}
+
+/// What happened to an instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Added,
+    Edited,
+    Removed,
+    /// Reparented (possibly renamed as well)
+    Moved,
+    Renamed,
+}
+
+/// An instance-level change written to disk and not yet committed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudioChange {
+    pub action: ChangeAction,
+    /// Instance path (e.g. `Workspace/Map/Part`); the old path if removed
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_name: Option<String>,
+    /// Files and directories written, moved or removed for it
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
+}
+
+/// Pending Studio changes per project directory
+#[derive(Debug, Default)]
+pub struct StudioStream {
+    projects: Mutex<HashMap<String, ProjectStream>>,
+}
+
+/// Pending changes for one project (for `/sync/studio-stream/status`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStreamStatus {
+    pub project_dir: String,
+    pub pending: usize,
+    pub pending_paths: Vec<String>,
+}
+
+/// Result of applying changes to disk
+#[derive(Debug, Default)]
+pub struct WriteOutcome {
+    pub files_written: usize,
+    pub errors: Vec<String>,
+    /// Paths written, removed, or moved (old and new)
+    pub paths: Vec<PathBuf>,
+}
+
+impl WriteOutcome {
+    fn merge(&mut self, other: WriteOutcome) {
+        self.files_written += other.files_written;
+        self.errors.extend(other.errors);
+        self.paths.extend(other.paths);
+    }
+}
+
+impl StudioStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a create or property change; returns true if it was merged into
+    /// a change already pending for the same instance
+    fn queue(&self, project_dir: &str, path: &str, class_name: Option<String>, data: serde_json::Value) -> bool {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        let project = projects.entry(project_dir.to_string()).or_default();
+        let now = Instant::now();
+
+        match project.pending.get_mut(path) {
+            Some(pending) => {
+                merge_change(&mut pending.data, data);
+                if class_name.is_some() {
+                    pending.class_name = class_name;
+                }
+                pending.last_seen = now;
+                true
+            }
+            None => {
+                project.pending.insert(
+                    path.to_string(),
+                    PendingWrite {
+                        class_name,
+                        data,
+                        first_seen: now,
+                        last_seen: now,
+                    },
+                );
+                false
+            }
+        }
+    }
+
+    /// Drop pending writes for an instance and its descendants
+    fn discard(&self, project_dir: &str, path: &str) {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(project) = projects.get_mut(project_dir) {
+            project.pending.retain(|pending, _| !is_same_or_descendant(pending, path));
+        }
+    }
+
+    /// Re-key pending writes after a rename
+    fn rename(&self, project_dir: &str, from: &str, to: &str) {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(project) = projects.get_mut(project_dir) else {
+            return;
+        };
+        let moved: Vec<String> = project
+            .pending
+            .keys()
+            .filter(|path| is_same_or_descendant(path, from))
+            .cloned()
+            .collect();
+        for old in moved {
+            if let Some(pending) = project.pending.remove(&old) {
+                project.pending.insert(format!("{}{}", to, &old[from.len()..]), pending);
+            }
+        }
+    }
+
+    /// Take the writes that are due (all of them if `force`)
+    fn take_ready(&self, project_dir: &str, force: bool) -> Vec<(String, PendingWrite)> {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(project) = projects.get_mut(project_dir) else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let ready: Vec<String> = project
+            .pending
+            .iter()
+            .filter(|(path, pending)| {
+                if force {
+                    return true;
+                }
+                let settled = now.duration_since(pending.last_seen) >= QUIET_PERIOD
+                    || now.duration_since(pending.first_seen) >= MAX_DELAY;
+                let rate_ok = project
+                    .last_write
+                    .get(*path)
+                    .is_none_or(|last| now.duration_since(*last) >= MIN_WRITE_INTERVAL);
+                settled && rate_ok
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut taken = Vec::with_capacity(ready.len());
+        for path in ready {
+            if let Some(pending) = project.pending.remove(&path) {
+                project.last_write.insert(path.clone(), now);
+                taken.push((path, pending));
+            }
+        }
+        project
+            .last_write
+            .retain(|_, last| now.duration_since(*last) < MIN_WRITE_INTERVAL);
+        taken
+    }
+
+    /// Projects with pending writes
+    pub fn project_dirs(&self) -> Vec<String> {
+        let projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        projects
+            .iter()
+            .filter(|(_, project)| !project.pending.is_empty())
+            .map(|(dir, _)| dir.clone())
+            .collect()
+    }
+
+    pub fn status(&self) -> Vec<ProjectStreamStatus> {
+        let projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        let mut status: Vec<ProjectStreamStatus> = projects
+            .iter()
+            .map(|(dir, project)| {
+                let mut pending_paths: Vec<String> = project.pending.keys().cloned().collect();
+                pending_paths.sort();
+                ProjectStreamStatus {
+                    project_dir: dir.clone(),
+                    pending: pending_paths.len(),
+                    pending_paths,
+                }
+            })
+            .collect();
+        status.sort_by(|a, b| a.project_dir.cmp(&b.project_dir));
+        status
+    }
+
+    /// Number of writes pending for a project
+    pub fn pending_count(&self, project_dir: &str) -> usize {
+        let projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        projects.get(project_dir).map(|p| p.pending.len()).unwrap_or(0)
+    }
+
+    /// Add a change that reached disk to the project's journal
+    fn record(&self, project_dir: &str, change: StudioChange) {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        record_change(&mut projects.entry(project_dir.to_string()).or_default().journal, change);
+    }
+
+    /// Changes written to disk since they were last committed
+    pub fn journal(&self, project_dir: &str) -> Vec<StudioChange> {
+        let projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        projects.get(project_dir).map(|p| p.journal.clone()).unwrap_or_default()
+    }
+
+    /// Drop journal entries once they are committed (entries recorded or
+    /// merged since `changes` was taken are kept)
+    pub fn forget(&self, project_dir: &str, changes: &[StudioChange]) {
+        let mut projects = self.projects.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(project) = projects.get_mut(project_dir) {
+            for change in changes {
+                if let Some(index) = project.journal.iter().position(|c| c == change) {
+                    project.journal.remove(index);
+                }
+            }
+        }
+    }
+}
+
+/// Fold a change into the journal so each instance appears once
+///
+/// A create right after a delete of an instance with the same name and class
+/// is a move (the plugin reports reparenting that way), descendants added
+/// with their parent are part of it, and an instance added and removed again
+/// leaves nothing behind.
+fn record_change(journal: &mut Vec<StudioChange>, mut change: StudioChange) {
+    match change.action {
+        ChangeAction::Added | ChangeAction::Edited => {
+            if let Some(existing) = journal.iter_mut().find(|c| c.path == change.path) {
+                if existing.action == ChangeAction::Removed {
+                    existing.action = ChangeAction::Edited;
+                }
+                merge_into(existing, change);
+                return;
+            }
+            if change.action == ChangeAction::Added {
+                let parent_added = journal.iter_mut().find(|c| {
+                    matches!(c.action, ChangeAction::Added | ChangeAction::Moved)
+                        && is_descendant(&change.path, &c.path)
+                });
+                if let Some(parent) = parent_added {
+                    parent.files.extend(change.files);
+                    dedup_files(&mut parent.files);
+                    return;
+                }
+                let removed = journal.iter().position(|c| {
+                    c.action == ChangeAction::Removed
+                        && c.class_name == change.class_name
+                        && instance_name(&c.path) == instance_name(&change.path)
+                });
+                if let Some(index) = removed {
+                    let removed = journal.remove(index);
+                    change.action = ChangeAction::Moved;
+                    change.old_path = Some(removed.path);
+                    change.files.extend(removed.files);
+                }
+                // Descendants whose create came in first
+                journal.retain_mut(|c| {
+                    let absorbed = matches!(c.action, ChangeAction::Added | ChangeAction::Moved)
+                        && is_descendant(&c.path, &change.path);
+                    if absorbed {
+                        change.files.append(&mut c.files);
+                    }
+                    !absorbed
+                });
+                dedup_files(&mut change.files);
+            }
+            journal.push(change);
+        }
+        ChangeAction::Removed => {
+            // Descendants go with it
+            let mut files = std::mem::take(&mut change.files);
+            journal.retain_mut(|c| {
+                if is_descendant(&c.path, &change.path) {
+                    files.append(&mut c.files);
+                    false
+                } else {
+                    true
+                }
+            });
+            if let Some(index) = journal.iter().position(|c| c.path == change.path) {
+                let existing = journal.remove(index);
+                if existing.action == ChangeAction::Added {
+                    return;
+                }
+                if let Some(old_path) = existing.old_path {
+                    change.path = old_path;
+                }
+                files.extend(existing.files);
+                change.class_name = change.class_name.or(existing.class_name);
+            }
+            change.files = files;
+            dedup_files(&mut change.files);
+            // Nothing was on disk (e.g. a descendant of something already removed)
+            if !change.files.is_empty() {
+                journal.push(change);
+            }
+        }
+        ChangeAction::Moved | ChangeAction::Renamed => {
+            let from = change.old_path.clone().unwrap_or_default();
+            for c in journal.iter_mut().filter(|c| is_descendant(&c.path, &from)) {
+                c.path = format!("{}{}", change.path, &c.path[from.len()..]);
+            }
+            if let Some(existing) = journal.iter_mut().find(|c| c.path == from) {
+                existing.path = change.path.clone();
+                match existing.action {
+                    ChangeAction::Added => {}
+                    ChangeAction::Moved | ChangeAction::Renamed
+                        if existing.old_path.as_deref() == Some(change.path.as_str()) =>
+                    {
+                        // Back where it started
+                        existing.action = ChangeAction::Edited;
+                        existing.old_path = None;
+                    }
+                    ChangeAction::Moved | ChangeAction::Renamed => {
+                        let original = existing.old_path.clone().unwrap_or_default();
+                        existing.action = move_action(&original, &change.path);
+                    }
+                    _ => {
+                        existing.action = change.action;
+                        existing.old_path = Some(from);
+                    }
+                }
+                merge_into(existing, change);
+                return;
+            }
+            journal.push(change);
+        }
+    }
+}
+
+fn merge_into(existing: &mut StudioChange, change: StudioChange) {
+    if change.class_name.is_some() {
+        existing.class_name = change.class_name;
+    }
+    existing.files.extend(change.files);
+    dedup_files(&mut existing.files);
+}
+
+fn dedup_files(files: &mut Vec<PathBuf>) {
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+}
+
+/// `Renamed` if only the name changed, `Moved` if the parent did
+fn move_action(from: &str, to: &str) -> ChangeAction {
+    if parent_path(from) == parent_path(to) {
+        ChangeAction::Renamed
+    } else {
+        ChangeAction::Moved
+    }
+}
+
+fn parent_path(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+fn instance_name(path: &str) -> &str {
+    path.rsplit_once('/').map(|(_, name)| name).unwrap_or(path)
+}
+
+/// Whether Studio→disk sync is allowed by the project's `sync.mode`
+pub fn accepts_studio_changes(config: &Option<serde_json::Value>) -> bool {
+    let mode = config
+        .as_ref()
+        .and_then(|c| c.get("sync"))
+        .and_then(|s| s.get("mode"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("bidirectional");
+    mode != "push"
+}
+
+/// Apply a batch of Studio changes for a project
+///
+/// Deletes and renames are written immediately; creates and property changes
+/// are queued (or written now if `flush`).
+pub async fn receive(
+    state: &Arc<AppState>,
+    project_dir: &str,
+    operations: Vec<StudioChangeOperation>,
+    flush: bool,
+) -> WriteOutcome {
+    let src_dir = PathBuf::from(project_dir).join("src");
+    let tree_mapping = crate::get_tree_mapping(&crate::load_project_config(project_dir));
+    let mut outcome = WriteOutcome::default();
+
+    for op in operations {
+        state.metrics.studio_stream_changes.inc();
+        match op.change_type.as_str() {
+            "create" | "modify" => {
+                let Some(data) = op.data else {
+                    outcome.errors.push(format!("{} {} has no data", op.change_type, op.path));
+                    continue;
+                };
+                if state.studio_stream.queue(project_dir, &op.path, op.class_name, data) {
+                    state.metrics.studio_stream_coalesced.inc();
+                }
+            }
+            "delete" => {
+                state.studio_stream.discard(project_dir, &op.path);
+                let full_path = src_dir.join(crate::apply_tree_mapping(&op.path, &tree_mapping));
+                let deleted = delete_instance_files(state, &full_path);
+                state.studio_stream.record(
+                    project_dir,
+                    StudioChange {
+                        action: ChangeAction::Removed,
+                        path: op.path.clone(),
+                        old_path: None,
+                        class_name: op.class_name,
+                        files: deleted.paths.clone(),
+                    },
+                );
+                outcome.merge(deleted);
+            }
+            "rename" => {
+                let data = op.data.unwrap_or_default();
+                let (Some(old_path), Some(new_path)) = (
+                    data.get("oldPath").and_then(|v| v.as_str()),
+                    data.get("newPath").and_then(|v| v.as_str()),
+                ) else {
+                    outcome.errors.push("Rename operation missing oldPath or newPath".to_string());
+                    continue;
+                };
+                state.studio_stream.rename(project_dir, old_path, new_path);
+                let from = src_dir.join(crate::apply_tree_mapping(old_path, &tree_mapping));
+                let to = src_dir.join(crate::apply_tree_mapping(new_path, &tree_mapping));
+                let renamed = rename_instance_files(state, &from, &to);
+                state.studio_stream.record(
+                    project_dir,
+                    StudioChange {
+                        action: move_action(old_path, new_path),
+                        path: new_path.to_string(),
+                        old_path: Some(old_path.to_string()),
+                        class_name: op.class_name,
+                        files: renamed.paths.clone(),
+                    },
+                );
+                outcome.merge(renamed);
+            }
+            other => outcome.errors.push(format!("Unknown change type: {}", other)),
+        }
+    }
+
+    if flush {
+        outcome.merge(flush_project(state, project_dir, true).await);
+    }
+    state.metrics.studio_stream_files_written.add(outcome.files_written as u64);
+    outcome
+}
+
+/// Write the pending changes of a project that are due (all if `force`)
+pub async fn flush_project(state: &Arc<AppState>, project_dir: &str, force: bool) -> WriteOutcome {
+    let ready = state.studio_stream.take_ready(project_dir, force);
+    let mut outcome = WriteOutcome::default();
+    if ready.is_empty() {
+        return outcome;
+    }
+
+    let src_dir = PathBuf::from(project_dir).join("src");
+    let config = crate::load_project_config(project_dir);
+    let tree_mapping = crate::get_tree_mapping(&config);
+    let format = SourceFormat::from_project_config(config.as_ref());
+    for (path, pending) in ready {
+        let full_path = src_dir.join(crate::apply_tree_mapping(&path, &tree_mapping));
+        let created = instance_files_on_disk(&full_path).is_empty();
+        let class_name = pending
+            .class_name
+            .clone()
+            .or_else(|| pending.data.get("className").and_then(|c| c.as_str()).map(String::from));
+        match write_instance(state, &src_dir, &full_path, pending, &format) {
+            Ok(files) if files.is_empty() => {}
+            Ok(files) => {
+                outcome.files_written += files.len();
+                outcome.paths.extend(files.iter().cloned());
+                state.studio_stream.record(
+                    project_dir,
+                    StudioChange {
+                        action: if created { ChangeAction::Added } else { ChangeAction::Edited },
+                        path,
+                        old_path: None,
+                        class_name,
+                        files,
+                    },
+                );
+            }
+            Err(e) => outcome.errors.push(format!("{}: {}", path, e)),
+        }
+    }
+    for error in &outcome.errors {
+        tracing::warn!("Studio stream: {}", error);
+    }
+    outcome
+}
+
+/// Write due changes for every project, skipping while live sync is paused
+/// (an extraction is about to replace `src`)
+pub async fn flush_all(state: &Arc<AppState>, force: bool) {
+    if state.live_sync_paused.load(std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+    for project_dir in state.studio_stream.project_dirs() {
+        let outcome = flush_project(state, &project_dir, force).await;
+        state.metrics.studio_stream_files_written.add(outcome.files_written as u64);
+    }
+}
+
+/// Background task writing coalesced changes as they become due
+pub async fn run(state: Arc<AppState>) {
+    loop {
+        flush_all(&state, false).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Merge a later change into a pending one
+///
+/// A full serialization (has `className`) replaces the pending data; a
+/// partial one (e.g. just `Source`) updates the properties it carries.
+fn merge_change(pending: &mut serde_json::Value, change: serde_json::Value) {
+    if change.get("className").is_some() || !pending.is_object() {
+        *pending = change;
+        return;
+    }
+    let serde_json::Value::Object(change) = change else {
+        return;
+    };
+    for (key, value) in change {
+        match (key.as_str(), pending.get_mut("properties"), value) {
+            ("properties", Some(serde_json::Value::Object(props)), serde_json::Value::Object(new)) => {
+                props.extend(new);
+            }
+            (_, _, value) => {
+                pending[key.as_str()] = value;
+            }
+        }
+    }
+}
+
+fn is_same_or_descendant(path: &str, ancestor: &str) -> bool {
+    path == ancestor || is_descendant(path, ancestor)
+}
+
+fn is_descendant(path: &str, ancestor: &str) -> bool {
+    path.strip_prefix(ancestor).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Files in `full_path`'s parent directory that describe the instance named
+/// like `full_path` (scripts, `.rbxjson`, data files), plus its folder
+fn instance_files_on_disk(full_path: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some(name)) = (full_path.parent(), full_path.file_name().and_then(|n| n.to_str())) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if path.is_dir() {
+                filename == name
+            } else {
+                rbxsync_core::file_instance_path(filename).as_deref() == Some(name)
+            }
+        })
+        .collect()
+}
+
+/// Nearest `.model.json` that holds `full_path` as a descendant
+fn enclosing_model(src_dir: &Path, full_path: &Path) -> Option<PathBuf> {
+    full_path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(src_dir) && *dir != src_dir)
+        .map(|dir| rbxsync_core::pathbuf_with_suffix(dir, ".model.json"))
+        .find(|model| model.is_file())
+}
+
+/// Write one instance; returns the files written (or removed)
+fn write_instance(
+    state: &AppState,
+    src_dir: &Path,
+    full_path: &Path,
+    pending: PendingWrite,
+    format: &SourceFormat,
+) -> Result<Vec<PathBuf>, String> {
+    if let Some(model) = enclosing_model(src_dir, full_path) {
+        tracing::debug!("Studio stream: {:?} is inside {:?}, not written", full_path, model);
+        return Ok(Vec::new());
+    }
+
+    // Partial changes (a script's Source) only touch the source file
+    let full_serialization = pending.data.get("className").is_some();
+    let mut inst = pending.data;
+    if !full_serialization {
+        if let (Some(class_name), Some(obj)) = (&pending.class_name, inst.as_object_mut()) {
+            obj.insert("className".to_string(), serde_json::Value::String(class_name.clone()));
+        }
+    }
+    let existing = instance_files_on_disk(full_path);
+    let mut written = Vec::new();
+
+    // Keep data files (.json, .txt, .csv, .model.json) in their own format
+    if let Some(data_file) = existing.iter().find(|path| {
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        rbxsync_core::data_file_info(filename).is_some()
+    }) {
+        let file = crate::ExtractedDataFile {
+            rel_path: rbxsync_core::path_to_string(data_file.strip_prefix(src_dir).unwrap_or(data_file)),
+            content: std::fs::read_to_string(data_file).map_err(|e| e.to_string())?,
+        };
+        match crate::extracted_data_file_content(&inst, &file) {
+            Some(content) if content == file.content => return Ok(written),
+            Some(content) => {
+                write_file(state, data_file, &content)?;
+                written.push(data_file.clone());
+                return Ok(written);
+            }
+            None => {
+                // No longer representable as a data file; replace it
+                std::fs::remove_file(data_file).map_err(|e| e.to_string())?;
+                state.echo_filter.record_delete(data_file);
+                written.push(data_file.clone());
+            }
+        }
+    }
+
+    let is_container = full_path.is_dir();
+    let mut files = writer::instance_files(&inst, full_path, is_container);
+    if !full_serialization {
+        files.json = None;
+    }
+    if let Some(source) = files.source.as_mut() {
+        // Keep the existing file name (.lua, init scripts in a folder)
+        let existing_script = existing.iter().find(|path| {
+            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            rbxsync_core::script_file_info(filename).is_some()
+        });
+        if let Some(path) = existing_script {
+            source.path = path.clone();
+        } else if is_container {
+            let filename = source.path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let extension = filename.split_once('.').map(|(_, ext)| ext).unwrap_or("luau");
+            source.path = full_path.join(format!("init.{}", extension));
+        }
+        let existing = format
+            .uses_existing()
+            .then(|| std::fs::read_to_string(&source.path).ok())
+            .flatten();
+        source.content = format.to_disk(&source.content, existing.as_deref());
+    }
+
+    if let Some(parent) = full_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    for file in files.source.iter().chain(files.json.iter()) {
+        // Unchanged files aren't rewritten (a drag that ends where it started)
+        if std::fs::read_to_string(&file.path).is_ok_and(|current| current == file.content) {
+            continue;
+        }
+        write_file(state, &file.path, &file.content)?;
+        written.push(file.path.clone());
+    }
+    Ok(written)
+}
+
+fn write_file(state: &AppState, path: &Path, content: &str) -> Result<(), String> {
+    crate::atomic_fs::write(path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    state.echo_filter.record_write(path, content);
+    tracing::info!("Studio stream: wrote {:?}", path);
+    Ok(())
+}
+
+/// Remove every file of an instance
+fn delete_instance_files(state: &AppState, full_path: &Path) -> WriteOutcome {
+    let mut outcome = WriteOutcome::default();
+    for path in instance_files_on_disk(full_path) {
+        let result = if path.is_dir() {
+            record_tree_delete(state, &path);
+            std::fs::remove_dir_all(&path)
+        } else {
+            state.echo_filter.record_delete(&path);
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => {
+                tracing::info!("Studio stream: deleted {:?}", path);
+                outcome.files_written += 1;
+                outcome.paths.push(path);
+            }
+            Err(e) => outcome.errors.push(format!("Failed to delete {:?}: {}", path, e)),
+        }
+    }
+    outcome
+}
+
+/// Move every file of an instance to its new name and/or parent
+fn rename_instance_files(state: &AppState, from: &Path, to: &Path) -> WriteOutcome {
+    let mut outcome = WriteOutcome::default();
+    let (Some(old_name), Some(new_name), Some(new_parent)) = (
+        from.file_name().and_then(|n| n.to_str()),
+        to.file_name().and_then(|n| n.to_str()),
+        to.parent(),
+    ) else {
+        return outcome;
+    };
+    if let Err(e) = std::fs::create_dir_all(new_parent) {
+        outcome.errors.push(format!("Failed to create {:?}: {}", new_parent, e));
+        return outcome;
+    }
+
+    for path in instance_files_on_disk(from) {
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let target = new_parent.join(format!("{}{}", new_name, &filename[old_name.len()..]));
+        if path.is_dir() {
+            record_tree_delete(state, &path);
+        } else {
+            state.echo_filter.record_delete(&path);
+        }
+        match std::fs::rename(&path, &target) {
+            Ok(()) => {
+                if target.is_dir() {
+                    state.echo_filter.record_tree(&target);
+                } else if let Ok(content) = std::fs::read(&target) {
+                    state.echo_filter.record_write(&target, content);
+                }
+                tracing::info!("Studio stream: renamed {:?} -> {:?}", path, target);
+                outcome.files_written += 1;
+                outcome.paths.extend([path, target]);
+            }
+            Err(e) => outcome.errors.push(format!("Failed to rename {:?}: {}", path, e)),
+        }
+    }
+    outcome
+}
+
+/// Record a directory and everything in it as removed
+fn record_tree_delete(state: &AppState, dir: &Path) {
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        state.echo_filter.record_delete(&dir);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                state.echo_filter.record_delete(&path);
+            }
+        }
+    }
+}
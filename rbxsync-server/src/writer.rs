@@ -0,0 +1,73 @@
+//! Instance file layout
+//!
+//! How an instance from Studio maps to files: scripts get a `.luau` file for
+//! their source, and every instance gets an `.rbxjson` with its remaining
+//! properties (a sibling file for leaves, `_meta.rbxjson` inside the folder for
+//! instances with children). Extraction and Studio→disk streaming both write
+//! through here so they produce the same tree.
+
+use std::path::{Path, PathBuf};
+
+/// A file to write
+#[derive(Debug, Clone)]
+pub struct FileWrite {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Files written for one instance
+#[derive(Debug, Clone, Default)]
+pub struct InstanceFiles {
+    /// Script source (`.server.luau`, `.client.luau` or `.luau`)
+    pub source: Option<FileWrite>,
+    /// Properties other than `Source`
+    pub json: Option<FileWrite>,
+}
+
+/// Source file extension for a script class
+pub fn script_extension(class_name: &str) -> Option<&'static str> {
+    match class_name {
+        "Script" => Some(".server.luau"),
+        "LocalScript" => Some(".client.luau"),
+        "ModuleScript" => Some(".luau"),
+        _ => None,
+    }
+}
+
+/// Files for an instance whose filesystem path (without extension) is `full_path`
+///
+/// `is_container` instances keep their `.rbxjson` as `_meta.rbxjson` inside the
+/// folder at `full_path`; the caller creates that folder.
+pub fn instance_files(inst: &serde_json::Value, full_path: &Path, is_container: bool) -> InstanceFiles {
+    let class_name = inst.get("className").and_then(|v| v.as_str()).unwrap_or("Unknown");
+    let extension = script_extension(class_name);
+
+    let source = extension.and_then(|extension| {
+        rbxsync_core::diff::instance_source(inst).map(|source| FileWrite {
+            path: rbxsync_core::pathbuf_with_suffix(full_path, extension),
+            content: source.to_string(),
+        })
+    });
+
+    let json_path = if is_container {
+        full_path.join("_meta.rbxjson")
+    } else {
+        rbxsync_core::pathbuf_with_suffix(full_path, ".rbxjson")
+    };
+
+    // Source lives in the script file
+    let mut clean_inst = inst.clone();
+    if extension.is_some() {
+        if let Some(obj) = clean_inst.as_object_mut() {
+            obj.remove("source");
+        }
+        if let Some(props) = clean_inst.get_mut("properties").and_then(|p| p.as_object_mut()) {
+            props.remove("Source");
+        }
+    }
+    let json = serde_json::to_string_pretty(&clean_inst)
+        .ok()
+        .map(|content| FileWrite { path: json_path, content });
+
+    InstanceFiles { source, json }
+}
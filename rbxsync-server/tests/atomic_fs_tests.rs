@@ -0,0 +1,309 @@
+//! Tests for crash-safe writes and staged extraction
+//!
+//! Tests atomic single-file writes, the staged directory swap, recovery of
+//! interrupted swaps and undos, and that a failed finalize leaves `src`
+//! untouched.
+
+use std::fs;
+use std::path::Path;
+
+use axum_test::TestServer;
+use rbxsync_server::atomic_fs::{
+    self, StagedDir, BACKUP_DIR, COMPLETE_MARKER, REPLACED_SUFFIX, STAGING_DIR,
+};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_write_replaces_without_leftovers() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("nested/Main.luau");
+
+    atomic_fs::write(&path, "print('one')").unwrap();
+    atomic_fs::write(&path, "print('two')").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "print('two')");
+    assert_eq!(file_names(&dir.path().join("nested")), vec!["Main.luau"]);
+}
+
+#[test]
+fn test_staged_commit_swaps_and_backs_up() {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/Old.luau"), "old").unwrap();
+
+    let staged = StagedDir::begin(project.path(), "src").unwrap();
+    fs::write(staged.path().join("New.luau"), "new").unwrap();
+    // Nothing changes until commit
+    assert_eq!(file_names(&project.path().join("src")), vec!["Old.luau"]);
+    staged.commit().unwrap();
+
+    assert_eq!(file_names(&project.path().join("src")), vec!["New.luau"]);
+    assert_eq!(
+        file_names(&project.path().join(BACKUP_DIR).join("src")),
+        vec!["Old.luau"]
+    );
+    assert!(atomic_fs::recover(project.path()).is_empty());
+}
+
+#[test]
+fn test_recover_discards_incomplete_staging() {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/Main.luau"), "kept").unwrap();
+    fs::write(project.path().join("src/.Main.luau.1-0.rbxsync-tmp"), "half").unwrap();
+
+    // A build that crashed before completing
+    let staged = StagedDir::begin(project.path(), "src").unwrap();
+    fs::write(staged.path().join("Partial.luau"), "partial").unwrap();
+    std::mem::forget(staged);
+
+    let repaired = atomic_fs::recover(project.path());
+    assert_eq!(repaired.len(), 2);
+    assert!(!project.path().join(STAGING_DIR).exists());
+    assert_eq!(file_names(&project.path().join("src")), vec!["Main.luau"]);
+}
+
+#[test]
+fn test_recover_finishes_interrupted_swap() {
+    let project = TempDir::new().unwrap();
+    // Crash between moving src to the backup and moving the staged tree in
+    let staged = project.path().join(STAGING_DIR).join("src");
+    fs::create_dir_all(&staged).unwrap();
+    fs::write(staged.join("New.luau"), "new").unwrap();
+    fs::write(staged.join(COMPLETE_MARKER), "").unwrap();
+    fs::create_dir_all(project.path().join(BACKUP_DIR).join("src")).unwrap();
+
+    let repaired = atomic_fs::recover(project.path());
+    assert_eq!(repaired.len(), 1);
+    assert_eq!(file_names(&project.path().join("src")), vec!["New.luau"]);
+}
+
+#[test]
+fn test_restore_backup_swaps_back() {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/Old.luau"), "old").unwrap();
+    let staged = StagedDir::begin(project.path(), "src").unwrap();
+    fs::write(staged.path().join("New.luau"), "new").unwrap();
+    staged.commit().unwrap();
+
+    atomic_fs::restore_backup(project.path(), "src").unwrap();
+
+    assert_eq!(file_names(&project.path().join("src")), vec!["Old.luau"]);
+    assert!(file_names(&project.path().join(BACKUP_DIR)).is_empty());
+    assert!(atomic_fs::restore_backup(project.path(), "src").is_err());
+    assert_eq!(file_names(&project.path().join("src")), vec!["Old.luau"]);
+}
+
+#[test]
+fn test_recover_finishes_interrupted_restore() {
+    let project = TempDir::new().unwrap();
+    // Crash between setting src aside and moving the backup in
+    let backup = project.path().join(BACKUP_DIR);
+    fs::create_dir_all(backup.join("src")).unwrap();
+    fs::write(backup.join("src/Old.luau"), "old").unwrap();
+    let replaced = backup.join(format!("src{}", REPLACED_SUFFIX));
+    fs::create_dir_all(&replaced).unwrap();
+    fs::write(replaced.join("New.luau"), "new").unwrap();
+
+    let repaired = atomic_fs::recover(project.path());
+    assert_eq!(repaired.len(), 2);
+    assert_eq!(file_names(&project.path().join("src")), vec!["Old.luau"]);
+    assert!(file_names(&backup).is_empty());
+}
+
+#[test]
+fn test_recover_rolls_back_restore_without_backup() {
+    let project = TempDir::new().unwrap();
+    // The set-aside tree is all that is left of src
+    let replaced = project.path().join(BACKUP_DIR).join(format!("src{}", REPLACED_SUFFIX));
+    fs::create_dir_all(&replaced).unwrap();
+    fs::write(replaced.join("New.luau"), "new").unwrap();
+
+    atomic_fs::recover(project.path());
+    assert_eq!(file_names(&project.path().join("src")), vec!["New.luau"]);
+    assert!(!replaced.exists());
+}
+
+#[test]
+fn test_recover_drops_replaced_tree_after_restore() {
+    let project = TempDir::new().unwrap();
+    // Crash after the backup was moved in but before the old tree was deleted
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(project.path().join("src/Old.luau"), "old").unwrap();
+    let replaced = project.path().join(BACKUP_DIR).join(format!("src{}", REPLACED_SUFFIX));
+    fs::create_dir_all(&replaced).unwrap();
+
+    assert_eq!(atomic_fs::recover(project.path()).len(), 1);
+    assert_eq!(file_names(&project.path().join("src")), vec!["Old.luau"]);
+    assert!(!replaced.exists());
+}
+
+#[tokio::test]
+async fn test_failed_finalize_keeps_src() {
+    let project = TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().to_string();
+    let workspace = project.path().join("src/Workspace");
+    fs::create_dir_all(&workspace).unwrap();
+    fs::write(workspace.join("Existing.rbxjson"), "{}").unwrap();
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    // src is left alone while the extraction runs
+    assert!(workspace.join("Existing.rbxjson").exists());
+
+    // `A.rbxjson` has a child, so its folder collides with the file for `A`
+    server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": start["sessionId"],
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": [
+                { "className": "Part", "name": "A", "path": "Workspace/A", "referenceId": "a" },
+                { "className": "Folder", "name": "A.rbxjson", "path": "Workspace/A.rbxjson", "referenceId": "b" },
+                { "className": "Part", "name": "B", "path": "Workspace/A.rbxjson/B", "referenceId": "c" },
+            ],
+            "project_dir": project_dir,
+        }))
+        .await
+        .assert_status_ok();
+    assert!(project.path().join(STAGING_DIR).join("chunks").exists());
+
+    let response = server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await;
+    response.assert_status(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+    let body: serde_json::Value = response.json();
+    assert!(body["error"].as_str().unwrap().contains("src was not changed"));
+
+    assert_eq!(file_names(&workspace), vec!["Existing.rbxjson"]);
+    assert!(!project.path().join(STAGING_DIR).join("src").exists());
+    assert!(!project.path().join(BACKUP_DIR).exists());
+}
+
+#[tokio::test]
+async fn test_finalize_swaps_in_extracted_tree() {
+    let project = TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().to_string();
+    let workspace = project.path().join("src/Workspace");
+    fs::create_dir_all(&workspace).unwrap();
+    fs::write(workspace.join("Stale.rbxjson"), "{}").unwrap();
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": start["sessionId"],
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": [{ "className": "Part", "name": "Fresh", "path": "Workspace/Fresh", "referenceId": "f" }],
+            "project_dir": project_dir,
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+
+    assert_eq!(file_names(&workspace), vec!["Fresh.rbxjson"]);
+    assert_eq!(
+        file_names(&project.path().join(BACKUP_DIR).join("src/Workspace")),
+        vec!["Stale.rbxjson"]
+    );
+    assert!(!project.path().join(STAGING_DIR).join("src").exists());
+    assert!(!project.path().join(STAGING_DIR).join("chunks").exists());
+}
+
+#[tokio::test]
+async fn test_terrain_is_staged_until_finalize() {
+    let project = TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().to_string();
+    let terrain_dir = project.path().join("src/Workspace/Terrain");
+    fs::create_dir_all(&terrain_dir).unwrap();
+    fs::write(terrain_dir.join("terrain.rbxjson"), r#"{"chunks": ["old"]}"#).unwrap();
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let extract = |terrain: Option<serde_json::Value>| {
+        let server = &server;
+        let project_dir = project_dir.clone();
+        async move {
+            let start: serde_json::Value = server
+                .post("/extract/start")
+                .json(&json!({ "project_dir": project_dir }))
+                .await
+                .json();
+            server
+                .post("/extract/chunk")
+                .json(&json!({
+                    "session_id": start["sessionId"],
+                    "chunk_index": 0,
+                    "total_chunks": 1,
+                    "data": [{ "className": "Part", "name": "Part", "path": "Workspace/Part", "referenceId": "p" }],
+                    "project_dir": project_dir,
+                }))
+                .await
+                .assert_status_ok();
+            if let Some(terrain) = terrain {
+                for (index, chunk) in ["a", "b"].into_iter().enumerate() {
+                    server
+                        .post("/extract/terrain")
+                        .json(&json!({
+                            "project_dir": project_dir,
+                            "terrain": { "chunks": [chunk], "material": terrain },
+                            "batch_index": index + 1,
+                            "total_batches": 2,
+                        }))
+                        .await
+                        .assert_status_ok();
+                }
+            }
+        }
+    };
+
+    // Batches don't touch src while the extraction runs
+    extract(Some(json!("Grass"))).await;
+    let old: serde_json::Value = serde_json::from_str(&fs::read_to_string(terrain_dir.join("terrain.rbxjson")).unwrap()).unwrap();
+    assert_eq!(old["chunks"], json!(["old"]));
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+    let new: serde_json::Value = serde_json::from_str(&fs::read_to_string(terrain_dir.join("terrain.rbxjson")).unwrap()).unwrap();
+    assert_eq!(new["chunks"], json!(["a", "b"]));
+    assert!(!project.path().join(STAGING_DIR).join("terrain").exists());
+
+    // Extracting again without terrain drops it (RBXSYNC-27)
+    extract(None).await;
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+    assert!(!terrain_dir.exists());
+}
@@ -0,0 +1,180 @@
+//! Tests for branch switch handling
+//!
+//! Runs a real file watcher on a git repository in a temp directory and
+//! drives `branch_switch::poll` the way the live-sync loop does.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum_test::TestServer;
+use rbxsync_server::branch_switch::{self, GitActivity};
+use rbxsync_server::file_watcher::start_file_watcher;
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+}
+
+/// A project on `main`, with a `feature` branch that edits, adds and removes
+/// an instance
+fn project() -> TempDir {
+    let project = TempDir::new().unwrap();
+    let dir = project.path();
+    let map = dir.join("src/Workspace/Map");
+    fs::create_dir_all(&map).unwrap();
+    fs::write(dir.join("rbxsync.json"), r#"{"name": "Test"}"#).unwrap();
+    fs::write(map.join("_meta.rbxjson"), r#"{"className": "Folder"}"#).unwrap();
+    fs::write(map.join("Pad.rbxjson"), r#"{"className": "Part"}"#).unwrap();
+    fs::write(map.join("Tree.rbxjson"), r#"{"className": "Part"}"#).unwrap();
+    git(dir, &["init", "-q", "-b", "main"]);
+    git(dir, &["config", "user.email", "dev@example.com"]);
+    git(dir, &["config", "user.name", "Dev"]);
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", "Initial"]);
+
+    git(dir, &["checkout", "-q", "-b", "feature"]);
+    fs::write(map.join("Tree.rbxjson"), r#"{"className": "MeshPart"}"#).unwrap();
+    fs::write(map.join("Sign.rbxjson"), r#"{"className": "Part", "tags": ["Sign"]}"#).unwrap();
+    fs::remove_file(map.join("Pad.rbxjson")).unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", "Feature"]);
+    git(dir, &["checkout", "-q", "main"]);
+    project
+}
+
+/// Stops the watcher thread, which the runtime would otherwise wait for,
+/// even when a test fails
+struct Watching(Arc<AppState>, String);
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        if let Ok(mut watcher) = self.0.file_watcher_state.try_write() {
+            if let Some(running) = watcher.running.remove(&self.1) {
+                running.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+async fn watch(project: &TempDir) -> (Arc<AppState>, String, Watching) {
+    let state = AppState::new();
+    let dir = project.path().to_string_lossy().to_string();
+    start_file_watcher(dir.clone(), state.file_watcher_state.clone(), false)
+        .await
+        .unwrap();
+    // Let the watcher thread set up its watches
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let watching = Watching(state.clone(), dir.clone());
+    (state, dir, watching)
+}
+
+/// Poll until git activity has settled and any HEAD change has been
+/// compared, returning the last result
+async fn settle(state: &Arc<AppState>, dir: &str) -> GitActivity {
+    let mut seen = false;
+    for _ in 0..50 {
+        let activity = branch_switch::poll(state).await;
+        seen |= activity.held.contains(dir) || activity.paused.contains(dir);
+        if seen && !activity.held.contains(dir) && !state.branch_switches.is_comparing(dir) {
+            return activity;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("git activity in {} was never seen or never settled", dir);
+}
+
+#[tokio::test]
+async fn test_checkout_is_applied_as_one_batch() {
+    let project = project();
+    let (state, dir, _watching) = watch(&project).await;
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    git(project.path(), &["checkout", "-q", "feature"]);
+    let activity = settle(&state, &dir).await;
+    assert!(activity.paused.contains(&dir));
+
+    let body: serde_json::Value = server.get("/sync/branch-switch").await.json();
+    assert_eq!(body["count"], 1);
+    let switch = &body["switches"][0];
+    assert_eq!(switch["from"]["branch"], "main");
+    assert_eq!(switch["to"]["branch"], "feature");
+    assert_eq!(switch["action"], "checkout: moving from main to feature");
+    assert_eq!(switch["diff"]["added"][0]["path"], "Workspace/Map/Sign");
+    assert_eq!(switch["diff"]["removed"][0]["path"], "Workspace/Map/Pad");
+    assert_eq!(switch["diff"]["modified"][0]["path"], "Workspace/Map/Tree");
+
+    // Live sync stays paused until the switch is reviewed
+    assert!(branch_switch::poll(&state).await.paused.contains(&dir));
+
+    let body: serde_json::Value = server
+        .post("/sync/branch-switch")
+        .json(&json!({ "project_dir": dir }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+
+    let request: serde_json::Value = server.get("/rbxsync/request").await.json();
+    assert_eq!(request["command"], "sync:batch");
+    assert_eq!(request["id"], body["data"]["requestId"]);
+    let operations = request["payload"]["operations"].as_array().unwrap();
+    let ops: Vec<(&str, &str)> = operations
+        .iter()
+        .map(|op| (op["type"].as_str().unwrap(), op["path"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        ops,
+        vec![
+            ("create", "Workspace/Map/Sign"),
+            ("update", "Workspace/Map/Tree"),
+            ("delete", "Workspace/Map/Pad"),
+        ]
+    );
+    assert_eq!(operations[1]["data"]["className"], "MeshPart");
+
+    assert!(!branch_switch::poll(&state).await.paused.contains(&dir));
+    let body: serde_json::Value = server.get("/sync/branch-switch").await.json();
+    assert_eq!(body["count"], 0);
+}
+
+#[tokio::test]
+async fn test_commit_and_discard() {
+    let project = project();
+    let (state, dir, _watching) = watch(&project).await;
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    // Committing files that live sync already sent is not a branch switch
+    let tree = project.path().join("src/Workspace/Map/Tree.rbxjson");
+    fs::write(&tree, r#"{"className": "WedgePart"}"#).unwrap();
+    git(project.path(), &["commit", "-q", "-am", "Wedge"]);
+    let activity = settle(&state, &dir).await;
+    assert!(!activity.paused.contains(&dir));
+    assert!(state.branch_switches.get(&dir).is_none());
+
+    git(project.path(), &["reset", "-q", "--hard", "HEAD~1"]);
+    settle(&state, &dir).await;
+    let switch = state.branch_switches.get(&dir).unwrap();
+    assert_eq!(switch.diff.modified.len(), 1);
+    assert!(switch.action.unwrap().starts_with("reset"));
+
+    let body: serde_json::Value = server
+        .post("/sync/branch-switch")
+        .json(&json!({ "project_dir": dir, "action": "discard" }))
+        .await
+        .json();
+    assert_eq!(body["success"], true);
+    assert!(state.branch_switches.get(&dir).is_none());
+    assert!(!branch_switch::poll(&state).await.paused.contains(&dir));
+
+    let response = server
+        .post("/sync/branch-switch")
+        .json(&json!({ "project_dir": dir }))
+        .await;
+    response.assert_status_not_found();
+}
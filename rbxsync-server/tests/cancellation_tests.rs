@@ -0,0 +1,125 @@
+//! Integration tests for plugin request cancellation and per-command timeouts
+
+use std::time::Duration;
+
+use axum_test::TestServer;
+use rbxsync_server::plugin_requests::{CommandTimeouts, PendingRequest};
+use rbxsync_server::{create_router, AppState, PluginRequest};
+use serde_json::json;
+
+#[test]
+fn test_command_timeouts_lookup() {
+    let mut timeouts = CommandTimeouts::default();
+    assert_eq!(timeouts.get("sync:batch"), Duration::from_secs(300));
+    assert_eq!(timeouts.get("run:code"), Duration::from_secs(30));
+
+    timeouts.apply_override("run=90").unwrap();
+    assert_eq!(timeouts.get("run:code"), Duration::from_secs(90));
+    timeouts.apply_override("run:code=5").unwrap();
+    assert_eq!(timeouts.get("run:code"), Duration::from_secs(5));
+    timeouts.apply_override("default=45").unwrap();
+    assert_eq!(timeouts.get("unknown:command"), Duration::from_secs(45));
+
+    assert!(timeouts.apply_override("run:code").is_err());
+    assert!(timeouts.apply_override("run:code=abc").is_err());
+    assert!(timeouts.apply_override("run:code=0").is_err());
+}
+
+#[tokio::test]
+async fn test_get_timeouts() {
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    let body: serde_json::Value = server.get("/rbxsync/timeouts").await.json();
+    assert_eq!(body["default"], 30);
+    assert_eq!(body["overrides"]["sync:batch"], 300);
+}
+
+#[tokio::test]
+async fn test_cancel_unknown_request() {
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    let response = server
+        .post(&format!("/rbxsync/cancel/{}", uuid::Uuid::new_v4()))
+        .await;
+    response.assert_status_not_found();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["stage"], "unknown");
+}
+
+#[tokio::test]
+async fn test_cancel_queued_request() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    let request_id = uuid::Uuid::new_v4();
+    let _pending = PendingRequest::register(&state, request_id, Duration::from_secs(30)).await;
+    state
+        .request_queue
+        .lock()
+        .await
+        .push_back(PluginRequest::new(request_id, "run:code", json!({})));
+
+    let response = server.post(&format!("/rbxsync/cancel/{}", request_id)).await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["stage"], "queued");
+    assert!(state.request_queue.lock().await.is_empty());
+    assert!(state.pending_requests.read().await.is_empty());
+}
+
+#[tokio::test]
+async fn test_cancel_dispatched_request() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    let run = server.post("/run").json(&json!({ "code": "while true do end" }));
+    let plugin = async {
+        // Wait for the handler to queue the request, then poll it like the plugin
+        let request = loop {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let response = server.get("/rbxsync/request").await;
+            if response.status_code() == 200 {
+                break response.json::<serde_json::Value>();
+            }
+        };
+        assert_eq!(request["command"], "run:code");
+        assert!(request["deadline"].as_u64().unwrap() > 0);
+
+        let id = request["id"].as_str().unwrap().to_string();
+        let body: serde_json::Value = server
+            .post(&format!("/rbxsync/cancel/{}", id))
+            .await
+            .json();
+        assert_eq!(body["stage"], "dispatched");
+
+        // The plugin is told to abort the request
+        let cancel: serde_json::Value = server.get("/rbxsync/request").await.json();
+        assert_eq!(cancel["command"], "cancel");
+        assert_eq!(cancel["payload"]["requestId"], id);
+    };
+
+    let (response, _) = tokio::join!(async { run.await }, plugin);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["success"], false);
+    assert!(body["error"].as_str().unwrap().contains("cancelled"));
+}
+
+#[tokio::test]
+async fn test_dropped_handler_cancels_request() {
+    let state = AppState::new();
+
+    let request_id = uuid::Uuid::new_v4();
+    let pending = PendingRequest::register(&state, request_id, Duration::from_secs(30)).await;
+    state
+        .request_queue
+        .lock()
+        .await
+        .push_back(PluginRequest::new(request_id, "run:code", json!({})));
+
+    // Simulates the handler future being dropped when its client disconnects
+    drop(pending);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(state.request_queue.lock().await.is_empty());
+    assert_eq!(state.metrics.plugin_requests_cancelled.get(), 1);
+}
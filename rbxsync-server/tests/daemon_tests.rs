@@ -0,0 +1,187 @@
+//! Integration tests for daemon mode
+//!
+//! Tests the PID lock, log rotation, status and config reload.
+
+use std::io::Write;
+
+use axum_test::TestServer;
+use rbxsync_server::daemon::{self, ControlCommand, DaemonInfo, DaemonPaths, PidLock, RotatingLog};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+
+#[test]
+fn test_pid_lock_writes_and_removes_pid_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let paths = DaemonPaths::new(temp_dir.path(), 45001);
+
+    {
+        let _lock = PidLock::acquire(&paths, 45001).unwrap();
+        let info = DaemonInfo::read(&paths.pid_file).unwrap();
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(info.port, 45001);
+        assert_eq!(info.log_file, paths.log_file);
+    }
+
+    assert!(!paths.pid_file.exists());
+}
+
+#[test]
+fn test_pid_lock_replaces_stale_pid_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let paths = DaemonPaths::new(temp_dir.path(), 45002);
+    std::fs::write(
+        &paths.pid_file,
+        json!({
+            "pid": 0,
+            "port": 45002,
+            "startedAt": 0,
+            "logFile": paths.log_file,
+            "socket": paths.socket
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let _lock = PidLock::acquire(&paths, 45002).unwrap();
+    assert_eq!(DaemonInfo::read(&paths.pid_file).unwrap().pid, std::process::id());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pid_lock_refuses_live_daemon() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let paths = DaemonPaths::new(temp_dir.path(), 45003);
+    // PID 1 always exists
+    std::fs::write(
+        &paths.pid_file,
+        json!({
+            "pid": 1,
+            "port": 45003,
+            "startedAt": 0,
+            "logFile": paths.log_file,
+            "socket": paths.socket
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let err = PidLock::acquire(&paths, 45003).unwrap_err();
+    assert!(err.to_string().contains("already running"));
+    assert!(paths.pid_file.exists());
+}
+
+#[test]
+fn test_pid_lock_is_exclusive_and_keeps_the_winners_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let paths = DaemonPaths::new(temp_dir.path(), 45004);
+
+    // A second starter, even from the same process, loses without touching the file
+    let lock = PidLock::acquire(&paths, 45004).unwrap();
+    let err = PidLock::acquire(&paths, 45004).unwrap_err();
+    assert!(err.to_string().contains("already running"));
+    assert_eq!(DaemonInfo::read(&paths.pid_file).unwrap().port, 45004);
+
+    // The PID file now names someone else: dropping leaves it
+    let other = json!({
+        "pid": std::process::id() + 1,
+        "port": 45004,
+        "startedAt": 0,
+        "logFile": paths.log_file,
+        "socket": paths.socket
+    });
+    std::fs::write(&paths.pid_file, other.to_string()).unwrap();
+    drop(lock);
+    assert!(paths.pid_file.exists());
+}
+
+#[test]
+fn test_rotating_log_keeps_limited_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let log_path = temp_dir.path().join("daemon.log");
+    let mut log = RotatingLog::open(&log_path, 100, 2).unwrap();
+
+    for _ in 0..5 {
+        log.write_all(&[b'x'; 60]).unwrap();
+    }
+    log.flush().unwrap();
+
+    assert_eq!(std::fs::metadata(&log_path).unwrap().len(), 60);
+    assert!(temp_dir.path().join("daemon.log.1").exists());
+    assert!(temp_dir.path().join("daemon.log.2").exists());
+    assert!(!temp_dir.path().join("daemon.log.3").exists());
+}
+
+#[tokio::test]
+async fn test_status_groups_places_and_workspaces_by_project() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project_dir = temp_dir.path().to_string_lossy().to_string();
+
+    server
+        .post("/rbxsync/register")
+        .json(&json!({
+            "place_id": 1,
+            "place_name": "Project Place",
+            "project_dir": project_dir,
+            "session_id": "session-1"
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/rbxsync/register")
+        .json(&json!({
+            "place_id": 2,
+            "place_name": "Loose Place",
+            "project_dir": "",
+            "session_id": "session-2"
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/rbxsync/register-vscode")
+        .json(&json!({ "workspace_dir": project_dir }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/daemon/status").await.json();
+    assert_eq!(body["daemon"], false);
+    assert_eq!(body["pid"], std::process::id());
+    let projects = body["projects"].as_array().unwrap();
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0]["projectDir"], project_dir);
+    assert_eq!(projects[0]["workspace"], true);
+    assert_eq!(projects[0]["places"][0]["placeName"], "Project Place");
+    assert_eq!(body["unattachedPlaces"][0]["placeId"], 2);
+}
+
+#[tokio::test]
+async fn test_reload_restarts_workspace_watchers() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+    let project_dir = temp_dir.path().to_string_lossy().to_string();
+
+    server
+        .post("/rbxsync/register-vscode")
+        .json(&json!({ "workspace_dir": project_dir }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.post("/daemon/reload").await.json();
+    assert_eq!(body["success"], true);
+    assert_eq!(body["reloaded"], json!([project_dir]));
+
+    let events: serde_json::Value = server.get("/events/history").await.json();
+    let last = events["events"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["type"], "config_reloaded");
+}
+
+#[tokio::test]
+async fn test_control_status_command() {
+    let state = AppState::new();
+    let response = daemon::handle_control(&state, ControlCommand::Status).await;
+    assert_eq!(response["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(response["projects"], json!([]));
+}
@@ -0,0 +1,199 @@
+//! Tests for Rojo-style data files in live sync, read-tree and extraction
+
+use std::fs;
+use std::path::Path;
+
+use axum_test::TestServer;
+use rbxsync_server::file_watcher::{live_sync_tree_mapping, process_file_change, FileChange, FileChangeKind};
+use rbxsync_server::{create_router, load_project_config, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+const MODEL: &str = r#"{
+    "ClassName": "Model",
+    "Children": [{"Name": "Pad", "ClassName": "Part", "Properties": {"Anchored": true}}]
+}"#;
+
+fn setup() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let rs = dir.path().join("src/ReplicatedStorage");
+    fs::create_dir_all(&rs).unwrap();
+    fs::write(rs.join("Items.json"), r#"{"sword": {"damage": 10}}"#).unwrap();
+    fs::write(rs.join("Motd.txt"), "Welcome!").unwrap();
+    fs::write(rs.join("Strings.csv"), "Key,Source,es\ngreeting,Hello,Hola\n").unwrap();
+    fs::write(rs.join("Spawn.model.json"), MODEL).unwrap();
+    fs::write(rs.join("init.meta.json"), "{}").unwrap();
+    dir
+}
+
+fn modify(project: &Path, path: &Path) -> FileChange {
+    FileChange {
+        path: path.to_path_buf(),
+        project_dir: project.to_string_lossy().to_string(),
+        kind: FileChangeKind::Modify,
+    }
+}
+
+/// The operation live sync sends for a change, with the project's settings
+fn sync_op(change: &FileChange) -> Option<serde_json::Value> {
+    process_file_change(change, &live_sync_tree_mapping(&load_project_config(&change.project_dir)))
+}
+
+#[test]
+fn test_live_sync_data_files() {
+    let dir = setup();
+    let project = dir.path();
+    let rs = project.join("src/ReplicatedStorage");
+
+    let op = sync_op(&modify(project, &rs.join("Items.json"))).unwrap();
+    assert_eq!(op["type"], "update");
+    assert_eq!(op["path"], "ReplicatedStorage/Items");
+    assert_eq!(op["data"]["className"], "ModuleScript");
+    assert_eq!(
+        op["data"]["properties"]["Source"]["value"],
+        "return {\n\tsword = {\n\t\tdamage = 10,\n\t},\n}\n"
+    );
+
+    let op = sync_op(&modify(project, &rs.join("Motd.txt"))).unwrap();
+    assert_eq!(op["data"]["className"], "StringValue");
+    assert_eq!(op["data"]["properties"]["Value"]["value"], "Welcome!");
+
+    let op = sync_op(&modify(project, &rs.join("Spawn.model.json"))).unwrap();
+    assert_eq!(op["path"], "ReplicatedStorage/Spawn");
+    assert_eq!(op["data"]["className"], "Model");
+    assert_eq!(op["data"]["children"][0]["name"], "Pad");
+
+    assert!(sync_op(&modify(project, &rs.join("init.meta.json"))).is_none());
+
+    // Parse errors are skipped rather than sent to Studio
+    fs::write(rs.join("Items.json"), "{ not json").unwrap();
+    assert!(sync_op(&modify(project, &rs.join("Items.json"))).is_none());
+}
+
+#[tokio::test]
+async fn test_read_tree_includes_data_files() {
+    let dir = setup();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    let response = server
+        .post("/sync/read-tree")
+        .json(&json!({ "project_dir": dir.path().to_string_lossy() }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    let instances = body["instances"].as_array().unwrap();
+    let class_at = |path: &str| {
+        instances
+            .iter()
+            .find(|i| i["path"] == path)
+            .map(|i| i["className"].as_str().unwrap().to_string())
+    };
+
+    assert_eq!(class_at("ReplicatedStorage/Items").as_deref(), Some("ModuleScript"));
+    assert_eq!(class_at("ReplicatedStorage/Motd").as_deref(), Some("StringValue"));
+    assert_eq!(class_at("ReplicatedStorage/Strings").as_deref(), Some("LocalizationTable"));
+    assert_eq!(class_at("ReplicatedStorage/Spawn").as_deref(), Some("Model"));
+    assert_eq!(class_at("ReplicatedStorage/Spawn/Pad").as_deref(), Some("Part"));
+    assert!(instances.iter().all(|i| i.get("children").is_none()));
+}
+
+#[tokio::test]
+async fn test_extraction_keeps_data_files() {
+    let dir = setup();
+    let project = dir.path().to_string_lossy().to_string();
+    let rs = dir.path().join("src/ReplicatedStorage");
+    let items = fs::read_to_string(rs.join("Items.json")).unwrap();
+    let parsed = rbxsync_core::parse_data_file("Items.json", &items).unwrap();
+    let items_source = parsed["source"].as_str().unwrap();
+
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state)).unwrap();
+
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project }))
+        .await
+        .json();
+    let session_id = start["sessionId"].as_str().unwrap();
+
+    let string = |value: &str| json!({ "type": "string", "value": value });
+    let instances = json!([
+        { "className": "ReplicatedStorage", "name": "ReplicatedStorage", "path": "ReplicatedStorage", "referenceId": "rs" },
+        // Unchanged data module
+        { "className": "ModuleScript", "name": "Items", "path": "ReplicatedStorage/Items", "referenceId": "items",
+          "properties": { "Source": string(items_source) } },
+        // StringValue edited in Studio
+        { "className": "StringValue", "name": "Motd", "path": "ReplicatedStorage/Motd", "referenceId": "motd",
+          "properties": { "Value": string("Hello again") } },
+        { "className": "LocalizationTable", "name": "Strings", "path": "ReplicatedStorage/Strings", "referenceId": "strings",
+          "properties": { "Contents": string(r#"[{"key":"bye","source":"Bye","values":{"fr":"Salut"}}]"#) } },
+        { "className": "Model", "name": "Spawn", "path": "ReplicatedStorage/Spawn", "referenceId": "spawn" },
+        { "className": "Part", "name": "Pad", "path": "ReplicatedStorage/Spawn/Pad", "referenceId": "pad" },
+    ]);
+    server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": session_id,
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": instances,
+            "project_dir": project,
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project }))
+        .await
+        .assert_status_ok();
+
+    assert_eq!(fs::read_to_string(rs.join("Items.json")).unwrap(), items);
+    assert!(!rs.join("Items.luau").exists());
+    assert_eq!(fs::read_to_string(rs.join("Motd.txt")).unwrap(), "Hello again");
+    assert!(!rs.join("Motd.rbxjson").exists());
+    assert_eq!(
+        fs::read_to_string(rs.join("Strings.csv")).unwrap(),
+        "Key,Source,Context,Example,fr\nbye,Bye,,,Salut\n"
+    );
+    assert_eq!(fs::read_to_string(rs.join("Spawn.model.json")).unwrap(), MODEL);
+    assert!(!rs.join("Spawn").exists());
+}
+
+#[tokio::test]
+async fn test_extraction_writes_edited_data_module_as_script() {
+    let dir = setup();
+    let project = dir.path().to_string_lossy().to_string();
+    let rs = dir.path().join("src/ReplicatedStorage");
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project }))
+        .await
+        .json();
+
+    server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": start["sessionId"],
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": [{ "className": "ModuleScript", "name": "Items", "path": "ReplicatedStorage/Items",
+                       "referenceId": "items",
+                       "properties": { "Source": { "type": "string", "value": "return { edited = true }" } } }],
+            "project_dir": project,
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project }))
+        .await
+        .assert_status_ok();
+
+    assert!(!rs.join("Items.json").exists());
+    assert_eq!(
+        fs::read_to_string(rs.join("Items.luau")).unwrap(),
+        "return { edited = true }"
+    );
+}
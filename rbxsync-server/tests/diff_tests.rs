@@ -0,0 +1,170 @@
+//! Tests for the files ↔ Studio diff
+//!
+//! Plays the plugin's side of `studio:hashes` and `studio:read` and checks
+//! that only mismatched instances are read in full and compared.
+
+use std::fs;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use rbxsync_core::InstanceHashes;
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn part(anchored: bool) -> serde_json::Value {
+    json!({
+        "className": "Part",
+        "properties": { "Anchored": { "type": "bool", "value": anchored } },
+        "attributes": {},
+        "tags": []
+    })
+}
+
+fn script(source: &str) -> serde_json::Value {
+    json!({
+        "className": "Script",
+        "properties": {
+            "Disabled": { "type": "bool", "value": false },
+            "Source": { "type": "string", "value": source }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_diff_compares_properties_and_sources() {
+    let project = TempDir::new().unwrap();
+    let src = project.path().join("src");
+    fs::create_dir_all(src.join("Workspace")).unwrap();
+    fs::create_dir_all(src.join("ServerScriptService")).unwrap();
+    let write_json = |path: &str, value: serde_json::Value| {
+        fs::write(src.join(path), serde_json::to_string(&value).unwrap()).unwrap();
+    };
+    write_json("Workspace/Same.rbxjson", part(true));
+    write_json("Workspace/Moved.rbxjson", part(true));
+    write_json("Workspace/New.rbxjson", part(true));
+    write_json("ServerScriptService/Main.rbxjson", json!({ "className": "Script", "properties": {
+        "Disabled": { "type": "bool", "value": false }
+    } }));
+    fs::write(src.join("ServerScriptService/Main.server.luau"), "print('new')\r\n").unwrap();
+    write_json("ServerScriptService/Crlf.rbxjson", json!({ "className": "Script", "properties": {
+        "Disabled": { "type": "bool", "value": false }
+    } }));
+    fs::write(src.join("ServerScriptService/Crlf.server.luau"), "print('same')\r\n").unwrap();
+    fs::write(project.path().join("rbxsync.json"), r#"{"scripts": {"trailingNewline": "ensure"}}"#).unwrap();
+
+    let studio = [
+        ("Workspace/Same", part(true)),
+        ("Workspace/Moved", part(false)),
+        ("Workspace/Old", part(true)),
+        ("ServerScriptService/Main", script("print('old')\n")),
+        // Same script once line endings and the trailing newline are normalized
+        ("ServerScriptService/Crlf", script("print('same')")),
+    ];
+
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    let diff = server
+        .post("/diff")
+        .json(&json!({ "project_dir": project.path().to_string_lossy() }));
+    let plugin = async {
+        let mut read_paths = Vec::new();
+        for _ in 0..2 {
+            let request = loop {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let response = server.get("/rbxsync/request").await;
+                if response.status_code() == 200 {
+                    break response.json::<serde_json::Value>();
+                }
+            };
+            let data = match request["command"].as_str().unwrap() {
+                "studio:hashes" => json!({
+                    "success": true,
+                    "instances": studio.iter().map(|(path, inst)| json!({
+                        "path": path,
+                        "className": inst["className"],
+                        "hashes": InstanceHashes::of(inst),
+                    })).collect::<Vec<_>>()
+                }),
+                "studio:read" => {
+                    read_paths = request["payload"]["paths"].as_array().unwrap().clone();
+                    let instances: Vec<serde_json::Value> = studio
+                        .iter()
+                        .filter(|(path, _)| read_paths.iter().any(|p| p == path))
+                        .map(|(path, inst)| {
+                            let mut inst = inst.clone();
+                            inst["path"] = json!(path);
+                            inst
+                        })
+                        .collect();
+                    json!({ "success": true, "instances": instances, "missing": [] })
+                }
+                other => panic!("unexpected command {}", other),
+            };
+            server
+                .post("/rbxsync/response")
+                .json(&json!({ "id": request["id"], "success": true, "data": data }))
+                .await
+                .assert_status_ok();
+        }
+        read_paths
+    };
+
+    let (response, read_paths) = tokio::join!(async { diff.await }, plugin);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["success"], true, "{}", body);
+
+    // Identical instances are settled by their hashes
+    assert_eq!(read_paths.len(), 3);
+    assert!(!read_paths.iter().any(|p| p == "Workspace/Same"));
+
+    assert_eq!(body["added"][0]["path"], "Workspace/New");
+    assert_eq!(body["removed"][0]["path"], "Workspace/Old");
+    assert_eq!(body["unchanged"], 2);
+
+    let modified = body["modified"].as_array().unwrap();
+    assert_eq!(modified.len(), 2);
+    let main = &modified[0];
+    assert_eq!(main["path"], "ServerScriptService/Main");
+    assert!(main.get("properties").is_none());
+    let source_diff = main["sourceDiff"].as_str().unwrap();
+    assert!(source_diff.contains("-print('old')\n+print('new')"));
+    let moved = &modified[1];
+    assert_eq!(moved["path"], "Workspace/Moved");
+    assert_eq!(moved["properties"][0]["name"], "Anchored");
+    assert_eq!(moved["properties"][0]["new"]["value"], true);
+    assert_eq!(moved["properties"][0]["old"]["value"], false);
+}
+
+#[tokio::test]
+async fn test_plugin_error_is_a_bad_gateway() {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src/Workspace")).unwrap();
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let diff = server
+        .post("/diff")
+        .json(&json!({ "project_dir": project.path().to_string_lossy() }));
+    let plugin = async {
+        let request = loop {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let response = server.get("/rbxsync/request").await;
+            if response.status_code() == 200 {
+                break response.json::<serde_json::Value>();
+            }
+        };
+        assert_eq!(request["command"], "studio:hashes");
+        server
+            .post("/rbxsync/response")
+            .json(&json!({ "id": request["id"], "success": false, "error": "Unknown command: studio:hashes" }))
+            .await
+            .assert_status_ok();
+    };
+
+    let (response, ()) = tokio::join!(async { diff.await }, plugin);
+    response.assert_status(StatusCode::BAD_GATEWAY);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["error"], "Unknown command: studio:hashes");
+}
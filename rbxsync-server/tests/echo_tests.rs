@@ -0,0 +1,90 @@
+//! Tests for echo suppression of the server's own file writes
+
+use std::fs;
+
+use axum_test::TestServer;
+use rbxsync_server::echo::EchoFilter;
+use rbxsync_server::file_watcher::{FileChange, FileChangeKind};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn change(path: &std::path::Path, kind: FileChangeKind) -> FileChange {
+    FileChange {
+        path: path.to_path_buf(),
+        project_dir: String::new(),
+        kind,
+    }
+}
+
+#[test]
+fn test_matching_content_is_echo() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("Main.luau");
+    let filter = EchoFilter::new();
+
+    fs::write(&path, "print(1)").unwrap();
+    filter.record_write(&path, "print(1)");
+    assert!(filter.is_echo(&change(&path, FileChangeKind::Modify)));
+
+    // A user edit after the server write is not an echo
+    fs::write(&path, "print(2)").unwrap();
+    assert!(!filter.is_echo(&change(&path, FileChangeKind::Modify)));
+
+    // Unrelated files are never echoes
+    let other = dir.path().join("Other.luau");
+    fs::write(&other, "print(1)").unwrap();
+    assert!(!filter.is_echo(&change(&other, FileChangeKind::Create)));
+}
+
+#[test]
+fn test_deletes_and_moves() {
+    let dir = TempDir::new().unwrap();
+    let filter = EchoFilter::new();
+
+    let old = dir.path().join("Old");
+    let new = dir.path().join("New");
+    fs::create_dir_all(&old).unwrap();
+    fs::write(old.join("A.luau"), "return 1").unwrap();
+    fs::rename(&old, &new).unwrap();
+    filter.record_delete(&old);
+    filter.record_tree(&new);
+
+    assert!(filter.is_echo(&change(&old, FileChangeKind::Delete)));
+    assert!(filter.is_echo(&change(&new.join("A.luau"), FileChangeKind::Create)));
+    assert!(filter.is_echo(&change(&new, FileChangeKind::Move { from: old.clone() })));
+
+    // Recreated by the user after the server deleted it
+    fs::create_dir_all(&old).unwrap();
+    assert!(!filter.is_echo(&change(&old, FileChangeKind::Create)));
+}
+
+#[tokio::test]
+async fn test_sync_from_studio_records_writes() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    server
+        .post("/sync/from-studio")
+        .json(&json!({
+            "projectDir": dir.path().to_string_lossy(),
+            "operations": [{
+                "type": "create",
+                "path": "ServerScriptService/Main",
+                "className": "Script",
+                "data": { "className": "Script", "source": "print('hi')" }
+            }]
+        }))
+        .await
+        .assert_status_ok();
+
+    let script = src.join("ServerScriptService/Main.server.luau");
+    assert_eq!(fs::read_to_string(&script).unwrap(), "print('hi')");
+    assert!(state.echo_filter.is_echo(&change(&script, FileChangeKind::Create)));
+    let json = src.join("ServerScriptService/Main.rbxjson");
+    assert!(state.echo_filter.is_echo(&change(&json, FileChangeKind::Create)));
+}
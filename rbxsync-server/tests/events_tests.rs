@@ -0,0 +1,219 @@
+//! Integration tests for the unified event stream
+//!
+//! Tests event publishing from state-changing endpoints and resume-from-sequence.
+
+use axum_test::TestServer;
+use rbxsync_server::events::{Cursor, EventBus, ServerEvent, EVENT_BUFFER_SIZE};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+
+fn create_test_server() -> TestServer {
+    let state = AppState::new();
+    let router = create_router(state);
+    TestServer::new(router).unwrap()
+}
+
+#[tokio::test]
+async fn test_history_starts_empty() {
+    let server = create_test_server();
+
+    let response = server.get("/events/history").await;
+    response.assert_status_ok();
+
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["latestSeq"], 0);
+    assert_eq!(body["events"].as_array().unwrap().len(), 0);
+    assert_eq!(body["gap"], false);
+}
+
+#[tokio::test]
+async fn test_place_register_and_unregister_publish_events() {
+    let server = create_test_server();
+    let place = json!({
+        "place_id": 42,
+        "place_name": "Test Place",
+        "project_dir": "/tmp/project",
+        "session_id": "session-1"
+    });
+
+    server.post("/rbxsync/register").json(&place).await.assert_status_ok();
+    // Heartbeat re-registration must not publish a duplicate event
+    server.post("/rbxsync/register").json(&place).await.assert_status_ok();
+    server.post("/rbxsync/unregister").json(&place).await.assert_status_ok();
+
+    let body: serde_json::Value = server.get("/events/history").await.json();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+
+    assert_eq!(events[0]["type"], "place_connected");
+    assert_eq!(events[0]["seq"], 1);
+    assert_eq!(events[0]["placeId"], 42);
+    assert_eq!(events[0]["sessionKey"], "session-1");
+
+    assert_eq!(events[1]["type"], "place_disconnected");
+    assert_eq!(events[1]["seq"], 2);
+    assert_eq!(events[1]["reason"], "unregistered");
+}
+
+#[tokio::test]
+async fn test_history_resumes_from_sequence() {
+    let server = create_test_server();
+
+    server
+        .post("/console/push")
+        .json(&json!({
+            "messages": [
+                { "timestamp": "1", "message_type": "info", "message": "first", "source": null },
+                { "timestamp": "2", "message_type": "warn", "message": "second", "source": null },
+                { "timestamp": "3", "message_type": "error", "message": "third", "source": null }
+            ]
+        }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/events/history?since=1").await.json();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["type"], "console");
+    assert_eq!(events[0]["message"]["message"], "second");
+    assert_eq!(events[1]["message"]["message"], "third");
+    assert_eq!(body["latestSeq"], 3);
+
+    let limited: serde_json::Value = server.get("/events/history?since=0&limit=1").await.json();
+    assert_eq!(limited["events"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_bot_lifecycle_publishes_playtest_transitions() {
+    let server = create_test_server();
+
+    server.post("/bot/lifecycle").json(&json!({ "event": "hello" })).await.assert_status_ok();
+    // Heartbeats while active should not re-publish playtest_started
+    server.post("/bot/state").json(&json!({ "health": 100 })).await.assert_status_ok();
+    server
+        .post("/bot/lifecycle")
+        .json(&json!({ "event": "goodbye", "reason": "stopped" }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/events/history").await.json();
+    let types: Vec<&str> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, vec!["playtest_started", "playtest_stopped"]);
+    assert_eq!(body["events"][1]["reason"], "stopped");
+}
+
+#[tokio::test]
+async fn test_harness_init_publishes_event() {
+    let server = create_test_server();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project_path = temp_dir.path().to_string_lossy().to_string();
+
+    server
+        .post("/harness/init")
+        .json(&json!({ "projectDir": project_path, "gameName": "Evented" }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server.get("/events/history").await.json();
+    let event = &body["events"][0];
+    assert_eq!(event["type"], "harness_updated");
+    assert_eq!(event["change"], "init");
+    assert_eq!(event["projectDir"], project_path);
+}
+
+#[tokio::test]
+async fn test_subscribe_replays_backlog_then_live() {
+    let bus = EventBus::new();
+    bus.publish(ServerEvent::PlaytestStarted);
+    bus.publish(ServerEvent::PlaytestStopped { reason: None });
+
+    let mut sub = bus.subscribe(Some(Cursor::from(1)));
+    assert!(!sub.gap);
+    assert_eq!(sub.backlog.len(), 1);
+    assert_eq!(sub.backlog[0].seq, 2);
+
+    bus.publish(ServerEvent::PlaytestStarted);
+    let live = sub.receiver.recv().await.unwrap();
+    assert_eq!(live.seq, 3);
+}
+
+#[test]
+fn test_gap_reported_when_buffer_overflows() {
+    let bus = EventBus::new();
+    for _ in 0..(EVENT_BUFFER_SIZE + 5) {
+        bus.publish(ServerEvent::PlaytestStarted);
+    }
+
+    let (events, gap) = bus.since(0);
+    assert!(gap);
+    assert_eq!(events.len(), EVENT_BUFFER_SIZE);
+    assert_eq!(events[0].seq, 6);
+
+    let (_, gap) = bus.since(5);
+    assert!(!gap);
+}
+
+#[test]
+fn test_resync_after_server_restart() {
+    let old = EventBus::new();
+    for _ in 0..3 {
+        old.publish(ServerEvent::PlaytestStarted);
+    }
+    let (events, _) = old.since(0);
+    let last_seen = Cursor::parse(&events[2].id()).unwrap();
+    assert_eq!(last_seen.seq, 3);
+
+    // The restarted server has already published more than the client saw
+    let bus = EventBus::new();
+    for _ in 0..5 {
+        bus.publish(ServerEvent::PlaytestStarted);
+    }
+    let (events, gap) = bus.since(last_seen.clone());
+    assert!(gap);
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0].seq, 1);
+
+    let sub = bus.subscribe(Some(last_seen));
+    assert!(sub.gap);
+    assert_eq!(sub.backlog.len(), 5);
+
+    // Resuming within the same run
+    let (events, gap) = bus.since(Cursor::parse(&format!("{}:2", bus.epoch())).unwrap());
+    assert!(!gap);
+    assert_eq!(events.len(), 3);
+
+    // A bare sequence number ahead of the latest event is from an earlier run
+    let (events, gap) = bus.since(100);
+    assert!(gap);
+    assert_eq!(events.len(), 5);
+    assert!(!bus.since(2).1);
+}
+
+#[tokio::test]
+async fn test_history_reports_epoch() {
+    let server = create_test_server();
+    for _ in 0..3 {
+        server
+            .post("/console/push")
+            .json(&json!({ "messages": [{ "timestamp": "1", "message_type": "info", "message": "x", "source": null }] }))
+            .await
+            .assert_status_ok();
+    }
+
+    let body: serde_json::Value = server.get("/events/history").await.json();
+    let epoch = body["epoch"].as_str().unwrap().to_string();
+    assert_eq!(body["events"][0]["epoch"], epoch);
+
+    let body: serde_json::Value = server.get(&format!("/events/history?since={}:1", epoch)).await.json();
+    assert_eq!(body["gap"], false);
+    assert_eq!(body["events"].as_array().unwrap().len(), 2);
+
+    let body: serde_json::Value = server.get("/events/history?since=earlier:1").await.json();
+    assert_eq!(body["gap"], true);
+    assert_eq!(body["events"].as_array().unwrap().len(), 3);
+}
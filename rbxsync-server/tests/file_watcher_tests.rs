@@ -0,0 +1,400 @@
+//! Tests for live sync path resolution, move detection and watch roots in the file watcher
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum_test::TestServer;
+use rbxsync_server::file_watcher::{
+    coalesce_moves, index_directory, live_sync_tree_mapping, process_file_change, start_file_watcher,
+    stop_file_watcher, watch_roots, DiskFingerprints, FileChange, FileChangeKind, Fingerprint,
+};
+use rbxsync_server::{create_router, load_project_config, AppState};
+use tempfile::TempDir;
+
+fn change(project: &Path, path: &Path, kind: FileChangeKind) -> FileChange {
+    FileChange {
+        path: path.to_path_buf(),
+        project_dir: project.to_string_lossy().to_string(),
+        kind,
+    }
+}
+
+/// The operation live sync sends for a change, with the project's settings
+fn sync_op(change: &FileChange) -> Option<serde_json::Value> {
+    process_file_change(change, &live_sync_tree_mapping(&load_project_config(&change.project_dir)))
+}
+
+fn coalesce(changes: Vec<FileChange>, index: &mut HashMap<PathBuf, Fingerprint>) -> Vec<FileChange> {
+    let disk = DiskFingerprints::read(&changes);
+    coalesce_moves(changes, index, disk)
+}
+
+fn setup() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("src/ServerScriptService");
+    fs::create_dir_all(src.join("Old")).unwrap();
+    fs::write(src.join("Main.server.luau"), "print('main')").unwrap();
+    fs::write(src.join("Old/Util.luau"), "return {}").unwrap();
+    fs::write(
+        src.join("Old/_meta.rbxjson"),
+        r#"{"className": "Folder", "referenceId": "folder-ref"}"#,
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_renamed_script_becomes_move() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    let mut index = index_directory(&project.join("src"));
+
+    let old = sss.join("Main.server.luau");
+    let new = sss.join("Entry.server.luau");
+    fs::rename(&old, &new).unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &old, FileChangeKind::Delete),
+            change(project, &new, FileChangeKind::Create),
+        ],
+        &mut index,
+    );
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].kind, FileChangeKind::Move { from: old.clone() });
+    assert!(index.contains_key(&new));
+    assert!(!index.contains_key(&old));
+
+    let op = sync_op(&changes[0]).unwrap();
+    assert_eq!(op["type"], "move");
+    assert_eq!(op["from"], "ServerScriptService/Main");
+    assert_eq!(op["path"], "ServerScriptService/Entry");
+    assert_eq!(op["name"], "Entry");
+    assert_eq!(op["data"]["className"], "Script");
+}
+
+#[test]
+fn test_class_change_is_not_a_move() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    let mut index = index_directory(&project.join("src"));
+
+    let old = sss.join("Main.server.luau");
+    let new = sss.join("Main.client.luau");
+    fs::rename(&old, &new).unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &old, FileChangeKind::Delete),
+            change(project, &new, FileChangeKind::Create),
+        ],
+        &mut index,
+    );
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|c| !matches!(c.kind, FileChangeKind::Move { .. })));
+}
+
+#[test]
+fn test_ambiguous_content_is_not_paired() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    fs::write(sss.join("A.luau"), "return {}").unwrap();
+    let mut index = index_directory(&project.join("src"));
+
+    // Two deleted modules with the same source as the created one
+    let deleted_a = sss.join("A.luau");
+    let deleted_b = sss.join("Old/Util.luau");
+    let created = sss.join("B.luau");
+    fs::remove_file(&deleted_a).unwrap();
+    fs::remove_file(&deleted_b).unwrap();
+    fs::write(&created, "return {}").unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &deleted_a, FileChangeKind::Delete),
+            change(project, &deleted_b, FileChangeKind::Delete),
+            change(project, &created, FileChangeKind::Create),
+        ],
+        &mut index,
+    );
+    assert_eq!(changes.len(), 3);
+}
+
+#[test]
+fn test_moved_folder_becomes_single_move() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    let mut index = index_directory(&project.join("src"));
+
+    let old = sss.join("Old");
+    let new = sss.join("New");
+    fs::rename(&old, &new).unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &old, FileChangeKind::Delete),
+            change(project, &new, FileChangeKind::Create),
+            change(project, &new.join("Util.luau"), FileChangeKind::Create),
+            change(project, &new.join("_meta.rbxjson"), FileChangeKind::Create),
+        ],
+        &mut index,
+    );
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].kind, FileChangeKind::Move { from: old.clone() });
+    assert!(index.contains_key(&new.join("Util.luau")));
+    assert!(!index.keys().any(|p| p.starts_with(&old)));
+
+    let op = sync_op(&changes[0]).unwrap();
+    assert_eq!(op["from"], "ServerScriptService/Old");
+    assert_eq!(op["path"], "ServerScriptService/New");
+    assert_eq!(op["isFolder"], true);
+    assert!(op.get("data").is_none());
+}
+
+#[test]
+fn test_edit_inside_moved_folder_follows_the_move() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    fs::write(sss.join("Old/Part.rbxjson"), r#"{"className": "Part", "referenceId": "part-ref"}"#).unwrap();
+    let mut index = index_directory(&project.join("src"));
+
+    // Renamed, then a property edited before the batch went out: the
+    // referenceId still matches, so the folder is still paired
+    let old = sss.join("Old");
+    let new = sss.join("New");
+    fs::rename(&old, &new).unwrap();
+    fs::write(
+        new.join("Part.rbxjson"),
+        r#"{"className": "Part", "referenceId": "part-ref", "properties": {"Anchored": {"type": "bool", "value": true}}}"#,
+    )
+    .unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &old, FileChangeKind::Delete),
+            change(project, &new, FileChangeKind::Create),
+            change(project, &new.join("Part.rbxjson"), FileChangeKind::Modify),
+            change(project, &new.join("Util.luau"), FileChangeKind::Modify),
+        ],
+        &mut index,
+    );
+    // Util.luau's content is unchanged, so only the folder move and the edit remain
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].kind, FileChangeKind::Move { from: old.clone() });
+    assert_eq!(changes[1].kind, FileChangeKind::Modify);
+    assert_eq!(changes[1].path, new.join("Part.rbxjson"));
+
+    let op = sync_op(&changes[1]).unwrap();
+    assert_eq!(op["path"], "ServerScriptService/New/Part");
+    assert_eq!(op["data"]["properties"]["Anchored"]["value"], true);
+}
+
+#[test]
+fn test_rbxjson_paired_by_reference_id() {
+    let dir = setup();
+    let project = dir.path();
+    let sss = project.join("src/ServerScriptService");
+    let old = sss.join("Part.rbxjson");
+    fs::write(&old, r#"{"className": "Part", "referenceId": "part-ref"}"#).unwrap();
+    let mut index = index_directory(&project.join("src"));
+
+    // Moved into a folder and edited at the same time
+    let new = sss.join("Old/Part.rbxjson");
+    fs::remove_file(&old).unwrap();
+    fs::write(
+        &new,
+        r#"{"className": "Part", "referenceId": "part-ref", "properties": {"Anchored": {"type": "bool", "value": true}}}"#,
+    )
+    .unwrap();
+
+    let changes = coalesce(
+        vec![
+            change(project, &old, FileChangeKind::Delete),
+            change(project, &new, FileChangeKind::Create),
+        ],
+        &mut index,
+    );
+    assert_eq!(changes.len(), 1);
+
+    let op = sync_op(&changes[0]).unwrap();
+    assert_eq!(op["from"], "ServerScriptService/Part");
+    assert_eq!(op["path"], "ServerScriptService/Old/Part");
+    assert_eq!(op["data"]["properties"]["Anchored"]["value"], true);
+}
+
+#[test]
+fn test_init_script_updates_its_folder() {
+    let dir = setup();
+    let project = dir.path();
+    let lib = project.join("src/ReplicatedStorage/Lib");
+    fs::create_dir_all(&lib).unwrap();
+    let init = lib.join("init.server.luau");
+    fs::write(&init, "print('lib')").unwrap();
+
+    let op = sync_op(&change(project, &init, FileChangeKind::Modify)).unwrap();
+    assert_eq!(op["type"], "update");
+    assert_eq!(op["path"], "ReplicatedStorage/Lib");
+    assert_eq!(op["data"]["name"], "Lib");
+    assert_eq!(op["data"]["className"], "Script");
+
+    // Removing the init script must not delete the folder
+    fs::remove_file(&init).unwrap();
+    assert!(sync_op(&change(project, &init, FileChangeKind::Delete)).is_none());
+    let meta = project.join("src/ServerScriptService/Old/_meta.rbxjson");
+    fs::remove_file(&meta).unwrap();
+    assert!(sync_op(&change(project, &meta, FileChangeKind::Delete)).is_none());
+}
+
+#[test]
+fn test_lua_files_are_synced() {
+    let dir = setup();
+    let project = dir.path();
+    let path = project.join("src/StarterPlayer/StarterPlayerScripts/Input.client.lua");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "print('input')").unwrap();
+
+    let op = sync_op(&change(project, &path, FileChangeKind::Create)).unwrap();
+    assert_eq!(op["type"], "create");
+    assert_eq!(op["path"], "StarterPlayer/StarterPlayerScripts/Input");
+    assert_eq!(op["data"]["className"], "LocalScript");
+}
+
+#[test]
+fn test_tree_mapping_is_applied() {
+    let dir = setup();
+    let project = dir.path();
+    fs::write(
+        project.join("rbxsync.json"),
+        r#"{"treeMapping": {"ServerScriptService": "src/server", "ReplicatedStorage": "shared"}}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(project.join("src/server")).unwrap();
+    fs::create_dir_all(project.join("src/shared")).unwrap();
+
+    // Mapping target relative to the project
+    let server = project.join("src/server/Main.server.luau");
+    fs::write(&server, "print(1)").unwrap();
+    let op = sync_op(&change(project, &server, FileChangeKind::Modify)).unwrap();
+    assert_eq!(op["path"], "ServerScriptService/Main");
+
+    // Mapping target relative to src
+    let shared = project.join("src/shared/Util.luau");
+    fs::write(&shared, "return {}").unwrap();
+    let op = sync_op(&change(project, &shared, FileChangeKind::Modify)).unwrap();
+    assert_eq!(op["path"], "ReplicatedStorage/Util");
+
+    // Unmapped paths keep their layout
+    let workspace = project.join("src/Workspace/Part.rbxjson");
+    fs::create_dir_all(workspace.parent().unwrap()).unwrap();
+    fs::write(&workspace, r#"{"className": "Part"}"#).unwrap();
+    let op = sync_op(&change(project, &workspace, FileChangeKind::Modify)).unwrap();
+    assert_eq!(op["path"], "Workspace/Part");
+}
+
+#[test]
+fn test_watch_roots_from_config() {
+    let dir = setup();
+    let project = dir.path();
+    fs::create_dir_all(project.join("lib")).unwrap();
+    fs::create_dir_all(project.join("shared")).unwrap();
+    fs::create_dir_all(project.join("Packages")).unwrap();
+    let config = Some(serde_json::json!({
+        "sync": { "watchPaths": ["./src", "lib", "missing"] },
+        // Nested in src, and outside src
+        "treeMapping": { "ServerScriptService": "src/ServerScriptService", "ReplicatedStorage": "shared" }
+    }));
+
+    let roots = watch_roots(project, &config, false);
+    assert_eq!(roots, vec![project.join("lib"), project.join("shared"), project.join("src")]);
+
+    let roots = watch_roots(project, &config, true);
+    assert!(roots.contains(&project.join("Packages")));
+    assert_eq!(watch_roots(project, &None, false), vec![project.join("src")]);
+}
+
+#[test]
+fn test_packages_folder_resolves_to_shared_packages() {
+    let dir = setup();
+    let project = dir.path();
+    fs::write(project.join("rbxsync.json"), r#"{"packages": {"excludeFromWatch": false}}"#).unwrap();
+    let path = project.join("Packages/Promise.luau");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "return {}").unwrap();
+
+    let op = sync_op(&change(project, &path, FileChangeKind::Modify)).unwrap();
+    assert_eq!(op["path"], "ReplicatedStorage/Packages/Promise");
+}
+
+async fn wait_for(mut check: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if check() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("condition not met in time");
+}
+
+#[tokio::test]
+async fn test_watch_roots_follow_config_changes() {
+    let dir = setup();
+    let project = dir.path();
+    let project_dir = project.to_string_lossy().to_string();
+    fs::create_dir_all(project.join("shared")).unwrap();
+
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    start_file_watcher(project_dir.clone(), state.file_watcher_state.clone(), false)
+        .await
+        .unwrap();
+
+    // None while the watcher holds the state lock
+    let roots = |state: &AppState| -> Option<Vec<std::path::PathBuf>> {
+        let watcher = state.file_watcher_state.try_read().ok()?;
+        Some(watcher.roots[&project_dir].iter().map(|root| root.path.clone()).collect())
+    };
+    assert_eq!(roots(&state), Some(vec![project.join("src")]));
+
+    // Give the watcher thread time to register its watches
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    fs::write(project.join("rbxsync.json"), r#"{"treeMapping": {"ReplicatedStorage": "shared"}}"#).unwrap();
+    wait_for(|| roots(&state).is_some_and(|roots| roots.len() == 2)).await;
+
+    // Changes in the new root are picked up
+    fs::write(project.join("shared/Util.luau"), "return {}").unwrap();
+    let mut change = None;
+    for _ in 0..100 {
+        if let Ok(c) = state.file_change_rx.lock().await.try_recv() {
+            if c.path.ends_with("Util.luau") {
+                change = Some(c);
+                break;
+            }
+        } else {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+    let change = change.expect("change in added root");
+    let op = sync_op(&change).unwrap();
+    assert_eq!(op["path"], "ReplicatedStorage/Util");
+
+    let status: serde_json::Value = server.get("/watch/status").await.json();
+    let entry = &status["projects"][0];
+    assert_eq!(entry["projectDir"], project_dir);
+    assert_eq!(entry["roots"].as_array().unwrap().len(), 2);
+    assert!(entry["events"].as_u64().unwrap() >= 1);
+
+    // Removing the mapping removes the root
+    fs::write(project.join("rbxsync.json"), "{}").unwrap();
+    wait_for(|| roots(&state).is_some_and(|roots| roots.len() == 1)).await;
+
+    assert!(stop_file_watcher(&project_dir, &state.file_watcher_state).await);
+    let status: serde_json::Value = server.get("/watch/status").await.json();
+    assert_eq!(status["projects"].as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,309 @@
+//! Tests for the git endpoints
+//!
+//! Runs against real repositories in temp directories, with a bare repo
+//! standing in for the remote.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use axum_test::TestServer;
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn init_repo(dir: &Path) {
+    git(dir, &["init", "-q", "-b", "main"]);
+    git(dir, &["config", "user.email", "dev@example.com"]);
+    git(dir, &["config", "user.name", "Dev"]);
+}
+
+/// A committed project with a map folder and a script
+fn project() -> TempDir {
+    let project = TempDir::new().unwrap();
+    let src = project.path().join("src");
+    fs::create_dir_all(src.join("Workspace/Map/Spawn")).unwrap();
+    fs::create_dir_all(src.join("ServerScriptService")).unwrap();
+    fs::write(src.join("Workspace/Map/Spawn/_meta.rbxjson"), r#"{"className": "Model"}"#).unwrap();
+    fs::write(src.join("Workspace/Map/Spawn/Pad.rbxjson"), r#"{"className": "Part"}"#).unwrap();
+    fs::write(src.join("Workspace/Map/Tree.rbxjson"), r#"{"className": "Part"}"#).unwrap();
+    fs::write(src.join("ServerScriptService/Main.server.luau"), "print('a')\nprint('b')\n").unwrap();
+    init_repo(project.path());
+    git(project.path(), &["add", "-A"]);
+    git(project.path(), &["commit", "-q", "-m", "Initial"]);
+    project
+}
+
+fn server() -> TestServer {
+    TestServer::new(create_router(AppState::new())).unwrap()
+}
+
+#[tokio::test]
+async fn test_stage_by_instance_path() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let src = project.path().join("src");
+    fs::write(src.join("Workspace/Map/Spawn/Pad.rbxjson"), r#"{"className": "SpawnLocation"}"#).unwrap();
+    fs::write(src.join("Workspace/Map/Spawn/Sign.rbxjson"), r#"{"className": "Part"}"#).unwrap();
+    fs::remove_file(src.join("Workspace/Map/Spawn/_meta.rbxjson")).unwrap();
+    fs::write(src.join("Workspace/Map/Tree.rbxjson"), r#"{"className": "MeshPart"}"#).unwrap();
+
+    let server = server();
+    let body: serde_json::Value = server
+        .post("/git/stage")
+        .json(&json!({ "project_dir": dir, "paths": ["Workspace/Map/Spawn"] }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    assert_eq!(
+        body["data"],
+        json!([
+            "src/Workspace/Map/Spawn/Pad.rbxjson",
+            "src/Workspace/Map/Spawn/Sign.rbxjson",
+            "src/Workspace/Map/Spawn/_meta.rbxjson"
+        ])
+    );
+
+    // Modified, added and deleted files of the instance are staged; Tree isn't
+    let staged = git(project.path(), &["diff", "--cached", "--name-status"]);
+    assert!(staged.contains("M\tsrc/Workspace/Map/Spawn/Pad.rbxjson"));
+    assert!(staged.contains("A\tsrc/Workspace/Map/Spawn/Sign.rbxjson"));
+    assert!(staged.contains("D\tsrc/Workspace/Map/Spawn/_meta.rbxjson"));
+    assert!(!staged.contains("Tree"));
+
+    let body: serde_json::Value = server
+        .post("/git/stage")
+        .json(&json!({ "project_dir": dir, "paths": ["Workspace/Map/Missing"] }))
+        .await
+        .json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["kind"], "path_not_found");
+}
+
+#[tokio::test]
+async fn test_diff_and_blame_by_instance_path() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let script = project.path().join("src/ServerScriptService/Main.server.luau");
+    fs::write(&script, "print('a')\nprint('c')\n").unwrap();
+
+    let server = server();
+    let body: serde_json::Value = server
+        .post("/git/diff")
+        .json(&json!({ "project_dir": dir, "instance_path": "ServerScriptService/Main" }))
+        .await
+        .json();
+    let diff = body["data"].as_str().unwrap();
+    assert!(diff.contains("-print('b')\n+print('c')"), "{}", diff);
+
+    git(project.path(), &["commit", "-q", "-am", "Print c"]);
+    let body: serde_json::Value = server
+        .post("/git/blame")
+        .json(&json!({ "project_dir": dir, "instance_path": "ServerScriptService/Main", "line": 2 }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    assert_eq!(body["data"]["file"], "src/ServerScriptService/Main.server.luau");
+    assert_eq!(body["data"]["summary"], "Print c");
+    assert_eq!(body["data"]["author"], "Dev");
+    assert_eq!(body["data"]["content"], "print('c')");
+
+    let body: serde_json::Value = server
+        .post("/git/blame")
+        .json(&json!({ "project_dir": dir, "instance_path": "Workspace/Map/Tree", "line": 1 }))
+        .await
+        .json();
+    assert_eq!(body["kind"], "not_a_script");
+}
+
+#[tokio::test]
+async fn test_branches_and_stash() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let server = server();
+
+    let body: serde_json::Value = server
+        .post("/git/switch")
+        .json(&json!({ "project_dir": dir, "branch": "feature", "create": true }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    let body: serde_json::Value = server.post("/git/branches").json(&json!({ "project_dir": dir })).await.json();
+    let branches = body["data"].as_array().unwrap();
+    assert_eq!(branches.len(), 2);
+    let current: Vec<_> = branches.iter().filter(|b| b["current"] == true).collect();
+    assert_eq!(current[0]["name"], "feature");
+
+    let tree = project.path().join("src/Workspace/Map/Tree.rbxjson");
+    fs::write(&tree, r#"{"className": "MeshPart"}"#).unwrap();
+    let body: serde_json::Value = server
+        .post("/git/stash")
+        .json(&json!({ "project_dir": dir, "message": "wip tree" }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    assert_eq!(fs::read_to_string(&tree).unwrap(), r#"{"className": "Part"}"#);
+
+    let body: serde_json::Value = server
+        .post("/git/stash")
+        .json(&json!({ "project_dir": dir, "action": "list" }))
+        .await
+        .json();
+    assert_eq!(body["data"][0]["name"], "stash@{0}");
+    assert!(body["data"][0]["message"].as_str().unwrap().contains("wip tree"));
+
+    server
+        .post("/git/stash")
+        .json(&json!({ "project_dir": dir, "action": "pop" }))
+        .await
+        .assert_status_ok();
+    assert_eq!(fs::read_to_string(&tree).unwrap(), r#"{"className": "MeshPart"}"#);
+
+    let body: serde_json::Value = server
+        .post("/git/switch")
+        .json(&json!({ "project_dir": dir, "branch": "nope" }))
+        .await
+        .json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["kind"], "failed");
+}
+
+#[tokio::test]
+async fn test_push_and_pull_with_local_remote() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let remote = TempDir::new().unwrap();
+    git(remote.path(), &["init", "-q", "--bare", "-b", "main"]);
+    let remote_url = remote.path().to_string_lossy().to_string();
+    git(project.path(), &["remote", "add", "origin", &remote_url]);
+
+    let server = server();
+    let body: serde_json::Value = server
+        .post("/git/push")
+        .json(&json!({ "project_dir": dir, "remote": "origin", "branch": "main", "set_upstream": true }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+
+    // A teammate pushes a change
+    let clone = TempDir::new().unwrap();
+    git(clone.path(), &["clone", "-q", &remote_url, "."]);
+    git(clone.path(), &["config", "user.email", "mate@example.com"]);
+    git(clone.path(), &["config", "user.name", "Mate"]);
+    fs::write(clone.path().join("src/Workspace/Map/Tree.rbxjson"), r#"{"className": "MeshPart"}"#).unwrap();
+    git(clone.path(), &["commit", "-q", "-am", "Mesh tree"]);
+    git(clone.path(), &["push", "-q"]);
+
+    let body: serde_json::Value = server.post("/git/pull").json(&json!({ "project_dir": dir })).await.json();
+    assert_eq!(body["success"], true, "{}", body);
+    let log: serde_json::Value = server.post("/git/log").json(&json!({ "project_dir": dir, "limit": 1 })).await.json();
+    assert_eq!(log["data"][0]["message"], "Mesh tree");
+
+    let not_repo = TempDir::new().unwrap();
+    let body: serde_json::Value = server
+        .post("/git/status")
+        .json(&json!({ "project_dir": not_repo.path().to_string_lossy() }))
+        .await
+        .json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["kind"], "not_a_repository");
+    assert_eq!(body["error"], "Not a git repository");
+}
+
+#[tokio::test]
+async fn test_options_as_branch_or_remote_are_refused() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let server = server();
+
+    for branch in ["--orphan", "-f"] {
+        let body: serde_json::Value = server
+            .post("/git/switch")
+            .json(&json!({ "project_dir": dir, "branch": branch }))
+            .await
+            .json();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["kind"], "invalid_argument");
+    }
+    assert_eq!(git(project.path(), &["branch", "--show-current"]).trim(), "main");
+
+    // git would run the upload pack command
+    let marker = project.path().join("pwned");
+    let upload_pack = format!("--upload-pack=touch {}", marker.display());
+    for endpoint in ["/git/pull", "/git/push"] {
+        let body: serde_json::Value = server
+            .post(endpoint)
+            .json(&json!({ "project_dir": dir, "remote": upload_pack }))
+            .await
+            .json();
+        assert_eq!(body["kind"], "invalid_argument", "{}", body);
+        let body: serde_json::Value = server
+            .post(endpoint)
+            .json(&json!({ "project_dir": dir, "remote": "origin", "branch": "--force" }))
+            .await
+            .json();
+        assert_eq!(body["kind"], "invalid_argument", "{}", body);
+    }
+    assert!(!marker.exists());
+}
+
+#[test]
+fn test_staged_tree_and_pre_commit_hook() {
+    use rbxsync_server::git::{self, GitError};
+
+    let project = project();
+    let dir = project.path();
+    fs::write(dir.join("src/Workspace/Map/Tree.rbxjson"), r#"{"className": "MeshPart"}"#).unwrap();
+    git(dir, &["mv", "src/Workspace/Map/Spawn/Pad.rbxjson", "src/Workspace/Map/Spawn/Base.rbxjson"]);
+    git(dir, &["add", "src/Workspace/Map/Tree.rbxjson"]);
+    // Unstaged edits aren't part of the staged tree
+    fs::write(dir.join("src/Workspace/Map/Tree.rbxjson"), r#"{"className": "WedgePart"}"#).unwrap();
+
+    let mut staged = git::staged_files(dir).unwrap();
+    staged.sort();
+    assert_eq!(
+        staged,
+        vec![
+            "src/Workspace/Map/Spawn/Base.rbxjson",
+            "src/Workspace/Map/Spawn/Pad.rbxjson",
+            "src/Workspace/Map/Tree.rbxjson",
+        ]
+    );
+    assert_eq!(
+        git::file_at(dir, "HEAD", "src/Workspace/Map/Tree.rbxjson").unwrap().as_deref(),
+        Some(r#"{"className": "Part"}"#)
+    );
+    assert_eq!(git::file_at(dir, "HEAD", "src/Workspace/Map/Spawn/Base.rbxjson").unwrap(), None);
+
+    let export = TempDir::new().unwrap();
+    git::export_index(dir, export.path()).unwrap();
+    let tree = fs::read_to_string(export.path().join("src/Workspace/Map/Tree.rbxjson")).unwrap();
+    assert_eq!(tree, r#"{"className": "MeshPart"}"#);
+    assert!(export.path().join("src/Workspace/Map/Spawn/Base.rbxjson").exists());
+    assert!(!export.path().join("src/Workspace/Map/Spawn/Pad.rbxjson").exists());
+
+    let hook = git::install_pre_commit_hook(dir, false).unwrap();
+    assert_eq!(hook, dir.join(".git/hooks/pre-commit").canonicalize().unwrap());
+    let script = fs::read_to_string(&hook).unwrap();
+    assert!(script.ends_with("exec rbxsync verify --staged --path '.'\n"), "{}", script);
+    // Reinstalling replaces our own hook, but not someone else's
+    git::install_pre_commit_hook(dir, false).unwrap();
+    fs::write(&hook, "#!/bin/sh\nnpx lint-staged\n").unwrap();
+    assert!(matches!(git::install_pre_commit_hook(dir, false), Err(GitError::HookExists(_))));
+    git::install_pre_commit_hook(dir, true).unwrap();
+    assert!(fs::read_to_string(&hook).unwrap().contains("rbxsync verify --staged"));
+
+    // The project path reaches the hook's command line as one word
+    let nested = dir.join("it's here");
+    fs::create_dir_all(&nested).unwrap();
+    git::install_pre_commit_hook(&nested, false).unwrap();
+    let script = fs::read_to_string(&hook).unwrap().replace("exec rbxsync verify --staged --path", "printf %s");
+    let output = Command::new("sh").arg("-c").arg(&script).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "it's here");
+}
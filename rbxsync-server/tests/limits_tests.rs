@@ -0,0 +1,196 @@
+//! Tests for size guardrails
+//!
+//! Tests that extraction chunks, terrain batches and Studio changes over the
+//! project's `limits` are refused with 413 and reported to the console, that
+//! oversized instances are left out of an extraction, and that `force` skips
+//! the checks.
+
+use std::fs;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn project_with_limits(limits: serde_json::Value) -> TempDir {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src/Workspace")).unwrap();
+    fs::write(project.path().join("src/Workspace/Existing.rbxjson"), "{}").unwrap();
+    fs::write(
+        project.path().join("rbxsync.json"),
+        serde_json::to_string(&json!({ "limits": limits })).unwrap(),
+    )
+    .unwrap();
+    project
+}
+
+fn module(name: &str, source: &str) -> serde_json::Value {
+    json!({
+        "className": "ModuleScript",
+        "name": name,
+        "path": format!("Workspace/{}", name),
+        "referenceId": name,
+        "properties": { "Source": { "type": "string", "value": source } }
+    })
+}
+
+#[tokio::test]
+async fn test_oversized_instance_is_skipped() {
+    let project = project_with_limits(json!({ "maxFileSize": 1024 }));
+    let project_dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    let response = server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": start["sessionId"],
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": [module("Small", "return 1"), module("Huge", &"-".repeat(4096))],
+            "project_dir": project_dir,
+        }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["skipped"].as_array().unwrap().len(), 1);
+    assert!(body["skipped"][0].as_str().unwrap().contains("Workspace/Huge"));
+
+    let history: serde_json::Value = server.get("/console/history").await.json();
+    let message = &history["messages"][0];
+    assert_eq!(message["source"], "extract");
+    assert!(message["message"].as_str().unwrap().contains("limits.maxFileSize"));
+
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+    assert!(project.path().join("src/Workspace/Small.luau").exists());
+    assert!(!project.path().join("src/Workspace/Huge.luau").exists());
+}
+
+#[tokio::test]
+async fn test_chunk_over_batch_limit_can_be_resent() {
+    let project = project_with_limits(json!({ "maxBatchInstances": 1 }));
+    let project_dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let chunk = |session: &serde_json::Value, data: serde_json::Value| {
+        json!({
+            "session_id": session,
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": data,
+            "project_dir": project_dir,
+        })
+    };
+
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    let session = &start["sessionId"];
+    let response = server
+        .post("/extract/chunk")
+        .json(&chunk(session, json!([module("A", "return 1"), module("B", "return 2")])))
+        .await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(response.json::<serde_json::Value>()["limit"], "maxBatchInstances");
+
+    // The plugin splits the chunk and sends it again
+    for name in ["A", "B"] {
+        server
+            .post("/extract/chunk")
+            .json(&chunk(session, json!([module(name, "return 1")])))
+            .await
+            .assert_status_ok();
+    }
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+    assert!(project.path().join("src/Workspace/B.luau").exists());
+}
+
+#[tokio::test]
+async fn test_extraction_size_and_force() {
+    let project = project_with_limits(json!({ "maxExtractionSize": 2048 }));
+    let project_dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let chunk = |session: &serde_json::Value, index: usize| {
+        json!({
+            "session_id": session,
+            "chunk_index": index,
+            "total_chunks": 2,
+            "data": [module(&format!("M{}", index), &"-".repeat(1500))],
+            "project_dir": project_dir,
+        })
+    };
+
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    server.post("/extract/chunk").json(&chunk(&start["sessionId"], 0)).await.assert_status_ok();
+    let response = server.post("/extract/chunk").json(&chunk(&start["sessionId"], 1)).await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(response.json::<serde_json::Value>()["limit"], "maxExtractionSize");
+
+    // An intentional large extraction
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir, "force": true }))
+        .await
+        .json();
+    server.post("/extract/chunk").json(&chunk(&start["sessionId"], 0)).await.assert_status_ok();
+    server.post("/extract/chunk").json(&chunk(&start["sessionId"], 1)).await.assert_status_ok();
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+    assert!(project.path().join("src/Workspace/M1.luau").exists());
+}
+
+#[tokio::test]
+async fn test_studio_changes_batch_limit() {
+    let project = project_with_limits(json!({ "maxBatchInstances": 2 }));
+    let project_dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let operations: Vec<serde_json::Value> = (0..3)
+        .map(|i| {
+            json!({
+                "type": "create",
+                "path": format!("Workspace/Part{}", i),
+                "className": "Part",
+                "data": { "className": "Part", "name": format!("Part{}", i) }
+            })
+        })
+        .collect();
+
+    let response = server
+        .post("/sync/from-studio")
+        .json(&json!({ "operations": operations, "projectDir": project_dir }))
+        .await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(response.json::<serde_json::Value>()["limit"], "maxBatchInstances");
+    assert!(!project.path().join("src/Workspace/Part0.rbxjson").exists());
+
+    server
+        .post("/sync/from-studio")
+        .json(&json!({ "operations": operations, "projectDir": project_dir, "force": true }))
+        .await
+        .assert_status_ok();
+    assert!(project.path().join("src/Workspace/Part2.rbxjson").exists());
+
+    let metrics = server.get("/metrics").await.text();
+    assert!(metrics.contains("rbxsync_size_limit_hits_total{limit=\"maxBatchInstances\"} 1"));
+}
@@ -0,0 +1,205 @@
+//! Integration tests for live-sync batch tracking
+//!
+//! Tests that file watcher batches are followed to completion and that
+//! failures reach /sync/batches, the console history and the event stream.
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum_test::TestServer;
+use rbxsync_server::live_sync::queue_batch;
+use rbxsync_server::{create_router, load_project_config, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn operations() -> Vec<serde_json::Value> {
+    vec![
+        json!({ "type": "update", "path": "ServerScriptService/Main" }),
+        json!({ "type": "create", "path": "ReplicatedStorage/Util" }),
+    ]
+}
+
+/// Poll a batch until the tracking task has recorded its outcome
+async fn wait_for_batch(server: &TestServer, id: uuid::Uuid) -> serde_json::Value {
+    for _ in 0..100 {
+        let batch: serde_json::Value = server.get(&format!("/sync/batches/{}", id)).await.json();
+        if batch["status"] != "pending" {
+            return batch;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("batch {} never completed", id);
+}
+
+fn setup() -> (Arc<AppState>, TestServer) {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    (state, server)
+}
+
+#[tokio::test]
+async fn test_partial_failure_is_reported() {
+    let (state, server) = setup();
+    let id = queue_batch(&state, None, &None, operations()).await;
+
+    let request: serde_json::Value = server.get("/rbxsync/request").await.json();
+    assert_eq!(request["command"], "sync:batch");
+    assert_eq!(request["payload"]["atomic"], false);
+
+    let pending: serde_json::Value = server.get(&format!("/sync/batches/{}", id)).await.json();
+    assert_eq!(pending["status"], "pending");
+    assert_eq!(pending["operations"], 2);
+
+    server
+        .post("/rbxsync/response")
+        .json(&json!({
+            "id": id,
+            "success": false,
+            "data": {
+                "applied": 1, "skipped": 0, "failed": 1, "rolledBack": false,
+                "results": [
+                    { "path": "ServerScriptService/Main", "type": "update", "success": true, "action": "updated" },
+                    { "path": "ReplicatedStorage/Util", "type": "create", "success": false, "error": "Parent not found" }
+                ]
+            }
+        }))
+        .await
+        .assert_status_ok();
+
+    let batch = wait_for_batch(&server, id).await;
+    assert_eq!(batch["status"], "partial");
+    assert_eq!(batch["applied"], 1);
+    assert_eq!(batch["failed"], 1);
+    assert_eq!(batch["results"][1]["error"], "Parent not found");
+    assert!(batch["completedAt"].is_u64());
+
+    let console: serde_json::Value = server.get("/console/history").await.json();
+    let messages = console["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["message_type"], "error");
+    assert_eq!(messages[0]["source"], "sync");
+    assert!(messages[0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("ReplicatedStorage/Util - Parent not found"));
+
+    let history: serde_json::Value = server.get("/events/history").await.json();
+    let completed = history["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["type"] == "live_sync_completed")
+        .expect("live_sync_completed event");
+    assert_eq!(completed["requestId"], id.to_string());
+    assert_eq!(completed["status"], "partial");
+    assert_eq!(completed["failedPaths"], json!(["ReplicatedStorage/Util"]));
+
+    let metrics = server.get("/metrics").await.text();
+    assert!(metrics.contains("rbxsync_live_sync_batch_results_total{status=\"partial\"} 1"));
+    assert!(metrics.contains("rbxsync_live_sync_operations_failed_total 1"));
+}
+
+#[tokio::test]
+async fn test_atomic_batch_rolled_back() {
+    let project = TempDir::new().unwrap();
+    fs::write(project.path().join("rbxsync.json"), r#"{"sync": {"atomic": true}}"#).unwrap();
+    let project_dir = project.path().to_string_lossy().to_string();
+
+    let (state, server) = setup();
+    let id = queue_batch(&state, Some(project_dir.clone()), &load_project_config(&project_dir), operations()).await;
+
+    // No plugin registered for the project, so the batch falls back to the global queue
+    let request: serde_json::Value = server.get("/rbxsync/request").await.json();
+    assert_eq!(request["payload"]["atomic"], true);
+
+    server
+        .post("/rbxsync/response")
+        .json(&json!({
+            "id": id,
+            "success": false,
+            "error": "Rolled back 2 operations (1 failed)",
+            "data": {
+                "applied": 1, "skipped": 0, "failed": 1, "rolledBack": true,
+                "results": [
+                    { "path": "ServerScriptService/Main", "type": "update", "success": true },
+                    { "path": "ReplicatedStorage/Util", "type": "create", "success": false, "error": "boom" }
+                ]
+            }
+        }))
+        .await
+        .assert_status_ok();
+
+    let batch = wait_for_batch(&server, id).await;
+    assert_eq!(batch["status"], "rolled_back");
+    assert_eq!(batch["atomic"], true);
+    assert_eq!(batch["projectDir"], project_dir);
+
+    let console: serde_json::Value = server.get("/console/history").await.json();
+    let messages = console["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 2);
+    assert!(messages[1]["message"].as_str().unwrap().contains("rolled back"));
+}
+
+#[tokio::test]
+async fn test_skipped_and_applied_batches() {
+    let (state, server) = setup();
+
+    let skipped = queue_batch(&state, None, &None, operations()).await;
+    let applied = queue_batch(&state, None, &None, operations()).await;
+    server
+        .post("/rbxsync/response")
+        .json(&json!({
+            "id": skipped,
+            "success": true,
+            "data": { "skipped": true, "reason": "files_to_studio_disabled" }
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/rbxsync/response")
+        .json(&json!({
+            "id": applied,
+            "success": true,
+            "data": { "applied": 1, "skipped": 1, "failed": 0, "results": [] }
+        }))
+        .await
+        .assert_status_ok();
+
+    let batch = wait_for_batch(&server, skipped).await;
+    assert_eq!(batch["status"], "skipped");
+    assert_eq!(batch["error"], "files_to_studio_disabled");
+    let batch = wait_for_batch(&server, applied).await;
+    assert_eq!(batch["status"], "applied");
+
+    // Newest first
+    let list: serde_json::Value = server.get("/sync/batches?limit=1").await.json();
+    assert_eq!(list["count"], 1);
+    assert_eq!(list["batches"][0]["requestId"], applied.to_string());
+
+    server
+        .get(&format!("/sync/batches/{}", uuid::Uuid::new_v4()))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_unanswered_batch_times_out() {
+    let (state, server) = setup();
+    state
+        .command_timeouts
+        .write()
+        .await
+        .set("sync:batch", Duration::from_millis(100));
+
+    let id = queue_batch(&state, None, &None, operations()).await;
+    let batch = wait_for_batch(&server, id).await;
+    assert_eq!(batch["status"], "timed_out");
+
+    // The undispatched request is dropped from the queue
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(state.request_queue.lock().await.is_empty());
+
+    let console: serde_json::Value = server.get("/console/history").await.json();
+    assert!(console["messages"][0]["message"].as_str().unwrap().contains("timed out"));
+}
@@ -0,0 +1,110 @@
+//! Integration tests for the /metrics endpoint
+//!
+//! Tests the Prometheus text output, instrumentation of the plugin request path
+//! and extraction timing.
+
+use axum_test::TestServer;
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+
+fn sample(body: &str, name: &str) -> f64 {
+    body.lines()
+        .find(|line| line.starts_with(name) && line[name.len()..].starts_with(' '))
+        .and_then(|line| line.rsplit(' ').next())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("metric {} not found", name))
+}
+
+#[tokio::test]
+async fn test_metrics_exposes_prometheus_text() {
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    let response = server.get("/metrics").await;
+    response.assert_status_ok();
+    let content_type = response.header("content-type");
+    assert!(content_type.to_str().unwrap().starts_with("text/plain"));
+
+    let body = response.text();
+    assert!(body.contains("# TYPE rbxsync_plugin_response_seconds histogram"));
+    assert!(body.contains("rbxsync_plugin_response_seconds_bucket{le=\"+Inf\"} 0"));
+    assert_eq!(sample(&body, "rbxsync_queue_depth{queue=\"global\"}"), 0.0);
+    assert_eq!(sample(&body, "rbxsync_plugin_response_timeouts_total"), 0.0);
+}
+
+#[tokio::test]
+async fn test_metrics_track_dispatch_and_response() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    let request_id = uuid::Uuid::new_v4();
+    state.request_queue.lock().await.push_back(rbxsync_server::PluginRequest::new(
+        request_id,
+        "sync:batch",
+        json!({}),
+    ));
+
+    let body = server.get("/metrics").await.text();
+    assert_eq!(sample(&body, "rbxsync_queue_depth{queue=\"global\"}"), 1.0);
+
+    server.get("/rbxsync/request").await.assert_status_ok();
+    server
+        .post("/rbxsync/response")
+        .json(&json!({ "id": request_id, "success": true, "data": {} }))
+        .await
+        .assert_status_ok();
+
+    let body = server.get("/metrics").await.text();
+    assert_eq!(sample(&body, "rbxsync_queue_depth{queue=\"global\"}"), 0.0);
+    assert_eq!(sample(&body, "rbxsync_plugin_requests_total{command=\"sync:batch\"}"), 1.0);
+    assert_eq!(sample(&body, "rbxsync_plugin_responses_total{outcome=\"success\"}"), 1.0);
+    assert_eq!(sample(&body, "rbxsync_plugin_response_seconds_count"), 1.0);
+    // No handler was waiting on the response channel
+    assert_eq!(sample(&body, "rbxsync_plugin_responses_dropped_total"), 1.0);
+    assert_eq!(sample(&body, "rbxsync_polls_total{result=\"request\"}"), 1.0);
+}
+
+#[tokio::test]
+async fn test_metrics_count_console_messages() {
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    server
+        .post("/console/push")
+        .json(&json!({
+            "messages": [
+                { "timestamp": "1", "message_type": "info", "message": "a", "source": null },
+                { "timestamp": "2", "message_type": "info", "message": "b", "source": null }
+            ]
+        }))
+        .await
+        .assert_status_ok();
+
+    let body = server.get("/metrics").await.text();
+    assert_eq!(sample(&body, "rbxsync_console_messages_total"), 2.0);
+}
+
+#[tokio::test]
+async fn test_extraction_timer_restarts_per_session() {
+    let metrics = rbxsync_server::metrics::Metrics::new();
+    let pause = std::time::Duration::from_millis(200);
+
+    // An abandoned session doesn't count towards the next one
+    metrics.extraction_chunk("a", 10);
+    tokio::time::sleep(pause).await;
+    metrics.extraction_chunk("b", 10);
+    metrics.extraction_finished("b", 1);
+    assert_eq!(metrics.extraction_seconds.count(), 1);
+    assert!(metrics.extraction_seconds.sum() < pause.as_secs_f64());
+
+    // Nor does one cut short by a new /extract/start reusing its id
+    metrics.extraction_chunk("c", 10);
+    tokio::time::sleep(pause).await;
+    metrics.extraction_reset();
+    metrics.extraction_chunk("c", 10);
+    metrics.extraction_finished("c", 1);
+    assert_eq!(metrics.extraction_seconds.count(), 2);
+    assert!(metrics.extraction_seconds.sum() < pause.as_secs_f64());
+
+    // Finalizing a session that sent no chunks observes nothing
+    metrics.extraction_finished("d", 0);
+    assert_eq!(metrics.extraction_seconds.count(), 2);
+}
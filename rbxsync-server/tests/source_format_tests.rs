@@ -0,0 +1,80 @@
+//! Tests for script line ending and BOM handling
+//!
+//! Tests that sources reach Studio normalized and are written to disk in the
+//! project's `scripts` style.
+
+use std::fs;
+
+use axum_test::TestServer;
+use rbxsync_server::file_watcher::{live_sync_tree_mapping, process_file_change, FileChange, FileChangeKind};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+#[test]
+fn test_live_sync_sends_normalized_source() {
+    let project = TempDir::new().unwrap();
+    let scripts = project.path().join("src/ServerScriptService");
+    fs::create_dir_all(&scripts).unwrap();
+    let path = scripts.join("Main.server.luau");
+    fs::write(&path, "\u{feff}print('a')\r\nprint('b')\r\n").unwrap();
+
+    let change = FileChange {
+        path,
+        project_dir: project.path().to_string_lossy().to_string(),
+        kind: FileChangeKind::Modify,
+    };
+    let op = process_file_change(&change, &live_sync_tree_mapping(&None)).unwrap();
+    assert_eq!(op["data"]["source"], "print('a')\nprint('b')\n");
+    assert_eq!(op["data"]["properties"]["Source"]["value"], "print('a')\nprint('b')\n");
+}
+
+#[tokio::test]
+async fn test_extraction_follows_project_format() {
+    let project = TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().to_string();
+    let scripts = project.path().join("src/ServerScriptService");
+    fs::create_dir_all(&scripts).unwrap();
+    fs::write(scripts.join("Main.server.luau"), "\u{feff}old\r\n").unwrap();
+    fs::write(
+        project.path().join("rbxsync.json"),
+        r#"{"scripts": {"lineEndings": "preserve", "trailingNewline": "ensure", "bom": "preserve"}}"#,
+    )
+    .unwrap();
+
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let start: serde_json::Value = server
+        .post("/extract/start")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .json();
+    let source = |value: &str| json!({ "Source": { "type": "string", "value": value } });
+    server
+        .post("/extract/chunk")
+        .json(&json!({
+            "session_id": start["sessionId"],
+            "chunk_index": 0,
+            "total_chunks": 1,
+            "data": [
+                { "className": "Script", "name": "Main", "path": "ServerScriptService/Main",
+                  "referenceId": "main", "properties": source("print('a')\nprint('b')") },
+                { "className": "ModuleScript", "name": "New", "path": "ServerScriptService/New",
+                  "referenceId": "new", "properties": source("return 1") },
+            ],
+            "project_dir": project_dir,
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/extract/finalize")
+        .json(&json!({ "project_dir": project_dir }))
+        .await
+        .assert_status_ok();
+
+    // The existing file keeps its CRLF and BOM; new files default to LF
+    assert_eq!(
+        fs::read_to_string(scripts.join("Main.server.luau")).unwrap(),
+        "\u{feff}print('a')\r\nprint('b')\r\n"
+    );
+    assert_eq!(fs::read_to_string(scripts.join("New.luau")).unwrap(), "return 1\n");
+}
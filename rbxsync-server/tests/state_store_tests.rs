@@ -0,0 +1,72 @@
+//! Integration tests for persisted server state
+//!
+//! Tests capture/restore round trips and on-disk save/load.
+
+use axum_test::TestServer;
+use rbxsync_server::state_store::{PersistedState, StateStore};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_state_round_trips_through_disk() {
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+
+    server
+        .post("/rbxsync/register")
+        .json(&json!({
+            "place_id": 7,
+            "place_name": "Persisted Place",
+            "project_dir": "/tmp/persisted",
+            "session_id": "session-7"
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/console/push")
+        .json(&json!({
+            "messages": [
+                { "timestamp": "1", "message_type": "info", "message": "hello", "source": null }
+            ]
+        }))
+        .await
+        .assert_status_ok();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = StateStore::new(temp_dir.path(), 44755);
+    store.save_state(&state).await.unwrap();
+    assert!(store.path().ends_with("server-44755.json"));
+
+    let restored = AppState::new();
+    store.load().unwrap().unwrap().apply(&restored).await;
+
+    let registry = restored.place_registry.read().await;
+    let place = registry.get("session-7").unwrap();
+    assert_eq!(place.place_id, 7);
+    assert_eq!(place.project_dir, "/tmp/persisted");
+    assert!(place.last_heartbeat.is_some());
+    assert!(restored.project_queues.read().await.contains_key("/tmp/persisted"));
+
+    let console = restored.console_buffer.read().await;
+    assert_eq!(console.len(), 1);
+    assert_eq!(console[0].message, "hello");
+}
+
+#[tokio::test]
+async fn test_load_missing_file_returns_none() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = StateStore::new(temp_dir.path(), 12345);
+    assert!(store.load().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_load_ignores_unknown_version() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = StateStore::new(temp_dir.path(), 12345);
+    let persisted = PersistedState {
+        version: 999,
+        ..Default::default()
+    };
+    store.save(&persisted).unwrap();
+    assert!(store.load().unwrap().is_none());
+}
@@ -0,0 +1,227 @@
+//! Tests for committing Studio changes
+//!
+//! Streams changes the way the plugin does, then commits them through
+//! `/git/commit-studio` in a real repository.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use axum_test::TestServer;
+use rbxsync_server::studio_commit::commit_message;
+use rbxsync_server::studio_stream::{ChangeAction, StudioChange};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").args(args).current_dir(dir).output().unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// A committed project with three loose parts, a props folder and a script
+fn project() -> TempDir {
+    let project = TempDir::new().unwrap();
+    let src = project.path().join("src");
+    fs::create_dir_all(src.join("Workspace/Props")).unwrap();
+    fs::create_dir_all(src.join("ServerScriptService")).unwrap();
+    for name in ["Crate", "Barrel", "Fence"] {
+        fs::write(
+            src.join(format!("Workspace/{}.rbxjson", name)),
+            format!(r#"{{"className": "Part", "name": "{}"}}"#, name),
+        )
+        .unwrap();
+    }
+    fs::write(src.join("Workspace/Props/_meta.rbxjson"), r#"{"className": "Folder"}"#).unwrap();
+    fs::write(src.join("ServerScriptService/Combat.server.luau"), "print('old')\n").unwrap();
+    fs::write(project.path().join("notes.txt"), "todo\n").unwrap();
+
+    git(project.path(), &["init", "-q", "-b", "main"]);
+    git(project.path(), &["config", "user.email", "dev@example.com"]);
+    git(project.path(), &["config", "user.name", "Dev"]);
+    git(project.path(), &["add", "-A"]);
+    git(project.path(), &["commit", "-q", "-m", "Initial"]);
+    project
+}
+
+/// Moving a part into a folder, as the plugin reports it
+fn move_part(name: &str) -> Vec<serde_json::Value> {
+    vec![
+        json!({ "type": "delete", "path": format!("Workspace/{}", name), "className": "Part" }),
+        json!({
+            "type": "create",
+            "path": format!("Workspace/Props/{}", name),
+            "className": "Part",
+            "data": { "className": "Part", "name": name, "properties": {} }
+        }),
+    ]
+}
+
+fn change(action: ChangeAction, path: &str, class_name: &str) -> StudioChange {
+    StudioChange {
+        action,
+        path: path.to_string(),
+        old_path: None,
+        class_name: Some(class_name.to_string()),
+        files: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_commit_studio_commits_only_studio_changes() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    // The user's own work in progress, staged and unstaged
+    fs::write(project.path().join("notes.txt"), "todo: more\n").unwrap();
+    fs::write(project.path().join("plan.txt"), "plan\n").unwrap();
+    git(project.path(), &["add", "plan.txt"]);
+
+    let mut operations: Vec<serde_json::Value> = ["Crate", "Barrel", "Fence"].into_iter().flat_map(move_part).collect();
+    operations.push(json!({
+        "type": "modify",
+        "path": "ServerScriptService/Combat",
+        "className": "Script",
+        "data": { "properties": { "Source": { "type": "string", "value": "print('new')\n" } } }
+    }));
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({ "projectDir": dir, "operations": operations }))
+        .await
+        .assert_status_ok();
+
+    // Still pending in the stream; committing writes it first
+    let body: serde_json::Value = server
+        .post("/git/commit-studio")
+        .json(&json!({ "project_dir": dir }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    let message = body["data"]["message"].as_str().unwrap();
+    assert_eq!(
+        message.lines().next().unwrap(),
+        "ServerScriptService/Combat: edited; Workspace/Props: moved 3 parts"
+    );
+    assert!(message.contains("- moved Workspace/Crate → Workspace/Props/Crate"));
+    assert_eq!(body["data"]["changes"].as_array().unwrap().len(), 4);
+
+    let committed = git(project.path(), &["show", "--name-only", "--format=", "HEAD"]);
+    let mut committed: Vec<&str> = committed.lines().collect();
+    committed.sort();
+    assert_eq!(
+        committed,
+        [
+            "src/ServerScriptService/Combat.server.luau",
+            "src/Workspace/Barrel.rbxjson",
+            "src/Workspace/Crate.rbxjson",
+            "src/Workspace/Fence.rbxjson",
+            "src/Workspace/Props/Barrel.rbxjson",
+            "src/Workspace/Props/Crate.rbxjson",
+            "src/Workspace/Props/Fence.rbxjson",
+        ]
+    );
+    assert_eq!(git(project.path(), &["log", "-1", "--format=%H"]).trim(), body["data"]["commit"]);
+
+    // The user's changes are where they left them
+    let status = git(project.path(), &["status", "--porcelain"]);
+    assert!(status.contains(" M notes.txt"), "{}", status);
+    assert!(status.contains("A  plan.txt"), "{}", status);
+
+    let body: serde_json::Value = server
+        .post("/git/commit-studio")
+        .json(&json!({ "project_dir": dir }))
+        .await
+        .json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["kind"], "nothing_to_commit");
+}
+
+#[tokio::test]
+async fn test_commit_studio_with_subject_and_undone_changes() {
+    let project = project();
+    let dir = project.path().to_string_lossy().to_string();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+
+    // Added and removed again, then a rename
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({
+            "projectDir": dir,
+            "operations": [{
+                "type": "create",
+                "path": "Workspace/Tmp",
+                "className": "Part",
+                "data": { "className": "Part", "name": "Tmp", "properties": {} }
+            }],
+            "flush": true
+        }))
+        .await
+        .assert_status_ok();
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({
+            "projectDir": dir,
+            "operations": [
+                { "type": "delete", "path": "Workspace/Tmp", "className": "Part" },
+                {
+                    "type": "rename",
+                    "path": "Workspace/Box",
+                    "className": "Part",
+                    "data": { "oldPath": "Workspace/Crate", "newPath": "Workspace/Box" }
+                }
+            ]
+        }))
+        .await
+        .assert_status_ok();
+
+    let body: serde_json::Value = server
+        .post("/git/commit-studio")
+        .json(&json!({ "project_dir": dir, "message": "Rename the crate" }))
+        .await
+        .json();
+    assert_eq!(body["success"], true, "{}", body);
+    assert_eq!(body["data"]["message"], "Rename the crate\n\n- renamed Workspace/Crate → Workspace/Box");
+    assert_eq!(body["data"]["changes"][0]["action"], "renamed");
+    assert_eq!(body["data"]["changes"][0]["old_path"], "Workspace/Crate");
+    assert!(!project.path().join("src/Workspace/Tmp.rbxjson").exists());
+}
+
+#[tokio::test]
+async fn test_commit_studio_outside_repository() {
+    let project = TempDir::new().unwrap();
+    let server = TestServer::new(create_router(AppState::new())).unwrap();
+    let body: serde_json::Value = server
+        .post("/git/commit-studio")
+        .json(&json!({ "project_dir": project.path().to_string_lossy() }))
+        .await
+        .json();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["kind"], "not_a_repository");
+}
+
+#[test]
+fn test_commit_message_summary() {
+    let changes = vec![
+        change(ChangeAction::Added, "Workspace/Map/Rock1", "MeshPart"),
+        change(ChangeAction::Added, "Workspace/Map/Rock2", "MeshPart"),
+        change(ChangeAction::Removed, "Workspace/Map/Bush", "Model"),
+        change(ChangeAction::Edited, "Lighting", "Lighting"),
+        change(ChangeAction::Added, "StarterGui/Hud/List", "UIListLayout"),
+        change(ChangeAction::Added, "StarterGui/Hud/Grid", "UIListLayout"),
+    ];
+    let message = commit_message(&changes[..3], None);
+    assert_eq!(
+        message,
+        "Workspace/Map: added 2 mesh parts; Workspace/Map/Bush: removed\n\n\
+         - added Workspace/Map/Rock1\n\
+         - added Workspace/Map/Rock2\n\
+         - removed Workspace/Map/Bush"
+    );
+
+    // Too long for a subject line
+    let message = commit_message(&changes, None);
+    assert_eq!(message.lines().next().unwrap(), "Studio: 6 changes in Lighting, StarterGui, Workspace");
+    assert!(message.contains("- added StarterGui/Hud/Grid"));
+}
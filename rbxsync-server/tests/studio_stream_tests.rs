@@ -0,0 +1,189 @@
+//! Integration tests for live Studio→disk streaming
+//!
+//! Tests that streamed changes are coalesced per instance, rate-limited,
+//! written through the extraction layout and kept away from the file watcher.
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum_test::TestServer;
+use rbxsync_server::file_watcher::{FileChange, FileChangeKind};
+use rbxsync_server::studio_stream::{flush_all, MIN_WRITE_INTERVAL, QUIET_PERIOD};
+use rbxsync_server::{create_router, AppState};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, Arc<AppState>, TestServer) {
+    let project = TempDir::new().unwrap();
+    fs::create_dir_all(project.path().join("src/Workspace")).unwrap();
+    let state = AppState::new();
+    let server = TestServer::new(create_router(state.clone())).unwrap();
+    (project, state, server)
+}
+
+fn project_dir(project: &TempDir) -> String {
+    project.path().to_string_lossy().to_string()
+}
+
+fn part(x: f64) -> serde_json::Value {
+    json!({
+        "type": "modify",
+        "path": "Workspace/Part",
+        "className": "Part",
+        "data": {
+            "className": "Part",
+            "name": "Part",
+            "properties": { "Position": { "type": "Vector3", "value": { "x": x, "y": 0.0, "z": 0.0 } } }
+        }
+    })
+}
+
+fn is_echo(state: &AppState, project: &TempDir, path: &Path) -> bool {
+    state
+        .echo_filter
+        .filter(vec![FileChange {
+            path: path.to_path_buf(),
+            project_dir: project_dir(project),
+            kind: FileChangeKind::Modify,
+        }])
+        .is_empty()
+}
+
+#[tokio::test]
+async fn test_property_drag_is_coalesced() {
+    let (project, state, server) = setup();
+
+    for step in 0..20 {
+        let response: serde_json::Value = server
+            .post("/sync/studio-stream")
+            .json(&json!({ "projectDir": project_dir(&project), "operations": [part(step as f64)] }))
+            .await
+            .json();
+        assert_eq!(response["success"], true);
+        assert_eq!(response["pending"], 1);
+    }
+
+    let json_path = project.path().join("src/Workspace/Part.rbxjson");
+    flush_all(&state, false).await;
+    assert!(!json_path.exists(), "written before the drag settled");
+
+    tokio::time::sleep(QUIET_PERIOD + Duration::from_millis(50)).await;
+    flush_all(&state, false).await;
+    let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(written["properties"]["Position"]["value"]["x"], 19.0);
+    assert!(is_echo(&state, &project, &json_path));
+
+    let status: serde_json::Value = server.get("/sync/studio-stream").await.json();
+    assert_eq!(status["projects"][0]["pending"], 0);
+    assert_eq!(status["changes"], 20);
+    assert_eq!(status["coalesced"], 19);
+    assert_eq!(status["filesWritten"], 1);
+}
+
+#[tokio::test]
+async fn test_rewrites_are_rate_limited() {
+    let (project, state, server) = setup();
+    let json_path = project.path().join("src/Workspace/Part.rbxjson");
+
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({ "projectDir": project_dir(&project), "operations": [part(1.0)], "flush": true }))
+        .await
+        .assert_status_ok();
+    assert!(fs::read_to_string(&json_path).unwrap().contains("1.0"));
+
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({ "projectDir": project_dir(&project), "operations": [part(2.0)] }))
+        .await
+        .assert_status_ok();
+    tokio::time::sleep(QUIET_PERIOD + Duration::from_millis(50)).await;
+    flush_all(&state, false).await;
+    assert!(fs::read_to_string(&json_path).unwrap().contains("1.0"), "rewritten within the minimum interval");
+
+    tokio::time::sleep(MIN_WRITE_INTERVAL).await;
+    flush_all(&state, false).await;
+    assert!(fs::read_to_string(&json_path).unwrap().contains("2.0"));
+}
+
+#[tokio::test]
+async fn test_script_source_keeps_existing_file() {
+    let (project, state, server) = setup();
+    let scripts = project.path().join("src/ServerScriptService");
+    fs::create_dir_all(&scripts).unwrap();
+    let script_path = scripts.join("Main.server.lua");
+    fs::write(&script_path, "print('old')").unwrap();
+
+    let response: serde_json::Value = server
+        .post("/sync/studio-stream")
+        .json(&json!({
+            "projectDir": project_dir(&project),
+            "operations": [{
+                "type": "modify",
+                "path": "ServerScriptService/Main",
+                "className": "Script",
+                "data": { "properties": { "Source": { "type": "string", "value": "print('new')" } } }
+            }],
+            "flush": true
+        }))
+        .await
+        .json();
+    assert_eq!(response["filesWritten"], 1);
+
+    assert_eq!(fs::read_to_string(&script_path).unwrap(), "print('new')");
+    assert!(!scripts.join("Main.server.luau").exists());
+    // A Source-only change doesn't replace the instance's properties
+    assert!(!scripts.join("Main.rbxjson").exists());
+    assert!(is_echo(&state, &project, &script_path));
+}
+
+#[tokio::test]
+async fn test_delete_and_rename_apply_immediately() {
+    let (project, _state, server) = setup();
+    let workspace = project.path().join("src/Workspace");
+    fs::create_dir_all(workspace.join("Model")).unwrap();
+    fs::write(workspace.join("Model/_meta.rbxjson"), "{}").unwrap();
+    fs::write(workspace.join("Model/Child.rbxjson"), "{}").unwrap();
+    fs::write(workspace.join("Part.rbxjson"), "{}").unwrap();
+
+    server
+        .post("/sync/studio-stream")
+        .json(&json!({ "projectDir": project_dir(&project), "operations": [part(3.0)] }))
+        .await
+        .assert_status_ok();
+
+    let response: serde_json::Value = server
+        .post("/sync/studio-stream")
+        .json(&json!({
+            "projectDir": project_dir(&project),
+            "operations": [
+                { "type": "rename", "path": "Workspace/Car", "data": { "oldPath": "Workspace/Model", "newPath": "Workspace/Car" } },
+                { "type": "delete", "path": "Workspace/Part" }
+            ]
+        }))
+        .await
+        .json();
+    assert_eq!(response["success"], true);
+    // The pending write for the deleted part is dropped
+    assert_eq!(response["pending"], 0);
+
+    assert!(!workspace.join("Model").exists());
+    assert!(workspace.join("Car/_meta.rbxjson").exists());
+    assert!(workspace.join("Car/Child.rbxjson").exists());
+    assert!(!workspace.join("Part.rbxjson").exists());
+}
+
+#[tokio::test]
+async fn test_push_mode_rejects_studio_changes() {
+    let (project, _state, server) = setup();
+    fs::write(project.path().join("rbxsync.json"), r#"{"sync": {"mode": "push"}}"#).unwrap();
+
+    let response = server
+        .post("/sync/studio-stream")
+        .json(&json!({ "projectDir": project_dir(&project), "operations": [part(1.0)], "flush": true }))
+        .await;
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+    assert!(!project.path().join("src/Workspace/Part.rbxjson").exists());
+}